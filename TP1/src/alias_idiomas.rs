@@ -0,0 +1,124 @@
+use csv::ReaderBuilder;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Posiciones de las columnas del archivo de alias de idiomas
+const POSICION_IDIOMA: usize = 0;
+const POSICION_GRUPO: usize = 1;
+const POSICION_NOMBRE_VISIBLE: usize = 2;
+
+/// Struct que almacena la configuración de alias de idiomas, cargada desde un archivo csv con las
+/// columnas `language,group,display_name`.
+/// grupos es un HashMap donde la clave es el código de idioma de Steam y el valor es el código
+///     del grupo en el que se tiene que contar. Varios idiomas con el mismo grupo se combinan, y un
+///     idioma con un grupo distinto a su código se renombra
+/// nombres es un HashMap donde la clave es el código de un grupo y el valor es el nombre con el
+///     que se muestra en la salida
+#[derive(Debug, Default, Clone)]
+pub struct AliasIdiomas {
+    grupos: HashMap<String, String>,
+    nombres: HashMap<String, String>,
+}
+
+impl AliasIdiomas {
+    /// Recibe el path del archivo de alias y lo carga. Si la columna group está vacía el idioma
+    /// queda con su propio código, y si display_name está vacía el grupo se muestra con su código.
+    /// Devuelve un error con la descripción del problema si el archivo no se puede leer o tiene
+    /// una fila sin idioma
+    pub fn cargar(path: &Path) -> Result<AliasIdiomas, String> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| format!("No se pudo abrir {}: {}", path.display(), e))?;
+
+        let mut alias = AliasIdiomas::default();
+        for (fila, resultado) in reader.records().enumerate() {
+            let record = resultado.map_err(|e| format!("Error en {}: {}", path.display(), e))?;
+            let campo = |posicion| record.get(posicion).map(str::trim).unwrap_or("");
+
+            let idioma = campo(POSICION_IDIOMA);
+            if idioma.is_empty() {
+                return Err(format!(
+                    "Error en {}: la fila {} no tiene idioma",
+                    path.display(),
+                    fila + 2
+                ));
+            }
+            let grupo = match campo(POSICION_GRUPO) {
+                "" => idioma,
+                grupo => grupo,
+            };
+            alias.agregar(idioma, grupo, campo(POSICION_NOMBRE_VISIBLE));
+        }
+        Ok(alias)
+    }
+
+    /// Agrega un alias: el idioma se va a contar dentro del grupo, y si nombre_visible no está
+    /// vacío se usa como nombre del grupo en la salida
+    pub fn agregar(&mut self, idioma: &str, grupo: &str, nombre_visible: &str) {
+        if idioma != grupo {
+            self.grupos.insert(idioma.to_string(), grupo.to_string());
+        }
+        if !nombre_visible.is_empty() {
+            self.nombres
+                .insert(grupo.to_string(), nombre_visible.to_string());
+        }
+    }
+
+    /// Devuelve los grupos de todos los idiomas que tienen alias, ordenados por idioma
+    pub fn grupos(&self) -> BTreeMap<&str, &str> {
+        self.grupos
+            .iter()
+            .map(|(idioma, grupo)| (idioma.as_str(), grupo.as_str()))
+            .collect()
+    }
+
+    /// Devuelve el grupo en el que se tiene que contar un idioma, o None si el idioma no tiene
+    /// alias y se cuenta con su propio código
+    pub fn grupo(&self, idioma: &str) -> Option<&String> {
+        self.grupos.get(idioma)
+    }
+
+    /// Devuelve el nombre con el que se muestra un idioma (o grupo) en la salida. Si no tiene un
+    /// nombre configurado se muestra su código
+    pub fn nombre_visible<'a>(&'a self, idioma: &'a str) -> &'a str {
+        self.nombres
+            .get(idioma)
+            .map(String::as_str)
+            .unwrap_or(idioma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn cargar_archivo_de_alias() {
+        let path = std::env::temp_dir().join("alias_idiomas_test.csv");
+        let mut archivo = std::fs::File::create(&path).unwrap();
+        writeln!(archivo, "language,group,display_name").unwrap();
+        writeln!(archivo, "schinese,chinese,Chinese").unwrap();
+        writeln!(archivo, "tchinese,chinese,").unwrap();
+        writeln!(archivo, "english,,English").unwrap();
+        writeln!(archivo, "brazilian,portuguese").unwrap();
+
+        let alias = AliasIdiomas::cargar(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(alias.grupo("schinese"), Some(&"chinese".to_string()));
+        assert_eq!(alias.grupo("tchinese"), Some(&"chinese".to_string()));
+        assert_eq!(alias.grupo("brazilian"), Some(&"portuguese".to_string()));
+        assert_eq!(alias.grupo("english"), None);
+        assert_eq!(alias.nombre_visible("chinese"), "Chinese");
+        assert_eq!(alias.nombre_visible("english"), "English");
+        assert_eq!(alias.nombre_visible("portuguese"), "portuguese");
+    }
+
+    #[test]
+    fn cargar_archivo_inexistente_da_error() {
+        assert!(AliasIdiomas::cargar(Path::new("/no/existe/alias.csv")).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Struct que almacena las estadísticas parciales de un archivo csv, con las que se arma el
+/// desglose por archivo de la salida
+/// filas es la cantidad de registros leídos del archivo
+/// invalidas es la cantidad de registros que no se pudieron parsear, ya sea porque el csv está mal
+///     formado o porque les faltan columnas
+/// juegos es un HashMap donde la clave es el nombre del juego y el valor es la cantidad de reseñas
+///     del juego en el archivo
+/// idiomas son los idiomas distintos de las reseñas del archivo
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Archivo {
+    pub filas: usize,
+    pub invalidas: usize,
+    pub juegos: HashMap<String, usize>,
+    pub idiomas: HashSet<String>,
+}
+
+impl Archivo {
+    /// Suma las estadísticas de otro chunk del mismo archivo
+    pub fn combinar(&mut self, otro: Archivo) {
+        self.filas += otro.filas;
+        self.invalidas += otro.invalidas;
+        for (juego, reviews) in otro.juegos {
+            *self.juegos.entry(juego).or_insert(0) += reviews;
+        }
+        self.idiomas.extend(otro.idiomas);
+    }
+}
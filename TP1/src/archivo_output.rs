@@ -0,0 +1,28 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Struct que representa el desglose de un archivo csv en la salida
+/// archivo es el path del archivo
+/// filas es la cantidad de registros leídos del archivo
+/// juegos es la cantidad de juegos distintos de sus reseñas
+/// idiomas es la cantidad de idiomas distintos de sus reseñas
+/// top_juego es el juego con más reseñas del archivo, o None si no tiene reseñas
+/// top_juego_reviews es la cantidad de reseñas de ese juego en el archivo
+/// errores es la cantidad de registros que no se pudieron parsear
+#[derive(Serialize, JsonSchema)]
+pub struct ResumenArchivo {
+    #[serde(rename = "file")]
+    pub archivo: String,
+    #[serde(rename = "rows")]
+    pub filas: u32,
+    #[serde(rename = "games")]
+    pub juegos: u32,
+    #[serde(rename = "languages")]
+    pub idiomas: u32,
+    #[serde(rename = "top_game")]
+    pub top_juego: Option<String>,
+    #[serde(rename = "top_game_reviews")]
+    pub top_juego_reviews: u32,
+    #[serde(rename = "parse_errors")]
+    pub errores: u32,
+}
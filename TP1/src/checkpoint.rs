@@ -0,0 +1,602 @@
+use crate::configurar_argumentos::{Opciones, OpcionesCheckpoint};
+use crate::error::ErrorApp;
+use crate::estadisticas::{self, Estadisticas};
+use crate::metadatos::Metadatos;
+use crate::parsear_csv;
+use crate::progreso::Progreso;
+use csv::{Position, StringRecord};
+use rayon::ThreadPool;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Instant;
+
+/// Posición del inicio de un registro dentro de un csv, igual que csv::Position
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Posicion {
+    byte: u64,
+    linea: u64,
+    registro: u64,
+}
+
+impl Posicion {
+    /// Devuelve la posición en la que empieza el registro, o el principio del archivo si el
+    /// registro no tiene posición
+    pub fn de_registro(record: &StringRecord) -> Posicion {
+        let posicion = record.position().cloned().unwrap_or_else(Position::new);
+        Posicion {
+            byte: posicion.byte(),
+            linea: posicion.line(),
+            registro: posicion.record(),
+        }
+    }
+
+    fn a_csv(self) -> Position {
+        let mut posicion = Position::new();
+        posicion
+            .set_byte(self.byte)
+            .set_line(self.linea)
+            .set_record(self.registro);
+        posicion
+    }
+}
+
+/// Próxima porción de los archivos que falta combinar
+/// archivo es el índice del archivo dentro de la lista del checkpoint
+/// chunk es el índice del chunk dentro del archivo
+/// posicion es dónde empieza ese chunk en el csv, o None si el archivo todavía no se empezó
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+struct Siguiente {
+    archivo: usize,
+    chunk: usize,
+    posicion: Option<Posicion>,
+}
+
+/// Contenido del archivo de checkpoint
+/// archivos son los csv del análisis, en el orden en que se combinan
+/// firma resume las opciones de análisis, para no reanudar con opciones distintas
+/// siguiente es lo primero que falta combinar: todo lo anterior ya está en estadisticas
+/// fallidos son los mensajes de los archivos que se saltearon con --keep-going
+/// estadisticas son las estadísticas combinadas de todo lo anterior a siguiente
+#[derive(Serialize, Deserialize)]
+struct Checkpoint<E> {
+    archivos: Vec<PathBuf>,
+    firma: String,
+    siguiente: Siguiente,
+    fallidos: Vec<String>,
+    estadisticas: E,
+}
+
+/// Mensajes que envían los threads que procesan los archivos al que combina los resultados. Los
+/// usan los checkpoints y el ejecutor en pipeline (ver ejecutor.rs)
+/// Leido indica que se leyó un archivo a partir del chunk primer_chunk, con la posición en la que
+///     empieza cada uno de los chunks leídos
+/// Chunk son las estadísticas de un chunk procesado
+/// Error es el error de un archivo que no se pudo leer
+pub enum Mensaje {
+    Leido {
+        archivo: usize,
+        primer_chunk: usize,
+        inicios: Vec<Posicion>,
+    },
+    Chunk {
+        archivo: usize,
+        chunk: usize,
+        estadisticas: Box<Estadisticas>,
+    },
+    Error {
+        archivo: usize,
+        error: ErrorApp,
+    },
+}
+
+/// Archivo leído del que todavía no se combinaron todos los chunks
+struct ArchivoLeido {
+    primer_chunk: usize,
+    inicios: Vec<Posicion>,
+}
+
+/// Struct que combina los resultados de los chunks en el orden de los archivos y de los chunks,
+/// aunque lleguen desordenados, de forma que el resultado sea el mismo que el de la reducción en
+/// paralelo y que lo combinado hasta el momento se pueda guardar en un checkpoint
+/// siguiente es lo primero que falta combinar
+/// estadisticas son las estadísticas combinadas de todo lo anterior a siguiente
+/// fallidos son los errores de los archivos salteados antes de siguiente
+/// leidos, pendientes y errores son los resultados recibidos que todavía no se pueden combinar
+#[derive(Default)]
+pub struct Acumulador {
+    siguiente: Siguiente,
+    estadisticas: Estadisticas,
+    fallidos: Vec<ErrorApp>,
+    leidos: HashMap<usize, ArchivoLeido>,
+    pendientes: BTreeMap<(usize, usize), Estadisticas>,
+    errores: HashMap<usize, ErrorApp>,
+}
+
+impl Acumulador {
+    /// Guarda un mensaje recibido. Los errores sólo se reciben si se sigue ante errores
+    pub fn recibir(&mut self, mensaje: Mensaje) {
+        match mensaje {
+            Mensaje::Leido {
+                archivo,
+                primer_chunk,
+                inicios,
+            } => {
+                self.leidos.insert(
+                    archivo,
+                    ArchivoLeido {
+                        primer_chunk,
+                        inicios,
+                    },
+                );
+            }
+            Mensaje::Chunk {
+                archivo,
+                chunk,
+                estadisticas,
+            } => {
+                self.pendientes.insert((archivo, chunk), *estadisticas);
+            }
+            Mensaje::Error { archivo, error } => {
+                self.errores.insert(archivo, error);
+            }
+        }
+    }
+
+    /// Combina en orden todo lo que se pueda a partir de siguiente. Se detiene en el primer
+    /// archivo o chunk del que todavía no se recibió el resultado
+    pub fn avanzar(&mut self, total_archivos: usize) {
+        while self.siguiente.archivo < total_archivos {
+            let archivo = self.siguiente.archivo;
+            if let Some(error) = self.errores.remove(&archivo) {
+                self.fallidos.push(error);
+                self.siguiente = Siguiente {
+                    archivo: archivo + 1,
+                    ..Siguiente::default()
+                };
+                continue;
+            }
+            let Some(leido) = self.leidos.get(&archivo) else {
+                break;
+            };
+            let chunk = self.siguiente.chunk;
+            if chunk >= leido.primer_chunk + leido.inicios.len() {
+                self.leidos.remove(&archivo);
+                self.siguiente = Siguiente {
+                    archivo: archivo + 1,
+                    ..Siguiente::default()
+                };
+                continue;
+            }
+            let Some(estadisticas) = self.pendientes.remove(&(archivo, chunk)) else {
+                break;
+            };
+            self.estadisticas = estadisticas::combinar_estadisticas(
+                mem::take(&mut self.estadisticas),
+                estadisticas,
+            );
+            self.siguiente = Siguiente {
+                archivo,
+                chunk: chunk + 1,
+                posicion: leido.inicios.get(chunk + 1 - leido.primer_chunk).copied(),
+            };
+        }
+    }
+
+    /// Devuelve las estadísticas combinadas y los errores de los archivos salteados
+    pub fn terminar(self) -> (Estadisticas, Vec<ErrorApp>) {
+        (self.estadisticas, self.fallidos)
+    }
+}
+
+/// Devuelve un resumen de las opciones que cambian las estadísticas calculadas. Un checkpoint
+/// sólo se puede reanudar con la misma firma
+fn firma(opciones: &Opciones) -> String {
+    format!(
+        "terms={} detect_language={} relabel_language={} distributions={} segments={} per_file={} sanitize={:?} truncate={:?} sample={:?} aliases={:?} metadata={:?}",
+        opciones.analizar_terminos,
+        opciones.detectar_idioma,
+        opciones.reetiquetar_idioma,
+        opciones.calcular_distribuciones,
+        opciones.segmentar,
+        opciones.desglosar_archivos,
+        opciones.saneamiento.marcado,
+        opciones.saneamiento.max_grafemas,
+        opciones.muestreo.map(|muestreo| {
+            (
+                muestreo.fraccion,
+                muestreo.semilla,
+                muestreo.estrato,
+                muestreo.minimo_estrato,
+            )
+        }),
+        opciones.alias_idiomas.grupos(),
+        opciones.metadatos.as_ref().map(Metadatos::huella)
+    )
+}
+
+/// Escribe el checkpoint en un archivo temporal y lo renombra, para que si el programa se corta
+/// mientras se escribe quede el checkpoint anterior
+fn guardar(
+    path: &Path,
+    archivos: &[PathBuf],
+    firma: &str,
+    acumulador: &Acumulador,
+) -> Result<(), ErrorApp> {
+    let checkpoint = Checkpoint {
+        archivos: archivos.to_vec(),
+        firma: firma.to_string(),
+        siguiente: acumulador.siguiente,
+        fallidos: acumulador
+            .fallidos
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        estadisticas: &acumulador.estadisticas,
+    };
+    let mut temporal = path.as_os_str().to_owned();
+    temporal.push(".tmp");
+    let temporal = PathBuf::from(temporal);
+
+    let archivo = File::create(&temporal).map_err(|e| ErrorApp::io(&temporal, e))?;
+    let mut writer = BufWriter::new(archivo);
+    serde_json::to_writer(&mut writer, &checkpoint).map_err(|error| ErrorApp::Serializacion {
+        path: temporal.clone(),
+        error,
+    })?;
+    writer.flush().map_err(|e| ErrorApp::io(&temporal, e))?;
+    fs::rename(&temporal, path).map_err(|e| ErrorApp::io(path, e))
+}
+
+/// Lee el checkpoint guardado y verifica que corresponda a los mismos archivos y a las mismas
+/// opciones de análisis. Devuelve los archivos en el orden del checkpoint y el acumulador con lo
+/// que ya se había combinado
+fn cargar(
+    path: &Path,
+    archivos: &[PathBuf],
+    firma: &str,
+) -> Result<(Vec<PathBuf>, Acumulador), ErrorApp> {
+    let archivo = File::open(path).map_err(|e| ErrorApp::io(path, e))?;
+    let checkpoint: Checkpoint<Estadisticas> = serde_json::from_reader(BufReader::new(archivo))
+        .map_err(|error| ErrorApp::Serializacion {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+    if checkpoint.firma != firma {
+        return Err(ErrorApp::Configuracion(format!(
+            "{}: el checkpoint se guardó con otras opciones de análisis ({})",
+            path.display(),
+            checkpoint.firma
+        )));
+    }
+    let mut actuales = archivos.to_vec();
+    let mut guardados = checkpoint.archivos.clone();
+    actuales.sort();
+    guardados.sort();
+    if actuales != guardados {
+        return Err(ErrorApp::Configuracion(format!(
+            "{}: los archivos a procesar no son los mismos que los del checkpoint",
+            path.display()
+        )));
+    }
+
+    let acumulador = Acumulador {
+        siguiente: checkpoint.siguiente,
+        estadisticas: checkpoint.estadisticas,
+        fallidos: checkpoint
+            .fallidos
+            .into_iter()
+            .map(ErrorApp::Anterior)
+            .collect(),
+        ..Acumulador::default()
+    };
+    Ok((checkpoint.archivos, acumulador))
+}
+
+/// Borra el archivo de checkpoint, si existe. Se usa cuando el análisis terminó y ya no hace
+/// falta reanudarlo
+pub fn borrar(path: &Path) -> Result<(), ErrorApp> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(ErrorApp::io(path, e)),
+        _ => Ok(()),
+    }
+}
+
+/// Lee un archivo a partir del chunk primer_chunk, que empieza en la posición desde, y procesa sus
+/// chunks en paralelo. Envía el resultado de la lectura y el de cada chunk al thread que los
+/// combina
+fn procesar_archivo(
+    archivo: usize,
+    path: &Path,
+    (primer_chunk, desde): (usize, Option<Posicion>),
+    opciones: &Opciones,
+    progreso: &Progreso,
+    enviar: &Sender<Mensaje>,
+) {
+    let chunks = match parsear_csv::leer_chunks_desde(path, desde.map(Posicion::a_csv)) {
+        Ok(chunks) => chunks,
+        Err(error) => {
+            progreso.archivo_terminado();
+            // Si el que combina ya terminó por un error, no hace falta avisarle
+            let _ = enviar.send(Mensaje::Error { archivo, error });
+            return;
+        }
+    };
+    let limites = parsear_csv::limites_chunks(
+        path,
+        &chunks,
+        desde.map(|posicion| posicion.byte).unwrap_or(0),
+    );
+    let _ = enviar.send(Mensaje::Leido {
+        archivo,
+        primer_chunk,
+        inicios: chunks
+            .iter()
+            .map(|chunk| chunk.first().map(Posicion::de_registro).unwrap_or_default())
+            .collect(),
+    });
+
+    chunks
+        .into_par_iter()
+        .zip(limites.par_windows(2))
+        .enumerate()
+        .for_each_with(enviar.clone(), |enviar, (i, (chunk, limites))| {
+            let filas = chunk.len() as u64;
+            let estadisticas = parsear_csv::procesar_chunk(path, chunk, opciones);
+            progreso.chunk_procesado(filas, limites[1] - limites[0]);
+            let _ = enviar.send(Mensaje::Chunk {
+                archivo,
+                chunk: primer_chunk + i,
+                estadisticas: Box::new(estadisticas),
+            });
+        });
+    progreso.archivo_terminado();
+}
+
+/// Procesa los archivos igual que procesar_archivos, guardando cada intervalo del checkpoint las
+/// estadísticas combinadas hasta el momento junto con el archivo y el chunk por el que se iba.
+/// Los archivos se procesan en paralelo dentro del ThreadPool y envían sus resultados por un
+/// canal a este thread, que los combina en orden y escribe los checkpoints
+/// Si se pidió reanudar, se carga el checkpoint y sólo se procesa lo que faltaba, de forma que el
+/// resultado es el mismo que el de un análisis sin interrupciones
+/// Devuelve las estadísticas y los errores de los archivos salteados, o el primer error si no se
+/// sigue ante errores
+pub fn procesar(
+    archivos: Vec<PathBuf>,
+    pool: &ThreadPool,
+    opciones: &Opciones,
+    progreso: &Progreso,
+    checkpoint: &OpcionesCheckpoint,
+) -> Result<(Estadisticas, Vec<ErrorApp>), ErrorApp> {
+    let firma = firma(opciones);
+    let (archivos, mut acumulador) = if checkpoint.reanudar {
+        cargar(&checkpoint.archivo, &archivos, &firma)?
+    } else {
+        (archivos, Acumulador::default())
+    };
+    let inicio = acumulador.siguiente;
+    let cancelado = AtomicBool::new(false);
+    let (enviar, recibir) = mpsc::channel();
+
+    thread::scope(|s| {
+        let (archivos, cancelado) = (&archivos, &cancelado);
+        s.spawn(move || {
+            pool.install(|| {
+                archivos
+                    .par_iter()
+                    .enumerate()
+                    .skip(inicio.archivo)
+                    .for_each_with(enviar, |enviar, (archivo, path)| {
+                        if cancelado.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        let desde = if archivo == inicio.archivo {
+                            (inicio.chunk, inicio.posicion)
+                        } else {
+                            (0, None)
+                        };
+                        procesar_archivo(archivo, path, desde, opciones, progreso, enviar);
+                    })
+            })
+        });
+
+        let mut guardado = acumulador.siguiente;
+        let mut ultimo_guardado = Instant::now();
+        for mensaje in recibir {
+            let mensaje = match mensaje {
+                Mensaje::Error { error, .. } if !opciones.seguir_con_errores => {
+                    cancelado.store(true, Ordering::Relaxed);
+                    return Err(error);
+                }
+                mensaje => mensaje,
+            };
+            acumulador.recibir(mensaje);
+            acumulador.avanzar(archivos.len());
+            if acumulador.siguiente != guardado && ultimo_guardado.elapsed() >= checkpoint.intervalo
+            {
+                if let Err(error) = guardar(&checkpoint.archivo, archivos, &firma, &acumulador) {
+                    cancelado.store(true, Ordering::Relaxed);
+                    return Err(error);
+                }
+                guardado = acumulador.siguiente;
+                ultimo_guardado = Instant::now();
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(acumulador.terminar())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configurar_argumentos::ModoProgreso;
+    use crate::formatear_datos_json::Output;
+    use crate::metadatos::{Metadatos, MetadatosJuego};
+    use crate::pruebas::{self, opciones};
+    use std::time::Duration;
+
+    fn crear_archivos(directorio: &Path) -> Vec<PathBuf> {
+        pruebas::crear_archivos(
+            directorio,
+            &[
+                "0,1,FIFA,1,english,Crash on startup,-,-,-,5\n\
+                 1,1,FIFA,2,spanish,Muy buen juego,-,-,-,3\n",
+                "2,2,Terraria,3,english,Never crash great,-,-,-,9\n\
+                 3,1,FIFA,4,english,Great game,-,-,-,9\n",
+                "4,2,Terraria,5,spanish,Me encanta,-,-,-,1\n",
+            ],
+        )
+    }
+
+    fn salida(estadisticas: &Estadisticas, opciones: &Opciones) -> String {
+        serde_json::to_string(&Output::new(estadisticas, opciones)).unwrap()
+    }
+
+    #[test]
+    fn combinar_en_orden_aunque_lleguen_desordenados() {
+        let estadisticas = |juego: &str| {
+            let mut e = Estadisticas::default();
+            e.juegos.entry(juego.to_string()).or_default().reviews += 1;
+            e
+        };
+        let posicion = |byte| Posicion {
+            byte,
+            linea: 0,
+            registro: 0,
+        };
+        let mut acumulador = Acumulador::default();
+        acumulador.recibir(Mensaje::Chunk {
+            archivo: 0,
+            chunk: 1,
+            estadisticas: Box::new(estadisticas("FIFA")),
+        });
+        acumulador.recibir(Mensaje::Leido {
+            archivo: 0,
+            primer_chunk: 0,
+            inicios: vec![posicion(10), posicion(50)],
+        });
+        acumulador.avanzar(2);
+        assert_eq!(acumulador.siguiente, Siguiente::default());
+
+        acumulador.recibir(Mensaje::Chunk {
+            archivo: 0,
+            chunk: 0,
+            estadisticas: Box::new(estadisticas("Terraria")),
+        });
+        acumulador.avanzar(2);
+        assert_eq!(
+            acumulador.siguiente,
+            Siguiente {
+                archivo: 1,
+                ..Siguiente::default()
+            }
+        );
+        assert_eq!(acumulador.estadisticas.juegos.len(), 2);
+        assert!(acumulador.pendientes.is_empty());
+    }
+
+    #[test]
+    fn reanudar_da_el_mismo_resultado() {
+        let directorio = std::env::temp_dir().join("checkpoint_test");
+        let archivos = crear_archivos(&directorio);
+        let opciones = opciones();
+        let progreso = Progreso::new(ModoProgreso::Ninguno, &archivos, 2);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+
+        let completo = archivos
+            .iter()
+            .map(|path| parsear_csv::procesar_csv(path, &opciones, &progreso).unwrap())
+            .reduce(estadisticas::combinar_estadisticas)
+            .unwrap();
+
+        // Checkpoint guardado después de combinar el primer archivo
+        let path = directorio.join("checkpoint.json");
+        let mut acumulador = Acumulador::default();
+        let chunks = parsear_csv::leer_chunks(&archivos[0]).unwrap();
+        acumulador.recibir(Mensaje::Leido {
+            archivo: 0,
+            primer_chunk: 0,
+            inicios: vec![Posicion::de_registro(&chunks[0][0])],
+        });
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            acumulador.recibir(Mensaje::Chunk {
+                archivo: 0,
+                chunk: i,
+                estadisticas: Box::new(parsear_csv::procesar_chunk(&archivos[0], chunk, &opciones)),
+            });
+        }
+        acumulador.avanzar(archivos.len());
+        guardar(&path, &archivos, &firma(&opciones), &acumulador).unwrap();
+
+        let reanudar = OpcionesCheckpoint {
+            archivo: path.clone(),
+            intervalo: Duration::ZERO,
+            reanudar: true,
+        };
+        let (reanudado, fallidos) =
+            procesar(archivos.clone(), &pool, &opciones, &progreso, &reanudar).unwrap();
+        assert!(fallidos.is_empty());
+        assert_eq!(salida(&reanudado, &opciones), salida(&completo, &opciones));
+
+        // Con otras opciones de análisis no se puede reanudar
+        let otras = Opciones {
+            calcular_distribuciones: false,
+            ..pruebas::opciones()
+        };
+        guardar(&path, &archivos, &firma(&opciones), &acumulador).unwrap();
+        assert!(matches!(
+            procesar(archivos, &pool, &otras, &progreso, &reanudar),
+            Err(ErrorApp::Configuracion(_))
+        ));
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+
+    #[test]
+    fn la_firma_depende_del_contenido_de_los_metadatos() {
+        let con_metadatos = |desarrollador: &str| {
+            let mut metadatos = Metadatos::default();
+            metadatos.juegos.insert(
+                1,
+                MetadatosJuego {
+                    generos: vec!["Sports".to_string()],
+                    desarrollador: desarrollador.to_string(),
+                    anio: Some(2020),
+                },
+            );
+            firma(&Opciones {
+                metadatos: Some(metadatos),
+                ..opciones()
+            })
+        };
+        assert_eq!(con_metadatos("EA"), con_metadatos("EA"));
+        assert_ne!(con_metadatos("EA"), con_metadatos("Valve"));
+        assert_ne!(con_metadatos("EA"), firma(&opciones()));
+    }
+
+    #[test]
+    fn leer_desde_la_posicion_de_un_registro() {
+        let directorio = std::env::temp_dir().join("checkpoint_posicion_test");
+        let archivos = crear_archivos(&directorio);
+        let chunks = parsear_csv::leer_chunks(&archivos[1]).unwrap();
+        let segundo = Posicion::de_registro(&chunks[0][1]);
+
+        let desde = parsear_csv::leer_chunks_desde(&archivos[1], Some(segundo.a_csv())).unwrap();
+        assert_eq!(desde.len(), 1);
+        assert_eq!(desde[0].len(), 1);
+        assert_eq!(&desde[0][0], &chunks[0][1]);
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+}
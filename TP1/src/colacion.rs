@@ -0,0 +1,107 @@
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
+
+/// Colación con la que se desempatan alfabéticamente los juegos y los idiomas de los rankings
+/// Byte compara los nombres byte a byte, como String::cmp
+/// SinMayusculas compara los nombres en minúscula, así que "dota 2" y "Dota 2" quedan juntos
+/// Raiz es una aproximación a la colación raíz de Unicode: primero compara las letras base, sin
+///     acentos, en minúscula y con los caracteres de ancho completo llevados a su forma normal
+///     ("Ｆ" es "f"); después los acentos y el ancho, y por último las mayúsculas
+/// En todas, los nombres que la colación considera iguales se desempatan byte a byte, para que el
+/// orden sea total y no dependa del orden en que se procesaron los archivos
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Colacion {
+    #[default]
+    #[value(name = "byte")]
+    #[serde(rename = "byte")]
+    Byte,
+    #[value(name = "case-insensitive")]
+    #[serde(rename = "case-insensitive")]
+    SinMayusculas,
+    #[value(name = "root")]
+    #[serde(rename = "root")]
+    Raiz,
+}
+
+impl Colacion {
+    /// Compara dos nombres según la colación
+    pub fn comparar(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Colacion::Byte => a.cmp(b),
+            Colacion::SinMayusculas => a
+                .to_lowercase()
+                .cmp(&b.to_lowercase())
+                .then_with(|| a.cmp(b)),
+            Colacion::Raiz => letras_base(a)
+                .cmp(&letras_base(b))
+                .then_with(|| sin_mayusculas(a).cmp(&sin_mayusculas(b)))
+                .then_with(|| a.cmp(b)),
+        }
+    }
+}
+
+/// Devuelve el texto en forma normal de compatibilidad, sin marcas diacríticas y en minúscula
+fn letras_base(texto: &str) -> String {
+    texto
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Devuelve el texto en forma normal de compatibilidad y en minúscula, conservando los acentos
+fn sin_mayusculas(texto: &str) -> String {
+    texto.nfkd().collect::<String>().to_lowercase()
+}
+
+/// Devuelve el texto en forma normal NFC, para que dos nombres que se ven iguales pero están
+/// escritos con distintas secuencias de caracteres (por ejemplo "é" y "e" seguida del acento
+/// combinable) se cuenten como el mismo. Casi todos los nombres ya vienen en NFC, así que en ese
+/// caso no se copia el texto
+pub fn normalizar(texto: &str) -> Cow<'_, str> {
+    match is_nfc_quick(texto.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(texto),
+        _ => Cow::Owned(texto.nfc().collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ordenar(colacion: Colacion, nombres: &[&str]) -> Vec<String> {
+        let mut ordenados: Vec<String> = nombres.iter().map(|n| n.to_string()).collect();
+        ordenados.sort_by(|a, b| colacion.comparar(a, b));
+        ordenados
+    }
+
+    #[test]
+    fn comparar_con_cada_colacion() {
+        let nombres = ["zelda", "Élan", "Zoo", "elan", "Ｆez", "fez"];
+        assert_eq!(
+            ordenar(Colacion::Byte, &nombres),
+            vec!["Zoo", "elan", "fez", "zelda", "Élan", "Ｆez"]
+        );
+        assert_eq!(
+            ordenar(Colacion::SinMayusculas, &nombres),
+            vec!["elan", "fez", "zelda", "Zoo", "Élan", "Ｆez"]
+        );
+        assert_eq!(
+            ordenar(Colacion::Raiz, &nombres),
+            vec!["elan", "Élan", "fez", "Ｆez", "zelda", "Zoo"]
+        );
+    }
+
+    #[test]
+    fn normalizar_a_nfc() {
+        let descompuesto = "Pokémon".nfd().collect::<String>();
+        assert_ne!(descompuesto, "Pokémon");
+        assert_eq!(normalizar(&descompuesto), "Pokémon");
+        assert!(matches!(normalizar("Pokémon"), Cow::Borrowed(_)));
+    }
+}
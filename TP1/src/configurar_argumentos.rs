@@ -1,39 +1,1371 @@
-use std::env;
+use crate::alias_idiomas::AliasIdiomas;
+use crate::colacion::Colacion;
+use crate::consulta::FiltrosConsulta;
+use crate::error::ErrorApp;
+use crate::metadatos::Metadatos;
+use crate::muestreo::{Conteos, Estrato, MINIMO_ESTRATO_DEFAULT, Muestreo};
+use crate::saneamiento::{Marcado, Saneamiento};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-const ARGUMENTOS_ESPERADOS: usize = 4;
-const RUTA_DATASET: usize = 1;
-const N_THREADS: usize = 2;
-const ARCHIVO_SALIDA: usize = 3;
+/// Cantidad de resultados que devuelve una consulta si no se indica otra
+const LIMITE_CONSULTA_DEFAULT: usize = 10;
+/// Puerto en el que escucha el servidor si no se indica otro
+const PUERTO_DEFAULT: u16 = 7878;
+/// Cada cuántos segundos se guarda el checkpoint si no se indica otro intervalo
+const INTERVALO_CHECKPOINT_DEFAULT: u64 = 60;
+
+/// Línea de comandos del programa. Sin subcomando se ejecuta el análisis con los argumentos
+/// posicionales de siempre: `<input-path> <num-threads> <output-file-name>`
+#[derive(Parser, Debug)]
+#[command(
+    name = "app",
+    version,
+    about = "Procesa reseñas de juegos de Steam en paralelo"
+)]
+struct Cli {
+    #[command(flatten)]
+    analisis: AnalizarCli,
+    #[command(subcommand)]
+    comando: Option<SubcomandoCli>,
+}
+
+#[derive(Subcommand, Debug)]
+enum SubcomandoCli {
+    /// Construye el índice invertido de los textos de las reseñas
+    #[command(name = "index")]
+    Indexar(IndexarCli),
+    /// Busca reseñas en un índice ya construido
+    #[command(name = "query")]
+    Consultar(ConsultarCli),
+    /// Calcula las estadísticas una vez y las sirve por HTTP en localhost
+    #[command(name = "serve")]
+    Servir(ServirCli),
+    /// Compara dos archivos de salida del análisis
+    #[command(name = "diff")]
+    Comparar(CompararCli),
+    /// Imprime el JSON Schema de los archivos de salida del análisis
+    #[command(name = "schema")]
+    Esquema,
+    /// Valida un archivo de salida contra el JSON Schema e informa las rutas que no lo cumplen
+    #[command(name = "validate")]
+    Validar(ValidarCli),
+}
+
+/// Flag del archivo de configuración, que sólo tienen los comandos que toman opciones del archivo
+#[derive(Args, Debug, Default)]
+struct ConfigCli {
+    /// Archivo TOML con los valores de las opciones. Los argumentos de la línea de comandos tienen
+    /// prioridad sobre el archivo
+    #[arg(long = "config", value_name = "ARCHIVO")]
+    config: Option<PathBuf>,
+}
+
+/// Flags de la ejecución del procesamiento de los csv, compartidos por el análisis y el servidor
+#[derive(Args, Debug, Default)]
+struct EjecucionCli {
+    /// Si un archivo de entrada no se puede leer, lo saltea y termina con resultados parciales y
+    /// un resumen de los archivos que fallaron
+    #[arg(long = "keep-going")]
+    seguir_con_errores: bool,
+    /// Cómo se informa el progreso por salida de error [default: human]
+    #[arg(long = "progress", value_enum)]
+    progreso: Option<ModoProgreso>,
+    /// No informa el progreso ni la duración del programa
+    #[arg(long = "quiet", short = 'q', conflicts_with = "progreso")]
+    silencioso: bool,
+    /// Estrategia con la que se reparten los archivos y los chunks entre los threads
+    /// [default: rayon]
+    #[arg(long = "executor", value_enum)]
+    ejecutor: Option<EstrategiaEjecucion>,
+}
+
+/// Flags de las opciones de análisis, compartidos por el análisis y el servidor
+#[derive(Args, Debug, Default)]
+struct OpcionesCli {
+    /// Agrega los términos más frecuentes por idioma y por juego
+    #[arg(long = "terms")]
+    terminos: bool,
+    /// Detecta el idioma de cada reseña y lo compara con el declarado
+    #[arg(long = "detect-language")]
+    detectar_idioma: bool,
+    /// Igual que --detect-language, pero cuenta las reseñas erróneas con el idioma detectado
+    #[arg(long = "relabel-language")]
+    reetiquetar_idioma: bool,
+    /// Csv con las columnas language,group,display_name para combinar o renombrar idiomas
+    #[arg(long = "language-aliases", value_name = "ARCHIVO")]
+    alias_idiomas: Option<PathBuf>,
+    /// Csv con las columnas app_id,genres,developer,release_year de los juegos
+    #[arg(long = "metadata", value_name = "ARCHIVO")]
+    metadatos: Option<PathBuf>,
+    /// Agrega los percentiles de votos y de largo de las reseñas por idioma y por juego
+    #[arg(long = "distributions")]
+    distribuciones: bool,
+    /// Calcula la salida completa por separado para los segmentos steam_purchase,
+    /// received_for_free y written_during_early_access
+    #[arg(long = "segments")]
+    segmentos: bool,
+    /// Agrega el desglose de cada archivo csv: filas, juegos, idiomas, juego principal y registros
+    /// que no se pudieron parsear
+    #[arg(long = "per-file")]
+    por_archivo: bool,
+    /// Colación con la que se desempatan alfabéticamente los juegos y los idiomas
+    #[arg(long = "collation", value_name = "COLACION")]
+    colacion: Option<Colacion>,
+    /// Sanea el texto de las reseñas quitando o mostrando como texto el BBCode de Steam y
+    /// eliminando los caracteres de control
+    #[arg(long = "sanitize", value_name = "MARCADO")]
+    marcado: Option<Marcado>,
+    /// Trunca el texto de las reseñas a esta cantidad de grafemas
+    #[arg(long = "truncate", value_name = "N")]
+    max_grafemas: Option<usize>,
+    /// Analiza sólo esta fracción de las reseñas (mayor a 0 y hasta 1), elegidas al azar
+    #[arg(long = "sample", value_name = "FRACCION", value_parser = parsear_fraccion)]
+    fraccion_muestra: Option<f64>,
+    /// Semilla con la que se eligen las reseñas de la muestra
+    #[arg(long = "seed", value_name = "N")]
+    semilla: Option<u64>,
+    /// Muestrea y extrapola por juego o por idioma: cada uno entra en la muestra con al menos
+    /// --stratum-min reseñas y se extrapola con su propia proporción
+    #[arg(long = "stratify", value_name = "ESTRATO")]
+    estrato: Option<Estrato>,
+    /// Cantidad mínima de reseñas de cada juego o idioma que entran en la muestra estratificada
+    /// [default: 10]
+    #[arg(long = "stratum-min", value_name = "N")]
+    minimo_estrato: Option<usize>,
+    /// Informa las cantidades de reseñas de la muestra o extrapoladas al total
+    #[arg(long = "sample-counts", value_name = "CONTEOS")]
+    conteos: Option<Conteos>,
+}
+
+#[derive(Args, Debug)]
+struct AnalizarCli {
+    /// Directorio con los archivos csv
+    #[arg(value_name = "INPUT-PATH")]
+    ruta: Option<String>,
+    /// Cantidad de threads en paralelo (mayor a 0)
+    #[arg(value_name = "NUM-THREADS", value_parser = parsear_threads)]
+    n_threads: Option<usize>,
+    /// Archivo donde se escribe la salida en formato JSON
+    #[arg(value_name = "OUTPUT-FILE-NAME")]
+    archivo_salida: Option<String>,
+    #[command(flatten)]
+    opciones: OpcionesCli,
+    #[command(flatten)]
+    ejecucion: EjecucionCli,
+    #[command(flatten)]
+    matriz: MatrizCli,
+    #[command(flatten)]
+    checkpoint: CheckpointCli,
+    #[command(flatten)]
+    config: ConfigCli,
+}
+
+/// Flags de los checkpoints del análisis
+#[derive(Args, Debug, Default)]
+struct CheckpointCli {
+    /// Archivo donde se guarda periódicamente el estado del análisis para poder reanudarlo
+    #[arg(id = "checkpoint", long = "checkpoint", value_name = "ARCHIVO")]
+    archivo: Option<PathBuf>,
+    /// Cada cuántos segundos se guarda el checkpoint [default: 60]
+    #[arg(
+        id = "checkpoint_interval",
+        long = "checkpoint-interval",
+        value_name = "SEGUNDOS"
+    )]
+    intervalo: Option<u64>,
+    /// Reanuda el análisis desde el checkpoint en lugar de empezar de nuevo
+    #[arg(id = "resume", long = "resume")]
+    reanudar: bool,
+}
+
+/// Flags de la exportación de la matriz de juegos por idiomas
+#[derive(Args, Debug, Default)]
+struct MatrizCli {
+    /// Archivo donde se exporta la matriz de reseñas por juego e idioma
+    #[arg(long = "matrix", value_name = "ARCHIVO")]
+    archivo: Option<String>,
+    /// Formato de la matriz. Si no se indica se deduce de la extensión del archivo
+    #[arg(long = "matrix-format", value_enum)]
+    formato: Option<FormatoMatriz>,
+    /// Cantidad máxima de juegos de la matriz, los de más reseñas
+    #[arg(long = "matrix-games", value_name = "M")]
+    max_juegos: Option<usize>,
+    /// Cantidad máxima de idiomas de la matriz, los de más reseñas
+    #[arg(long = "matrix-languages", value_name = "L")]
+    max_idiomas: Option<usize>,
+}
+
+impl AnalizarCli {
+    /// Indica si no se pasó ningún argumento ni flag del análisis
+    fn vacio(&self) -> bool {
+        let opciones = &self.opciones;
+        self.ruta.is_none()
+            && self.n_threads.is_none()
+            && self.archivo_salida.is_none()
+            && !opciones.terminos
+            && !opciones.detectar_idioma
+            && !opciones.reetiquetar_idioma
+            && opciones.alias_idiomas.is_none()
+            && opciones.metadatos.is_none()
+            && !opciones.distribuciones
+            && !opciones.segmentos
+            && !opciones.por_archivo
+            && opciones.colacion.is_none()
+            && opciones.marcado.is_none()
+            && opciones.max_grafemas.is_none()
+            && opciones.fraccion_muestra.is_none()
+            && opciones.semilla.is_none()
+            && opciones.estrato.is_none()
+            && opciones.minimo_estrato.is_none()
+            && opciones.conteos.is_none()
+            && self.matriz.archivo.is_none()
+            && self.matriz.formato.is_none()
+            && self.matriz.max_juegos.is_none()
+            && self.matriz.max_idiomas.is_none()
+            && self.checkpoint.archivo.is_none()
+            && self.checkpoint.intervalo.is_none()
+            && !self.checkpoint.reanudar
+            && !self.ejecucion.seguir_con_errores
+            && self.ejecucion.progreso.is_none()
+            && !self.ejecucion.silencioso
+            && self.ejecucion.ejecutor.is_none()
+            && self.config.config.is_none()
+    }
+}
+
+#[derive(Args, Debug)]
+struct IndexarCli {
+    /// Directorio con los archivos csv
+    #[arg(value_name = "INPUT-PATH")]
+    ruta: Option<String>,
+    /// Directorio donde se escribe el índice
+    #[arg(value_name = "INDEX-DIR")]
+    directorio_indice: Option<String>,
+    /// Cantidad de threads en paralelo. Si no se indica se usa un thread por núcleo
+    #[arg(value_name = "NUM-THREADS", value_parser = parsear_threads)]
+    n_threads: Option<usize>,
+    /// Si un archivo de entrada no se puede leer, lo saltea y termina con un índice parcial y un
+    /// resumen de los archivos que fallaron
+    #[arg(long = "keep-going")]
+    seguir_con_errores: bool,
+    /// No informa la duración del programa
+    #[arg(long = "quiet", short = 'q')]
+    silencioso: bool,
+    #[command(flatten)]
+    config: ConfigCli,
+}
+
+#[derive(Args, Debug)]
+struct ConsultarCli {
+    /// Directorio de un índice construido con index
+    #[arg(value_name = "INDEX-DIR")]
+    directorio_indice: String,
+    /// Consulta con palabras combinadas con AND, OR, NOT y paréntesis
+    #[arg(value_name = "CONSULTA")]
+    consulta: String,
+    /// Nombre exacto del juego
+    #[arg(long = "game")]
+    juego: Option<String>,
+    /// Idioma exacto de la reseña
+    #[arg(long = "language")]
+    idioma: Option<String>,
+    /// Cantidad mínima de votes_helpful
+    #[arg(long = "min-votes")]
+    votos_minimos: Option<u32>,
+    /// Cantidad máxima de resultados [default: 10]
+    #[arg(long = "limit")]
+    limite: Option<usize>,
+    #[command(flatten)]
+    config: ConfigCli,
+}
+
+#[derive(Args, Debug)]
+struct ServirCli {
+    /// Directorio con los archivos csv
+    #[arg(value_name = "INPUT-PATH")]
+    ruta: Option<String>,
+    /// Cantidad de threads en paralelo (mayor a 0)
+    #[arg(value_name = "NUM-THREADS", value_parser = parsear_threads)]
+    n_threads: Option<usize>,
+    /// Puerto de localhost en el que escucha el servidor [default: 7878]
+    #[arg(long = "port")]
+    puerto: Option<u16>,
+    #[command(flatten)]
+    opciones: OpcionesCli,
+    #[command(flatten)]
+    ejecucion: EjecucionCli,
+    #[command(flatten)]
+    config: ConfigCli,
+}
+
+#[derive(Args, Debug)]
+struct CompararCli {
+    /// Primer archivo de salida
+    #[arg(value_name = "A")]
+    archivo_a: String,
+    /// Segundo archivo de salida
+    #[arg(value_name = "B")]
+    archivo_b: String,
+    /// Los cambios de posición en los rankings no cuentan como diferencias
+    #[arg(long = "ignore-order")]
+    ignorar_orden: bool,
+    #[command(flatten)]
+    config: ConfigCli,
+}
+
+impl Cli {
+    /// Devuelve el archivo de configuración del comando a ejecutar. schema y validate no toman
+    /// opciones del archivo, así que no lo aceptan
+    fn archivo_config(&self) -> Option<&Path> {
+        let config = match &self.comando {
+            None => &self.analisis.config,
+            Some(SubcomandoCli::Indexar(indice)) => &indice.config,
+            Some(SubcomandoCli::Consultar(consulta)) => &consulta.config,
+            Some(SubcomandoCli::Servir(servidor)) => &servidor.config,
+            Some(SubcomandoCli::Comparar(comparar)) => &comparar.config,
+            Some(SubcomandoCli::Esquema | SubcomandoCli::Validar(_)) => return None,
+        };
+        config.config.as_deref()
+    }
+}
+
+#[derive(Args, Debug)]
+struct ValidarCli {
+    /// Archivo de salida a validar
+    #[arg(value_name = "ARCHIVO")]
+    archivo: String,
+}
+
+/// Struct con el contenido del archivo de configuración TOML. Todas las claves son opcionales
+/// input, threads, output e index_dir son los valores por defecto de los argumentos posicionales
+/// keep_going, progress, quiet y executor son los equivalentes a --keep-going, --progress, --quiet
+///     y --executor
+/// analysis son las opciones de análisis, usadas por el análisis y por serve
+/// matrix es la exportación de la matriz de juegos por idiomas del análisis
+/// checkpoint son los checkpoints del análisis
+/// query, serve y diff son las opciones de cada subcomando
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "input")]
+    ruta: Option<String>,
+    #[serde(rename = "threads")]
+    n_threads: Option<usize>,
+    #[serde(rename = "output")]
+    archivo_salida: Option<String>,
+    #[serde(rename = "index_dir")]
+    directorio_indice: Option<String>,
+    #[serde(rename = "keep_going")]
+    seguir_con_errores: bool,
+    #[serde(rename = "progress")]
+    progreso: Option<ModoProgreso>,
+    #[serde(rename = "quiet")]
+    silencioso: bool,
+    #[serde(rename = "executor")]
+    ejecutor: Option<EstrategiaEjecucion>,
+    #[serde(rename = "analysis")]
+    analisis: ConfigAnalisis,
+    #[serde(rename = "matrix")]
+    matriz: ConfigMatriz,
+    #[serde(rename = "checkpoint")]
+    checkpoint: ConfigCheckpoint,
+    #[serde(rename = "query")]
+    consulta: ConfigConsulta,
+    #[serde(rename = "serve")]
+    servidor: ConfigServidor,
+    #[serde(rename = "diff")]
+    comparar: ConfigComparar,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigAnalisis {
+    #[serde(rename = "terms")]
+    terminos: bool,
+    #[serde(rename = "detect_language")]
+    detectar_idioma: bool,
+    #[serde(rename = "relabel_language")]
+    reetiquetar_idioma: bool,
+    #[serde(rename = "language_aliases")]
+    alias_idiomas: Option<PathBuf>,
+    #[serde(rename = "metadata")]
+    metadatos: Option<PathBuf>,
+    #[serde(rename = "distributions")]
+    distribuciones: bool,
+    #[serde(rename = "segments")]
+    segmentos: bool,
+    #[serde(rename = "per_file")]
+    por_archivo: bool,
+    #[serde(rename = "collation")]
+    colacion: Option<Colacion>,
+    #[serde(rename = "sanitize")]
+    marcado: Option<Marcado>,
+    #[serde(rename = "truncate")]
+    max_grafemas: Option<usize>,
+    #[serde(rename = "sample")]
+    fraccion_muestra: Option<f64>,
+    #[serde(rename = "seed")]
+    semilla: Option<u64>,
+    #[serde(rename = "stratify")]
+    estrato: Option<Estrato>,
+    #[serde(rename = "stratum_min")]
+    minimo_estrato: Option<usize>,
+    #[serde(rename = "sample_counts")]
+    conteos: Option<Conteos>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigMatriz {
+    #[serde(rename = "output")]
+    archivo: Option<String>,
+    #[serde(rename = "format")]
+    formato: Option<FormatoMatriz>,
+    #[serde(rename = "games")]
+    max_juegos: Option<usize>,
+    #[serde(rename = "languages")]
+    max_idiomas: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigCheckpoint {
+    #[serde(rename = "file")]
+    archivo: Option<PathBuf>,
+    #[serde(rename = "interval_secs")]
+    intervalo: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigConsulta {
+    #[serde(rename = "game")]
+    juego: Option<String>,
+    #[serde(rename = "language")]
+    idioma: Option<String>,
+    #[serde(rename = "min_votes")]
+    votos_minimos: Option<u32>,
+    #[serde(rename = "limit")]
+    limite: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigServidor {
+    #[serde(rename = "port")]
+    puerto: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigComparar {
+    #[serde(rename = "ignore_order")]
+    ignorar_orden: bool,
+}
+
+/// Comando a ejecutar según los argumentos introducidos por terminal
+/// Analizar es el análisis de los csv que genera el archivo de salida
+/// Indexar construye el índice invertido de los textos de las reseñas
+/// Consultar busca reseñas en un índice ya construido
+/// Servir calcula las estadísticas una vez y las sirve por HTTP en localhost
+/// Comparar compara dos archivos de salida del análisis
+/// Esquema imprime el JSON Schema de la salida del análisis
+/// Validar valida un archivo de salida contra el JSON Schema
+pub enum Comando {
+    Analizar(Argumentos),
+    Indexar(ArgumentosIndice),
+    Consultar(ArgumentosConsulta),
+    Servir(ArgumentosServidor),
+    Comparar(ArgumentosComparar),
+    Esquema,
+    Validar(String),
+}
 
 /// Struct que almacena los argumentos introducidos por terminal.
 /// ruta es el path del directorio del que se quieren leer los archivos
 /// n_threads son la cantidad de threads en paralelo que se van a ejecutar
 /// archivo_salida es el nombre del archivo en donde se va a encontrar el resultado del programa
+/// opciones son las opciones de análisis que se pueden habilitar con flags luego de los
+///     argumentos obligatorios
+/// matriz es la exportación de la matriz de juegos por idiomas. Si es None no se exporta
+/// checkpoint son los checkpoints del análisis. Si es None no se guardan
 ///
 pub struct Argumentos {
     pub ruta: String,
     pub n_threads: usize,
     pub archivo_salida: String,
+    pub opciones: Opciones,
+    pub matriz: Option<ExportacionMatriz>,
+    pub checkpoint: Option<OpcionesCheckpoint>,
+}
+
+/// Struct que almacena las opciones de los checkpoints del análisis (ver checkpoint.rs)
+/// archivo es el archivo donde se guarda el checkpoint
+/// intervalo es cada cuánto se guarda
+/// reanudar indica si el análisis continúa desde el checkpoint guardado en archivo
+#[derive(Debug, Clone)]
+pub struct OpcionesCheckpoint {
+    pub archivo: PathBuf,
+    pub intervalo: Duration,
+    pub reanudar: bool,
+}
+
+/// Forma de informar el progreso del procesamiento por salida de error
+/// Humano es una línea con el avance que se actualiza mientras se procesa
+/// Json es un objeto JSON por línea, para los scripts que ejecutan el programa
+/// Ninguno no informa el progreso
+#[derive(ValueEnum, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum ModoProgreso {
+    #[default]
+    #[value(name = "human")]
+    #[serde(rename = "human")]
+    Humano,
+    #[value(name = "json")]
+    #[serde(rename = "json")]
+    Json,
+    #[value(name = "none")]
+    #[serde(rename = "none")]
+    Ninguno,
+}
+
+/// Estrategia de ejecución con la que se procesan los archivos (ver ejecutor.rs)
+/// Rayon reparte los archivos y los chunks con el work stealing de rayon
+/// Threads divide los archivos en partes iguales entre threads de std::thread, que envían sus
+///     resultados por un canal
+/// Pipeline separa un thread lector, los workers que procesan los chunks y el thread que combina
+///     los resultados, comunicados por canales
+#[derive(ValueEnum, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EstrategiaEjecucion {
+    #[default]
+    Rayon,
+    Threads,
+    Pipeline,
+}
+
+/// Formato del archivo de la matriz de juegos por idiomas
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatoMatriz {
+    Csv,
+    Json,
+}
+
+/// Struct que almacena la exportación de la matriz de juegos por idiomas
+/// archivo es el archivo donde se escribe la matriz
+/// formato es el formato del archivo
+/// max_juegos y max_idiomas limitan la matriz a los juegos y los idiomas con más reseñas. Si son
+///     None se exportan todos
+pub struct ExportacionMatriz {
+    pub archivo: String,
+    pub formato: FormatoMatriz,
+    pub max_juegos: Option<usize>,
+    pub max_idiomas: Option<usize>,
+}
+
+/// Struct que almacena los argumentos del comando index
+/// ruta es el path del directorio del que se quieren leer los archivos
+/// directorio_indice es el directorio donde se escribe el índice
+/// n_threads son la cantidad de threads en paralelo. Si no se indica se usa un thread por núcleo
+/// seguir_con_errores indica si se saltean los archivos que no se pueden leer
+/// silencioso indica si no se informa la duración del programa
+pub struct ArgumentosIndice {
+    pub ruta: String,
+    pub directorio_indice: String,
+    pub n_threads: Option<usize>,
+    pub seguir_con_errores: bool,
+    pub silencioso: bool,
+}
+
+/// Struct que almacena los argumentos del comando query
+/// directorio_indice es el directorio de un índice construido con index
+/// consulta es la consulta booleana (ver consulta.rs)
+/// filtros son los filtros por juego, idioma, votos y cantidad de resultados
+pub struct ArgumentosConsulta {
+    pub directorio_indice: String,
+    pub consulta: String,
+    pub filtros: FiltrosConsulta,
+}
+
+/// Struct que almacena los argumentos del comando serve
+/// ruta es el path del directorio del que se quieren leer los archivos
+/// n_threads son la cantidad de threads en paralelo con los que se calculan las estadísticas
+/// puerto es el puerto de localhost en el que escucha el servidor
+/// opciones son las mismas opciones de análisis que se pueden pasar al análisis
+pub struct ArgumentosServidor {
+    pub ruta: String,
+    pub n_threads: usize,
+    pub puerto: u16,
+    pub opciones: Opciones,
+}
+
+/// Struct que almacena los argumentos del comando diff
+/// archivo_a y archivo_b son los archivos de salida a comparar
+/// ignorar_orden indica si los cambios de posición en los rankings no cuentan como diferencias
+pub struct ArgumentosComparar {
+    pub archivo_a: String,
+    pub archivo_b: String,
+    pub ignorar_orden: bool,
+}
+
+/// Struct que almacena las opciones de análisis opcionales
+/// analizar_terminos indica si se deben calcular los términos más frecuentes por idioma y juego
+/// detectar_idioma indica si se debe detectar el idioma de cada reseña y compararlo con el declarado
+/// reetiquetar_idioma indica si a las reseñas con idioma erróneo se les asigna el idioma detectado
+/// alias_idiomas son los alias y nombres visibles de los idiomas (ver alias_idiomas.rs). Si no se
+///     configuró ningún archivo de alias cada idioma se cuenta y se muestra con su código
+/// metadatos son los metadatos de los juegos (ver metadatos.rs) con los que se arman los rankings
+///     por género, desarrollador y año. Si es None no se hace el cruce
+/// calcular_distribuciones indica si se deben calcular los percentiles de votos y de largo de las
+///     reseñas por idioma y juego
+/// segmentar indica si se deben calcular las estadísticas de cada segmento de reseñas (ver
+///     segmento.rs) además de las generales
+/// desglosar_archivos indica si se deben calcular las estadísticas parciales de cada archivo csv
+///     (ver archivo.rs)
+/// colacion es la colación con la que se desempatan alfabéticamente los rankings (ver colacion.rs)
+/// saneamiento son las opciones con las que se sanea y se trunca el texto de las reseñas al
+///     leerlas (ver saneamiento.rs)
+/// muestreo son las opciones con las que se muestrean las reseñas (ver muestreo.rs). Si es None se
+///     analizan todas
+/// seguir_con_errores indica si los archivos que no se pueden leer se saltean en lugar de cortar
+///     el procesamiento
+/// progreso es la forma de informar el avance del procesamiento
+/// silencioso indica si no se informa la duración del programa
+/// ejecutor es la estrategia con la que se procesan los archivos en paralelo
+#[derive(Debug, Default, Clone)]
+pub struct Opciones {
+    pub analizar_terminos: bool,
+    pub detectar_idioma: bool,
+    pub reetiquetar_idioma: bool,
+    pub alias_idiomas: AliasIdiomas,
+    pub metadatos: Option<Metadatos>,
+    pub calcular_distribuciones: bool,
+    pub segmentar: bool,
+    pub desglosar_archivos: bool,
+    pub colacion: Colacion,
+    pub saneamiento: Saneamiento,
+    pub muestreo: Option<Muestreo>,
+    pub seguir_con_errores: bool,
+    pub progreso: ModoProgreso,
+    pub silencioso: bool,
+    pub ejecutor: EstrategiaEjecucion,
+}
+
+/// Opciones de la ejecución del procesamiento de los csv, del análisis y de serve
+/// seguir_con_errores indica si se saltean los archivos que no se pueden leer
+/// progreso es la forma de informar el avance del procesamiento
+/// silencioso indica si no se informa el progreso ni la duración del programa
+/// ejecutor es la estrategia con la que se procesan los archivos
+struct Ejecucion {
+    seguir_con_errores: bool,
+    progreso: ModoProgreso,
+    silencioso: bool,
+    ejecutor: EstrategiaEjecucion,
 }
 
-/// Función que se encarga de parsear los comandos ingresados
-/// n_threads debe ser un número natural >= 0
-/// En caso de que el archivo de salida no tenga la extensión .json, se la agrega antes de procesar
-pub fn parsear_argumentos() -> Option<Argumentos> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != ARGUMENTOS_ESPERADOS {
-        eprintln!("Cantidad de argumentos inválida");
-        return None;
-    }
-    let ruta = args[RUTA_DATASET].clone();
-    let n_threads = args[N_THREADS].parse::<usize>().unwrap_or(1);
-    let mut archivo_salida = args[ARCHIVO_SALIDA].clone();
-    if !archivo_salida.ends_with(".json") {
-        archivo_salida.push_str(".json");
-    }
-    Some(Argumentos {
-        ruta,
-        n_threads,
-        archivo_salida,
+/// Error al combinar la línea de comandos con el archivo de configuración
+/// Faltante indica un argumento obligatorio que no está en ninguno de los dos
+/// Invalido indica un valor inválido o un archivo de opciones que no se pudo cargar
+#[derive(Debug, PartialEq)]
+enum ErrorArgumentos {
+    Faltante(&'static str),
+    Invalido(String),
+}
+
+/// Función que se encarga de parsear los comandos ingresados. Si se pasó --config al comando, los
+/// valores que no estén en la línea de comandos se toman del archivo. Los errores de la línea de
+/// comandos (y --help) los informa clap y terminan el programa. Si falta un argumento obligatorio también
+/// se informa con el formato de clap; si no se pudo cargar algún archivo se devuelve el error
+pub fn parsear_argumentos() -> Result<Comando, ErrorApp> {
+    let cli = Cli::parse();
+    let config = match cli.archivo_config() {
+        Some(path) => cargar_config(path).map_err(ErrorApp::Configuracion)?,
+        None => Config::default(),
+    };
+    match combinar(cli, config) {
+        Ok(comando) => Ok(comando),
+        Err(ErrorArgumentos::Faltante(argumento)) => Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                format!(
+                    "falta {}, por línea de comandos o en el archivo de configuración",
+                    argumento
+                ),
+            )
+            .exit(),
+        Err(ErrorArgumentos::Invalido(e)) => Err(ErrorApp::Configuracion(e)),
+    }
+}
+
+/// Lee y parsea el archivo de configuración TOML
+fn cargar_config(path: &Path) -> Result<Config, String> {
+    let contenido = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    toml::from_str(&contenido).map_err(|e| format!("Error en {}: {}", path.display(), e))
+}
+
+/// Valida la cantidad de threads recibida por línea de comandos
+fn parsear_threads(valor: &str) -> Result<usize, String> {
+    match valor.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "la cantidad de threads debe ser un número mayor a 0, se recibió {}",
+            valor
+        )),
+    }
+}
+
+/// Parsea la fracción de reseñas a muestrear de la línea de comandos
+fn parsear_fraccion(valor: &str) -> Result<f64, String> {
+    validar_fraccion(valor.parse::<f64>().unwrap_or(f64::NAN), valor)
+}
+
+/// Verifica que la fracción de reseñas a muestrear sea mayor a 0 y hasta 1
+fn validar_fraccion(fraccion: f64, valor: &str) -> Result<f64, String> {
+    if fraccion > 0.0 && fraccion <= 1.0 {
+        Ok(fraccion)
+    } else {
+        Err(format!(
+            "la fracción a muestrear debe ser un número mayor a 0 y hasta 1, se recibió {}",
+            valor
+        ))
+    }
+}
+
+/// Devuelve el valor de la línea de comandos o, si no está, el del archivo de configuración
+fn requerido<T>(
+    cli: Option<T>,
+    config: Option<T>,
+    nombre: &'static str,
+) -> Result<T, ErrorArgumentos> {
+    cli.or(config).ok_or(ErrorArgumentos::Faltante(nombre))
+}
+
+/// Devuelve la cantidad de threads de la línea de comandos o del archivo de configuración,
+/// validando la del archivo igual que la de la línea de comandos
+fn threads(cli: Option<usize>, config: Option<usize>) -> Result<Option<usize>, ErrorArgumentos> {
+    match (cli, config) {
+        (Some(n), _) => Ok(Some(n)),
+        (None, Some(0)) => Err(ErrorArgumentos::Invalido(
+            "threads debe ser mayor a 0 en el archivo de configuración".to_string(),
+        )),
+        (None, n) => Ok(n),
+    }
+}
+
+/// Combina los argumentos de la línea de comandos con los del archivo de configuración, dándole
+/// prioridad a la línea de comandos, y arma el comando a ejecutar
+fn combinar(cli: Cli, config: Config) -> Result<Comando, ErrorArgumentos> {
+    let ejecucion = |flags: EjecucionCli| {
+        let silencioso = flags.silencioso || config.silencioso;
+        // --progress tiene prioridad sobre quiet en el archivo de configuración, y --quiet sobre
+        // progress
+        let progreso = flags
+            .progreso
+            .or(silencioso.then_some(ModoProgreso::Ninguno))
+            .or(config.progreso)
+            .unwrap_or_default();
+        Ejecucion {
+            seguir_con_errores: flags.seguir_con_errores || config.seguir_con_errores,
+            progreso,
+            silencioso,
+            ejecutor: flags.ejecutor.or(config.ejecutor).unwrap_or_default(),
+        }
+    };
+    // Los argumentos del análisis se aceptan antes del subcomando para que clap reconozca el
+    // subcomando después de ellos, así que se rechazan acá. Las opciones de los subcomandos, como
+    // --config, van después del subcomando
+    if cli.comando.is_some() && !cli.analisis.vacio() {
+        return Err(ErrorArgumentos::Invalido(
+            "los argumentos del análisis no se pueden combinar con un subcomando".to_string(),
+        ));
+    }
+    Ok(match cli.comando {
+        None => {
+            let analisis = cli.analisis;
+            let ejecucion = ejecucion(analisis.ejecucion);
+            Comando::Analizar(Argumentos {
+                ruta: requerido(analisis.ruta, config.ruta, "INPUT-PATH")?,
+                n_threads: requerido(
+                    threads(analisis.n_threads, config.n_threads)?,
+                    None,
+                    "NUM-THREADS",
+                )?,
+                archivo_salida: requerido(
+                    analisis.archivo_salida,
+                    config.archivo_salida,
+                    "OUTPUT-FILE-NAME",
+                )?,
+                opciones: armar_opciones(analisis.opciones, config.analisis, &ejecucion)?,
+                matriz: armar_matriz(analisis.matriz, config.matriz),
+                checkpoint: armar_checkpoint(
+                    analisis.checkpoint,
+                    config.checkpoint,
+                    ejecucion.ejecutor,
+                )?,
+            })
+        }
+        Some(SubcomandoCli::Indexar(indice)) => Comando::Indexar(ArgumentosIndice {
+            ruta: requerido(indice.ruta, config.ruta, "INPUT-PATH")?,
+            directorio_indice: requerido(
+                indice.directorio_indice,
+                config.directorio_indice,
+                "INDEX-DIR",
+            )?,
+            n_threads: threads(indice.n_threads, config.n_threads)?,
+            seguir_con_errores: indice.seguir_con_errores || config.seguir_con_errores,
+            silencioso: indice.silencioso || config.silencioso,
+        }),
+        Some(SubcomandoCli::Consultar(consulta)) => Comando::Consultar(ArgumentosConsulta {
+            directorio_indice: consulta.directorio_indice,
+            consulta: consulta.consulta,
+            filtros: FiltrosConsulta {
+                juego: consulta.juego.or(config.consulta.juego),
+                idioma: consulta.idioma.or(config.consulta.idioma),
+                votos_minimos: consulta
+                    .votos_minimos
+                    .or(config.consulta.votos_minimos)
+                    .unwrap_or(0),
+                limite: consulta
+                    .limite
+                    .or(config.consulta.limite)
+                    .unwrap_or(LIMITE_CONSULTA_DEFAULT),
+            },
+        }),
+        Some(SubcomandoCli::Servir(servidor)) => {
+            let ejecucion = ejecucion(servidor.ejecucion);
+            Comando::Servir(ArgumentosServidor {
+                ruta: requerido(servidor.ruta, config.ruta, "INPUT-PATH")?,
+                n_threads: requerido(
+                    threads(servidor.n_threads, config.n_threads)?,
+                    None,
+                    "NUM-THREADS",
+                )?,
+                puerto: servidor
+                    .puerto
+                    .or(config.servidor.puerto)
+                    .unwrap_or(PUERTO_DEFAULT),
+                opciones: armar_opciones(servidor.opciones, config.analisis, &ejecucion)?,
+            })
+        }
+        Some(SubcomandoCli::Comparar(comparar)) => Comando::Comparar(ArgumentosComparar {
+            archivo_a: comparar.archivo_a,
+            archivo_b: comparar.archivo_b,
+            ignorar_orden: comparar.ignorar_orden || config.comparar.ignorar_orden,
+        }),
+        Some(SubcomandoCli::Esquema) => Comando::Esquema,
+        Some(SubcomandoCli::Validar(validar)) => Comando::Validar(validar.archivo),
+    })
+}
+
+/// Combina los flags de la matriz con los del archivo de configuración. Si no se indicó el
+/// formato se usa JSON para los archivos .json y csv para el resto
+fn armar_matriz(cli: MatrizCli, config: ConfigMatriz) -> Option<ExportacionMatriz> {
+    let archivo = cli.archivo.or(config.archivo)?;
+    let formato =
+        cli.formato
+            .or(config.formato)
+            .unwrap_or_else(|| match Path::new(&archivo).extension() {
+                Some(extension) if extension.eq_ignore_ascii_case("json") => FormatoMatriz::Json,
+                _ => FormatoMatriz::Csv,
+            });
+    Some(ExportacionMatriz {
+        archivo,
+        formato,
+        max_juegos: cli.max_juegos.or(config.max_juegos),
+        max_idiomas: cli.max_idiomas.or(config.max_idiomas),
     })
 }
+
+/// Combina los flags de los checkpoints con los del archivo de configuración. Para reanudar hace
+/// falta indicar el archivo del checkpoint, y los checkpoints sólo se guardan con el ejecutor de
+/// rayon
+fn armar_checkpoint(
+    cli: CheckpointCli,
+    config: ConfigCheckpoint,
+    ejecutor: EstrategiaEjecucion,
+) -> Result<Option<OpcionesCheckpoint>, ErrorArgumentos> {
+    let Some(archivo) = cli.archivo.or(config.archivo) else {
+        if cli.reanudar {
+            return Err(ErrorArgumentos::Invalido(
+                "--resume necesita el archivo del checkpoint (--checkpoint)".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+    if ejecutor != EstrategiaEjecucion::Rayon {
+        return Err(ErrorArgumentos::Invalido(
+            "los checkpoints sólo se pueden usar con --executor rayon".to_string(),
+        ));
+    }
+    let segundos = cli
+        .intervalo
+        .or(config.intervalo)
+        .unwrap_or(INTERVALO_CHECKPOINT_DEFAULT);
+    Ok(Some(OpcionesCheckpoint {
+        archivo,
+        intervalo: Duration::from_secs(segundos),
+        reanudar: cli.reanudar,
+    }))
+}
+
+/// Combina los flags de análisis con los del archivo de configuración y arma las Opciones,
+/// cargando los archivos de alias y de metadatos si se indicaron
+/// No se puede estratificar por idioma reetiquetando los idiomas: el estrato de cada reseña se
+/// decide antes de parsearla con el idioma declarado, y la reseña se cuenta con el detectado
+fn armar_opciones(
+    cli: OpcionesCli,
+    config: ConfigAnalisis,
+    ejecucion: &Ejecucion,
+) -> Result<Opciones, ErrorArgumentos> {
+    let reetiquetar_idioma = cli.reetiquetar_idioma || config.reetiquetar_idioma;
+    let alias_idiomas = match cli.alias_idiomas.or(config.alias_idiomas) {
+        Some(path) => AliasIdiomas::cargar(&path).map_err(ErrorArgumentos::Invalido)?,
+        None => AliasIdiomas::default(),
+    };
+    let muestreo = match cli.fraccion_muestra.or(config.fraccion_muestra) {
+        Some(fraccion) => Some(Muestreo {
+            fraccion: validar_fraccion(fraccion, &fraccion.to_string())
+                .map_err(ErrorArgumentos::Invalido)?,
+            semilla: cli.semilla.or(config.semilla).unwrap_or(0),
+            estrato: cli.estrato.or(config.estrato),
+            minimo_estrato: cli
+                .minimo_estrato
+                .or(config.minimo_estrato)
+                .unwrap_or(MINIMO_ESTRATO_DEFAULT),
+            conteos: cli.conteos.or(config.conteos).unwrap_or_default(),
+        }),
+        None => None,
+    };
+    if reetiquetar_idioma
+        && muestreo.is_some_and(|muestreo| muestreo.estrato == Some(Estrato::Idioma))
+    {
+        return Err(ErrorArgumentos::Invalido(
+            "--stratify language no se puede combinar con --relabel-language".to_string(),
+        ));
+    }
+    let metadatos = match cli.metadatos.or(config.metadatos) {
+        Some(path) => Some(Metadatos::cargar(&path).map_err(ErrorArgumentos::Invalido)?),
+        None => None,
+    };
+    Ok(Opciones {
+        analizar_terminos: cli.terminos || config.terminos,
+        detectar_idioma: cli.detectar_idioma || config.detectar_idioma || reetiquetar_idioma,
+        reetiquetar_idioma,
+        alias_idiomas,
+        metadatos,
+        calcular_distribuciones: cli.distribuciones || config.distribuciones,
+        segmentar: cli.segmentos || config.segmentos,
+        desglosar_archivos: cli.por_archivo || config.por_archivo,
+        colacion: cli.colacion.or(config.colacion).unwrap_or_default(),
+        saneamiento: Saneamiento {
+            marcado: cli.marcado.or(config.marcado),
+            max_grafemas: cli.max_grafemas.or(config.max_grafemas),
+        },
+        muestreo,
+        seguir_con_errores: ejecucion.seguir_con_errores,
+        progreso: ejecucion.progreso,
+        silencioso: ejecucion.silencioso,
+        ejecutor: ejecucion.ejecutor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsear(args: &[&str], config: &str) -> Result<Comando, ErrorArgumentos> {
+        let cli = Cli::try_parse_from(std::iter::once("app").chain(args.iter().copied())).unwrap();
+        combinar(cli, toml::from_str(config).unwrap())
+    }
+
+    #[test]
+    fn definicion_de_la_linea_de_comandos() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn argumentos_posicionales_sin_subcomando() {
+        let Ok(Comando::Analizar(args)) = parsear(&["datos", "4", "salida", "--terms"], "") else {
+            panic!("Se esperaba el análisis");
+        };
+        assert_eq!(args.ruta, "datos");
+        assert_eq!(args.n_threads, 4);
+        assert_eq!(args.archivo_salida, "salida");
+        assert!(args.opciones.analizar_terminos);
+    }
+
+    #[test]
+    fn threads_invalidos() {
+        for threads in ["0", "-1", "cuatro"] {
+            assert!(Cli::try_parse_from(["app", "datos", threads, "salida.json"]).is_err());
+        }
+        assert_eq!(
+            parsear(&["datos"], "threads = 0\noutput = \"salida.json\"").err(),
+            Some(ErrorArgumentos::Invalido(
+                "threads debe ser mayor a 0 en el archivo de configuración".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn linea_de_comandos_tiene_prioridad_sobre_config() {
+        let config = r#"
+            input = "datos_config"
+            threads = 2
+            output = "config.json"
+
+            [analysis]
+            detect_language = true
+        "#;
+        let Ok(Comando::Analizar(args)) = parsear(&["datos", "8"], config) else {
+            panic!("Se esperaba el análisis");
+        };
+        assert_eq!(args.ruta, "datos");
+        assert_eq!(args.n_threads, 8);
+        assert_eq!(args.archivo_salida, "config.json");
+        assert!(args.opciones.detectar_idioma);
+    }
+
+    #[test]
+    fn exportacion_de_la_matriz() {
+        let Ok(Comando::Analizar(args)) = parsear(&["datos", "4", "salida"], "") else {
+            panic!("Se esperaba el análisis");
+        };
+        assert!(args.matriz.is_none());
+
+        let config = "[matrix]\noutput = \"matriz.json\"\ngames = 20\n";
+        let Ok(Comando::Analizar(args)) =
+            parsear(&["datos", "4", "salida", "--matrix-languages", "5"], config)
+        else {
+            panic!("Se esperaba el análisis");
+        };
+        let matriz = args.matriz.unwrap();
+        assert_eq!(matriz.formato, FormatoMatriz::Json);
+        assert_eq!(matriz.max_juegos, Some(20));
+        assert_eq!(matriz.max_idiomas, Some(5));
+
+        let Ok(Comando::Analizar(args)) = parsear(&["datos", "4", "salida", "--matrix", "m"], "")
+        else {
+            panic!("Se esperaba el análisis");
+        };
+        assert_eq!(args.matriz.unwrap().formato, FormatoMatriz::Csv);
+    }
+
+    #[test]
+    fn falta_argumento_obligatorio() {
+        assert_eq!(
+            parsear(&["datos", "4"], "").err(),
+            Some(ErrorArgumentos::Faltante("OUTPUT-FILE-NAME"))
+        );
+    }
+
+    #[test]
+    fn subcomandos_con_config() {
+        let config = "[query]\nlimit = 3\nlanguage = \"english\"\n\n[serve]\nport = 9000\n";
+        let Ok(Comando::Consultar(args)) =
+            parsear(&["query", "indice", "crash", "--limit", "5"], config)
+        else {
+            panic!("Se esperaba una consulta");
+        };
+        assert_eq!(args.filtros.limite, 5);
+        assert_eq!(args.filtros.idioma.as_deref(), Some("english"));
+
+        let Ok(Comando::Servir(args)) = parsear(&["serve", "datos", "2"], config) else {
+            panic!("Se esperaba el servidor");
+        };
+        assert_eq!(args.puerto, 9000);
+        assert!(!args.opciones.seguir_con_errores);
+        assert_eq!(args.opciones.progreso, ModoProgreso::Humano);
+
+        let Ok(Comando::Indexar(args)) =
+            parsear(&["index", "datos", "indice"], "keep_going = true")
+        else {
+            panic!("Se esperaba el índice");
+        };
+        assert!(args.seguir_con_errores);
+    }
+
+    #[test]
+    fn config_solo_en_los_comandos_que_la_usan() {
+        let archivo = |args: &[&str]| {
+            let cli = Cli::try_parse_from(std::iter::once("app").chain(args.iter().copied()));
+            cli.unwrap().archivo_config().map(Path::to_path_buf)
+        };
+        let config = Some(PathBuf::from("c.toml"));
+        assert_eq!(archivo(&["--config", "c.toml"]), config);
+        assert_eq!(archivo(&["index", "datos", "--config", "c.toml"]), config);
+        assert_eq!(
+            archivo(&["query", "i", "crash", "--config", "c.toml"]),
+            config
+        );
+        assert_eq!(archivo(&["serve", "--config", "c.toml"]), config);
+        assert_eq!(archivo(&["diff", "a", "b", "--config", "c.toml"]), config);
+        assert_eq!(archivo(&["validate", "salida.json"]), None);
+        for args in [
+            ["schema", "--config", "c.toml"].as_slice(),
+            &["validate", "salida.json", "--config", "c.toml"],
+        ] {
+            assert!(
+                Cli::try_parse_from(std::iter::once("app").chain(args.iter().copied())).is_err()
+            );
+        }
+        assert!(matches!(
+            parsear(&["--config", "c.toml", "diff", "a.json", "b.json"], ""),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+    }
+
+    #[test]
+    fn flags_del_analisis_antes_de_un_subcomando() {
+        for flag in ["--terms", "--keep-going"] {
+            assert!(matches!(
+                parsear(&[flag, "diff", "a.json", "b.json"], ""),
+                Err(ErrorArgumentos::Invalido(_))
+            ));
+        }
+        assert!(Cli::try_parse_from(["app", "diff", "a.json", "b.json", "--keep-going"]).is_err());
+    }
+
+    #[test]
+    fn seguir_con_errores() {
+        let Ok(Comando::Analizar(args)) = parsear(&["datos", "4", "salida", "--keep-going"], "")
+        else {
+            panic!("Se esperaba el análisis");
+        };
+        assert!(args.opciones.seguir_con_errores);
+        let Ok(Comando::Servir(args)) = parsear(&["serve", "datos", "2", "--keep-going"], "")
+        else {
+            panic!("Se esperaba el servidor");
+        };
+        assert!(args.opciones.seguir_con_errores);
+        let Ok(Comando::Indexar(args)) = parsear(&["index", "datos", "indice", "--keep-going"], "")
+        else {
+            panic!("Se esperaba el índice");
+        };
+        assert!(args.seguir_con_errores);
+    }
+
+    #[test]
+    fn checkpoint_y_reanudar() {
+        let Ok(Comando::Analizar(args)) = parsear(
+            &["datos", "4", "salida", "--resume"],
+            "[checkpoint]\nfile = \"estado.json\"\n",
+        ) else {
+            panic!("Se esperaba el análisis");
+        };
+        let checkpoint = args.checkpoint.unwrap();
+        assert_eq!(checkpoint.archivo, PathBuf::from("estado.json"));
+        assert_eq!(checkpoint.intervalo, Duration::from_secs(60));
+        assert!(checkpoint.reanudar);
+
+        assert!(matches!(
+            parsear(&["datos", "4", "salida", "--resume"], ""),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+    }
+
+    #[test]
+    fn modo_de_progreso() {
+        let modo = |args: &[&str], config| match parsear(args, config) {
+            Ok(Comando::Analizar(args)) => (args.opciones.progreso, args.opciones.silencioso),
+            _ => panic!("Se esperaba el análisis"),
+        };
+        let posicionales = ["datos", "4", "salida"];
+        assert_eq!(modo(&posicionales, ""), (ModoProgreso::Humano, false));
+        assert_eq!(
+            modo(&["--quiet", "datos", "4", "salida"], "progress = \"json\""),
+            (ModoProgreso::Ninguno, true)
+        );
+        assert_eq!(
+            modo(
+                &["--progress", "json", "datos", "4", "salida"],
+                "quiet = true"
+            ),
+            (ModoProgreso::Json, true)
+        );
+        assert!(Cli::try_parse_from(["app", "-q", "--progress", "json", "datos"]).is_err());
+        let Ok(Comando::Servir(args)) = parsear(&["serve", "datos", "2", "--progress", "json"], "")
+        else {
+            panic!("Se esperaba el servidor");
+        };
+        assert_eq!(args.opciones.progreso, ModoProgreso::Json);
+        let Ok(Comando::Indexar(args)) = parsear(&["index", "datos", "indice", "-q"], "") else {
+            panic!("Se esperaba el índice");
+        };
+        assert!(args.silencioso);
+        assert!(Cli::try_parse_from(["app", "index", "datos", "--progress", "json"]).is_err());
+        assert!(Cli::try_parse_from(["app", "diff", "a.json", "b.json", "-q"]).is_err());
+    }
+
+    #[test]
+    fn estrategia_de_ejecucion() {
+        let ejecutor = |args: &[&str], config| match parsear(args, config) {
+            Ok(Comando::Analizar(args)) => args.opciones.ejecutor,
+            _ => panic!("Se esperaba el análisis"),
+        };
+        assert_eq!(
+            ejecutor(&["datos", "4", "salida"], ""),
+            EstrategiaEjecucion::Rayon
+        );
+        assert_eq!(
+            ejecutor(&["datos", "4", "salida"], "executor = \"pipeline\""),
+            EstrategiaEjecucion::Pipeline
+        );
+        assert_eq!(
+            ejecutor(
+                &["--executor", "threads", "datos", "4", "salida"],
+                "executor = \"pipeline\""
+            ),
+            EstrategiaEjecucion::Threads
+        );
+        assert!(matches!(
+            parsear(
+                &["--executor", "threads", "datos", "4", "salida"],
+                "[checkpoint]\nfile = \"estado.json\"\n"
+            ),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+        let Ok(Comando::Servir(args)) =
+            parsear(&["serve", "datos", "2", "--executor", "pipeline"], "")
+        else {
+            panic!("Se esperaba el servidor");
+        };
+        assert_eq!(args.opciones.ejecutor, EstrategiaEjecucion::Pipeline);
+        for args in [
+            ["index", "datos", "--executor", "threads"].as_slice(),
+            &["query", "indice", "crash", "--executor", "threads"],
+        ] {
+            assert!(
+                Cli::try_parse_from(std::iter::once("app").chain(args.iter().copied())).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn colacion_de_desempate() {
+        let colacion = |args: &[&str], config| match parsear(args, config) {
+            Ok(Comando::Analizar(args)) => args.opciones.colacion,
+            _ => panic!("Se esperaba el análisis"),
+        };
+        assert_eq!(colacion(&["datos", "4", "salida"], ""), Colacion::Byte);
+        assert_eq!(
+            colacion(
+                &["datos", "4", "salida"],
+                "[analysis]\ncollation = \"case-insensitive\"\n"
+            ),
+            Colacion::SinMayusculas
+        );
+        assert_eq!(
+            colacion(
+                &["--collation", "root", "datos", "4", "salida"],
+                "[analysis]\ncollation = \"byte\"\n"
+            ),
+            Colacion::Raiz
+        );
+    }
+
+    #[test]
+    fn saneamiento_de_reviews() {
+        let saneamiento = |args: &[&str], config| match parsear(args, config) {
+            Ok(Comando::Analizar(args)) => args.opciones.saneamiento,
+            _ => panic!("Se esperaba el análisis"),
+        };
+        assert!(saneamiento(&["datos", "4", "salida"], "").es_nulo());
+        assert_eq!(
+            saneamiento(
+                &["--sanitize", "render", "datos", "4", "salida"],
+                "[analysis]\nsanitize = \"strip\"\ntruncate = 280\n"
+            ),
+            Saneamiento {
+                marcado: Some(Marcado::Renderizar),
+                max_grafemas: Some(280),
+            }
+        );
+    }
+
+    #[test]
+    fn muestreo_de_reviews() {
+        let muestreo = |args: &[&str], config| match parsear(args, config) {
+            Ok(Comando::Analizar(args)) => args.opciones.muestreo,
+            _ => panic!("Se esperaba el análisis"),
+        };
+        assert_eq!(
+            muestreo(&["datos", "4", "salida"], "[analysis]\nseed = 3\n"),
+            None
+        );
+        assert_eq!(
+            muestreo(
+                &[
+                    "--sample",
+                    "0.1",
+                    "--stratify",
+                    "game",
+                    "datos",
+                    "4",
+                    "salida"
+                ],
+                "[analysis]\nsample = 0.5\nseed = 3\nsample_counts = \"extrapolated\"\n"
+            ),
+            Some(Muestreo {
+                fraccion: 0.1,
+                semilla: 3,
+                estrato: Some(Estrato::Juego),
+                minimo_estrato: MINIMO_ESTRATO_DEFAULT,
+                conteos: Conteos::Extrapolados,
+            })
+        );
+        assert!(Cli::try_parse_from(["app", "--sample", "0", "datos"]).is_err());
+        assert!(matches!(
+            parsear(
+                &[
+                    "--sample",
+                    "0.1",
+                    "--stratify",
+                    "language",
+                    "--relabel-language",
+                    "datos",
+                    "4",
+                    "salida"
+                ],
+                ""
+            ),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+        assert!(matches!(
+            parsear(
+                &["--stratify", "language", "datos", "4", "salida"],
+                "[analysis]\nsample = 0.1\nrelabel_language = true\n"
+            ),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+        assert!(
+            muestreo(
+                &[
+                    "--stratify",
+                    "game",
+                    "--relabel-language",
+                    "datos",
+                    "4",
+                    "salida"
+                ],
+                "[analysis]\nsample = 0.1\n"
+            )
+            .is_some()
+        );
+        assert!(matches!(
+            parsear(&["datos", "4", "salida"], "[analysis]\nsample = 1.5\n"),
+            Err(ErrorArgumentos::Invalido(_))
+        ));
+    }
+
+    #[test]
+    fn config_con_claves_desconocidas() {
+        assert!(toml::from_str::<Config>("thread = 4").is_err());
+    }
+}
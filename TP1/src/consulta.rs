@@ -0,0 +1,292 @@
+use crate::indice::SegmentoIndexado;
+use crate::terminos;
+use serde::Serialize;
+use std::io;
+
+/// Operadores de la consulta. Se escriben en mayúscula para distinguirlos de las palabras buscadas
+const OPERADOR_Y: &str = "AND";
+const OPERADOR_O: &str = "OR";
+const OPERADOR_NO: &str = "NOT";
+
+/// Nodo del árbol de una consulta booleana
+/// Termino busca los documentos que contienen todos los tokens de una palabra de la consulta (una
+///     palabra como "crash-report" genera dos tokens)
+/// Todos representa una palabra que no genera tokens (por ejemplo una palabra vacía), y no filtra
+///     ningún documento
+#[derive(Debug, PartialEq)]
+pub enum Nodo {
+    Termino(Vec<String>),
+    Todos,
+    Y(Box<Nodo>, Box<Nodo>),
+    O(Box<Nodo>, Box<Nodo>),
+    No(Box<Nodo>),
+}
+
+/// Struct con los filtros que se aplican a los resultados de una consulta
+/// juego es el nombre exacto del juego, o None para no filtrar por juego
+/// idioma es el idioma exacto de la reseña, o None para no filtrar por idioma
+/// votos_minimos es la cantidad mínima de votes_helpful
+/// limite es la cantidad máxima de resultados
+#[derive(Debug, Clone)]
+pub struct FiltrosConsulta {
+    pub juego: Option<String>,
+    pub idioma: Option<String>,
+    pub votos_minimos: u32,
+    pub limite: usize,
+}
+
+/// Struct que representa una reseña encontrada por una consulta
+#[derive(Serialize)]
+pub struct ResultadoConsulta {
+    #[serde(rename = "game")]
+    pub juego: String,
+    #[serde(rename = "language")]
+    pub idioma: String,
+    #[serde(rename = "votes")]
+    pub votos: u32,
+    pub review: String,
+}
+
+/// Separa el texto de la consulta en palabras, operadores y paréntesis
+fn separar(consulta: &str) -> Vec<String> {
+    consulta
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parser recursivo descendente de consultas booleanas con la gramática:
+///     o := y (OR y)*
+///     y := no ([AND] no)*
+///     no := NOT no | '(' o ')' | palabra
+/// Dos palabras seguidas sin operador se toman como AND
+struct Parser<'a> {
+    partes: Vec<String>,
+    posicion: usize,
+    idioma: &'a str,
+}
+
+impl Parser<'_> {
+    fn actual(&self) -> Option<&str> {
+        self.partes.get(self.posicion).map(String::as_str)
+    }
+
+    fn o(&mut self) -> Result<Nodo, String> {
+        let mut nodo = self.y()?;
+        while self.actual() == Some(OPERADOR_O) {
+            self.posicion += 1;
+            nodo = Nodo::O(Box::new(nodo), Box::new(self.y()?));
+        }
+        Ok(nodo)
+    }
+
+    fn y(&mut self) -> Result<Nodo, String> {
+        let mut nodo = self.no()?;
+        while let Some(parte) = self.actual() {
+            if parte == OPERADOR_O || parte == ")" {
+                break;
+            }
+            if parte == OPERADOR_Y {
+                self.posicion += 1;
+            }
+            nodo = Nodo::Y(Box::new(nodo), Box::new(self.no()?));
+        }
+        Ok(nodo)
+    }
+
+    fn no(&mut self) -> Result<Nodo, String> {
+        let parte = self
+            .actual()
+            .ok_or("La consulta termina de forma inesperada")?
+            .to_string();
+        self.posicion += 1;
+        match parte.as_str() {
+            OPERADOR_NO => Ok(Nodo::No(Box::new(self.no()?))),
+            "(" => {
+                let nodo = self.o()?;
+                if self.actual() != Some(")") {
+                    return Err("Falta cerrar un paréntesis".to_string());
+                }
+                self.posicion += 1;
+                Ok(nodo)
+            }
+            ")" | OPERADOR_Y | OPERADOR_O => {
+                Err(format!("Se esperaba una palabra y se encontró {}", parte))
+            }
+            palabra => {
+                let tokens = terminos::tokenizar(palabra, self.idioma);
+                Ok(if tokens.is_empty() {
+                    Nodo::Todos
+                } else {
+                    Nodo::Termino(tokens)
+                })
+            }
+        }
+    }
+}
+
+/// Recibe el texto de una consulta y lo convierte en un árbol. Las palabras se tokenizan igual que
+/// al indexar, usando las palabras vacías del idioma recibido (o ninguna si es vacío). Devuelve un
+/// error si la consulta está mal formada
+pub fn parsear(consulta: &str, idioma: &str) -> Result<Nodo, String> {
+    let mut parser = Parser {
+        partes: separar(consulta),
+        posicion: 0,
+        idioma,
+    };
+    let nodo = parser.o()?;
+    if let Some(parte) = parser.actual() {
+        return Err(format!("Sobra {} al final de la consulta", parte));
+    }
+    Ok(nodo)
+}
+
+/// Intersección de dos listas ordenadas de ids
+fn interseccion(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (mut i, mut j, mut resultado) = (0, 0, Vec::new());
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                resultado.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    resultado
+}
+
+/// Unión de dos listas ordenadas de ids
+fn union(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut resultado: Vec<u32> = a.iter().chain(b).copied().collect();
+    resultado.sort_unstable();
+    resultado.dedup();
+    resultado
+}
+
+/// Complemento de una lista ordenada de ids respecto de todos los documentos del segmento
+fn complemento(a: &[u32], total: u32) -> Vec<u32> {
+    let mut i = 0;
+    (0..total)
+        .filter(|id| {
+            if a.get(i) == Some(id) {
+                i += 1;
+                false
+            } else {
+                true
+            }
+        })
+        .collect()
+}
+
+/// Evalúa el árbol de la consulta sobre un segmento y devuelve los ids ordenados de los
+/// documentos que la cumplen
+pub fn evaluar(nodo: &Nodo, segmento: &SegmentoIndexado) -> io::Result<Vec<u32>> {
+    let total = segmento.documentos.len() as u32;
+    Ok(match nodo {
+        Nodo::Todos => (0..total).collect(),
+        Nodo::Termino(tokens) => {
+            let mut resultado = segmento.postings(&tokens[0])?;
+            for token in &tokens[1..] {
+                resultado = interseccion(&resultado, &segmento.postings(token)?);
+            }
+            resultado
+        }
+        Nodo::Y(a, b) => interseccion(&evaluar(a, segmento)?, &evaluar(b, segmento)?),
+        Nodo::O(a, b) => union(&evaluar(a, segmento)?, &evaluar(b, segmento)?),
+        Nodo::No(a) => complemento(&evaluar(a, segmento)?, total),
+    })
+}
+
+/// Ejecuta una consulta sobre todos los segmentos del índice. Los documentos que la cumplen se
+/// filtran por juego, idioma y votos, se ordenan de mayor a menor por votes_helpful y se leen del
+/// csv original sólo los que entran en el límite
+pub fn consultar(
+    segmentos: &[SegmentoIndexado],
+    nodo: &Nodo,
+    filtros: &FiltrosConsulta,
+) -> io::Result<Vec<ResultadoConsulta>> {
+    let mut candidatos = Vec::new();
+    for (i, segmento) in segmentos.iter().enumerate() {
+        for id in evaluar(nodo, segmento)? {
+            let documento = &segmento.documentos[id as usize];
+            let juego = &segmento.juegos[documento.juego as usize];
+            let idioma = &segmento.idiomas[documento.idioma as usize];
+            if filtros.juego.as_ref().is_some_and(|j| j != juego)
+                || filtros.idioma.as_ref().is_some_and(|i| i != idioma)
+                || documento.votos < filtros.votos_minimos
+            {
+                continue;
+            }
+            candidatos.push((documento.votos, i, id));
+        }
+    }
+    candidatos.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| (a.1, a.2).cmp(&(b.1, b.2))));
+    candidatos.truncate(filtros.limite);
+
+    let mut resultados = Vec::new();
+    for (_, i, id) in candidatos {
+        let segmento = &segmentos[i];
+        let documento = &segmento.documentos[id as usize];
+        if let Some(review) = segmento.leer_review(documento)? {
+            resultados.push(ResultadoConsulta {
+                juego: review.app_name,
+                idioma: segmento.idiomas[documento.idioma as usize].clone(),
+                votos: documento.votos,
+                review: review.review,
+            });
+        }
+    }
+    Ok(resultados)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn termino(palabra: &str) -> Box<Nodo> {
+        Box::new(Nodo::Termino(vec![palabra.to_string()]))
+    }
+
+    #[test]
+    fn parsear_consulta_con_precedencia() {
+        let nodo = parsear("crash OR bug AND NOT lag", "").unwrap();
+        assert_eq!(
+            nodo,
+            Nodo::O(
+                termino("crash"),
+                Box::new(Nodo::Y(termino("bug"), Box::new(Nodo::No(termino("lag")))))
+            )
+        );
+    }
+
+    #[test]
+    fn parsear_consulta_con_parentesis_y_and_implicito() {
+        let nodo = parsear("(crash OR bug) servers", "").unwrap();
+        assert_eq!(
+            nodo,
+            Nodo::Y(
+                Box::new(Nodo::O(termino("crash"), termino("bug"))),
+                termino("servers")
+            )
+        );
+    }
+
+    #[test]
+    fn parsear_consultas_invalidas() {
+        assert!(parsear("(crash OR bug", "").is_err());
+        assert!(parsear("crash OR", "").is_err());
+        assert!(parsear("crash )", "").is_err());
+    }
+
+    #[test]
+    fn operaciones_sobre_listas() {
+        assert_eq!(interseccion(&[1, 3, 5, 7], &[3, 4, 7]), vec![3, 7]);
+        assert_eq!(union(&[1, 5], &[2, 5, 9]), vec![1, 2, 5, 9]);
+        assert_eq!(complemento(&[0, 2], 5), vec![1, 3, 4]);
+    }
+}
@@ -0,0 +1,33 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Struct que representa la sección de validación de idiomas del archivo de salida
+/// reetiquetado indica si las reseñas con idioma erróneo se reetiquetaron con el idioma detectado
+/// no_coincidentes es la cantidad de reseñas cuyo idioma declarado no coincide con el detectado
+/// reetiquetadas es la cantidad de reseñas a las que se les cambió el idioma
+/// matriz es la matriz de confusión entre idioma declarado y detectado, una fila por idioma
+///     declarado
+#[derive(Serialize, JsonSchema)]
+pub struct SeccionDeteccion {
+    #[serde(rename = "relabeled")]
+    pub reetiquetado: bool,
+    #[serde(rename = "mismatches")]
+    pub no_coincidentes: u32,
+    #[serde(rename = "relabeled_count")]
+    pub reetiquetadas: u32,
+    #[serde(rename = "confusion_matrix")]
+    pub matriz: Vec<FilaConfusion>,
+}
+
+/// Struct que representa una fila de la matriz de confusión
+/// declarado es el idioma declarado en el csv
+/// detectados es un mapa con la cantidad de reseñas detectadas en cada idioma. Las reseñas cuyo
+///     idioma no se pudo determinar aparecen como "unknown"
+#[derive(Serialize, JsonSchema)]
+pub struct FilaConfusion {
+    #[serde(rename = "declared")]
+    pub declarado: String,
+    #[serde(rename = "detected")]
+    pub detectados: BTreeMap<String, u32>,
+}
@@ -0,0 +1,436 @@
+use crate::review::Review;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+
+/// Cantidad de n-gramas que forman un perfil, tanto de un idioma como de un texto a clasificar
+const LARGO_PERFIL: usize = 300;
+/// Largo máximo de los n-gramas que se toman de cada palabra
+const MAXIMO_N: usize = 3;
+/// Cantidad mínima de letras que tiene que tener un texto para intentar detectar su idioma. Con
+/// textos más cortos (por ejemplo "10/10" o "gg") la detección no es confiable
+const MINIMO_LETRAS: usize = 20;
+/// Cantidad máxima de caracteres de la reseña que se usan para la detección
+const MAXIMO_CARACTERES: usize = 1000;
+/// Proporción mínima de kana sobre el total de letras para considerar un texto como japonés
+const PROPORCION_KANA_JAPONES: f64 = 0.1;
+/// Idioma detectado que se usa en la matriz de confusión cuando no se pudo determinar el idioma
+pub const IDIOMA_DESCONOCIDO: &str = "unknown";
+
+/// Escrituras que se distinguen antes de comparar perfiles. Un texto sólo se compara contra los
+/// perfiles de idiomas que usan su misma escritura
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Escritura {
+    Latina,
+    Cirilica,
+    Han,
+    Kana,
+    Hangul,
+    Tailandesa,
+}
+
+/// Perfiles de n-gramas incluidos en el repositorio (ver la carpeta perfiles). Cada archivo tiene
+/// un n-grama por línea, ordenados de mayor a menor frecuencia, y se generaron con
+/// `perfil_texto` a partir de un texto de muestra en cada idioma
+const PERFILES: &[(&str, Escritura, &str)] = &[
+    (
+        "english",
+        Escritura::Latina,
+        include_str!("../perfiles/english.txt"),
+    ),
+    (
+        "spanish",
+        Escritura::Latina,
+        include_str!("../perfiles/spanish.txt"),
+    ),
+    (
+        "brazilian",
+        Escritura::Latina,
+        include_str!("../perfiles/brazilian.txt"),
+    ),
+    (
+        "french",
+        Escritura::Latina,
+        include_str!("../perfiles/french.txt"),
+    ),
+    (
+        "german",
+        Escritura::Latina,
+        include_str!("../perfiles/german.txt"),
+    ),
+    (
+        "italian",
+        Escritura::Latina,
+        include_str!("../perfiles/italian.txt"),
+    ),
+    (
+        "polish",
+        Escritura::Latina,
+        include_str!("../perfiles/polish.txt"),
+    ),
+    (
+        "turkish",
+        Escritura::Latina,
+        include_str!("../perfiles/turkish.txt"),
+    ),
+    (
+        "russian",
+        Escritura::Cirilica,
+        include_str!("../perfiles/russian.txt"),
+    ),
+    (
+        "ukrainian",
+        Escritura::Cirilica,
+        include_str!("../perfiles/ukrainian.txt"),
+    ),
+    (
+        "schinese",
+        Escritura::Han,
+        include_str!("../perfiles/schinese.txt"),
+    ),
+    (
+        "tchinese",
+        Escritura::Han,
+        include_str!("../perfiles/tchinese.txt"),
+    ),
+];
+
+/// Idiomas que se detectan sólo por su escritura, sin necesidad de un perfil
+const IDIOMAS_POR_ESCRITURA: &[(&str, Escritura)] = &[
+    ("japanese", Escritura::Kana),
+    ("koreana", Escritura::Hangul),
+    ("thai", Escritura::Tailandesa),
+];
+
+/// Perfil de un idioma ya cargado: para cada n-grama se guarda su posición en el ranking
+struct PerfilIdioma {
+    idioma: &'static str,
+    escritura: Escritura,
+    posiciones: HashMap<&'static str, usize>,
+}
+
+/// Devuelve los perfiles de los idiomas, parseándolos la primera vez que se los necesita
+fn perfiles() -> &'static [PerfilIdioma] {
+    static CARGADOS: OnceLock<Vec<PerfilIdioma>> = OnceLock::new();
+    CARGADOS.get_or_init(|| {
+        PERFILES
+            .iter()
+            .map(|(idioma, escritura, contenido)| PerfilIdioma {
+                idioma,
+                escritura: *escritura,
+                posiciones: contenido
+                    .lines()
+                    .filter(|linea| !linea.is_empty())
+                    .enumerate()
+                    .map(|(posicion, ngrama)| (ngrama, posicion))
+                    .collect(),
+            })
+            .collect()
+    })
+}
+
+/// Devuelve la escritura de un caracter, o None si no es una letra de las escrituras conocidas
+fn escritura_caracter(c: char) -> Option<Escritura> {
+    match c {
+        '\u{3040}'..='\u{30FF}' => Some(Escritura::Kana),
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => {
+            Some(Escritura::Han)
+        }
+        '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+            Some(Escritura::Hangul)
+        }
+        '\u{0E00}'..='\u{0E7F}' => Some(Escritura::Tailandesa),
+        '\u{0400}'..='\u{04FF}' => Some(Escritura::Cirilica),
+        c if c.is_alphabetic() && (c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c)) => {
+            Some(Escritura::Latina)
+        }
+        _ => None,
+    }
+}
+
+/// Recibe un texto y devuelve su escritura predominante junto con la cantidad de letras que tiene.
+/// Si el texto tiene una proporción suficiente de kana se lo considera japonés aunque tenga más
+/// ideogramas que kana
+fn escritura_predominante(texto: &str) -> Option<(Escritura, usize)> {
+    let mut conteo: BTreeMap<Escritura, usize> = BTreeMap::new();
+    for c in texto.chars() {
+        if let Some(escritura) = escritura_caracter(c) {
+            *conteo.entry(escritura).or_insert(0) += 1;
+        }
+    }
+    let letras: usize = conteo.values().sum();
+    let kana = conteo.get(&Escritura::Kana).copied().unwrap_or(0);
+    if letras > 0 && kana as f64 / letras as f64 >= PROPORCION_KANA_JAPONES {
+        return Some((Escritura::Kana, letras));
+    }
+    conteo
+        .into_iter()
+        .max_by_key(|(_, cantidad)| *cantidad)
+        .map(|(escritura, _)| (escritura, letras))
+}
+
+/// Recibe un texto y devuelve su perfil: los n-gramas de caracteres (de 1 a 3) más frecuentes,
+/// ordenados de mayor a menor frecuencia y desempatados alfabéticamente. Cada palabra se rodea
+/// con '_' para que los n-gramas de principio y fin de palabra se distingan del resto
+pub fn perfil_texto(texto: &str, largo: usize) -> Vec<String> {
+    let normalizado = texto.nfkc().collect::<String>().to_lowercase();
+    let mut frecuencias: HashMap<String, usize> = HashMap::new();
+
+    for palabra in normalizado
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|p| !p.is_empty())
+    {
+        let caracteres: Vec<char> = std::iter::once('_')
+            .chain(palabra.chars())
+            .chain(std::iter::once('_'))
+            .collect();
+        for n in 1..=MAXIMO_N {
+            for ventana in caracteres.windows(n) {
+                if n == 1 && ventana[0] == '_' {
+                    continue;
+                }
+                *frecuencias.entry(ventana.iter().collect()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ngramas: Vec<(String, usize)> = frecuencias.into_iter().collect();
+    ngramas.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ngramas.truncate(largo);
+    ngramas.into_iter().map(|(ngrama, _)| ngrama).collect()
+}
+
+/// Calcula la distancia "out of place" entre el perfil de un texto y el de un idioma: para cada
+/// n-grama del texto se suma la diferencia entre su posición en ambos perfiles, o el largo del
+/// perfil si el idioma no lo tiene
+fn distancia(perfil: &[String], idioma: &PerfilIdioma) -> usize {
+    perfil
+        .iter()
+        .enumerate()
+        .map(
+            |(posicion, ngrama)| match idioma.posiciones.get(ngrama.as_str()) {
+                Some(posicion_idioma) => posicion.abs_diff(*posicion_idioma),
+                None => LARGO_PERFIL,
+            },
+        )
+        .sum()
+}
+
+/// Recibe el texto de una reseña y devuelve el idioma detectado, o None si el texto es muy corto
+/// o está escrito en una escritura que no se puede clasificar.
+/// Primero se determina la escritura predominante: japonés, coreano y tailandés se detectan
+/// directamente por su escritura, y para el resto se elige el perfil más cercano entre los idiomas
+/// que comparten la escritura del texto
+pub fn detectar(texto: &str) -> Option<&'static str> {
+    let texto: String = texto.chars().take(MAXIMO_CARACTERES).collect();
+    let (escritura, letras) = escritura_predominante(&texto)?;
+    if letras < MINIMO_LETRAS {
+        return None;
+    }
+    if let Some((idioma, _)) = IDIOMAS_POR_ESCRITURA.iter().find(|(_, e)| *e == escritura) {
+        return Some(idioma);
+    }
+
+    let perfil = perfil_texto(&texto, LARGO_PERFIL);
+    perfiles()
+        .iter()
+        .filter(|p| p.escritura == escritura)
+        .min_by_key(|p| distancia(&perfil, p))
+        .map(|p| p.idioma)
+}
+
+/// Normaliza los códigos de idioma de Steam que corresponden a un mismo idioma, para que por
+/// ejemplo una reseña declarada como "latam" y detectada como "spanish" no se marque como errónea
+fn idioma_base(idioma: &str) -> &str {
+    match idioma {
+        "latam" => "spanish",
+        "portuguese" => "brazilian",
+        otro => otro,
+    }
+}
+
+/// Indica si el detector es capaz de reconocer un idioma. Sólo se marcan como erróneas las
+/// reseñas cuyo idioma declarado se puede detectar, ya que si no cualquier idioma sin perfil
+/// (por ejemplo "czech") se confundiría con el más parecido que sí tenga
+fn es_detectable(idioma: &str) -> bool {
+    let base = idioma_base(idioma);
+    PERFILES.iter().any(|(i, _, _)| *i == base)
+        || IDIOMAS_POR_ESCRITURA.iter().any(|(i, _)| *i == base)
+}
+
+/// Struct que almacena los resultados de la validación de idiomas
+/// matriz es la matriz de confusión: para cada idioma declarado, cuántas reseñas se detectaron en
+///     cada idioma
+/// no_coincidentes es la cantidad de reseñas cuyo idioma declarado no coincide con el detectado
+/// reetiquetadas es la cantidad de reseñas a las que se les cambió el idioma por el detectado
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DeteccionIdiomas {
+    pub matriz: BTreeMap<String, BTreeMap<String, usize>>,
+    pub no_coincidentes: usize,
+    pub reetiquetadas: usize,
+}
+
+/// Resultado de la validación del idioma de una reseña
+/// declarado es el idioma con el que vino la reseña
+/// detectado es el idioma detectado, o None si no se pudo determinar
+/// no_coincide indica si el idioma detectado no coincide con el declarado
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validacion {
+    pub declarado: String,
+    pub detectado: Option<&'static str>,
+    pub no_coincide: bool,
+}
+
+impl DeteccionIdiomas {
+    /// Detecta el idioma de la review y lo registra en la matriz de confusión. Si el idioma
+    /// detectado no coincide con el declarado se marca la reseña, y si reetiquetar es true se le
+    /// asigna el idioma detectado antes de agregarla a las estadísticas
+    /// Devuelve el resultado de la validación, para registrarlo también en otras instancias sin
+    /// volver a detectar el idioma (ver registrar)
+    pub fn validar_review(&mut self, review: &mut Review, reetiquetar: bool) -> Validacion {
+        let detectado = detectar(&review.review);
+        let validacion = Validacion {
+            no_coincide: detectado.is_some_and(|detectado| {
+                es_detectable(&review.language) && idioma_base(&review.language) != detectado
+            }),
+            declarado: review.language.clone(),
+            detectado,
+        };
+        self.registrar(&validacion, reetiquetar);
+        if reetiquetar
+            && validacion.no_coincide
+            && let Some(detectado) = validacion.detectado
+        {
+            review.language = detectado.to_string();
+        }
+        validacion
+    }
+
+    /// Suma una validación ya hecha a la matriz de confusión y a las cantidades de reseñas no
+    /// coincidentes y reetiquetadas
+    pub fn registrar(&mut self, validacion: &Validacion, reetiquetar: bool) {
+        *self
+            .matriz
+            .entry(validacion.declarado.clone())
+            .or_default()
+            .entry(
+                validacion
+                    .detectado
+                    .unwrap_or(IDIOMA_DESCONOCIDO)
+                    .to_string(),
+            )
+            .or_insert(0) += 1;
+        if validacion.no_coincide {
+            self.no_coincidentes += 1;
+            if reetiquetar {
+                self.reetiquetadas += 1;
+            }
+        }
+    }
+
+    /// Combina los resultados de otra instancia con los actuales, sumando las cantidades
+    pub fn combinar(&mut self, otra: DeteccionIdiomas) {
+        for (declarado, detectados) in otra.matriz {
+            let fila = self.matriz.entry(declarado).or_default();
+            for (detectado, cantidad) in detectados {
+                *fila.entry(detectado).or_insert(0) += cantidad;
+            }
+        }
+        self.no_coincidentes += otra.no_coincidentes;
+        self.reetiquetadas += otra.reetiquetadas;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(language: &str, texto: &str) -> Review {
+        Review {
+            app_id: 1,
+            app_name: "FIFA".to_string(),
+            language: language.to_string(),
+            review: texto.to_string(),
+            votes_helpful: 0,
+            truncada: false,
+        }
+    }
+
+    #[test]
+    fn detectar_idiomas_con_perfil() {
+        assert_eq!(
+            detectar("The game is really fun to play with friends, but the servers are bad"),
+            Some("english")
+        );
+        assert_eq!(
+            detectar(
+                "El juego es muy divertido para jugar con amigos, pero los servidores son malos"
+            ),
+            Some("spanish")
+        );
+        assert_eq!(
+            detectar("Игра очень интересная, но сервера постоянно падают"),
+            Some("russian")
+        );
+        assert_eq!(
+            detectar("这个游戏真的很好玩，画面也很漂亮，推荐大家购买"),
+            Some("schinese")
+        );
+    }
+
+    #[test]
+    fn detectar_idiomas_por_escritura() {
+        assert_eq!(
+            detectar("このゲームはとても面白いです。友達と一緒に遊びました"),
+            Some("japanese")
+        );
+        assert_eq!(
+            detectar("이 게임은 정말 재미있어요. 친구들과 같이 하면 최고입니다"),
+            Some("koreana")
+        );
+    }
+
+    #[test]
+    fn textos_cortos_no_se_detectan() {
+        assert_eq!(detectar("10/10"), None);
+        assert_eq!(detectar("good game"), None);
+    }
+
+    #[test]
+    fn validar_review_reetiqueta_si_no_coincide() {
+        let mut deteccion = DeteccionIdiomas::default();
+        let mut r = review(
+            "schinese",
+            "The game is really fun to play with friends, but the servers are bad",
+        );
+
+        deteccion.validar_review(&mut r, true);
+
+        assert_eq!(r.language, "english");
+        assert_eq!(deteccion.no_coincidentes, 1);
+        assert_eq!(deteccion.reetiquetadas, 1);
+        assert_eq!(deteccion.matriz["schinese"]["english"], 1);
+    }
+
+    #[test]
+    fn validar_review_sin_reetiquetar_y_alias() {
+        let mut deteccion = DeteccionIdiomas::default();
+        let mut r1 = review(
+            "latam",
+            "El juego es muy divertido para jugar con amigos, pero los servidores son malos",
+        );
+        let mut r2 = review(
+            "russian",
+            "The game is really fun to play with friends, but the servers are bad",
+        );
+
+        deteccion.validar_review(&mut r1, false);
+        deteccion.validar_review(&mut r2, false);
+
+        // latam y spanish son el mismo idioma, así que sólo la segunda se marca
+        assert_eq!(deteccion.no_coincidentes, 1);
+        assert_eq!(deteccion.reetiquetadas, 0);
+        assert_eq!(r2.language, "russian");
+    }
+}
@@ -0,0 +1,493 @@
+use crate::idioma_output::{IdiomaMasVotado, ReviewIdioma};
+use crate::juego_output::{IdiomaPorJuego, JuegoMasVotado};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Cantidad máxima de caracteres de una reseña que se muestran al informar una diferencia
+const LARGO_MAXIMO_REVIEW: usize = 60;
+
+/// Struct con las partes de un archivo de salida que se comparan en detalle. El resto de las
+/// secciones se guardan sin tipar en otras y se comparan enteras
+#[derive(Deserialize)]
+struct Salida {
+    #[serde(rename = "top_games")]
+    juegos: Vec<JuegoMasVotado>,
+    #[serde(rename = "top_languages")]
+    idiomas: Vec<IdiomaMasVotado>,
+    #[serde(flatten)]
+    otras: Map<String, Value>,
+}
+
+/// Diferencia encontrada entre dos archivos de salida. entidad describe dónde está la diferencia,
+/// por ejemplo `juego "Terraria"` o `idioma "english" de juego "Terraria"`
+/// Las posiciones empiezan en 1, igual que un ranking
+#[derive(Debug, PartialEq)]
+pub enum Diferencia {
+    Agregado {
+        entidad: String,
+        posicion: usize,
+    },
+    Eliminado {
+        entidad: String,
+        posicion: usize,
+    },
+    Posicion {
+        entidad: String,
+        antes: usize,
+        despues: usize,
+    },
+    Cantidad {
+        entidad: String,
+        antes: u32,
+        despues: u32,
+    },
+    TopReview {
+        entidad: String,
+        antes: (String, u32),
+        despues: (String, u32),
+    },
+    ReviewAgregada {
+        entidad: String,
+        review: (String, u32),
+    },
+    ReviewEliminada {
+        entidad: String,
+        review: (String, u32),
+    },
+    OrdenReviews {
+        entidad: String,
+    },
+    Seccion {
+        nombre: String,
+        antes: bool,
+        despues: bool,
+    },
+}
+
+/// Recorta el texto de una reseña para mostrarlo en una sola línea
+fn resumir((texto, votos): &(String, u32)) -> String {
+    let mut resumen: String = texto
+        .chars()
+        .take(LARGO_MAXIMO_REVIEW)
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if texto.chars().count() > LARGO_MAXIMO_REVIEW {
+        resumen.push('…');
+    }
+    format!("\"{}\" ({} votos)", resumen, votos)
+}
+
+impl fmt::Display for Diferencia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diferencia::Agregado { entidad, posicion } => {
+                write!(f, "+ {} agregado en la posición {}", entidad, posicion)
+            }
+            Diferencia::Eliminado { entidad, posicion } => {
+                write!(f, "- {} eliminado de la posición {}", entidad, posicion)
+            }
+            Diferencia::Posicion {
+                entidad,
+                antes,
+                despues,
+            } => write!(
+                f,
+                "~ {} pasa de la posición {} a la {}",
+                entidad, antes, despues
+            ),
+            Diferencia::Cantidad {
+                entidad,
+                antes,
+                despues,
+            } => write!(
+                f,
+                "~ {}: {} -> {} reseñas ({:+})",
+                entidad,
+                antes,
+                despues,
+                *despues as i64 - *antes as i64
+            ),
+            Diferencia::TopReview {
+                entidad,
+                antes,
+                despues,
+            } => write!(
+                f,
+                "~ {}: top review {} -> {}",
+                entidad,
+                resumir(antes),
+                resumir(despues)
+            ),
+            Diferencia::ReviewAgregada { entidad, review } => {
+                write!(f, "+ {}: review {}", entidad, resumir(review))
+            }
+            Diferencia::ReviewEliminada { entidad, review } => {
+                write!(f, "- {}: review {}", entidad, resumir(review))
+            }
+            Diferencia::OrdenReviews { entidad } => {
+                write!(f, "~ {}: mismas reviews en distinto orden", entidad)
+            }
+            Diferencia::Seccion {
+                nombre,
+                antes,
+                despues,
+            } => match (antes, despues) {
+                (false, _) => write!(f, "+ sección {} agregada", nombre),
+                (_, false) => write!(f, "- sección {} eliminada", nombre),
+                _ => write!(f, "~ sección {} distinta", nombre),
+            },
+        }
+    }
+}
+
+/// Compara dos rankings cuyos elementos se identifican por nombre. Informa los elementos
+/// agregados y eliminados y, salvo que se ignore el orden, los que cambiaron de posición. Para los
+/// elementos que están en ambos rankings llama a comparar_elemento
+fn comparar_rankings<T>(
+    a: &[T],
+    b: &[T],
+    nombre: impl Fn(&T) -> &str,
+    entidad: impl Fn(&str) -> String,
+    ignorar_orden: bool,
+    diferencias: &mut Vec<Diferencia>,
+    mut comparar_elemento: impl FnMut(&str, &T, &T, &mut Vec<Diferencia>),
+) {
+    for (i, elemento_a) in a.iter().enumerate() {
+        let nombre_a = nombre(elemento_a);
+        match b
+            .iter()
+            .position(|elemento_b| nombre(elemento_b) == nombre_a)
+        {
+            None => diferencias.push(Diferencia::Eliminado {
+                entidad: entidad(nombre_a),
+                posicion: i + 1,
+            }),
+            Some(j) => {
+                if i != j && !ignorar_orden {
+                    diferencias.push(Diferencia::Posicion {
+                        entidad: entidad(nombre_a),
+                        antes: i + 1,
+                        despues: j + 1,
+                    });
+                }
+                comparar_elemento(nombre_a, elemento_a, &b[j], diferencias);
+            }
+        }
+    }
+    for (j, elemento_b) in b.iter().enumerate() {
+        let nombre_b = nombre(elemento_b);
+        if !a.iter().any(|elemento_a| nombre(elemento_a) == nombre_b) {
+            diferencias.push(Diferencia::Agregado {
+                entidad: entidad(nombre_b),
+                posicion: j + 1,
+            });
+        }
+    }
+}
+
+/// Agrega la diferencia de cantidad de reseñas si las cantidades no son iguales
+fn comparar_cantidad(entidad: String, antes: u32, despues: u32, diferencias: &mut Vec<Diferencia>) {
+    if antes != despues {
+        diferencias.push(Diferencia::Cantidad {
+            entidad,
+            antes,
+            despues,
+        });
+    }
+}
+
+/// Compara un idioma de un juego: su cantidad de reseñas y su reseña más votada
+fn comparar_idioma_juego(
+    entidad: String,
+    a: &IdiomaPorJuego,
+    b: &IdiomaPorJuego,
+    diferencias: &mut Vec<Diferencia>,
+) {
+    comparar_cantidad(entidad.clone(), a.reviews, b.reviews, diferencias);
+    if a.top_review != b.top_review || a.top_review_votos != b.top_review_votos {
+        diferencias.push(Diferencia::TopReview {
+            entidad,
+            antes: (a.top_review.clone(), a.top_review_votos),
+            despues: (b.top_review.clone(), b.top_review_votos),
+        });
+    }
+}
+
+/// Compara las reseñas más votadas de un idioma como multiconjuntos: informa las que sólo están en
+/// una de las dos salidas y, si son las mismas en distinto orden y no se ignora el orden, lo
+/// informa también
+fn comparar_reviews(
+    entidad: &str,
+    a: &[ReviewIdioma],
+    b: &[ReviewIdioma],
+    ignorar_orden: bool,
+    diferencias: &mut Vec<Diferencia>,
+) {
+    let como_tupla = |r: &ReviewIdioma| (r.review.clone(), r.votos);
+    let mut restantes_b: Vec<(String, u32)> = b.iter().map(como_tupla).collect();
+    for review in a.iter().map(como_tupla) {
+        match restantes_b.iter().position(|r| *r == review) {
+            Some(j) => {
+                restantes_b.remove(j);
+            }
+            None => diferencias.push(Diferencia::ReviewEliminada {
+                entidad: entidad.to_string(),
+                review,
+            }),
+        }
+    }
+    let hay_diferencias = !restantes_b.is_empty();
+    for review in restantes_b {
+        diferencias.push(Diferencia::ReviewAgregada {
+            entidad: entidad.to_string(),
+            review,
+        });
+    }
+    let mismo_orden = a.iter().map(como_tupla).eq(b.iter().map(como_tupla));
+    if !ignorar_orden && !hay_diferencias && a.len() == b.len() && !mismo_orden {
+        diferencias.push(Diferencia::OrdenReviews {
+            entidad: entidad.to_string(),
+        });
+    }
+}
+
+/// Compara las secciones que no son top_games ni top_languages (padron, terms, etc.) enteras. Como
+/// los objetos JSON se comparan sin importar el orden de sus keys, sólo se informa si la sección
+/// se agregó, se eliminó o su contenido es distinto
+fn comparar_secciones(a: &Map<String, Value>, b: &Map<String, Value>) -> Vec<Diferencia> {
+    let mut nombres: Vec<&String> = a.keys().chain(b.keys()).collect();
+    nombres.sort();
+    nombres.dedup();
+    nombres
+        .into_iter()
+        .filter(|nombre| a.get(*nombre) != b.get(*nombre))
+        .map(|nombre| Diferencia::Seccion {
+            nombre: nombre.clone(),
+            antes: a.contains_key(nombre),
+            despues: b.contains_key(nombre),
+        })
+        .collect()
+}
+
+/// Compara dos salidas ya parseadas y devuelve todas las diferencias. Si ignorar_orden es true,
+/// dos salidas con los mismos elementos en distinto orden se consideran iguales
+fn comparar_salidas(a: &Salida, b: &Salida, ignorar_orden: bool) -> Vec<Diferencia> {
+    let mut diferencias = comparar_secciones(&a.otras, &b.otras);
+
+    comparar_rankings(
+        &a.juegos,
+        &b.juegos,
+        |juego| &juego.nombre,
+        |nombre| format!("juego \"{}\"", nombre),
+        ignorar_orden,
+        &mut diferencias,
+        |nombre_juego, juego_a, juego_b, diferencias| {
+            let entidad_juego = format!("juego \"{}\"", nombre_juego);
+            comparar_cantidad(
+                entidad_juego.clone(),
+                juego_a.reviews,
+                juego_b.reviews,
+                diferencias,
+            );
+            let entidad_idioma =
+                |idioma: &str| format!("idioma \"{}\" de {}", idioma, entidad_juego);
+            comparar_rankings(
+                &juego_a.idiomas,
+                &juego_b.idiomas,
+                |idioma| &idioma.idioma,
+                entidad_idioma,
+                ignorar_orden,
+                diferencias,
+                |idioma, idioma_a, idioma_b, diferencias| {
+                    comparar_idioma_juego(entidad_idioma(idioma), idioma_a, idioma_b, diferencias)
+                },
+            );
+        },
+    );
+
+    comparar_rankings(
+        &a.idiomas,
+        &b.idiomas,
+        |idioma| &idioma.idioma,
+        |nombre| format!("idioma \"{}\"", nombre),
+        ignorar_orden,
+        &mut diferencias,
+        |nombre_idioma, idioma_a, idioma_b, diferencias| {
+            let entidad = format!("idioma \"{}\"", nombre_idioma);
+            comparar_cantidad(
+                entidad.clone(),
+                idioma_a.reviews,
+                idioma_b.reviews,
+                diferencias,
+            );
+            comparar_reviews(
+                &entidad,
+                &idioma_a.top_reviews,
+                &idioma_b.top_reviews,
+                ignorar_orden,
+                diferencias,
+            );
+        },
+    );
+
+    diferencias
+}
+
+/// Lee un archivo de salida del análisis
+fn leer_salida(path: &Path) -> Result<Salida, String> {
+    let contenido = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    serde_json::from_str(&contenido).map_err(|e| {
+        format!(
+            "{} no es un archivo de salida válido: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Recibe los paths de dos archivos de salida y devuelve sus diferencias, o un error si alguno de
+/// los archivos no se puede leer o no tiene el formato de la salida
+pub fn comparar_archivos(
+    a: &Path,
+    b: &Path,
+    ignorar_orden: bool,
+) -> Result<Vec<Diferencia>, String> {
+    Ok(comparar_salidas(
+        &leer_salida(a)?,
+        &leer_salida(b)?,
+        ignorar_orden,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn salida(json: Value) -> Salida {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn juego(nombre: &str, reviews: u32, idiomas: &[(&str, u32, &str, u32)]) -> Value {
+        let idiomas: Vec<Value> = idiomas
+            .iter()
+            .map(|(idioma, reviews, review, votos)| {
+                serde_json::json!({
+                    "language": idioma,
+                    "review_count": reviews,
+                    "top_review": review,
+                    "top_review_votes": votos,
+                })
+            })
+            .collect();
+        serde_json::json!({"game": nombre, "review_count": reviews, "languages": idiomas})
+    }
+
+    fn idioma(nombre: &str, reviews: u32, top: &[(&str, u32)]) -> Value {
+        let top: Vec<Value> = top
+            .iter()
+            .map(|(review, votos)| serde_json::json!({"review": review, "votes": votos}))
+            .collect();
+        serde_json::json!({"language": nombre, "review_count": reviews, "top_reviews": top})
+    }
+
+    fn salida_base() -> Value {
+        serde_json::json!({
+            "padron": 110310,
+            "top_games": [
+                juego("A", 10, &[("english", 6, "Good", 5), ("spanish", 4, "Bueno", 3)]),
+                juego("B", 8, &[("english", 8, "Fine", 2)]),
+            ],
+            "top_languages": [idioma("english", 14, &[("Good", 5), ("Fine", 2)])],
+        })
+    }
+
+    #[test]
+    fn salidas_iguales_sin_diferencias() {
+        let a = salida(salida_base());
+        let b = salida(salida_base());
+        assert!(comparar_salidas(&a, &b, false).is_empty());
+    }
+
+    #[test]
+    fn cambios_de_posicion_y_cantidades() {
+        let a = salida(salida_base());
+        let b = salida(serde_json::json!({
+            "padron": 110310,
+            "top_games": [
+                juego("B", 12, &[("english", 12, "Fine", 2)]),
+                juego("A", 10, &[("english", 6, "Great", 9), ("spanish", 4, "Bueno", 3)]),
+            ],
+            "top_languages": [idioma("english", 18, &[("Good", 5), ("Fine", 2)])],
+        }));
+        let diferencias = comparar_salidas(&a, &b, false);
+        assert!(diferencias.contains(&Diferencia::Posicion {
+            entidad: "juego \"A\"".to_string(),
+            antes: 1,
+            despues: 2,
+        }));
+        assert!(diferencias.contains(&Diferencia::Cantidad {
+            entidad: "juego \"B\"".to_string(),
+            antes: 8,
+            despues: 12,
+        }));
+        assert!(diferencias.contains(&Diferencia::TopReview {
+            entidad: "idioma \"english\" de juego \"A\"".to_string(),
+            antes: ("Good".to_string(), 5),
+            despues: ("Great".to_string(), 9),
+        }));
+        assert_eq!(diferencias.len(), 6);
+    }
+
+    #[test]
+    fn ignorar_orden() {
+        let a = salida(salida_base());
+        let b = salida(serde_json::json!({
+            "top_games": [
+                juego("B", 8, &[("english", 8, "Fine", 2)]),
+                juego("A", 10, &[("spanish", 4, "Bueno", 3), ("english", 6, "Good", 5)]),
+            ],
+            "top_languages": [idioma("english", 14, &[("Fine", 2), ("Good", 5)])],
+            "padron": 110310,
+        }));
+        assert!(comparar_salidas(&a, &b, true).is_empty());
+        assert!(!comparar_salidas(&a, &b, false).is_empty());
+    }
+
+    #[test]
+    fn entradas_y_secciones_agregadas_y_eliminadas() {
+        let a = salida(salida_base());
+        let b = salida(serde_json::json!({
+            "padron": 110310,
+            "top_games": [juego("A", 10, &[("english", 6, "Good", 5), ("spanish", 4, "Bueno", 3)])],
+            "top_languages": [idioma("english", 14, &[("Good", 5), ("Nuevo", 1)])],
+            "terms": {"languages": [], "games": []},
+        }));
+        let diferencias = comparar_salidas(&a, &b, false);
+        assert_eq!(
+            diferencias,
+            vec![
+                Diferencia::Seccion {
+                    nombre: "terms".to_string(),
+                    antes: false,
+                    despues: true,
+                },
+                Diferencia::Eliminado {
+                    entidad: "juego \"B\"".to_string(),
+                    posicion: 2,
+                },
+                Diferencia::ReviewEliminada {
+                    entidad: "idioma \"english\"".to_string(),
+                    review: ("Fine".to_string(), 2),
+                },
+                Diferencia::ReviewAgregada {
+                    entidad: "idioma \"english\"".to_string(),
+                    review: ("Nuevo".to_string(), 1),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::review::Review;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Error relativo máximo de los cuantiles estimados. Con 1% los valores menores a 50 caen cada
+/// uno en su propia cubeta, así que los votos y largos chicos se estiman sin error
+const ERROR_RELATIVO: f64 = 0.01;
+
+/// Sketch de cuantiles de valores enteros no negativos con error relativo acotado. Cada valor se
+/// guarda en una cubeta de tamaño creciente en escala logarítmica, de forma que el tamaño del
+/// sketch depende del rango de los valores y no de la cantidad. Dos sketches se combinan sumando
+/// sus cubetas, por lo que el resultado no depende de cómo se repartan las reseñas entre threads
+/// cubetas es un BTreeMap donde la clave es el índice de la cubeta y el valor la cantidad de
+///     valores que cayeron en ella
+/// ceros es la cantidad de valores iguales a 0, que no tienen cubeta logarítmica
+/// total es la cantidad total de valores agregados
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SketchCuantiles {
+    cubetas: BTreeMap<i32, u64>,
+    pub ceros: u64,
+    pub total: u64,
+}
+
+/// Base de las cubetas: la cubeta i contiene los valores en (gamma^(i-1), gamma^i]
+fn gamma() -> f64 {
+    (1.0 + ERROR_RELATIVO) / (1.0 - ERROR_RELATIVO)
+}
+
+impl SketchCuantiles {
+    /// Agrega un valor al sketch
+    pub fn agregar(&mut self, valor: u64) {
+        self.total += 1;
+        if valor == 0 {
+            self.ceros += 1;
+            return;
+        }
+        let cubeta = ((valor as f64).ln() / gamma().ln()).ceil() as i32;
+        *self.cubetas.entry(cubeta).or_insert(0) += 1;
+    }
+
+    /// Suma las cubetas de otro sketch al actual
+    pub fn combinar(&mut self, otro: SketchCuantiles) {
+        for (cubeta, cantidad) in otro.cubetas {
+            *self.cubetas.entry(cubeta).or_insert(0) += cantidad;
+        }
+        self.ceros += otro.ceros;
+        self.total += otro.total;
+    }
+
+    /// Devuelve el valor estimado del cuantil q (entre 0 y 1), redondeado al entero más cercano.
+    /// Si el sketch está vacío devuelve 0
+    pub fn cuantil(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rango = (q.clamp(0.0, 1.0) * (self.total - 1) as f64).floor() as u64;
+        if rango < self.ceros {
+            return 0;
+        }
+        let mut acumulado = self.ceros;
+        for (cubeta, cantidad) in &self.cubetas {
+            acumulado += cantidad;
+            if acumulado > rango {
+                // El punto de la cubeta que está a igual error relativo de ambos extremos
+                let gamma = gamma();
+                return (2.0 * gamma.powi(*cubeta) / (gamma + 1.0)).round() as u64;
+            }
+        }
+        0
+    }
+}
+
+/// Struct que almacena la distribución de un conjunto de reseñas
+/// votos es el sketch de los votes_helpful de las reseñas. Su cantidad de ceros es la cantidad de
+///     reseñas sin votos
+/// largo es el sketch del largo en caracteres del texto de las reseñas
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DistribucionReviews {
+    pub votos: SketchCuantiles,
+    pub largo: SketchCuantiles,
+}
+
+impl DistribucionReviews {
+    /// Suma los votos y el largo de la review a la distribución
+    fn agregar_review(&mut self, review: &Review) {
+        self.votos.agregar(review.votes_helpful as u64);
+        self.largo.agregar(review.review.chars().count() as u64);
+    }
+
+    /// Combina la distribución de otra instancia con la actual
+    fn combinar(&mut self, otra: DistribucionReviews) {
+        self.votos.combinar(otra.votos);
+        self.largo.combinar(otra.largo);
+    }
+}
+
+/// Struct que almacena las distribuciones de todas las reseñas leídas
+/// por_idioma es un HashMap donde la clave es el idioma y el valor su DistribucionReviews
+/// por_juego es un HashMap donde la clave es el nombre del juego y el valor su
+///     DistribucionReviews
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AnalisisDistribuciones {
+    pub por_idioma: HashMap<String, DistribucionReviews>,
+    pub por_juego: HashMap<String, DistribucionReviews>,
+}
+
+impl AnalisisDistribuciones {
+    /// Suma la review a la distribución de su juego y a la de su idioma
+    pub fn agregar_review(&mut self, review: &Review) {
+        self.por_idioma
+            .entry(review.language.clone())
+            .or_default()
+            .agregar_review(review);
+        self.por_juego
+            .entry(review.app_name.clone())
+            .or_default()
+            .agregar_review(review);
+    }
+
+    /// Combina las distribuciones de otra instancia con las actuales
+    pub fn combinar(&mut self, otro: AnalisisDistribuciones) {
+        for (idioma, distribucion) in otro.por_idioma {
+            self.por_idioma
+                .entry(idioma)
+                .or_default()
+                .combinar(distribucion);
+        }
+        for (juego, distribucion) in otro.por_juego {
+            self.por_juego
+                .entry(juego)
+                .or_default()
+                .combinar(distribucion);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuantiles_con_error_relativo_acotado() {
+        let mut sketch = SketchCuantiles::default();
+        for valor in 1..=10_000 {
+            sketch.agregar(valor);
+        }
+        for (q, esperado) in [(0.5, 5_000.0), (0.9, 9_000.0), (0.99, 9_900.0)] {
+            let estimado = sketch.cuantil(q) as f64;
+            assert!((estimado - esperado).abs() <= esperado * ERROR_RELATIVO + 1.0);
+        }
+    }
+
+    #[test]
+    fn valores_chicos_sin_error() {
+        let mut sketch = SketchCuantiles::default();
+        for valor in [0, 0, 0, 1, 2, 3, 5, 8, 13, 21] {
+            sketch.agregar(valor);
+        }
+        assert_eq!(sketch.ceros, 3);
+        assert_eq!(sketch.cuantil(0.0), 0);
+        assert_eq!(sketch.cuantil(0.5), 2);
+        assert_eq!(sketch.cuantil(1.0), 21);
+        assert_eq!(SketchCuantiles::default().cuantil(0.5), 0);
+    }
+
+    #[test]
+    fn combinar_es_igual_a_agregar_todo_junto() {
+        let mut todo = SketchCuantiles::default();
+        let mut a = SketchCuantiles::default();
+        let mut b = SketchCuantiles::default();
+        for valor in 0..5_000u64 {
+            let valor = valor * valor % 7_919;
+            todo.agregar(valor);
+            if valor % 2 == 0 {
+                a.agregar(valor);
+            } else {
+                b.agregar(valor);
+            }
+        }
+        a.combinar(b);
+        assert_eq!(a, todo);
+    }
+}
@@ -0,0 +1,57 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Struct que representa la sección de distribuciones del archivo de salida
+/// idiomas es un vector con la distribución de las reseñas de cada idioma
+/// juegos es un vector con la distribución de las reseñas de cada uno de los juegos del top
+#[derive(Serialize, JsonSchema)]
+pub struct SeccionDistribuciones {
+    #[serde(rename = "languages")]
+    pub idiomas: Vec<DistribucionIdioma>,
+    #[serde(rename = "games")]
+    pub juegos: Vec<DistribucionJuego>,
+}
+
+/// Struct que almacena la distribución de las reseñas de un idioma
+/// idioma es el nombre del idioma
+/// distribucion son sus percentiles y su proporción de reseñas sin votos
+#[derive(Serialize, JsonSchema)]
+pub struct DistribucionIdioma {
+    #[serde(rename = "language")]
+    pub idioma: String,
+    #[serde(flatten)]
+    pub distribucion: Distribucion,
+}
+
+/// Struct que almacena la distribución de las reseñas de un juego
+/// nombre es el nombre del juego
+/// distribucion son sus percentiles y su proporción de reseñas sin votos
+#[derive(Serialize, JsonSchema)]
+pub struct DistribucionJuego {
+    #[serde(rename = "game")]
+    pub nombre: String,
+    #[serde(flatten)]
+    pub distribucion: Distribucion,
+}
+
+/// Struct que almacena la distribución de un conjunto de reseñas
+/// votos son los percentiles de votes_helpful
+/// largo son los percentiles del largo en caracteres del texto
+/// sin_votos es la proporción de reseñas con 0 votes_helpful, entre 0 y 1
+#[derive(Serialize, JsonSchema)]
+pub struct Distribucion {
+    #[serde(rename = "votes_helpful")]
+    pub votos: Percentiles,
+    #[serde(rename = "length_chars")]
+    pub largo: Percentiles,
+    #[serde(rename = "zero_votes_share")]
+    pub sin_votos: f64,
+}
+
+/// Struct que almacena los percentiles 50, 90 y 99 estimados de un valor
+#[derive(Serialize, JsonSchema)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
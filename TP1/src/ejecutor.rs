@@ -0,0 +1,443 @@
+use crate::checkpoint::{Acumulador, Mensaje, Posicion};
+use crate::configurar_argumentos::Opciones;
+use crate::error::ErrorApp;
+use crate::estadisticas::{self, Estadisticas};
+use crate::parsear_csv;
+use crate::progreso::Progreso;
+use csv::StringRecord;
+use rayon::ThreadPool;
+use rayon::prelude::*;
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Cantidad de chunks leídos que pueden esperar a un worker en el pipeline por cada worker. Limita
+/// la memoria si el lector es más rápido que los workers
+const CHUNKS_EN_ESPERA_POR_WORKER: usize = 2;
+
+/// Estadísticas combinadas de los archivos procesados y errores de los que se saltearon
+pub type Resultado = (Estadisticas, Vec<ErrorApp>);
+
+/// Estrategia para procesar los archivos en paralelo. Todas combinan los resultados en el orden
+/// de los archivos y de los chunks, por lo que calculan exactamente las mismas estadísticas y
+/// sólo cambia cómo se reparte el trabajo entre los threads
+pub trait Ejecutor {
+    /// Nombre de la estrategia, el mismo que el de --executor
+    fn nombre(&self) -> &'static str;
+
+    /// Procesa los archivos y devuelve sus estadísticas combinadas. Si algún archivo no se puede
+    /// leer se devuelve su error, salvo que en las opciones se haya pedido seguir ante errores:
+    /// en ese caso el archivo se saltea y su error se devuelve junto con las estadísticas del
+    /// resto. Cada chunk procesado se suma al progreso
+    fn procesar(
+        &self,
+        archivos: &[PathBuf],
+        opciones: &Opciones,
+        progreso: &Progreso,
+    ) -> Result<Resultado, ErrorApp>;
+}
+
+/// Ejecutor con el work stealing de rayon: los archivos se recorren con par_iter() dentro del
+/// ThreadPool, los chunks de cada archivo también se procesan en paralelo, y los resultados se
+/// combinan con reduce()
+pub struct EjecutorRayon {
+    pub pool: ThreadPool,
+}
+
+/// Ejecutor con partición estática: los archivos se dividen en n_threads partes consecutivas de
+/// igual cantidad, y cada parte se procesa en un thread de std::thread que envía su resultado por
+/// un canal. El thread principal combina los resultados en el orden de las partes
+pub struct EjecutorThreads {
+    pub n_threads: usize,
+}
+
+/// Ejecutor en pipeline: un thread lector recorre los archivos de a uno y envía los chunks por un
+/// canal acotado a n_workers workers, que los procesan y envían sus estadísticas al thread
+/// principal, que las combina en orden a medida que llegan
+pub struct EjecutorPipeline {
+    pub n_workers: usize,
+}
+
+/// Chunk leído que el lector del pipeline le envía a los workers
+/// archivo y chunk son los índices del archivo y del chunk dentro del archivo
+/// registros son los registros del chunk y bytes lo que ocupan en el archivo
+struct Trabajo {
+    archivo: usize,
+    chunk: usize,
+    registros: Vec<StringRecord>,
+    bytes: u64,
+}
+
+impl Ejecutor for EjecutorRayon {
+    fn nombre(&self) -> &'static str {
+        "rayon"
+    }
+
+    fn procesar(
+        &self,
+        archivos: &[PathBuf],
+        opciones: &Opciones,
+        progreso: &Progreso,
+    ) -> Result<Resultado, ErrorApp> {
+        self.pool.install(|| {
+            let resultados = archivos.par_iter().map(|path| {
+                let resultado = parsear_csv::procesar_csv(path, opciones, progreso);
+                progreso.archivo_terminado();
+                resultado
+            });
+            if opciones.seguir_con_errores {
+                Ok(resultados
+                    .map(|resultado| match resultado {
+                        Ok(estadisticas) => (estadisticas, Vec::new()),
+                        Err(error) => (Estadisticas::default(), vec![error]),
+                    })
+                    .reduce(
+                        || (Estadisticas::default(), Vec::new()),
+                        |(a, mut fallidos_a), (b, fallidos_b)| {
+                            fallidos_a.extend(fallidos_b);
+                            (estadisticas::combinar_estadisticas(a, b), fallidos_a)
+                        },
+                    ))
+            } else {
+                let e = resultados.try_reduce(Estadisticas::default, |a, b| {
+                    Ok(estadisticas::combinar_estadisticas(a, b))
+                })?;
+                Ok((e, Vec::new()))
+            }
+        })
+    }
+}
+
+impl Ejecutor for EjecutorThreads {
+    fn nombre(&self) -> &'static str {
+        "threads"
+    }
+
+    fn procesar(
+        &self,
+        archivos: &[PathBuf],
+        opciones: &Opciones,
+        progreso: &Progreso,
+    ) -> Result<Resultado, ErrorApp> {
+        let por_thread = archivos.len().div_ceil(self.n_threads.max(1)).max(1);
+        let (enviar, recibir) = mpsc::channel();
+
+        thread::scope(|s| {
+            for (worker, parte) in archivos.chunks(por_thread).enumerate() {
+                let enviar = enviar.clone();
+                s.spawn(move || {
+                    let resultados = parte.iter().map(|path| {
+                        let resultado = procesar_archivo(path, opciones, progreso, worker);
+                        progreso.archivo_terminado();
+                        resultado
+                    });
+                    let resultado = combinar_en_orden(resultados, opciones.seguir_con_errores);
+                    // El receptor existe hasta que terminan todos los threads
+                    let _ = enviar.send((worker, resultado));
+                });
+            }
+        });
+        drop(enviar);
+
+        let mut partes: Vec<_> = recibir.into_iter().collect();
+        partes.sort_by_key(|(worker, _)| *worker);
+        let mut resultado = (Estadisticas::default(), Vec::new());
+        for (_, parte) in partes {
+            let (estadisticas, fallidos) = parte?;
+            resultado.0 = estadisticas::combinar_estadisticas(resultado.0, estadisticas);
+            resultado.1.extend(fallidos);
+        }
+        Ok(resultado)
+    }
+}
+
+impl Ejecutor for EjecutorPipeline {
+    fn nombre(&self) -> &'static str {
+        "pipeline"
+    }
+
+    fn procesar(
+        &self,
+        archivos: &[PathBuf],
+        opciones: &Opciones,
+        progreso: &Progreso,
+    ) -> Result<Resultado, ErrorApp> {
+        let n_workers = self.n_workers.max(1);
+        let (enviar_trabajo, recibir_trabajo) =
+            mpsc::sync_channel::<Trabajo>(n_workers * CHUNKS_EN_ESPERA_POR_WORKER);
+        let recibir_trabajo = Arc::new(Mutex::new(recibir_trabajo));
+        let (enviar, recibir) = mpsc::channel();
+        let cancelado = AtomicBool::new(false);
+
+        thread::scope(|s| {
+            let cancelado = &cancelado;
+            let enviar_lector = enviar.clone();
+            s.spawn(move || {
+                leer_archivos(
+                    archivos,
+                    &enviar_trabajo,
+                    &enviar_lector,
+                    cancelado,
+                    progreso,
+                )
+            });
+
+            for worker in 0..n_workers {
+                let recibir_trabajo = Arc::clone(&recibir_trabajo);
+                let enviar = enviar.clone();
+                s.spawn(move || {
+                    loop {
+                        // El lock sólo se mantiene mientras se espera el próximo chunk
+                        let trabajo = match recibir_trabajo.lock() {
+                            Ok(recibir) => recibir.recv(),
+                            Err(_) => break,
+                        };
+                        let Ok(trabajo) = trabajo else {
+                            break;
+                        };
+                        let filas = trabajo.registros.len() as u64;
+                        let estadisticas = parsear_csv::procesar_chunk(
+                            &archivos[trabajo.archivo],
+                            trabajo.registros,
+                            opciones,
+                        );
+                        progreso.chunk_procesado_por(Some(worker), filas, trabajo.bytes);
+                        let mensaje = Mensaje::Chunk {
+                            archivo: trabajo.archivo,
+                            chunk: trabajo.chunk,
+                            estadisticas: Box::new(estadisticas),
+                        };
+                        if enviar.send(mensaje).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            // Los workers se quedan con las únicas referencias al receptor de los chunks, para
+            // que el lector deje de esperar si terminan todos
+            drop(recibir_trabajo);
+            drop(enviar);
+
+            let mut acumulador = Acumulador::default();
+            for mensaje in recibir {
+                let mensaje = match mensaje {
+                    Mensaje::Error { error, .. } if !opciones.seguir_con_errores => {
+                        cancelado.store(true, Ordering::Relaxed);
+                        return Err(error);
+                    }
+                    mensaje => mensaje,
+                };
+                acumulador.recibir(mensaje);
+                acumulador.avanzar(archivos.len());
+            }
+            Ok(acumulador.terminar())
+        })
+    }
+}
+
+/// Lector del pipeline: recorre los archivos en orden y envía cada chunk a los workers. Al
+/// terminar un archivo le envía al thread que combina la cantidad de chunks que tenía, o el error
+/// si no se pudo leer. Deja de leer si se canceló el procesamiento o ya no quedan workers
+fn leer_archivos(
+    archivos: &[PathBuf],
+    enviar_trabajo: &mpsc::SyncSender<Trabajo>,
+    enviar: &mpsc::Sender<Mensaje>,
+    cancelado: &AtomicBool,
+    progreso: &Progreso,
+) {
+    for (archivo, path) in archivos.iter().enumerate() {
+        let mut inicios = Vec::new();
+        let resultado = parsear_csv::recorrer_chunks(path, |registros, bytes| {
+            if cancelado.load(Ordering::Relaxed) {
+                return ControlFlow::Break(());
+            }
+            let trabajo = Trabajo {
+                archivo,
+                chunk: inicios.len(),
+                registros,
+                bytes,
+            };
+            inicios.push(
+                trabajo
+                    .registros
+                    .first()
+                    .map(Posicion::de_registro)
+                    .unwrap_or_default(),
+            );
+            match enviar_trabajo.send(trabajo) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(_) => ControlFlow::Break(()),
+            }
+        });
+        progreso.archivo_terminado();
+        let mensaje = match resultado {
+            Ok(()) => Mensaje::Leido {
+                archivo,
+                primer_chunk: 0,
+                inicios,
+            },
+            Err(error) => Mensaje::Error { archivo, error },
+        };
+        if cancelado.load(Ordering::Relaxed) || enviar.send(mensaje).is_err() {
+            return;
+        }
+    }
+}
+
+/// Procesa un archivo en el thread actual, chunk por chunk a medida que se lee, sumando las filas
+/// al worker recibido
+fn procesar_archivo(
+    path: &Path,
+    opciones: &Opciones,
+    progreso: &Progreso,
+    worker: usize,
+) -> Result<Estadisticas, ErrorApp> {
+    let mut estadisticas = Estadisticas::default();
+    parsear_csv::recorrer_chunks(path, |registros, bytes| {
+        let filas = registros.len() as u64;
+        estadisticas = estadisticas::combinar_estadisticas(
+            mem::take(&mut estadisticas),
+            parsear_csv::procesar_chunk(path, registros, opciones),
+        );
+        progreso.chunk_procesado_por(Some(worker), filas, bytes);
+        ControlFlow::Continue(())
+    })?;
+    Ok(estadisticas)
+}
+
+/// Combina en orden los resultados de varios archivos. Si no se sigue ante errores, se deja de
+/// procesar en el primer archivo que falla y se devuelve su error
+fn combinar_en_orden(
+    resultados: impl Iterator<Item = Result<Estadisticas, ErrorApp>>,
+    seguir_con_errores: bool,
+) -> Result<Resultado, ErrorApp> {
+    let mut combinadas = Estadisticas::default();
+    let mut fallidos = Vec::new();
+    for resultado in resultados {
+        match resultado {
+            Ok(estadisticas) => {
+                combinadas = estadisticas::combinar_estadisticas(combinadas, estadisticas)
+            }
+            Err(error) if seguir_con_errores => fallidos.push(error),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok((combinadas, fallidos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configurar_argumentos::ModoProgreso;
+    use crate::formatear_datos_json::Output;
+    use crate::pruebas;
+    use std::fs;
+
+    fn ejecutores() -> Vec<Box<dyn Ejecutor>> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        vec![
+            Box::new(EjecutorRayon { pool }),
+            Box::new(EjecutorThreads { n_threads: 3 }),
+            Box::new(EjecutorPipeline { n_workers: 3 }),
+        ]
+    }
+
+    fn crear_archivos(directorio: &Path) -> Vec<PathBuf> {
+        pruebas::crear_archivos(
+            directorio,
+            &[
+                "0,1,FIFA,1,english,Crash on startup,-,-,-,5\n\
+                 1,1,FIFA,2,spanish,Muy buen juego,-,-,-,3\n",
+                "2,2,Terraria,3,english,Never crash great,-,-,-,9\n\
+                 3,1,FIFA,4,english,Great game,-,-,-,9\n\
+                 4,1,FIFA\n\
+                 5,3,Portal,6\n",
+                "",
+                "4,2,Terraria,5,spanish,Me encanta,-,-,-,1\n\
+                 5,3,Portal,6,english,Great puzzles,-,-,-,9\n",
+            ],
+        )
+    }
+
+    fn opciones(seguir_con_errores: bool) -> Opciones {
+        Opciones {
+            desglosar_archivos: true,
+            seguir_con_errores,
+            ..pruebas::opciones()
+        }
+    }
+
+    #[test]
+    fn todos_los_ejecutores_dan_el_mismo_resultado() {
+        let directorio = std::env::temp_dir().join("ejecutor_test");
+        let archivos = crear_archivos(&directorio);
+        let opciones = opciones(false);
+        let tamanio: u64 = archivos
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().len())
+            .sum();
+
+        let mut salidas = Vec::new();
+        for ejecutor in ejecutores() {
+            let progreso = Progreso::new(ModoProgreso::Ninguno, &archivos, 3);
+            let (estadisticas, fallidos) =
+                ejecutor.procesar(&archivos, &opciones, &progreso).unwrap();
+            assert!(fallidos.is_empty());
+            let estado = progreso.estado(true);
+            assert_eq!(estado.filas, 8, "{}", ejecutor.nombre());
+            assert_eq!(estado.bytes_procesados, tamanio, "{}", ejecutor.nombre());
+            assert_eq!(estado.archivos_terminados, archivos.len());
+            salidas.push(serde_json::to_string(&Output::new(&estadisticas, &opciones)).unwrap());
+        }
+        assert!(salidas.iter().all(|salida| salida == &salidas[0]));
+
+        // Los registros mal formados cuentan como errores, y los archivos vacíos igual aparecen
+        // en el desglose
+        let salida: serde_json::Value = serde_json::from_str(&salidas[0]).unwrap();
+        let por_archivo = salida["per_file"].as_array().unwrap();
+        assert_eq!(por_archivo.len(), archivos.len());
+        assert_eq!(
+            por_archivo[1],
+            serde_json::json!({
+                "file": archivos[1].display().to_string(),
+                "rows": 4,
+                "games": 2,
+                "languages": 1,
+                "top_game": "FIFA",
+                "top_game_reviews": 1,
+                "parse_errors": 2,
+            })
+        );
+        assert_eq!(por_archivo[2]["rows"], 0);
+        assert!(por_archivo[2]["top_game"].is_null());
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+
+    #[test]
+    fn archivos_con_errores() {
+        let directorio = std::env::temp_dir().join("ejecutor_errores_test");
+        let mut archivos = crear_archivos(&directorio);
+        archivos.insert(1, directorio.join("inexistente.csv"));
+
+        for ejecutor in ejecutores() {
+            let progreso = Progreso::new(ModoProgreso::Ninguno, &archivos, 3);
+            assert!(matches!(
+                ejecutor.procesar(&archivos, &opciones(false), &progreso),
+                Err(ErrorApp::Io { .. })
+            ));
+            let (estadisticas, fallidos) = ejecutor
+                .procesar(&archivos, &opciones(true), &progreso)
+                .unwrap();
+            assert_eq!(fallidos.len(), 1, "{}", ejecutor.nombre());
+            assert_eq!(estadisticas.juegos["FIFA"].reviews, 3);
+        }
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Códigos de salida del programa según el tipo de error. El 2 es el mismo que usa clap para los
+/// errores en los argumentos
+pub const CODIGO_RESULTADO_PARCIAL: i32 = 1;
+const CODIGO_CONFIGURACION: i32 = 2;
+const CODIGO_IO: i32 = 3;
+const CODIGO_CSV: i32 = 4;
+const CODIGO_ESQUEMA: i32 = 5;
+const CODIGO_SERIALIZACION: i32 = 6;
+
+/// Errores del programa
+/// Io es un error al leer o escribir un archivo o directorio
+/// Csv es un error del lector de csv que impide seguir leyendo el archivo. linea es la línea del
+///     archivo en la que ocurrió, si se conoce
+/// Esquema indica que un csv no tiene las columnas esperadas
+/// Serializacion es un error al convertir la salida a JSON o al leer un JSON
+/// Configuracion es un error en los argumentos, en el archivo de configuración o en alguno de los
+///     archivos de opciones (alias, metadatos), o una consulta inválida
+/// Anterior es el error de un archivo que se salteó antes de guardar un checkpoint, del que sólo
+///     se conserva el mensaje (ver checkpoint.rs)
+#[derive(Debug)]
+pub enum ErrorApp {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Csv {
+        path: PathBuf,
+        linea: Option<u64>,
+        error: csv::Error,
+    },
+    Esquema {
+        path: PathBuf,
+        linea: u64,
+        mensaje: String,
+    },
+    Serializacion {
+        path: PathBuf,
+        error: serde_json::Error,
+    },
+    Configuracion(String),
+    Anterior(String),
+}
+
+impl ErrorApp {
+    /// Crea un error de entrada/salida sobre el path recibido
+    pub fn io(path: &Path, error: io::Error) -> ErrorApp {
+        ErrorApp::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    /// Crea un error de csv sobre el path recibido, tomando la línea de la posición del error
+    pub fn csv(path: &Path, error: csv::Error) -> ErrorApp {
+        ErrorApp::Csv {
+            path: path.to_path_buf(),
+            linea: error.position().map(|posicion| posicion.line()),
+            error,
+        }
+    }
+
+    /// Código con el que termina el programa si ocurre este error
+    pub fn codigo_salida(&self) -> i32 {
+        match self {
+            ErrorApp::Io { .. } => CODIGO_IO,
+            ErrorApp::Csv { .. } => CODIGO_CSV,
+            ErrorApp::Esquema { .. } => CODIGO_ESQUEMA,
+            ErrorApp::Serializacion { .. } => CODIGO_SERIALIZACION,
+            ErrorApp::Configuracion(_) => CODIGO_CONFIGURACION,
+            ErrorApp::Anterior(_) => CODIGO_RESULTADO_PARCIAL,
+        }
+    }
+}
+
+impl fmt::Display for ErrorApp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorApp::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ErrorApp::Csv {
+                path,
+                linea: Some(linea),
+                error,
+            } => write!(f, "{}:{}: {}", path.display(), linea, error),
+            ErrorApp::Csv {
+                path,
+                linea: None,
+                error,
+            } => write!(f, "{}: {}", path.display(), error),
+            ErrorApp::Esquema {
+                path,
+                linea,
+                mensaje,
+            } => write!(f, "{}:{}: {}", path.display(), linea, mensaje),
+            ErrorApp::Serializacion { path, error } => {
+                write!(f, "{}: error de JSON: {}", path.display(), error)
+            }
+            ErrorApp::Configuracion(mensaje) | ErrorApp::Anterior(mensaje) => {
+                write!(f, "{}", mensaje)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErrorApp {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ErrorApp::Io { error, .. } => Some(error),
+            ErrorApp::Csv { error, .. } => Some(error),
+            ErrorApp::Serializacion { error, .. } => Some(error),
+            ErrorApp::Esquema { .. } | ErrorApp::Configuracion(_) | ErrorApp::Anterior(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mensajes_con_archivo_y_linea() {
+        let error = ErrorApp::Esquema {
+            path: PathBuf::from("datos.csv"),
+            linea: 1,
+            mensaje: "falta la columna review".to_string(),
+        };
+        assert_eq!(error.to_string(), "datos.csv:1: falta la columna review");
+        assert_eq!(error.codigo_salida(), CODIGO_ESQUEMA);
+
+        let error = ErrorApp::io(
+            Path::new("salida.json"),
+            io::Error::new(io::ErrorKind::PermissionDenied, "permiso denegado"),
+        );
+        assert_eq!(error.to_string(), "salida.json: permiso denegado");
+        assert_eq!(error.codigo_salida(), CODIGO_IO);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::formatear_datos_json::Output;
+use schemars::Schema;
+use schemars::generate::SchemaSettings;
+use schemars::transform::RecursiveTransform;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Parte del JSON que se informa cuando la violación está en la raíz del archivo
+const RUTA_RAIZ: &str = "/";
+
+/// Violación del esquema encontrada en un archivo de salida
+/// ruta es el JSON Pointer del valor que no cumple el esquema, por ejemplo `/top_games/0/game`
+/// mensaje describe qué regla del esquema no se cumple
+#[derive(Debug, PartialEq)]
+pub struct Violacion {
+    pub ruta: String,
+    pub mensaje: String,
+}
+
+impl fmt::Display for Violacion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.ruta, self.mensaje)
+    }
+}
+
+/// Devuelve el JSON Schema (draft 2020-12) de la salida del análisis, generado a partir de los
+/// structs de la salida (ver formatear_datos_json.rs). Las claves que se omiten cuando la sección
+/// no está habilitada no son obligatorias, y los objetos no admiten claves que no estén en el
+/// esquema, para detectar los cambios de nombre
+pub fn generar() -> Value {
+    let mut generador = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .with_transform(RecursiveTransform(cerrar_objeto))
+        .into_generator();
+    generador.root_schema_for::<Output>().to_value()
+}
+
+/// Si el esquema es el de un struct, no admite otras claves además de sus campos
+fn cerrar_objeto(esquema: &mut Schema) {
+    if esquema.get("properties").is_some() {
+        esquema.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+}
+
+/// Valida un archivo de salida ya leído contra el esquema y devuelve todas sus violaciones, en el
+/// orden en que aparecen
+pub fn validar(salida: &Value) -> Vec<Violacion> {
+    let esquema = generar();
+    let validador = match jsonschema::draft202012::new(&esquema) {
+        Ok(validador) => validador,
+        Err(error) => {
+            // El esquema se genera de los structs de la salida, así que siempre es válido
+            return vec![Violacion {
+                ruta: RUTA_RAIZ.to_string(),
+                mensaje: format!("esquema inválido: {}", error),
+            }];
+        }
+    };
+    validador
+        .iter_errors(salida)
+        .map(|error| {
+            let ruta = error.instance_path.to_string();
+            Violacion {
+                ruta: if ruta.is_empty() {
+                    RUTA_RAIZ.to_string()
+                } else {
+                    ruta
+                },
+                mensaje: error.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Recibe el path de un archivo de salida y devuelve sus violaciones del esquema, o un error si
+/// el archivo no se puede leer o no es JSON
+pub fn validar_archivo(path: &Path) -> Result<Vec<Violacion>, String> {
+    let contenido = fs::read_to_string(path)
+        .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    let salida: Value = serde_json::from_str(&contenido)
+        .map_err(|e| format!("{} no es un archivo JSON válido: {}", path.display(), e))?;
+    Ok(validar(&salida))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configurar_argumentos::Opciones;
+    use crate::estadisticas::Estadisticas;
+    use crate::review::Review;
+
+    fn salida(opciones: &Opciones) -> Value {
+        let mut estadisticas = Estadisticas::default();
+        for (juego, idioma, votos) in [("FIFA", "english", 3), ("Terraria", "spanish", 0)] {
+            estadisticas.agregar_review(Review {
+                app_id: 1,
+                app_name: juego.to_string(),
+                language: idioma.to_string(),
+                review: "Great".to_string(),
+                votes_helpful: votos,
+                truncada: false,
+            });
+        }
+        serde_json::to_value(Output::new(&estadisticas, opciones)).unwrap()
+    }
+
+    #[test]
+    fn la_salida_cumple_el_esquema() {
+        assert!(validar(&salida(&Opciones::default())).is_empty());
+        let opciones = Opciones {
+            analizar_terminos: true,
+            detectar_idioma: true,
+            calcular_distribuciones: true,
+            segmentar: true,
+            desglosar_archivos: true,
+            ..Opciones::default()
+        };
+        assert!(validar(&salida(&opciones)).is_empty());
+    }
+
+    #[test]
+    fn informar_la_ruta_de_cada_violacion() {
+        let mut json = salida(&Opciones::default());
+        json["top_games"][0]["review_count"] = Value::from("muchas");
+        json["top_languages"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("top_reviews");
+        json["extra"] = Value::Bool(true);
+
+        let rutas: Vec<String> = validar(&json)
+            .into_iter()
+            .map(|violacion| violacion.ruta)
+            .collect();
+        assert_eq!(rutas.len(), 3);
+        assert!(rutas.contains(&"/".to_string()));
+        assert!(rutas.contains(&"/top_games/0/review_count".to_string()));
+        assert!(rutas.contains(&"/top_languages/0".to_string()));
+    }
+}
@@ -1,17 +1,47 @@
+use crate::archivo::Archivo;
+use crate::detectar_idioma::DeteccionIdiomas;
+use crate::distribucion::AnalisisDistribuciones;
 use crate::idioma::Idioma;
 use crate::juego::Juego;
+use crate::metadatos::AgregadoMetadatos;
+use crate::muestreo::TotalesMuestreo;
 use crate::review::Review;
-use std::collections::HashMap;
+use crate::segmento::Segmento;
+use crate::terminos::AnalisisTerminos;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// Struct que almacena las estadísticas generales de los archivos csv
 /// juegos es un HashMap donde la clave es el nombre del juego y el valor es una instancia del
 ///     struct 'Juego' (ver juego.rs)
 /// idiomas es un HashMap donde la clave es un idioma y el valor es una instancia del struct
 ///     'Idioma' (ver idioma.rs)
-#[derive(Debug, Default, Clone)]
+/// terminos es el análisis de términos por idioma y por juego (ver terminos.rs). Sólo se completa
+///     si se habilitó el análisis de términos
+/// deteccion son los resultados de la validación de idiomas (ver detectar_idioma.rs). Sólo se
+///     completa si se habilitó la detección de idiomas
+/// metadatos son las estadísticas por género, desarrollador y año (ver metadatos.rs). Sólo se
+///     completa si se cargó un archivo de metadatos
+/// distribuciones son los sketches de votos y largo por idioma y por juego (ver distribucion.rs).
+///     Sólo se completa si se habilitó el cálculo de distribuciones
+/// segmentos son las estadísticas de las reseñas de cada segmento (ver segmento.rs), calculadas
+///     igual que las generales. Sólo se completa si se habilitaron los segmentos
+/// muestreo son los totales de reseñas leídas y muestreadas (ver muestreo.rs). Sólo se completa si
+///     se habilitó el muestreo
+/// archivos es un BTreeMap donde la clave es el path de cada archivo csv y el valor son sus
+///     estadísticas parciales (ver archivo.rs). Sólo se completa si se habilitó el desglose por
+///     archivo
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Estadisticas {
     pub juegos: HashMap<String, Juego>,
     pub idiomas: HashMap<String, Idioma>,
+    pub terminos: AnalisisTerminos,
+    pub deteccion: DeteccionIdiomas,
+    pub metadatos: AgregadoMetadatos,
+    pub distribuciones: AnalisisDistribuciones,
+    pub segmentos: BTreeMap<Segmento, Estadisticas>,
+    pub muestreo: TotalesMuestreo,
+    pub archivos: BTreeMap<String, Archivo>,
 }
 impl Estadisticas {
     /// Función que recibe una instancia de una Review y la agrega a las estadísticas internas
@@ -27,13 +57,14 @@ impl Estadisticas {
         juego
             .reviews_idiomas
             .entry(review.language.clone())
-            .and_modify(|(texto_actual, votos_actual)| {
+            .and_modify(|(texto_actual, votos_actual, truncada_actual)| {
                 if review.votes_helpful > *votos_actual {
                     *texto_actual = review.review.clone();
                     *votos_actual = review.votes_helpful;
+                    *truncada_actual = review.truncada;
                 }
             })
-            .or_insert((review.review.clone(), review.votes_helpful as u32));
+            .or_insert((review.review.clone(), review.votes_helpful, review.truncada));
 
         // Actualizar idioma
         let idioma = self.idiomas.entry(review.language.clone()).or_default();
@@ -42,7 +73,7 @@ impl Estadisticas {
         if review.votes_helpful > 0 {
             idioma
                 .top_reviews
-                .push((review.review, review.votes_helpful));
+                .push((review.review, review.votes_helpful, review.truncada));
         }
     }
 }
@@ -56,6 +87,13 @@ pub fn combinar_estadisticas(a: Estadisticas, b: Estadisticas) -> Estadisticas {
     let mut resultado = Estadisticas {
         juegos: a.juegos,
         idiomas: a.idiomas,
+        terminos: a.terminos,
+        deteccion: a.deteccion,
+        metadatos: a.metadatos,
+        distribuciones: a.distribuciones,
+        segmentos: a.segmentos,
+        muestreo: a.muestreo,
+        archivos: a.archivos,
     };
 
     for (key, juego_b) in b.juegos {
@@ -75,13 +113,14 @@ pub fn combinar_estadisticas(a: Estadisticas, b: Estadisticas) -> Estadisticas {
             *count_a += count_b;
         }
 
-        for (idioma_b, (review_b, votos_b)) in juego_b.reviews_idiomas {
-            let entry = juego_a
-                .reviews_idiomas
-                .entry(idioma_b.clone())
-                .or_insert((review_b.clone(), votos_b));
+        for (idioma_b, (review_b, votos_b, truncada_b)) in juego_b.reviews_idiomas {
+            let entry = juego_a.reviews_idiomas.entry(idioma_b.clone()).or_insert((
+                review_b.clone(),
+                votos_b,
+                truncada_b,
+            ));
             if votos_b > entry.1 {
-                *entry = (review_b.clone(), votos_b);
+                *entry = (review_b.clone(), votos_b, truncada_b);
             }
         }
     }
@@ -99,6 +138,27 @@ pub fn combinar_estadisticas(a: Estadisticas, b: Estadisticas) -> Estadisticas {
         idioma_a.top_reviews.extend(idioma_b_info.top_reviews);
     }
 
+    resultado.terminos.combinar(b.terminos);
+    resultado.terminos.podar_juegos(&resultado.juegos);
+    resultado.deteccion.combinar(b.deteccion);
+    resultado.metadatos.combinar(b.metadatos);
+    resultado.distribuciones.combinar(b.distribuciones);
+    resultado.muestreo.combinar(b.muestreo);
+    for (path, archivo_b) in b.archivos {
+        resultado
+            .archivos
+            .entry(path)
+            .or_default()
+            .combinar(archivo_b);
+    }
+    for (segmento, estadisticas_b) in b.segmentos {
+        let estadisticas_a = resultado.segmentos.remove(&segmento).unwrap_or_default();
+        resultado.segmentos.insert(
+            segmento,
+            combinar_estadisticas(estadisticas_a, estadisticas_b),
+        );
+    }
+
     resultado
 }
 
@@ -109,10 +169,12 @@ mod tests {
     fn agregar_una_review() {
         let mut estadisticas = Estadisticas::default();
         let review = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Alto juego".to_string(),
             votes_helpful: 100,
+            truncada: false,
         };
 
         estadisticas.agregar_review(review.clone());
@@ -123,29 +185,36 @@ mod tests {
         assert_eq!(juego.idiomas.get("Español"), Some(&1));
         assert_eq!(
             juego.reviews_idiomas.get("Español"),
-            Some(&("Alto juego".to_string(), 100))
+            Some(&("Alto juego".to_string(), 100, false))
         );
 
         assert!(estadisticas.idiomas.contains_key("Español"));
         let idioma = estadisticas.idiomas.get("Español").unwrap();
         assert_eq!(idioma.cantidad_reviews, 1);
-        assert_eq!(idioma.top_reviews, vec![("Alto juego".to_string(), 100)]);
+        assert_eq!(
+            idioma.top_reviews,
+            vec![("Alto juego".to_string(), 100, false)]
+        );
     }
 
     #[test]
     fn agregar_2_reviews_mismo_juego_distinto_idioma() {
         let mut estadisticas = Estadisticas::default();
         let r1 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Alto juego".to_string(),
             votes_helpful: 100,
+            truncada: false,
         };
         let r2 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Inglés".to_string(),
             review: "Very Good".to_string(),
             votes_helpful: 50,
+            truncada: false,
         };
 
         estadisticas.agregar_review(r1.clone());
@@ -160,27 +229,34 @@ mod tests {
         assert!(estadisticas.idiomas.contains_key("Inglés"));
         let esp = estadisticas.idiomas.get("Español").unwrap();
         assert_eq!(esp.cantidad_reviews, 1);
-        assert_eq!(esp.top_reviews, vec![("Alto juego".to_string(), 100)]);
+        assert_eq!(
+            esp.top_reviews,
+            vec![("Alto juego".to_string(), 100, false)]
+        );
 
         let ing = estadisticas.idiomas.get("Inglés").unwrap();
         assert_eq!(ing.cantidad_reviews, 1);
-        assert_eq!(ing.top_reviews, vec![("Very Good".to_string(), 50)]);
+        assert_eq!(ing.top_reviews, vec![("Very Good".to_string(), 50, false)]);
     }
 
     #[test]
     fn agregar_2_reviews_mismo_juego_mismo_idioma() {
         let mut estadisticas = Estadisticas::default();
         let r1 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Alto juego".to_string(),
             votes_helpful: 100,
+            truncada: false,
         };
         let r2 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Aguante el modo carrera".to_string(),
             votes_helpful: 50,
+            truncada: false,
         };
 
         estadisticas.agregar_review(r1.clone());
@@ -191,7 +267,7 @@ mod tests {
         assert_eq!(juego.idiomas.get("Español"), Some(&2));
         assert_eq!(
             juego.reviews_idiomas.get("Español"),
-            Some(&("Alto juego".to_string(), 100))
+            Some(&("Alto juego".to_string(), 100, false))
         );
         let esp = estadisticas.idiomas.get("Español").unwrap();
         assert_eq!(esp.cantidad_reviews, 2);
@@ -201,16 +277,20 @@ mod tests {
     fn agregar_mismo_mismo_juego_mismo_idioma_con_mas_votos() {
         let mut estadisticas = Estadisticas::default();
         let r1 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Alto juego".to_string(),
             votes_helpful: 100,
+            truncada: false,
         };
         let r2 = Review {
+            app_id: 1,
             app_name: "FIFA".to_string(),
             language: "Español".to_string(),
             review: "Aguante el modo carrera".to_string(),
             votes_helpful: 200,
+            truncada: false,
         };
 
         estadisticas.agregar_review(r1.clone());
@@ -220,7 +300,7 @@ mod tests {
         assert_eq!(juego.idiomas.get("Español"), Some(&2));
         assert_eq!(
             juego.reviews_idiomas.get("Español"),
-            Some(&("Aguante el modo carrera".to_string(), 200))
+            Some(&("Aguante el modo carrera".to_string(), 200, false))
         );
         let esp = estadisticas.idiomas.get("Español").unwrap();
         assert_eq!(esp.cantidad_reviews, 2);
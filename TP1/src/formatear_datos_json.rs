@@ -1,33 +1,189 @@
+use crate::alias_idiomas::AliasIdiomas;
+use crate::archivo::Archivo;
+use crate::archivo_output::ResumenArchivo;
+use crate::colacion::Colacion;
+use crate::configurar_argumentos::Opciones;
+use crate::deteccion_output::{FilaConfusion, SeccionDeteccion};
+use crate::distribucion::{DistribucionReviews, SketchCuantiles};
+use crate::distribucion_output::{
+    Distribucion, DistribucionIdioma, DistribucionJuego, Percentiles, SeccionDistribuciones,
+};
 use crate::estadisticas::Estadisticas;
+use crate::idioma::Idioma;
 use crate::idioma_output::{IdiomaMasVotado, ReviewIdioma};
+use crate::juego::Juego;
 use crate::juego_output::{IdiomaPorJuego, JuegoMasVotado};
+use crate::metadatos::Agrupacion;
+use crate::metadatos_output::{AgrupacionMasVotada, SeccionMetadatos};
+use crate::muestreo::Muestreo;
+use crate::muestreo_output::SeccionMuestreo;
+use crate::segmento::Segmento;
+use crate::terminos::{Terminos, top_terminos};
+use crate::terminos_output::{SeccionTerminos, TerminoContado, TerminosIdioma, TerminosJuego};
+use schemars::JsonSchema;
 use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
 const PADRON: u32 = 110310;
+/// Cantidad máxima de reseñas que se muestran por idioma
+const TOP_REVIEWS_IDIOMA: usize = 10;
+/// Cantidad de unigramas y bigramas que se muestran por idioma y por juego
+const TOP_TERMINOS: usize = 10;
+/// Cantidad de géneros, desarrolladores y años que se muestran en cada ranking de metadatos
+const TOP_AGRUPACIONES: usize = 10;
 
 /// Struct que contiene la información que va a ser luego escrita en el archivo de salida
 /// padron es mi padrón personal: 110310
+/// colacion es la colación con la que se desempataron alfabéticamente los rankings (ver
+///     colacion.rs). Sólo aparece en la salida si se eligió una distinta de byte, que es el orden
+///     que se usó siempre
+/// muestreo es la sección opcional que indica que el análisis se hizo sobre una muestra de las
+///     reseñas (ver muestreo_output.rs), y si las cantidades son de la muestra o están
+///     extrapoladas. Sólo aparece en la salida si se habilitó el muestreo
 /// top3_juegos es un vector de JuegoMasVotado (ver juego_output.rs) que contiene los 3 juegos más
 ///     votados
 /// top3_idiomas es un vector de IdiomaMasVotado (ver idioma_output.rs) que contiene los 3 idiomas
 ///     con mayor cantidad de reseñas
-#[derive(Serialize)]
+/// terminos es la sección opcional con los términos más frecuentes (ver terminos_output.rs). Sólo
+///     aparece en la salida si se habilitó el análisis de términos
+/// deteccion es la sección opcional con la validación de idiomas (ver deteccion_output.rs). Sólo
+///     aparece en la salida si se habilitó la detección de idiomas
+/// metadatos es la sección opcional con los rankings por género, desarrollador y año (ver
+///     metadatos_output.rs). Sólo aparece en la salida si se cargó un archivo de metadatos
+/// distribuciones es la sección opcional con los percentiles de votos y de largo de las reseñas
+///     (ver distribucion_output.rs). Sólo aparece en la salida si se habilitaron las distribuciones
+/// segmentos es la sección opcional con la salida completa de cada segmento de reseñas (ver
+///     segmento.rs), con la misma estructura que la general. Sólo aparece en la salida si se
+///     habilitaron los segmentos
+/// por_archivo es la sección opcional con el desglose de cada archivo csv (ver
+///     archivo_output.rs), ordenada por path. Sólo aparece en la salida si se habilitó el desglose
+///     por archivo
+#[derive(Serialize, JsonSchema)]
 pub struct Output {
     pub padron: u32,
+    #[serde(rename = "collation", skip_serializing_if = "Option::is_none")]
+    pub colacion: Option<Colacion>,
+    #[serde(rename = "sampling", skip_serializing_if = "Option::is_none")]
+    pub muestreo: Option<SeccionMuestreo>,
     #[serde(rename = "top_games")]
     pub top3_juegos: Vec<JuegoMasVotado>,
     #[serde(rename = "top_languages")]
     pub top3_idiomas: Vec<IdiomaMasVotado>,
+    #[serde(rename = "terms", skip_serializing_if = "Option::is_none")]
+    pub terminos: Option<SeccionTerminos>,
+    #[serde(rename = "language_detection", skip_serializing_if = "Option::is_none")]
+    pub deteccion: Option<SeccionDeteccion>,
+    #[serde(rename = "metadata", skip_serializing_if = "Option::is_none")]
+    pub metadatos: Option<SeccionMetadatos>,
+    #[serde(rename = "distributions", skip_serializing_if = "Option::is_none")]
+    pub distribuciones: Option<SeccionDistribuciones>,
+    #[serde(rename = "segments", skip_serializing_if = "Option::is_none")]
+    pub segmentos: Option<BTreeMap<Segmento, Output>>,
+    #[serde(rename = "per_file", skip_serializing_if = "Option::is_none")]
+    pub por_archivo: Option<Vec<ResumenArchivo>>,
 }
 
 impl Output {
     /// Recibe las estadísticas leídas y devuelve una instancia de Output con los juegos y los
-    /// idiomas ya filtrados por cantidad de reviews
-    pub fn new(e: &Estadisticas) -> Output {
+    /// idiomas ya filtrados por cantidad de reviews. Las secciones opcionales se agregan según las
+    /// opciones recibidas. Los idiomas se muestran con su nombre visible según los alias
+    /// configurados
+    pub fn new(e: &Estadisticas, opciones: &Opciones) -> Output {
+        let mut output = Self::sin_segmentos(e, opciones);
+        output.colacion = (opciones.colacion != Colacion::Byte).then_some(opciones.colacion);
+        output.muestreo = opciones
+            .muestreo
+            .map(|muestreo| Self::armar_muestreo(e, &muestreo));
+        output.segmentos = opciones
+            .segmentar
+            .then(|| Self::armar_segmentos(e, opciones));
+        output.por_archivo = opciones
+            .desglosar_archivos
+            .then(|| Self::armar_por_archivo(e, opciones.colacion));
+        output
+    }
+
+    /// Arma la salida de cada segmento con sus propias estadísticas. Los segmentos sin reseñas
+    /// aparecen con los rankings vacíos
+    fn armar_segmentos(e: &Estadisticas, opciones: &Opciones) -> BTreeMap<Segmento, Output> {
+        let vacio = Estadisticas::default();
+        Segmento::TODOS
+            .into_iter()
+            .map(|segmento| {
+                let estadisticas = e.segmentos.get(&segmento).unwrap_or(&vacio);
+                (segmento, Self::sin_segmentos(estadisticas, opciones))
+            })
+            .collect()
+    }
+
+    /// Arma la sección de muestreo con las opciones y los totales de reseñas leídas y muestreadas
+    fn armar_muestreo(e: &Estadisticas, muestreo: &Muestreo) -> SeccionMuestreo {
+        SeccionMuestreo {
+            fraccion: muestreo.fraccion,
+            semilla: muestreo.semilla,
+            estrato: muestreo.estrato,
+            minimo_estrato: muestreo.estrato.map(|_| muestreo.minimo_estrato),
+            conteos: muestreo.conteos,
+            leidas: e.muestreo.leidas as u32,
+            muestreadas: e.muestreo.muestreadas as u32,
+        }
+    }
+
+    /// Arma el desglose de cada archivo a partir de sus estadísticas parciales. El juego principal
+    /// de cada archivo es el que tiene más reseñas, desempatando alfabéticamente
+    fn armar_por_archivo(e: &Estadisticas, colacion: Colacion) -> Vec<ResumenArchivo> {
+        e.archivos
+            .iter()
+            .map(|(path, archivo)| Self::armar_archivo(path, archivo, colacion))
+            .collect()
+    }
+
+    /// Arma el resumen de un archivo
+    fn armar_archivo(path: &str, archivo: &Archivo, colacion: Colacion) -> ResumenArchivo {
+        let top_juego = archivo
+            .juegos
+            .iter()
+            .min_by(|a, b| b.1.cmp(a.1).then_with(|| colacion.comparar(a.0, b.0)));
+        ResumenArchivo {
+            archivo: path.to_string(),
+            filas: archivo.filas as u32,
+            juegos: archivo.juegos.len() as u32,
+            idiomas: archivo.idiomas.len() as u32,
+            top_juego: top_juego.map(|(nombre, _)| nombre.clone()),
+            top_juego_reviews: top_juego.map(|(_, reviews)| *reviews as u32).unwrap_or(0),
+            errores: archivo.invalidas as u32,
+        }
+    }
+
+    /// Igual que new, pero sin la sección de segmentos, la colación, el muestreo ni el desglose
+    /// por archivo, que se informan una sola vez
+    fn sin_segmentos(e: &Estadisticas, opciones: &Opciones) -> Output {
+        let alias = &opciones.alias_idiomas;
+        let colacion = opciones.colacion;
+        let top3_juegos = Self::filtrar_juegos(e, alias, colacion);
+        let terminos = opciones
+            .analizar_terminos
+            .then(|| Self::filtrar_terminos(e, &top3_juegos, alias, colacion));
+        let distribuciones = opciones
+            .calcular_distribuciones
+            .then(|| Self::armar_distribuciones(e, &top3_juegos, alias, colacion));
         Output {
             padron: PADRON,
-            top3_juegos: Self::filtrar_juegos(e),
-            top3_idiomas: Self::filtrar_idiomas(e),
+            colacion: None,
+            muestreo: None,
+            top3_juegos,
+            top3_idiomas: Self::filtrar_idiomas(e, alias, colacion),
+            terminos,
+            deteccion: opciones
+                .detectar_idioma
+                .then(|| Self::armar_deteccion(e, opciones.reetiquetar_idioma)),
+            metadatos: opciones
+                .metadatos
+                .is_some()
+                .then(|| Self::armar_metadatos(e, colacion)),
+            distribuciones,
+            segmentos: None,
+            por_archivo: None,
         }
     }
 
@@ -35,99 +191,370 @@ impl Output {
     /// `Vec<JuegoMasVotado>` es un vector que contiene los 3 juegos con más reseñas, y para cada
     /// juego se tiene un vector con los 3 idiomas con más reviews. Y para cada uno de esos idiomas,
     /// se obtiene la review con mayor votos junto con su contenido
-    fn filtrar_juegos(e: &Estadisticas) -> Vec<JuegoMasVotado> {
+    /// Los idiomas se muestran con su nombre visible, que también se usa para desempatar
+    fn filtrar_juegos(
+        e: &Estadisticas,
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+    ) -> Vec<JuegoMasVotado> {
+        Self::top_juegos(e, alias, colacion, 3)
+    }
+
+    /// Devuelve los n juegos con más reseñas, desempatando por nombre según la colación, cada uno
+    /// con sus 3 idiomas con más reviews
+    pub fn top_juegos(
+        e: &Estadisticas,
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+        n: usize,
+    ) -> Vec<JuegoMasVotado> {
         let mut juegos: Vec<JuegoMasVotado> = e
             .juegos
             .iter()
-            .map(|(nombre, juego)| {
-                let mut idiomas: Vec<IdiomaPorJuego> = juego
-                    .idiomas
-                    .iter()
-                    .map(|(idioma, cant_reviews)| {
-                        let (texto, votos) = juego
-                            .reviews_idiomas
-                            .get(idioma)
-                            .cloned()
-                            .unwrap_or_else(|| ("".to_string(), 0));
-
-                        IdiomaPorJuego {
-                            idioma: idioma.clone(),
-                            reviews: *cant_reviews as u32,
-                            top_review: texto,
-                            top_review_votos: votos,
-                        }
-                    })
-                    .collect();
-
-                idiomas.sort_by(|a, b| {
-                    b.reviews
-                        .cmp(&a.reviews)
-                        .then_with(|| a.idioma.cmp(&b.idioma))
-                });
-                idiomas.truncate(3);
-
-                JuegoMasVotado {
-                    nombre: nombre.clone(),
-                    reviews: juego.reviews as u32,
-                    idiomas,
-                }
-            })
+            .map(|(nombre, juego)| Self::armar_juego(nombre, juego, alias, colacion, 3))
             .collect();
         juegos.sort_by(|a, b| {
             b.reviews
                 .cmp(&a.reviews)
-                .then_with(|| a.nombre.cmp(&b.nombre))
+                .then_with(|| colacion.comparar(&a.nombre, &b.nombre))
         });
-        juegos.truncate(3);
+        juegos.truncate(n);
 
         juegos
     }
 
+    /// Recibe un juego y devuelve su JuegoMasVotado con los max_idiomas idiomas con más reviews.
+    /// Para cada idioma se obtiene la review con mayor votos junto con su contenido
+    pub fn armar_juego(
+        nombre: &str,
+        juego: &Juego,
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+        max_idiomas: usize,
+    ) -> JuegoMasVotado {
+        let mut idiomas: Vec<IdiomaPorJuego> = juego
+            .idiomas
+            .iter()
+            .map(|(idioma, cant_reviews)| {
+                let (texto, votos, truncada) = juego
+                    .reviews_idiomas
+                    .get(idioma)
+                    .cloned()
+                    .unwrap_or_else(|| ("".to_string(), 0, false));
+
+                IdiomaPorJuego {
+                    idioma: alias.nombre_visible(idioma).to_string(),
+                    reviews: *cant_reviews as u32,
+                    top_review: texto,
+                    top_review_votos: votos,
+                    top_review_truncada: truncada,
+                }
+            })
+            .collect();
+
+        idiomas.sort_by(|a, b| {
+            b.reviews
+                .cmp(&a.reviews)
+                .then_with(|| colacion.comparar(&a.idioma, &b.idioma))
+        });
+        idiomas.truncate(max_idiomas);
+
+        JuegoMasVotado {
+            nombre: nombre.to_string(),
+            reviews: juego.reviews as u32,
+            idiomas,
+        }
+    }
+
     ///Recibe las estadisticas leidas y devuelve un `Vec<IdiomaMasVotado>`
     /// `Vec<IdiomaMasVotado>` es un vector con los 3 idiomas que obtuvieron más reseñas, junto con
     /// su cantidad de reseñas
     /// Para cada uno de estos idiomas, se muestran las 10 reseñas con más votos junto con su texto
     /// y su cantidad de votos
-    fn filtrar_idiomas(e: &Estadisticas) -> Vec<IdiomaMasVotado> {
+    /// Los idiomas se muestran con su nombre visible, que también se usa para desempatar
+    fn filtrar_idiomas(
+        e: &Estadisticas,
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+    ) -> Vec<IdiomaMasVotado> {
         let mut idiomas: Vec<IdiomaMasVotado> = e
             .idiomas
             .iter()
             .map(|(nombre_idioma, datos_idioma)| {
-                let mut top: Vec<ReviewIdioma> = datos_idioma
-                    .top_reviews
-                    .iter()
-                    .map(|(texto, votos)| ReviewIdioma {
-                        review: texto.clone(),
-                        votos: *votos,
-                    })
-                    .collect();
-
-                top.sort_by(|a, b| b.votos.cmp(&a.votos));
-                top.truncate(10); // Máximo 10 reviews por idioma
-
-                IdiomaMasVotado {
-                    idioma: nombre_idioma.clone(),
-                    reviews: datos_idioma.cantidad_reviews as u32,
-                    top_reviews: top,
-                }
+                // Se ordenan referencias para clonar sólo los textos que se muestran
+                let mut top: Vec<&(String, u32, bool)> = datos_idioma.top_reviews.iter().collect();
+                top.sort_by_key(|(_, votos, _)| std::cmp::Reverse(*votos));
+                Self::armar_idioma_con_reviews(
+                    nombre_idioma,
+                    datos_idioma,
+                    alias,
+                    top.into_iter().take(TOP_REVIEWS_IDIOMA),
+                )
             })
             .collect();
 
         idiomas.sort_by(|a, b| {
             b.reviews
                 .cmp(&a.reviews)
-                .then_with(|| a.idioma.cmp(&b.idioma))
+                .then_with(|| colacion.comparar(&a.idioma, &b.idioma))
         });
         idiomas.truncate(3);
 
         idiomas
     }
+
+    /// Recibe un idioma y devuelve su IdiomaMasVotado con una página de sus reseñas: se saltean
+    /// las primeras `desde` y se muestran hasta `cantidad`. Las reseñas del idioma tienen que
+    /// estar ordenadas de mayor a menor por votos (ver Servidor::new), para no reordenarlas en
+    /// cada página
+    pub fn armar_idioma(
+        nombre: &str,
+        idioma: &Idioma,
+        alias: &AliasIdiomas,
+        desde: usize,
+        cantidad: usize,
+    ) -> IdiomaMasVotado {
+        Self::armar_idioma_con_reviews(
+            nombre,
+            idioma,
+            alias,
+            idioma.top_reviews.iter().skip(desde).take(cantidad),
+        )
+    }
+
+    /// Recibe un idioma y las reseñas a mostrar, ya ordenadas, y devuelve su IdiomaMasVotado
+    fn armar_idioma_con_reviews<'a>(
+        nombre: &str,
+        idioma: &Idioma,
+        alias: &AliasIdiomas,
+        reviews: impl Iterator<Item = &'a (String, u32, bool)>,
+    ) -> IdiomaMasVotado {
+        IdiomaMasVotado {
+            idioma: alias.nombre_visible(nombre).to_string(),
+            reviews: idioma.cantidad_reviews as u32,
+            top_reviews: reviews
+                .map(|(texto, votos, truncada)| ReviewIdioma {
+                    review: texto.clone(),
+                    votos: *votos,
+                    truncada: *truncada,
+                })
+                .collect(),
+        }
+    }
+
+    /// Recibe las estadísticas leídas y los juegos del top y devuelve la sección de términos.
+    /// Se incluyen todos los idiomas, ordenados por cantidad de reseñas, y los juegos del top en
+    /// el mismo orden en el que aparecen en `top_games`
+    fn filtrar_terminos(
+        e: &Estadisticas,
+        top_juegos: &[JuegoMasVotado],
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+    ) -> SeccionTerminos {
+        let vacio = Terminos::default();
+        let idiomas = Self::idiomas_ordenados(e, alias, colacion)
+            .into_iter()
+            .map(|(nombre, nombre_visible)| {
+                let terminos = e.terminos.por_idioma.get(nombre).unwrap_or(&vacio);
+                TerminosIdioma {
+                    idioma: nombre_visible.to_string(),
+                    unigramas: Self::contar_terminos(&terminos.unigramas),
+                    bigramas: Self::contar_terminos(&terminos.bigramas),
+                }
+            })
+            .collect();
+
+        let juegos = top_juegos
+            .iter()
+            .map(|juego| {
+                let terminos = e.terminos.por_juego.get(&juego.nombre).unwrap_or(&vacio);
+                TerminosJuego {
+                    nombre: juego.nombre.clone(),
+                    unigramas: Self::contar_terminos(&terminos.unigramas),
+                    bigramas: Self::contar_terminos(&terminos.bigramas),
+                }
+            })
+            .collect();
+
+        SeccionTerminos { idiomas, juegos }
+    }
+
+    /// Devuelve todos los idiomas junto con su nombre visible, ordenados por cantidad de reseñas
+    /// y desempatando por nombre visible según la colación
+    pub fn idiomas_ordenados<'a>(
+        e: &'a Estadisticas,
+        alias: &'a AliasIdiomas,
+        colacion: Colacion,
+    ) -> Vec<(&'a String, &'a str)> {
+        let mut idiomas: Vec<(&String, &str, usize)> = e
+            .idiomas
+            .iter()
+            .map(|(nombre, idioma)| {
+                (
+                    nombre,
+                    alias.nombre_visible(nombre),
+                    idioma.cantidad_reviews,
+                )
+            })
+            .collect();
+        idiomas.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| colacion.comparar(a.1, b.1)));
+        idiomas
+            .into_iter()
+            .map(|(nombre, nombre_visible, _)| (nombre, nombre_visible))
+            .collect()
+    }
+
+    /// Recibe las estadísticas leídas y los juegos del top y devuelve la sección de
+    /// distribuciones, con los idiomas y los juegos en el mismo orden que la sección de términos
+    fn armar_distribuciones(
+        e: &Estadisticas,
+        top_juegos: &[JuegoMasVotado],
+        alias: &AliasIdiomas,
+        colacion: Colacion,
+    ) -> SeccionDistribuciones {
+        let vacia = DistribucionReviews::default();
+        let idiomas = Self::idiomas_ordenados(e, alias, colacion)
+            .into_iter()
+            .map(|(nombre, nombre_visible)| DistribucionIdioma {
+                idioma: nombre_visible.to_string(),
+                distribucion: Self::resumir_distribucion(
+                    e.distribuciones.por_idioma.get(nombre).unwrap_or(&vacia),
+                ),
+            })
+            .collect();
+
+        let juegos = top_juegos
+            .iter()
+            .map(|juego| DistribucionJuego {
+                nombre: juego.nombre.clone(),
+                distribucion: Self::resumir_distribucion(
+                    e.distribuciones
+                        .por_juego
+                        .get(&juego.nombre)
+                        .unwrap_or(&vacia),
+                ),
+            })
+            .collect();
+
+        SeccionDistribuciones { idiomas, juegos }
+    }
+
+    /// Calcula los percentiles y la proporción de reseñas sin votos de una distribución
+    fn resumir_distribucion(distribucion: &DistribucionReviews) -> Distribucion {
+        let percentiles = |sketch: &SketchCuantiles| Percentiles {
+            p50: sketch.cuantil(0.5),
+            p90: sketch.cuantil(0.9),
+            p99: sketch.cuantil(0.99),
+        };
+        let votos = &distribucion.votos;
+        Distribucion {
+            votos: percentiles(votos),
+            largo: percentiles(&distribucion.largo),
+            sin_votos: if votos.total == 0 {
+                0.0
+            } else {
+                votos.ceros as f64 / votos.total as f64
+            },
+        }
+    }
+
+    /// Recibe las estadísticas leídas y devuelve la sección de validación de idiomas, con una fila
+    /// de la matriz de confusión por cada idioma declarado en orden alfabético
+    fn armar_deteccion(e: &Estadisticas, reetiquetado: bool) -> SeccionDeteccion {
+        let matriz = e
+            .deteccion
+            .matriz
+            .iter()
+            .map(|(declarado, detectados)| FilaConfusion {
+                declarado: declarado.clone(),
+                detectados: detectados
+                    .iter()
+                    .map(|(idioma, cantidad)| (idioma.clone(), *cantidad as u32))
+                    .collect(),
+            })
+            .collect();
+
+        SeccionDeteccion {
+            reetiquetado,
+            no_coincidentes: e.deteccion.no_coincidentes as u32,
+            reetiquetadas: e.deteccion.reetiquetadas as u32,
+            matriz,
+        }
+    }
+
+    /// Recibe las estadísticas leídas y devuelve la sección de metadatos, con los rankings de
+    /// géneros, desarrolladores y años de lanzamiento
+    fn armar_metadatos(e: &Estadisticas, colacion: Colacion) -> SeccionMetadatos {
+        let metadatos = &e.metadatos;
+        SeccionMetadatos {
+            encontradas: metadatos.encontradas as u32,
+            no_encontradas: metadatos.no_encontradas as u32,
+            generos: Self::rankear_agrupaciones(
+                metadatos.generos.iter().map(|(g, a)| (g.clone(), a)),
+                colacion,
+            ),
+            desarrolladores: Self::rankear_agrupaciones(
+                metadatos
+                    .desarrolladores
+                    .iter()
+                    .map(|(d, a)| (d.clone(), a)),
+                colacion,
+            ),
+            anios: Self::rankear_agrupaciones(
+                metadatos
+                    .anios
+                    .iter()
+                    .map(|(anio, a)| (anio.to_string(), a)),
+                colacion,
+            ),
+        }
+    }
+
+    /// Recibe los grupos de juegos junto con su nombre y devuelve los que tienen más reseñas,
+    /// desempatando por orden alfabético según la colación. Para cada grupo se muestra su juego
+    /// con más reseñas
+    fn rankear_agrupaciones<'a>(
+        agrupaciones: impl Iterator<Item = (String, &'a Agrupacion)>,
+        colacion: Colacion,
+    ) -> Vec<AgrupacionMasVotada> {
+        let mut ranking: Vec<AgrupacionMasVotada> = agrupaciones
+            .map(|(nombre, agrupacion)| {
+                let top_juego = agrupacion
+                    .juegos
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| colacion.comparar(b.0, a.0)))
+                    .map(|(juego, _)| juego.clone())
+                    .unwrap_or_default();
+                AgrupacionMasVotada {
+                    nombre,
+                    reviews: agrupacion.reviews as u32,
+                    juegos: agrupacion.juegos.len() as u32,
+                    top_juego,
+                }
+            })
+            .collect();
+
+        ranking.sort_by(|a, b| {
+            b.reviews
+                .cmp(&a.reviews)
+                .then_with(|| colacion.comparar(&a.nombre, &b.nombre))
+        });
+        ranking.truncate(TOP_AGRUPACIONES);
+        ranking
+    }
+
+    /// Convierte un HashMap de frecuencias en el vector de los términos más frecuentes
+    fn contar_terminos(frecuencias: &HashMap<String, u32>) -> Vec<TerminoContado> {
+        top_terminos(frecuencias, TOP_TERMINOS)
+            .into_iter()
+            .map(|(termino, cantidad)| TerminoContado { termino, cantidad })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::juego::Juego;
     use std::collections::HashMap;
 
     fn generar_estadisticas() -> Estadisticas {
@@ -141,10 +568,10 @@ mod tests {
         juego1.idiomas.insert("Inglés".to_string(), 2);
         juego1
             .reviews_idiomas
-            .insert("Español".to_string(), ("Muy bueno".to_string(), 50));
+            .insert("Español".to_string(), ("Muy bueno".to_string(), 50, false));
         juego1
             .reviews_idiomas
-            .insert("Inglés".to_string(), ("Very good".to_string(), 100));
+            .insert("Inglés".to_string(), ("Very good".to_string(), 100, false));
 
         let mut juego2 = Juego {
             reviews: 7,
@@ -153,12 +580,13 @@ mod tests {
         };
         juego2.idiomas.insert("Francés".to_string(), 6);
         juego2.idiomas.insert("Inglés".to_string(), 1);
+        juego2.reviews_idiomas.insert(
+            "Francés".to_string(),
+            ("Très bien!".to_string(), 200, false),
+        );
         juego2
             .reviews_idiomas
-            .insert("Francés".to_string(), ("Très bien!".to_string(), 200));
-        juego2
-            .reviews_idiomas
-            .insert("Inglés".to_string(), ("Very good".to_string(), 20));
+            .insert("Inglés".to_string(), ("Very good".to_string(), 20, false));
 
         let mut juego3 = Juego {
             reviews: 7,
@@ -170,13 +598,13 @@ mod tests {
         juego3.idiomas.insert("Francés".to_string(), 1);
         juego3
             .reviews_idiomas
-            .insert("Español".to_string(), ("Alto juego".to_string(), 70));
+            .insert("Español".to_string(), ("Alto juego".to_string(), 70, false));
         juego3
             .reviews_idiomas
-            .insert("Francés".to_string(), (":)".to_string(), 110));
+            .insert("Francés".to_string(), (":)".to_string(), 110, false));
         juego3
             .reviews_idiomas
-            .insert("Alemán".to_string(), ("!!!".to_string(), 200));
+            .insert("Alemán".to_string(), ("!!!".to_string(), 200, false));
 
         let mut juego4 = Juego {
             reviews: 2,
@@ -186,7 +614,7 @@ mod tests {
         juego4.idiomas.insert("Inglés".to_string(), 2);
         juego4
             .reviews_idiomas
-            .insert("Inglés".to_string(), ("Nice".to_string(), 105));
+            .insert("Inglés".to_string(), ("Nice".to_string(), 105, false));
 
         estadisticas.juegos.insert("NBA 2k25".to_string(), juego1);
         estadisticas.juegos.insert("God of War".to_string(), juego2);
@@ -197,7 +625,8 @@ mod tests {
     #[test]
     fn test_filtrar_juegos() {
         let estadisticas = generar_estadisticas();
-        let resultado = Output::filtrar_juegos(&estadisticas);
+        let resultado =
+            Output::filtrar_juegos(&estadisticas, &AliasIdiomas::default(), Colacion::Byte);
         //deberian ser solo 3
         assert_eq!(resultado.len(), 3);
 
@@ -210,7 +639,8 @@ mod tests {
     #[test]
     fn chequear_idiomas_en_top3_juegos() {
         let estadisticas = generar_estadisticas();
-        let resultado = Output::filtrar_juegos(&estadisticas);
+        let resultado =
+            Output::filtrar_juegos(&estadisticas, &AliasIdiomas::default(), Colacion::Byte);
 
         let idiomas_fifa = &resultado[0].idiomas;
         let idiomas_gow = &resultado[1].idiomas;
@@ -256,17 +686,13 @@ mod tests {
     fn test_filtrar_idiomas() {
         use crate::estadisticas::*;
         use crate::idioma::*;
-        use std::collections::HashMap;
 
-        let mut estadisticas = Estadisticas {
-            juegos: HashMap::new(),
-            idiomas: HashMap::new(),
-        };
+        let mut estadisticas = Estadisticas::default();
 
         // Idioma italiano con 15 reviews, para que despues queden 10
         let mut reviews_it = Vec::new();
         for i in 1..=15 {
-            reviews_it.push((format!("Review {}", i), i * 10)); // votos: 10, 20, ..., 150
+            reviews_it.push((format!("Review {}", i), i * 10, false)); // votos: 10, 20, ..., 150
         }
         let ita = Idioma {
             cantidad_reviews: 15,
@@ -276,27 +702,27 @@ mod tests {
         let ingl = Idioma {
             cantidad_reviews: 5,
             top_reviews: vec![
-                ("Great!".to_string(), 80),
-                ("Loved it".to_string(), 95),
-                ("Very fun".to_string(), 60),
-                ("Nice game".to_string(), 75),
-                ("Good enough".to_string(), 50),
+                ("Great!".to_string(), 80, false),
+                ("Loved it".to_string(), 95, false),
+                ("Very fun".to_string(), 60, false),
+                ("Nice game".to_string(), 75, false),
+                ("Good enough".to_string(), 50, false),
             ],
         };
 
         let esp = Idioma {
             cantidad_reviews: 3,
             top_reviews: vec![
-                ("Me encantó".to_string(), 70),
-                ("Muy bueno".to_string(), 65),
-                ("Excelente".to_string(), 85),
+                ("Me encantó".to_string(), 70, false),
+                ("Muy bueno".to_string(), 65, false),
+                ("Excelente".to_string(), 85, false),
             ],
         };
 
         //Este deberia quedar afuera por ser el que menos reviews tiene
         let fra = Idioma {
             cantidad_reviews: 1,
-            top_reviews: vec![("Incroyable".to_string(), 99)],
+            top_reviews: vec![("Incroyable".to_string(), 99, false)],
         };
 
         estadisticas.idiomas.insert("it".to_string(), ita);
@@ -304,7 +730,8 @@ mod tests {
         estadisticas.idiomas.insert("es".to_string(), esp);
         estadisticas.idiomas.insert("fr".to_string(), fra);
 
-        let resultado = Output::filtrar_idiomas(&estadisticas);
+        let resultado =
+            Output::filtrar_idiomas(&estadisticas, &AliasIdiomas::default(), Colacion::Byte);
 
         // Solo deben quedar 3 idiomas
         assert_eq!(resultado.len(), 3);
@@ -330,4 +757,267 @@ mod tests {
         // Chequeo que francés haya quedado afuera
         assert!(resultado.iter().all(|i| i.idioma != "fr"));
     }
+
+    #[test]
+    fn test_filtrar_terminos() {
+        use crate::review::Review;
+
+        let mut estadisticas = generar_estadisticas();
+        estadisticas
+            .idiomas
+            .insert("english".to_string(), Default::default());
+        let review = Review {
+            app_id: 1,
+            app_name: "FIFA 17".to_string(),
+            language: "english".to_string(),
+            review: "Great game, great career mode".to_string(),
+            votes_helpful: 10,
+            truncada: false,
+        };
+        estadisticas.terminos.agregar_review(&review, "english");
+
+        let top_juegos =
+            Output::filtrar_juegos(&estadisticas, &AliasIdiomas::default(), Colacion::Byte);
+        let resultado = Output::filtrar_terminos(
+            &estadisticas,
+            &top_juegos,
+            &AliasIdiomas::default(),
+            Colacion::Byte,
+        );
+
+        assert_eq!(resultado.idiomas.len(), 1);
+        assert_eq!(resultado.idiomas[0].idioma, "english");
+        assert_eq!(resultado.idiomas[0].unigramas[0].termino, "great");
+        assert_eq!(resultado.idiomas[0].unigramas[0].cantidad, 2);
+
+        // Los juegos aparecen en el mismo orden que en el top, aunque no tengan términos
+        assert_eq!(resultado.juegos.len(), 3);
+        assert_eq!(resultado.juegos[0].nombre, "FIFA 17");
+        assert_eq!(resultado.juegos[0].bigramas[0].termino, "career mode");
+        assert!(resultado.juegos[1].unigramas.is_empty());
+    }
+
+    #[test]
+    fn test_filtrar_idiomas_con_nombres_visibles() {
+        use crate::idioma::Idioma;
+
+        let mut estadisticas = Estadisticas::default();
+        for codigo in ["schinese", "english", "russian", "turkish"] {
+            let idioma = Idioma {
+                cantidad_reviews: 10,
+                top_reviews: Vec::new(),
+            };
+            estadisticas.idiomas.insert(codigo.to_string(), idioma);
+        }
+        let mut alias = AliasIdiomas::default();
+        alias.agregar("schinese", "schinese", "Chinese");
+        alias.agregar("russian", "russian", "zz russian");
+
+        let resultado = Output::filtrar_idiomas(&estadisticas, &alias, Colacion::Byte);
+
+        // Con el mismo número de reseñas se desempata por el nombre visible y no por el código
+        assert_eq!(resultado[0].idioma, "Chinese");
+        assert_eq!(resultado[1].idioma, "english");
+        assert_eq!(resultado[2].idioma, "turkish");
+    }
+
+    #[test]
+    fn test_armar_metadatos() {
+        use crate::metadatos::{Metadatos, MetadatosJuego};
+        use crate::review::Review;
+
+        let mut metadatos = Metadatos::default();
+        for (app_id, genero, desarrollador) in [(1, "RPG", "Estudio A"), (2, "RPG", "Estudio B")] {
+            let juego = MetadatosJuego {
+                generos: vec![genero.to_string()],
+                desarrollador: desarrollador.to_string(),
+                anio: Some(2020),
+            };
+            metadatos.juegos.insert(app_id, juego);
+        }
+        let mut estadisticas = Estadisticas::default();
+        for (app_id, app_name) in [(1, "Juego A"), (2, "Juego B"), (2, "Juego B"), (3, "Otro")] {
+            let review = Review {
+                app_id,
+                app_name: app_name.to_string(),
+                language: "english".to_string(),
+                review: "".to_string(),
+                votes_helpful: 0,
+                truncada: false,
+            };
+            estadisticas.metadatos.agregar_review(&review, &metadatos);
+        }
+
+        let resultado = Output::armar_metadatos(&estadisticas, Colacion::Byte);
+
+        assert_eq!(resultado.encontradas, 3);
+        assert_eq!(resultado.no_encontradas, 1);
+        assert_eq!(resultado.generos.len(), 1);
+        assert_eq!(resultado.generos[0].reviews, 3);
+        assert_eq!(resultado.generos[0].juegos, 2);
+        assert_eq!(resultado.generos[0].top_juego, "Juego B");
+        assert_eq!(resultado.desarrolladores[0].nombre, "Estudio B");
+        assert_eq!(resultado.desarrolladores[1].nombre, "Estudio A");
+        assert_eq!(resultado.anios[0].nombre, "2020");
+    }
+
+    #[test]
+    fn test_armar_distribuciones() {
+        use crate::review::Review;
+
+        let mut estadisticas = generar_estadisticas();
+        estadisticas
+            .idiomas
+            .insert("english".to_string(), Default::default());
+        for (texto, votos) in [
+            ("Bueno", 0),
+            ("Muy bueno", 0),
+            ("Excelente juego", 4),
+            ("", 8),
+        ] {
+            let review = Review {
+                app_id: 1,
+                app_name: "FIFA 17".to_string(),
+                language: "english".to_string(),
+                review: texto.to_string(),
+                votes_helpful: votos,
+                truncada: false,
+            };
+            estadisticas.distribuciones.agregar_review(&review);
+        }
+
+        let top_juegos =
+            Output::filtrar_juegos(&estadisticas, &AliasIdiomas::default(), Colacion::Byte);
+        let resultado = Output::armar_distribuciones(
+            &estadisticas,
+            &top_juegos,
+            &AliasIdiomas::default(),
+            Colacion::Byte,
+        );
+
+        let english = &resultado.idiomas[0].distribucion;
+        assert_eq!(resultado.idiomas[0].idioma, "english");
+        assert_eq!(english.votos.p50, 0);
+        assert_eq!(english.votos.p99, 4);
+        assert_eq!(english.largo.p90, 9);
+        assert_eq!(english.sin_votos, 0.5);
+
+        // Los juegos sin reseñas agregadas aparecen con la distribución vacía
+        assert_eq!(resultado.juegos[0].nombre, "FIFA 17");
+        assert_eq!(resultado.juegos[0].distribucion.votos.p99, 4);
+        assert_eq!(resultado.juegos[1].distribucion.sin_votos, 0.0);
+    }
+
+    #[test]
+    fn test_armar_segmentos() {
+        let mut estadisticas = generar_estadisticas();
+        let mut compra_steam = Estadisticas::default();
+        compra_steam.agregar_review(crate::review::Review {
+            app_id: 1,
+            app_name: "Terraria".to_string(),
+            language: "english".to_string(),
+            review: "Great".to_string(),
+            votes_helpful: 3,
+            truncada: false,
+        });
+        estadisticas
+            .segmentos
+            .insert(Segmento::CompraSteam, compra_steam);
+        let opciones = Opciones {
+            segmentar: true,
+            ..Opciones::default()
+        };
+
+        let output = Output::new(&estadisticas, &opciones);
+        let segmentos = output.segmentos.as_ref().unwrap();
+        assert_eq!(segmentos.len(), 3);
+        assert_eq!(segmentos[&Segmento::CompraSteam].top3_juegos.len(), 1);
+        assert_eq!(
+            segmentos[&Segmento::CompraSteam].top3_juegos[0].nombre,
+            "Terraria"
+        );
+        assert!(segmentos[&Segmento::Gratis].top3_juegos.is_empty());
+
+        let json = serde_json::to_value(&output).unwrap();
+        let claves: Vec<&String> = json["segments"].as_object().unwrap().keys().collect();
+        assert_eq!(
+            claves,
+            [
+                "received_for_free",
+                "steam_purchase",
+                "written_during_early_access"
+            ]
+        );
+        assert!(json["segments"]["steam_purchase"].get("segments").is_none());
+        assert!(
+            Output::new(&estadisticas, &Opciones::default())
+                .segmentos
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_desempate_con_colacion() {
+        let mut estadisticas = Estadisticas::default();
+        for nombre in ["zelda", "Zoo", "\u{c9}lan"] {
+            estadisticas.agregar_review(crate::review::Review {
+                app_id: 1,
+                app_name: nombre.to_string(),
+                language: "english".to_string(),
+                review: "Great".to_string(),
+                votes_helpful: 3,
+                truncada: false,
+            });
+        }
+        let nombres = |colacion| -> Vec<String> {
+            Output::filtrar_juegos(&estadisticas, &AliasIdiomas::default(), colacion)
+                .into_iter()
+                .map(|juego| juego.nombre)
+                .collect()
+        };
+        assert_eq!(nombres(Colacion::Byte), ["Zoo", "zelda", "\u{c9}lan"]);
+        assert_eq!(
+            nombres(Colacion::SinMayusculas),
+            ["zelda", "Zoo", "\u{c9}lan"]
+        );
+        assert_eq!(nombres(Colacion::Raiz), ["\u{c9}lan", "zelda", "Zoo"]);
+
+        let opciones = Opciones {
+            colacion: Colacion::Raiz,
+            segmentar: true,
+            ..Opciones::default()
+        };
+        let json = serde_json::to_value(Output::new(&estadisticas, &opciones)).unwrap();
+        assert_eq!(json["collation"], "root");
+        assert!(
+            json["segments"]["steam_purchase"]
+                .get("collation")
+                .is_none()
+        );
+        let json = serde_json::to_value(Output::new(&estadisticas, &Opciones::default())).unwrap();
+        assert!(json.get("collation").is_none());
+    }
+
+    #[test]
+    fn test_marcar_reviews_truncadas() {
+        let mut estadisticas = Estadisticas::default();
+        for (texto, votos, truncada) in [("Larga", 10, true), ("Corta", 5, false)] {
+            estadisticas.agregar_review(crate::review::Review {
+                app_id: 1,
+                app_name: "Terraria".to_string(),
+                language: "english".to_string(),
+                review: texto.to_string(),
+                votes_helpful: votos,
+                truncada,
+            });
+        }
+
+        let json = serde_json::to_value(Output::new(&estadisticas, &Opciones::default())).unwrap();
+        let idioma = &json["top_games"][0]["languages"][0];
+        assert_eq!(idioma["top_review"], "Larga");
+        assert_eq!(idioma["top_review_truncated"], true);
+        let reviews = &json["top_languages"][0]["top_reviews"];
+        assert_eq!(reviews[0]["truncated"], true);
+        assert!(reviews[1].get("truncated").is_none());
+    }
 }
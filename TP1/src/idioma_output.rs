@@ -1,10 +1,11 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 /// Struct que almacena la información ya procesada de un Idioma
 /// idioma es el nombre del idioma
 /// reviews es la cantidad de reseñas que obtuvo
 /// top_reviews es el vector con las reseñas escritas en dicho idioma, junto con sus votos
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct IdiomaMasVotado {
     #[serde(rename = "language")]
     pub idioma: String,
@@ -16,9 +17,17 @@ pub struct IdiomaMasVotado {
 /// Struct que almacena una reseña en un idioma determinado
 /// review es el contenido de la reseña
 /// votos es la cantidad de votos que obtuvo
-#[derive(Serialize)]
+/// truncada indica si el texto de la reseña se truncó al sanearlo. Sólo aparece en la salida si
+///     es true
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct ReviewIdioma {
     pub review: String,
     #[serde(rename = "votes")]
     pub votos: u32,
+    #[serde(
+        rename = "truncated",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub truncada: bool,
 }
@@ -0,0 +1,476 @@
+use crate::error::ErrorApp;
+use crate::parsear_csv;
+use crate::review::Review;
+use crate::saneamiento::Saneamiento;
+use crate::terminos;
+use csv::{ReaderBuilder, StringRecord};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Versión del formato del índice. Si cambia el formato de los archivos hay que incrementarla para
+/// que no se lean índices viejos como si fueran nuevos
+const VERSION_INDICE: u32 = 1;
+/// Nombres de los archivos que forman el índice
+const ARCHIVO_INDICE: &str = "indice.json";
+const ARCHIVO_SEGMENTO: &str = "segmento.json";
+const ARCHIVO_DOCUMENTOS: &str = "documentos.bin";
+const ARCHIVO_DICCIONARIO: &str = "diccionario.bin";
+const ARCHIVO_POSTINGS: &str = "postings.bin";
+/// Tamaño en bytes de un documento en documentos.bin: offset (u64), juego, idioma y votos (u32)
+const TAMANO_DOCUMENTO: usize = 20;
+
+/// Struct que se guarda en indice.json con la información general del índice
+#[derive(Serialize, Deserialize)]
+struct MetaIndice {
+    version: u32,
+    segmentos: usize,
+}
+
+/// Struct que se guarda en el segmento.json de cada segmento
+/// archivo es el csv del que se leyeron las reseñas del segmento
+/// juegos e idiomas son los nombres referenciados por posición desde los documentos
+#[derive(Serialize, Deserialize)]
+struct MetaSegmento {
+    archivo: PathBuf,
+    juegos: Vec<String>,
+    idiomas: Vec<String>,
+    documentos: usize,
+}
+
+/// Struct que representa una reseña indexada
+/// offset es la posición en bytes del registro dentro del csv, para poder leer el texto completo
+///     sin guardarlo en el índice
+/// juego e idioma son las posiciones del nombre del juego y del idioma en el segmento
+/// votos son los votes_helpful de la reseña
+#[derive(Debug, Clone, Copy)]
+pub struct Documento {
+    pub offset: u64,
+    pub juego: u32,
+    pub idioma: u32,
+    pub votos: u32,
+}
+
+/// Struct con la parte de un segmento construida a partir de un chunk del csv. Los ids de los
+/// documentos son locales al chunk y se corren al combinar los chunks
+#[derive(Default)]
+struct SegmentoParcial {
+    documentos: Vec<(u64, String, String, u32)>,
+    postings: HashMap<String, Vec<u32>>,
+}
+
+/// Struct que representa un segmento del índice en construcción. Cada csv genera un segmento
+/// archivo es el csv del que se leyeron las reseñas
+/// juegos e idiomas son los nombres de juegos e idiomas del segmento
+/// documentos son las reseñas indexadas, su posición en el vector es su id
+/// postings es un mapa donde la clave es un término y el valor los ids ordenados de los
+///     documentos que lo contienen
+#[derive(Default)]
+struct Segmento {
+    archivo: PathBuf,
+    juegos: Vec<String>,
+    idiomas: Vec<String>,
+    documentos: Vec<Documento>,
+    postings: BTreeMap<String, Vec<u32>>,
+}
+
+/// Recibe un chunk de registros y arma su segmento parcial: guarda los datos de cada reseña y
+/// agrega su id a la lista de cada término distinto de su texto. El índice se arma con el texto
+/// original de las reseñas, sin sanear
+fn indexar_chunk(chunk: Vec<StringRecord>) -> SegmentoParcial {
+    let mut parcial = SegmentoParcial::default();
+    for record in chunk {
+        let (Some(review), Some(posicion)) = (
+            Review::parse_record(&record, &Saneamiento::default()),
+            record.position(),
+        ) else {
+            continue;
+        };
+        let id = parcial.documentos.len() as u32;
+        let mut tokens = terminos::tokenizar(&review.review, &review.language);
+        tokens.sort();
+        tokens.dedup();
+        for token in tokens {
+            parcial.postings.entry(token).or_default().push(id);
+        }
+        parcial.documentos.push((
+            posicion.byte(),
+            review.app_name,
+            review.language,
+            review.votes_helpful,
+        ));
+    }
+    parcial
+}
+
+/// Devuelve la posición de un nombre dentro de la lista, agregándolo si no estaba
+fn posicion_nombre(
+    nombre: String,
+    lista: &mut Vec<String>,
+    posiciones: &mut HashMap<String, u32>,
+) -> u32 {
+    *posiciones.entry(nombre).or_insert_with_key(|nombre| {
+        lista.push(nombre.clone());
+        (lista.len() - 1) as u32
+    })
+}
+
+/// Recibe el path de un csv y construye su segmento. Los chunks se indexan en paralelo y después
+/// se combinan en orden, corriendo los ids de cada chunk para que las listas de cada término
+/// queden ordenadas. Devuelve un error si no se pudo leer el archivo
+fn construir_segmento(path: &Path) -> Result<Segmento, ErrorApp> {
+    let parciales: Vec<SegmentoParcial> = parsear_csv::leer_chunks(path)?
+        .into_par_iter()
+        .map(indexar_chunk)
+        .collect();
+
+    let mut segmento = Segmento {
+        archivo: path.to_path_buf(),
+        ..Segmento::default()
+    };
+    let mut juegos = HashMap::new();
+    let mut idiomas = HashMap::new();
+    for parcial in parciales {
+        let base = segmento.documentos.len() as u32;
+        for (offset, juego, idioma, votos) in parcial.documentos {
+            segmento.documentos.push(Documento {
+                offset,
+                juego: posicion_nombre(juego, &mut segmento.juegos, &mut juegos),
+                idioma: posicion_nombre(idioma, &mut segmento.idiomas, &mut idiomas),
+                votos,
+            });
+        }
+        for (termino, ids) in parcial.postings {
+            segmento
+                .postings
+                .entry(termino)
+                .or_default()
+                .extend(ids.into_iter().map(|id| id + base));
+        }
+    }
+    Ok(segmento)
+}
+
+impl Segmento {
+    /// Escribe el segmento en el directorio recibido: segmento.json con los nombres, documentos.bin
+    /// con los documentos de tamaño fijo, diccionario.bin con cada término y la posición de su
+    /// lista, y postings.bin con las listas de ids. Todos los números se guardan en little endian
+    fn escribir(&self, directorio: &Path) -> io::Result<()> {
+        fs::create_dir_all(directorio)?;
+        let meta = MetaSegmento {
+            archivo: self.archivo.clone(),
+            juegos: self.juegos.clone(),
+            idiomas: self.idiomas.clone(),
+            documentos: self.documentos.len(),
+        };
+        serde_json::to_writer(File::create(directorio.join(ARCHIVO_SEGMENTO))?, &meta)?;
+
+        let mut documentos = BufWriter::new(File::create(directorio.join(ARCHIVO_DOCUMENTOS))?);
+        for documento in &self.documentos {
+            documentos.write_all(&documento.offset.to_le_bytes())?;
+            documentos.write_all(&documento.juego.to_le_bytes())?;
+            documentos.write_all(&documento.idioma.to_le_bytes())?;
+            documentos.write_all(&documento.votos.to_le_bytes())?;
+        }
+        documentos.flush()?;
+
+        let mut diccionario = BufWriter::new(File::create(directorio.join(ARCHIVO_DICCIONARIO))?);
+        let mut postings = BufWriter::new(File::create(directorio.join(ARCHIVO_POSTINGS))?);
+        let mut posicion: u64 = 0;
+        for (termino, ids) in &self.postings {
+            diccionario.write_all(&(termino.len() as u32).to_le_bytes())?;
+            diccionario.write_all(termino.as_bytes())?;
+            diccionario.write_all(&posicion.to_le_bytes())?;
+            diccionario.write_all(&(ids.len() as u32).to_le_bytes())?;
+            for id in ids {
+                postings.write_all(&id.to_le_bytes())?;
+            }
+            posicion += ids.len() as u64;
+        }
+        diccionario.flush()?;
+        postings.flush()
+    }
+}
+
+/// Resultado de construir un índice
+/// documentos es la cantidad de reseñas indexadas
+/// fallidos son los errores de los archivos que no se pudieron indexar, si se pidió seguir ante
+///     errores
+pub struct Indexado {
+    pub documentos: usize,
+    pub fallidos: Vec<ErrorApp>,
+}
+
+/// Path del directorio del segmento con el número recibido
+fn directorio_segmento(directorio: &Path, numero: usize) -> PathBuf {
+    directorio.join(format!("segmento_{}", numero))
+}
+
+/// Recibe los csv a indexar y el directorio del índice. Construye y escribe un segmento por cada
+/// archivo en paralelo, y al final escribe indice.json. Los archivos se ordenan por nombre para
+/// que el mismo directorio genere siempre el mismo índice.
+/// Si un archivo no se puede leer se devuelve su error, salvo que seguir_con_errores sea true: en
+/// ese caso el archivo se saltea y los segmentos restantes se renumeran para que queden
+/// consecutivos. Los errores al escribir el índice siempre se devuelven
+pub fn indexar(
+    archivos: &[PathBuf],
+    directorio: &Path,
+    seguir_con_errores: bool,
+) -> Result<Indexado, ErrorApp> {
+    let mut archivos = archivos.to_vec();
+    archivos.sort();
+    fs::create_dir_all(directorio).map_err(|e| ErrorApp::io(directorio, e))?;
+
+    let resultados = archivos
+        .par_iter()
+        .enumerate()
+        .map(|(i, path)| match construir_segmento(path) {
+            Ok(segmento) => {
+                let destino = directorio_segmento(directorio, i);
+                segmento
+                    .escribir(&destino)
+                    .map_err(|e| ErrorApp::io(&destino, e))?;
+                Ok(Ok(segmento.documentos.len()))
+            }
+            Err(e) if seguir_con_errores => Ok(Err(e)),
+            Err(e) => Err(e),
+        })
+        .collect::<Result<Vec<Result<usize, ErrorApp>>, ErrorApp>>()?;
+
+    let mut indexado = Indexado {
+        documentos: 0,
+        fallidos: Vec::new(),
+    };
+    let mut segmentos = 0;
+    for (i, resultado) in resultados.into_iter().enumerate() {
+        match resultado {
+            Ok(documentos) => {
+                if i != segmentos {
+                    renombrar_segmento(directorio, i, segmentos)?;
+                }
+                indexado.documentos += documentos;
+                segmentos += 1;
+            }
+            Err(e) => indexado.fallidos.push(e),
+        }
+    }
+
+    let meta = MetaIndice {
+        version: VERSION_INDICE,
+        segmentos,
+    };
+    let path = directorio.join(ARCHIVO_INDICE);
+    let archivo = File::create(&path).map_err(|e| ErrorApp::io(&path, e))?;
+    serde_json::to_writer(archivo, &meta)
+        .map_err(|error| ErrorApp::Serializacion { path, error })?;
+    Ok(indexado)
+}
+
+/// Mueve un segmento a otro número, borrando lo que hubiera quedado con ese número de un índice
+/// anterior en el mismo directorio
+fn renombrar_segmento(directorio: &Path, desde: usize, hasta: usize) -> Result<(), ErrorApp> {
+    let destino = directorio_segmento(directorio, hasta);
+    if destino.exists() {
+        fs::remove_dir_all(&destino).map_err(|e| ErrorApp::io(&destino, e))?;
+    }
+    fs::rename(directorio_segmento(directorio, desde), &destino)
+        .map_err(|e| ErrorApp::io(&destino, e))
+}
+
+/// Struct que representa un segmento ya escrito, abierto para consultas
+/// archivo es el csv del que se leyeron las reseñas
+/// juegos e idiomas son los nombres referenciados por los documentos
+/// documentos son las reseñas indexadas
+/// diccionario es un mapa donde la clave es un término y el valor la posición y el largo de su
+///     lista en postings.bin
+/// postings es el path del archivo con las listas de ids, que se leen a medida que se necesitan
+pub struct SegmentoIndexado {
+    pub archivo: PathBuf,
+    pub juegos: Vec<String>,
+    pub idiomas: Vec<String>,
+    pub documentos: Vec<Documento>,
+    diccionario: HashMap<String, (u64, u32)>,
+    postings: PathBuf,
+}
+
+/// Crea un error de datos inválidos con el mensaje recibido
+fn error_formato(mensaje: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, mensaje)
+}
+
+/// Lee un u32 o un u64 en little endian desde un slice que se sabe que tiene el largo justo
+fn u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().unwrap_or_default())
+}
+
+fn u64_le(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap_or_default())
+}
+
+impl SegmentoIndexado {
+    /// Abre un segmento leyendo sus nombres, sus documentos y su diccionario
+    fn abrir(directorio: &Path) -> io::Result<SegmentoIndexado> {
+        let meta: MetaSegmento = serde_json::from_reader(BufReader::new(File::open(
+            directorio.join(ARCHIVO_SEGMENTO),
+        )?))?;
+
+        let bytes = fs::read(directorio.join(ARCHIVO_DOCUMENTOS))?;
+        if bytes.len() != meta.documentos * TAMANO_DOCUMENTO {
+            return Err(error_formato(format!(
+                "{} no tiene la cantidad de documentos esperada",
+                directorio.display()
+            )));
+        }
+        let documentos = bytes
+            .chunks_exact(TAMANO_DOCUMENTO)
+            .map(|d| Documento {
+                offset: u64_le(&d[0..8]),
+                juego: u32_le(&d[8..12]),
+                idioma: u32_le(&d[12..16]),
+                votos: u32_le(&d[16..20]),
+            })
+            .collect();
+
+        let bytes = fs::read(directorio.join(ARCHIVO_DICCIONARIO))?;
+        let mut diccionario = HashMap::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let invalido = || error_formato(format!("{} está corrupto", directorio.display()));
+            let largo = u32_le(bytes.get(i..i + 4).ok_or_else(invalido)?) as usize;
+            let termino = bytes.get(i + 4..i + 4 + largo).ok_or_else(invalido)?;
+            let termino = String::from_utf8(termino.to_vec()).map_err(|_| invalido())?;
+            let datos = bytes
+                .get(i + 4 + largo..i + 16 + largo)
+                .ok_or_else(invalido)?;
+            diccionario.insert(termino, (u64_le(&datos[0..8]), u32_le(&datos[8..12])));
+            i += 16 + largo;
+        }
+
+        Ok(SegmentoIndexado {
+            archivo: meta.archivo,
+            juegos: meta.juegos,
+            idiomas: meta.idiomas,
+            documentos,
+            diccionario,
+            postings: directorio.join(ARCHIVO_POSTINGS),
+        })
+    }
+
+    /// Devuelve los ids ordenados de los documentos que contienen el término. Si el término no
+    /// está en el segmento devuelve una lista vacía
+    pub fn postings(&self, termino: &str) -> io::Result<Vec<u32>> {
+        let Some((posicion, largo)) = self.diccionario.get(termino) else {
+            return Ok(Vec::new());
+        };
+        let mut archivo = File::open(&self.postings)?;
+        archivo.seek(SeekFrom::Start(posicion * 4))?;
+        let mut bytes = vec![0; *largo as usize * 4];
+        archivo.read_exact(&mut bytes)?;
+        Ok(bytes.chunks_exact(4).map(u32_le).collect())
+    }
+
+    /// Lee del csv original la reseña de un documento
+    pub fn leer_review(&self, documento: &Documento) -> io::Result<Option<Review>> {
+        let mut archivo = File::open(&self.archivo)?;
+        archivo.seek(SeekFrom::Start(documento.offset))?;
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(BufReader::new(archivo));
+        let mut record = StringRecord::new();
+        if !reader.read_record(&mut record).map_err(io::Error::other)? {
+            return Ok(None);
+        }
+        Ok(Review::parse_record(&record, &Saneamiento::default()))
+    }
+}
+
+/// Abre todos los segmentos del índice que está en el directorio recibido. Devuelve un error si el
+/// índice fue generado con otra versión del formato
+pub fn abrir_indice(directorio: &Path) -> io::Result<Vec<SegmentoIndexado>> {
+    let meta: MetaIndice =
+        serde_json::from_reader(BufReader::new(File::open(directorio.join(ARCHIVO_INDICE))?))?;
+    if meta.version != VERSION_INDICE {
+        return Err(error_formato(format!(
+            "El índice tiene la versión {} y se esperaba la {}",
+            meta.version, VERSION_INDICE
+        )));
+    }
+    (0..meta.segmentos)
+        .map(|i| SegmentoIndexado::abrir(&directorio_segmento(directorio, i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexar_y_abrir_un_csv() {
+        let directorio = std::env::temp_dir().join("indice_test");
+        let _ = fs::remove_dir_all(&directorio);
+        fs::create_dir_all(&directorio).unwrap();
+        let csv = directorio.join("reviews.csv");
+        fs::write(
+            &csv,
+            "index,app_id,app_name,review_id,language,review,a,b,c,votes_helpful\n\
+             0,1,FIFA,1,english,\"Crash on startup, again\",-,-,-,5\n\
+             1,1,FIFA,2,spanish,Muy buen juego,-,-,-,3\n\
+             2,2,Terraria,3,english,\"Never crash, great\",-,-,-,9\n",
+        )
+        .unwrap();
+
+        let indexado = indexar(&[csv], &directorio.join("indice"), false).unwrap();
+        let segmentos = abrir_indice(&directorio.join("indice")).unwrap();
+
+        assert_eq!(indexado.documentos, 3);
+        assert_eq!(segmentos.len(), 1);
+        let segmento = &segmentos[0];
+        assert_eq!(segmento.postings("crash").unwrap(), vec![0, 2]);
+        assert_eq!(segmento.postings("juego").unwrap(), vec![1]);
+        assert!(segmento.postings("inexistente").unwrap().is_empty());
+        assert_eq!(
+            segmento.juegos[segmento.documentos[2].juego as usize],
+            "Terraria"
+        );
+
+        let review = segmento
+            .leer_review(&segmento.documentos[2])
+            .unwrap()
+            .unwrap();
+        assert_eq!(review.review, "Never crash, great");
+        assert_eq!(review.votes_helpful, 9);
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+
+    #[test]
+    fn indexar_salteando_archivos_fallidos() {
+        let directorio = std::env::temp_dir().join("indice_fallidos_test");
+        let _ = fs::remove_dir_all(&directorio);
+        fs::create_dir_all(&directorio).unwrap();
+        let inexistente = directorio.join("a.csv");
+        let csv = directorio.join("b.csv");
+        fs::write(
+            &csv,
+            "index,app_id,app_name,review_id,language,review,a,b,c,votes_helpful\n\
+             0,1,FIFA,1,english,Crash on startup,-,-,-,5\n",
+        )
+        .unwrap();
+        let archivos = [inexistente, csv];
+        let indice = directorio.join("indice");
+
+        assert!(matches!(
+            indexar(&archivos, &indice, false),
+            Err(ErrorApp::Io { .. })
+        ));
+        let indexado = indexar(&archivos, &indice, true).unwrap();
+        assert_eq!(indexado.documentos, 1);
+        assert_eq!(indexado.fallidos.len(), 1);
+        let segmentos = abrir_indice(&indice).unwrap();
+        assert_eq!(segmentos.len(), 1);
+        assert_eq!(segmentos[0].postings("crash").unwrap(), vec![0]);
+        fs::remove_dir_all(&directorio).unwrap();
+    }
+}
@@ -1,11 +1,12 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 /// Struct que representa la salida de un juego.
 /// nombre es el nombre del juego
 /// reviews es la cantidad de reseñas que obtuvo
 /// idiomas es un vector con los idiomas en los que se escribieron reseñas para dicho juego,
 ///     junto con sus reseñas y votos
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct JuegoMasVotado {
     #[serde(rename = "game")]
     pub nombre: String,
@@ -21,7 +22,9 @@ pub struct JuegoMasVotado {
 /// top_review es el contenido de la review en un idioma con la mayor cantidad de votos
 /// top_review_votos es la cantidad de votos que obtuvo la reseña con más votos para un juego en
 ///     un idioma
-#[derive(Serialize)]
+/// top_review_truncada indica si el texto de la reseña con más votos se truncó al sanearlo. Sólo
+///     aparece en la salida si es true
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct IdiomaPorJuego {
     #[serde(rename = "language")]
     pub idioma: String,
@@ -31,4 +34,10 @@ pub struct IdiomaPorJuego {
     pub top_review: String,
     #[serde(rename = "top_review_votes")]
     pub top_review_votos: u32,
+    #[serde(
+        rename = "top_review_truncated",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub top_review_truncada: bool,
 }
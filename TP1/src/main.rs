@@ -1,94 +1,390 @@
+mod alias_idiomas;
+mod archivo;
+mod archivo_output;
+mod checkpoint;
+mod colacion;
 mod configurar_argumentos;
+mod consulta;
+mod deteccion_output;
+mod detectar_idioma;
+mod diferencias;
+mod distribucion;
+mod distribucion_output;
+mod ejecutor;
+mod error;
+mod esquema;
 mod estadisticas;
 mod formatear_datos_json;
 mod idioma;
 mod idioma_output;
+mod indice;
 mod juego;
 mod juego_output;
+mod matriz;
+mod matriz_output;
+mod metadatos;
+mod metadatos_output;
+mod muestreo;
+mod muestreo_output;
+mod palabras_vacias;
 mod parsear_csv;
+mod progreso;
+#[cfg(test)]
+mod pruebas;
 mod review;
+mod saneamiento;
+mod segmento;
+mod servidor;
+mod terminos;
+mod terminos_output;
 
+use crate::configurar_argumentos::{
+    Argumentos, ArgumentosComparar, ArgumentosConsulta, ArgumentosIndice, ArgumentosServidor,
+    Comando, EstrategiaEjecucion, Opciones, OpcionesCheckpoint,
+};
+use crate::ejecutor::{Ejecutor, EjecutorPipeline, EjecutorRayon, EjecutorThreads};
+use crate::error::ErrorApp;
 use crate::estadisticas::Estadisticas;
 
 use crate::formatear_datos_json::Output;
-use rayon::prelude::*;
+use crate::progreso::Progreso;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::fs;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 /// Obtiene todos los archivos que se encuentren en la ruta del directorio recibida por parámetro
-/// y los devuelve cómo un vector de Paths
-fn obtener_archivos(ruta_archivo: &str) -> Vec<PathBuf> {
-    let files = fs::read_dir(ruta_archivo).expect("Failed to read ruta archivos");
-    files
+/// y los devuelve cómo un vector de Paths. Devuelve un error si no se pudo leer el directorio
+fn obtener_archivos(ruta_archivo: &str) -> Result<Vec<PathBuf>, ErrorApp> {
+    let files = fs::read_dir(ruta_archivo).map_err(|e| ErrorApp::io(Path::new(ruta_archivo), e))?;
+    Ok(files
         .filter_map(Result::ok)
         .map(|entry| entry.path())
         .filter(|path| path.extension().map(|ext| ext == "csv").unwrap_or(false))
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>())
 }
 
 /// Recibe la cantidad de threads que se quieren correr en paralelo y crea la ThreadPool
-fn lanzar_thread_pool(n: usize) -> ThreadPool {
+fn lanzar_thread_pool(n: usize) -> Result<ThreadPool, ErrorApp> {
     ThreadPoolBuilder::new()
         .num_threads(n)
         .build()
-        .expect("Failed to create thread pool")
+        .map_err(|e| ErrorApp::Configuracion(format!("No se pudo crear el ThreadPool: {}", e)))
+}
+
+/// Crea el ejecutor de la estrategia recibida con n threads (ver ejecutor.rs)
+fn crear_ejecutor(
+    estrategia: EstrategiaEjecucion,
+    n: usize,
+) -> Result<Box<dyn Ejecutor>, ErrorApp> {
+    Ok(match estrategia {
+        EstrategiaEjecucion::Rayon => Box::new(EjecutorRayon {
+            pool: lanzar_thread_pool(n)?,
+        }),
+        EstrategiaEjecucion::Threads => Box::new(EjecutorThreads { n_threads: n }),
+        EstrategiaEjecucion::Pipeline => Box::new(EjecutorPipeline { n_workers: n }),
+    })
+}
+
+/// Resultado de procesar todos los archivos csv
+/// estadisticas son las estadísticas combinadas de todos los archivos que se pudieron leer
+/// fallidos son los errores de los archivos que no se pudieron leer, si se pidió seguir ante
+///     errores
+struct Procesamiento {
+    estadisticas: Estadisticas,
+    fallidos: Vec<ErrorApp>,
 }
 
 /// Recibe el vector con los archivos y una referencia mutable de una instancia de Estadisticas,
-/// así como también un ThreadPool.
-/// Lanza el ThreadPool y en paralelo recorre los archivos en 'archivos' usando par_iter()
-/// A cada archivo de 'archivos' le aplica de forma concurrente la funcion 'procesar_csv()'
-/// Luego al final, combina todas las estadísticas recibidas de los distintos archivos en una
-/// instancia de Estadisticas y crea el Output para realizar la salida
-fn procesar_archivos(archivos: Vec<PathBuf>, mut e: Estadisticas, pool: ThreadPool) -> Output {
-    pool.install(|| {
-        e = archivos
-            .par_iter()
-            .map(|path| parsear_csv::procesar_csv(path))
-            .reduce(
-                || Estadisticas {
-                    juegos: std::collections::HashMap::new(),
-                    idiomas: std::collections::HashMap::new(),
-                },
-                estadisticas::combinar_estadisticas,
-            )
-    });
+/// así como también la cantidad de threads y las opciones de análisis.
+/// Procesa los archivos en paralelo con el ejecutor elegido en las opciones (ver ejecutor.rs), que
+/// a cada archivo le aplica el procesamiento de sus chunks y al final combina todas las
+/// estadísticas recibidas de los distintos archivos en una instancia de Estadisticas
+/// Si algún archivo no se puede leer se devuelve su error, salvo que en las opciones se haya
+/// pedido seguir ante errores: en ese caso el archivo se saltea y su error se devuelve junto con
+/// las estadísticas del resto
+/// Mientras se procesan los archivos se informa el progreso según las opciones (ver progreso.rs),
+/// y al terminar se imprime cuánto tardó el ejecutor
+/// Si se muestrearon las reseñas, una vez combinadas todas las estadísticas se completan los
+/// estratos con menos reseñas muestreadas que el mínimo y, si se pidieron conteos extrapolados, se
+/// extrapolan las cantidades (ver muestreo.rs)
+/// Si se recibieron opciones de checkpoint, el avance se guarda periódicamente y se puede
+/// reanudar (ver checkpoint.rs). Los checkpoints siempre usan el ThreadPool de rayon
+fn procesar_archivos(
+    archivos: Vec<PathBuf>,
+    mut e: Estadisticas,
+    n_threads: usize,
+    opciones: &Opciones,
+    checkpoint: Option<&OpcionesCheckpoint>,
+) -> Result<Procesamiento, ErrorApp> {
+    let progreso = Progreso::new(opciones.progreso, &archivos, n_threads);
+    let inicio = Instant::now();
+    let (nombre, (procesadas, fallidos)) = match checkpoint {
+        Some(checkpoint) => {
+            let pool = lanzar_thread_pool(n_threads)?;
+            let resultado = progreso.reportar(|| {
+                checkpoint::procesar(archivos, &pool, opciones, &progreso, checkpoint)
+            })?;
+            ("rayon", resultado)
+        }
+        None => {
+            let ejecutor = crear_ejecutor(opciones.ejecutor, n_threads)?;
+            let resultado =
+                progreso.reportar(|| ejecutor.procesar(&archivos, opciones, &progreso))?;
+            (ejecutor.nombre(), resultado)
+        }
+    };
+    if !opciones.silencioso {
+        println!(
+            "Procesamiento con {}: {:.3} segs",
+            nombre,
+            inicio.elapsed().as_secs_f64()
+        );
+    }
+    e = estadisticas::combinar_estadisticas(e, procesadas);
+    if let Some(muestreo) = &opciones.muestreo {
+        let registros = muestreo.completar_estratos(&mut e.muestreo);
+        e = estadisticas::combinar_estadisticas(
+            e,
+            parsear_csv::procesar_registros(registros, opciones),
+        );
+        muestreo.extrapolar(&mut e);
+    }
 
-    let o = Output::new(&e);
-    o
+    Ok(Procesamiento {
+        estadisticas: e,
+        fallidos,
+    })
 }
 
 /// Recibe el Output resultante de procesar todos los archivos csv y lo escribe en el archivo de
 /// salida que se recibió por línea de comandos al principio de la ejecución del programa
-fn escribir_resultado(resultado: Output, salida: &str) -> std::io::Result<()> {
-    let json = serde_json::to_string_pretty(&resultado).expect("Error al serializar salida");
-    let mut archivo = File::create(&salida)?;
-    archivo.write_all(json.as_bytes())?;
-    Ok(())
+fn escribir_resultado(resultado: Output, salida: &str) -> Result<(), ErrorApp> {
+    let path = Path::new(salida);
+    let json =
+        serde_json::to_string_pretty(&resultado).map_err(|error| ErrorApp::Serializacion {
+            path: path.to_path_buf(),
+            error,
+        })?;
+    let mut archivo = File::create(path).map_err(|e| ErrorApp::io(path, e))?;
+    archivo
+        .write_all(json.as_bytes())
+        .map_err(|e| ErrorApp::io(path, e))
 }
 
-fn main() -> std::io::Result<()> {
+/// Ejecuta el análisis de los csv y escribe el resultado en el archivo de salida, y la matriz de
+/// juegos por idiomas si se pidió exportarla. Devuelve los errores de los archivos que se
+/// saltearon
+fn analizar(args: Argumentos) -> Result<Vec<ErrorApp>, ErrorApp> {
+    let archivos = obtener_archivos(&args.ruta)?;
+
+    let estadisticas_totales = Estadisticas::default();
+
+    let procesamiento = procesar_archivos(
+        archivos,
+        estadisticas_totales,
+        args.n_threads,
+        &args.opciones,
+        args.checkpoint.as_ref(),
+    )?;
+
+    escribir_resultado(
+        Output::new(&procesamiento.estadisticas, &args.opciones),
+        &args.archivo_salida,
+    )?;
+    if let Some(exportacion) = &args.matriz {
+        let matriz = matriz::armar_matriz(
+            &procesamiento.estadisticas,
+            &args.opciones.alias_idiomas,
+            args.opciones.colacion,
+            exportacion.max_juegos,
+            exportacion.max_idiomas,
+        );
+        matriz::escribir_matriz(&matriz, &exportacion.archivo, exportacion.formato)?;
+    }
+    if let Some(checkpoint) = &args.checkpoint {
+        checkpoint::borrar(&checkpoint.archivo)?;
+    }
+    Ok(procesamiento.fallidos)
+}
+
+/// Construye el índice invertido de los csv del directorio recibido, indexando los archivos en
+/// paralelo dentro del ThreadPool. Devuelve los errores de los archivos que se saltearon
+fn indexar(args: ArgumentosIndice) -> Result<Vec<ErrorApp>, ErrorApp> {
+    let archivos = obtener_archivos(&args.ruta)?;
+    let pool = lanzar_thread_pool(args.n_threads.unwrap_or(0))?;
+    let indexado = pool.install(|| {
+        indice::indexar(
+            &archivos,
+            Path::new(&args.directorio_indice),
+            args.seguir_con_errores,
+        )
+    })?;
+    println!("Reseñas indexadas: {}", indexado.documentos);
+    Ok(indexado.fallidos)
+}
+
+/// Ejecuta una consulta sobre un índice ya construido e imprime los resultados en formato JSON
+/// por salida estándar
+fn consultar(args: ArgumentosConsulta) -> Result<Vec<ErrorApp>, ErrorApp> {
+    let idioma = args.filtros.idioma.as_deref().unwrap_or("");
+    let nodo = consulta::parsear(&args.consulta, idioma)
+        .map_err(|e| ErrorApp::Configuracion(format!("Consulta inválida: {}", e)))?;
+    let directorio = Path::new(&args.directorio_indice);
+    let segmentos = indice::abrir_indice(directorio).map_err(|e| ErrorApp::io(directorio, e))?;
+    let resultados = consulta::consultar(&segmentos, &nodo, &args.filtros)
+        .map_err(|e| ErrorApp::io(directorio, e))?;
+    let json =
+        serde_json::to_string_pretty(&resultados).map_err(|error| ErrorApp::Serializacion {
+            path: directorio.to_path_buf(),
+            error,
+        })?;
+    println!("{}", json);
+    Ok(Vec::new())
+}
+
+/// Calcula las estadísticas de los csv una sola vez y las sirve por HTTP hasta que se corte el
+/// programa. Si se saltearon archivos se informan antes de empezar a escuchar
+fn servir(args: ArgumentosServidor) -> Result<Vec<ErrorApp>, ErrorApp> {
     let start = Instant::now();
+    let archivos = obtener_archivos(&args.ruta)?;
+    let procesamiento = procesar_archivos(
+        archivos,
+        Estadisticas::default(),
+        args.n_threads,
+        &args.opciones,
+        None,
+    )?;
+    if !args.opciones.silencioso {
+        println!(
+            "Estadísticas calculadas en {} segs",
+            start.elapsed().as_secs()
+        );
+    }
+    informar_fallidos(&procesamiento.fallidos);
+    servidor::Servidor::new(procesamiento.estadisticas, args.opciones)
+        .escuchar(args.puerto)
+        .map_err(|e| ErrorApp::io(Path::new(&format!("localhost:{}", args.puerto)), e))?;
+    Ok(Vec::new())
+}
 
-    let args = match configurar_argumentos::parsear_argumentos() {
-        Some(a) => a,
-        None => std::process::exit(1),
-    };
+/// Código de salida de diff cuando los archivos son distintos
+const CODIGO_DIFERENTES: i32 = 1;
+/// Código de salida de diff cuando no se pudo leer alguno de los archivos, para distinguirlo de
+/// archivos distintos en los chequeos de regresión
+const CODIGO_ERROR_COMPARAR: i32 = 2;
+
+/// Compara dos archivos de salida e imprime sus diferencias. Devuelve el código de salida del
+/// programa: 0 si son iguales, 1 si son distintos y 2 si no se pudieron comparar
+fn comparar(args: ArgumentosComparar) -> i32 {
+    match diferencias::comparar_archivos(
+        Path::new(&args.archivo_a),
+        Path::new(&args.archivo_b),
+        args.ignorar_orden,
+    ) {
+        Ok(diferencias) if diferencias.is_empty() => {
+            println!("Los archivos son equivalentes");
+            0
+        }
+        Ok(diferencias) => {
+            for diferencia in &diferencias {
+                println!("{}", diferencia);
+            }
+            println!("{} diferencias", diferencias.len());
+            CODIGO_DIFERENTES
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            CODIGO_ERROR_COMPARAR
+        }
+    }
+}
 
-    let archivos = obtener_archivos(&args.ruta);
+/// Código de salida de validate cuando el archivo no cumple el esquema
+const CODIGO_INVALIDO: i32 = 1;
+/// Código de salida de validate cuando no se pudo leer el archivo, igual que en diff
+const CODIGO_ERROR_VALIDAR: i32 = 2;
 
-    let estadisticas_totales = Estadisticas::default();
+/// Valida un archivo de salida contra el JSON Schema e imprime la ruta de cada valor que no lo
+/// cumple. Devuelve el código de salida del programa: 0 si es válido, 1 si no cumple el esquema y
+/// 2 si no se pudo leer
+fn validar(archivo: &str) -> i32 {
+    match esquema::validar_archivo(Path::new(archivo)) {
+        Ok(violaciones) if violaciones.is_empty() => {
+            println!("El archivo cumple el esquema");
+            0
+        }
+        Ok(violaciones) => {
+            for violacion in &violaciones {
+                println!("{}", violacion);
+            }
+            println!("{} violaciones del esquema", violaciones.len());
+            CODIGO_INVALIDO
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            CODIGO_ERROR_VALIDAR
+        }
+    }
+}
 
-    let pool = lanzar_thread_pool(args.n_threads);
+/// Imprime por salida de error el resumen de los archivos que se saltearon con --keep-going
+fn informar_fallidos(fallidos: &[ErrorApp]) {
+    if fallidos.is_empty() {
+        return;
+    }
+    eprintln!(
+        "No se pudieron procesar {} archivos, los resultados son parciales:",
+        fallidos.len()
+    );
+    for error in fallidos {
+        eprintln!("  - {}", error);
+    }
+}
 
-    let resultado = procesar_archivos(archivos, estadisticas_totales, pool);
+fn main() {
+    let start = Instant::now();
 
-    escribir_resultado(resultado, &args.archivo_salida)?;
+    let resultado = configurar_argumentos::parsear_argumentos().and_then(|comando| {
+        let (fallidos, silencioso) = match comando {
+            Comando::Analizar(args) => {
+                let silencioso = args.opciones.silencioso;
+                (analizar(args)?, silencioso)
+            }
+            Comando::Indexar(args) => {
+                let silencioso = args.silencioso;
+                (indexar(args)?, silencioso)
+            }
+            // La consulta imprime JSON por salida estándar, así que no se le agrega la duración
+            Comando::Consultar(args) => return consultar(args),
+            Comando::Servir(args) => return servir(args),
+            Comando::Comparar(args) => std::process::exit(comparar(args)),
+            Comando::Esquema => {
+                // El esquema se genera de los structs de la salida, así que siempre se puede
+                // serializar
+                let esquema = serde_json::to_string_pretty(&esquema::generar()).unwrap_or_default();
+                println!("{}", esquema);
+                return Ok(Vec::new());
+            }
+            Comando::Validar(archivo) => std::process::exit(validar(&archivo)),
+        };
+        if !silencioso {
+            println!("Duración del programa: {} segs", start.elapsed().as_secs());
+        }
+        Ok(fallidos)
+    });
 
-    println!("Duración del programa: {} segs", start.elapsed().as_secs());
-    Ok(())
+    match resultado {
+        Ok(fallidos) if fallidos.is_empty() => {}
+        Ok(fallidos) => {
+            informar_fallidos(&fallidos);
+            std::process::exit(error::CODIGO_RESULTADO_PARCIAL);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(e.codigo_salida());
+        }
+    }
 }
@@ -0,0 +1,165 @@
+use crate::alias_idiomas::AliasIdiomas;
+use crate::colacion::Colacion;
+use crate::configurar_argumentos::FormatoMatriz;
+use crate::error::ErrorApp;
+use crate::estadisticas::Estadisticas;
+use crate::formatear_datos_json::Output;
+use crate::matriz_output::{FilaMatriz, MatrizJuegosIdiomas};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Recibe las estadísticas leídas y arma la matriz de cantidad de reseñas por juego e idioma.
+/// Los juegos se ordenan por cantidad de reseñas, desempatando por nombre según la colación, y
+/// los idiomas igual que en la sección de términos. Si se recibe un máximo de juegos o de idiomas, se dejan sólo los de más reseñas
+pub fn armar_matriz(
+    e: &Estadisticas,
+    alias: &AliasIdiomas,
+    colacion: Colacion,
+    max_juegos: Option<usize>,
+    max_idiomas: Option<usize>,
+) -> MatrizJuegosIdiomas {
+    let mut idiomas = Output::idiomas_ordenados(e, alias, colacion);
+    idiomas.truncate(max_idiomas.unwrap_or(usize::MAX));
+
+    let mut juegos: Vec<_> = e.juegos.iter().collect();
+    juegos.sort_by(|a, b| {
+        b.1.reviews
+            .cmp(&a.1.reviews)
+            .then_with(|| colacion.comparar(a.0, b.0))
+    });
+    juegos.truncate(max_juegos.unwrap_or(usize::MAX));
+
+    MatrizJuegosIdiomas {
+        juegos: juegos
+            .into_iter()
+            .map(|(nombre, juego)| FilaMatriz {
+                nombre: nombre.clone(),
+                total: juego.reviews as u32,
+                conteos: idiomas
+                    .iter()
+                    .map(|(idioma, _)| juego.idiomas.get(*idioma).copied().unwrap_or(0) as u32)
+                    .collect(),
+            })
+            .collect(),
+        idiomas: idiomas
+            .into_iter()
+            .map(|(_, nombre_visible)| nombre_visible.to_string())
+            .collect(),
+    }
+}
+
+/// Escribe la matriz en el archivo recibido con el formato indicado. En csv la primera fila tiene
+/// las columnas `game,total` seguidas de los idiomas, y cada una de las siguientes es un juego
+pub fn escribir_matriz(
+    matriz: &MatrizJuegosIdiomas,
+    salida: &str,
+    formato: FormatoMatriz,
+) -> Result<(), ErrorApp> {
+    let path = Path::new(salida);
+    match formato {
+        FormatoMatriz::Csv => escribir_csv(matriz, path),
+        FormatoMatriz::Json => {
+            let json =
+                serde_json::to_string_pretty(matriz).map_err(|error| ErrorApp::Serializacion {
+                    path: path.to_path_buf(),
+                    error,
+                })?;
+            let mut archivo = File::create(path).map_err(|e| ErrorApp::io(path, e))?;
+            archivo
+                .write_all(json.as_bytes())
+                .map_err(|e| ErrorApp::io(path, e))
+        }
+    }
+}
+
+/// Escribe la matriz en formato csv
+fn escribir_csv(matriz: &MatrizJuegosIdiomas, path: &Path) -> Result<(), ErrorApp> {
+    let archivo = File::create(path).map_err(|e| ErrorApp::io(path, e))?;
+    let mut writer = csv::Writer::from_writer(archivo);
+    let encabezado = ["game", "total"]
+        .into_iter()
+        .chain(matriz.idiomas.iter().map(String::as_str));
+    writer
+        .write_record(encabezado)
+        .map_err(|e| ErrorApp::csv(path, e))?;
+    for fila in &matriz.juegos {
+        let valores = [fila.nombre.clone(), fila.total.to_string()]
+            .into_iter()
+            .chain(fila.conteos.iter().map(u32::to_string));
+        writer
+            .write_record(valores)
+            .map_err(|e| ErrorApp::csv(path, e))?;
+    }
+    writer.flush().map_err(|e| ErrorApp::io(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::Review;
+
+    fn generar_estadisticas() -> Estadisticas {
+        let mut estadisticas = Estadisticas::default();
+        for (juego, idioma, cantidad) in [
+            ("Terraria", "english", 3),
+            ("Terraria", "spanish", 1),
+            ("Portal 2", "english", 1),
+            ("Portal 2", "russian", 1),
+            ("Rust", "russian", 1),
+        ] {
+            for _ in 0..cantidad {
+                estadisticas.agregar_review(Review {
+                    app_id: 1,
+                    app_name: juego.to_string(),
+                    language: idioma.to_string(),
+                    review: "".to_string(),
+                    votes_helpful: 0,
+                    truncada: false,
+                });
+            }
+        }
+        estadisticas
+    }
+
+    #[test]
+    fn matriz_completa_y_limitada() {
+        let estadisticas = generar_estadisticas();
+        let alias = AliasIdiomas::default();
+
+        let matriz = armar_matriz(&estadisticas, &alias, Colacion::Byte, None, None);
+        assert_eq!(matriz.idiomas, vec!["english", "russian", "spanish"]);
+        let nombres: Vec<&str> = matriz.juegos.iter().map(|f| f.nombre.as_str()).collect();
+        assert_eq!(nombres, vec!["Terraria", "Portal 2", "Rust"]);
+        assert_eq!(matriz.juegos[0].conteos, vec![3, 0, 1]);
+        assert_eq!(matriz.juegos[2].conteos, vec![0, 1, 0]);
+
+        let matriz = armar_matriz(&estadisticas, &alias, Colacion::Byte, Some(2), Some(1));
+        assert_eq!(matriz.idiomas, vec!["english"]);
+        assert_eq!(matriz.juegos.len(), 2);
+        assert_eq!(matriz.juegos[0].total, 4);
+        assert_eq!(matriz.juegos[0].conteos, vec![3]);
+    }
+
+    #[test]
+    fn escribir_matriz_csv() {
+        let estadisticas = generar_estadisticas();
+        let matriz = armar_matriz(
+            &estadisticas,
+            &AliasIdiomas::default(),
+            Colacion::Byte,
+            Some(2),
+            None,
+        );
+        let path = std::env::temp_dir().join("matriz_test.csv");
+
+        escribir_matriz(&matriz, path.to_str().unwrap(), FormatoMatriz::Csv).unwrap();
+        let contenido = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            contenido,
+            "game,total,english,russian,spanish\nTerraria,4,3,0,1\nPortal 2,2,1,1,0\n"
+        );
+    }
+}
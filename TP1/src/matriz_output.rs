@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+/// Struct que representa la matriz de cantidad de reseñas por juego e idioma que se exporta junto
+/// con la salida
+/// idiomas son los nombres visibles de los idiomas, en el orden de las columnas
+/// juegos son las filas de la matriz, una por juego
+#[derive(Serialize)]
+pub struct MatrizJuegosIdiomas {
+    #[serde(rename = "languages")]
+    pub idiomas: Vec<String>,
+    #[serde(rename = "games")]
+    pub juegos: Vec<FilaMatriz>,
+}
+
+/// Struct que representa una fila de la matriz
+/// nombre es el nombre del juego
+/// total es la cantidad de reseñas del juego en todos los idiomas, incluso los que no están en la
+///     matriz
+/// conteos son las cantidades de reseñas del juego en cada idioma, en el orden de las columnas
+#[derive(Serialize)]
+pub struct FilaMatriz {
+    #[serde(rename = "game")]
+    pub nombre: String,
+    pub total: u32,
+    #[serde(rename = "counts")]
+    pub conteos: Vec<u32>,
+}
@@ -0,0 +1,245 @@
+use crate::muestreo::{FNV_BASE, FNV_PRIMO};
+use crate::review::Review;
+use csv::ReaderBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Posiciones de las columnas del archivo de metadatos
+const POSICION_APP_ID: usize = 0;
+const POSICION_GENEROS: usize = 1;
+const POSICION_DESARROLLADOR: usize = 2;
+const POSICION_ANIO: usize = 3;
+/// Separador de los géneros dentro de la columna genres
+const SEPARADOR_GENEROS: char = ';';
+
+/// Struct que almacena los metadatos de un juego
+/// generos son los géneros del juego
+/// desarrollador es el nombre del desarrollador
+/// anio es el año de lanzamiento, si se conoce
+#[derive(Debug, Default, Clone)]
+pub struct MetadatosJuego {
+    pub generos: Vec<String>,
+    pub desarrollador: String,
+    pub anio: Option<u16>,
+}
+
+/// Struct que almacena los metadatos de los juegos cargados desde un csv local con las columnas
+/// `app_id,genres,developer,release_year`. Los géneros se separan con ';'
+/// juegos es un HashMap donde la clave es el app_id del juego y el valor sus metadatos
+#[derive(Debug, Default, Clone)]
+pub struct Metadatos {
+    pub juegos: HashMap<u32, MetadatosJuego>,
+}
+
+impl Metadatos {
+    /// Recibe el path del archivo de metadatos y lo carga. Devuelve un error con la descripción
+    /// del problema si el archivo no se puede leer o alguna fila tiene un app_id inválido
+    pub fn cargar(path: &Path) -> Result<Metadatos, String> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| format!("No se pudo abrir {}: {}", path.display(), e))?;
+
+        let mut metadatos = Metadatos::default();
+        for (fila, resultado) in reader.records().enumerate() {
+            let record = resultado.map_err(|e| format!("Error en {}: {}", path.display(), e))?;
+            let campo = |posicion| record.get(posicion).map(str::trim).unwrap_or("");
+
+            let app_id = campo(POSICION_APP_ID).parse::<u32>().map_err(|_| {
+                format!(
+                    "Error en {}: la fila {} tiene un app_id inválido",
+                    path.display(),
+                    fila + 2
+                )
+            })?;
+            let generos = campo(POSICION_GENEROS)
+                .split(SEPARADOR_GENEROS)
+                .map(str::trim)
+                .filter(|genero| !genero.is_empty())
+                .map(str::to_string)
+                .collect();
+
+            metadatos.juegos.insert(
+                app_id,
+                MetadatosJuego {
+                    generos,
+                    desarrollador: campo(POSICION_DESARROLLADOR).to_string(),
+                    anio: campo(POSICION_ANIO).parse::<u16>().ok(),
+                },
+            );
+        }
+        Ok(metadatos)
+    }
+
+    /// Devuelve un hash FNV-1a del contenido de los metadatos, recorriendo los juegos en orden de
+    /// app_id para que no dependa del orden de las filas del archivo. Lo usan los checkpoints para
+    /// no reanudar con otros metadatos
+    pub fn huella(&self) -> u64 {
+        let mut juegos: Vec<_> = self.juegos.iter().collect();
+        juegos.sort_unstable_by_key(|(app_id, _)| **app_id);
+        juegos.into_iter().fold(FNV_BASE, |hash, (app_id, juego)| {
+            let fila = format!(
+                "{}\t{}\t{}\t{:?}\n",
+                app_id,
+                juego.generos.join(";"),
+                juego.desarrollador,
+                juego.anio
+            );
+            fila.bytes().fold(hash, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIMO)
+            })
+        })
+    }
+}
+
+/// Struct que almacena las estadísticas de un grupo de juegos (un género, un desarrollador o un
+/// año de lanzamiento)
+/// reviews es la cantidad de reseñas de todos los juegos del grupo
+/// juegos es un HashMap donde la clave es el nombre de un juego del grupo y el valor su cantidad
+///     de reseñas
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Agrupacion {
+    pub reviews: usize,
+    pub juegos: HashMap<String, usize>,
+}
+
+impl Agrupacion {
+    /// Suma una reseña del juego recibido al grupo
+    fn agregar(&mut self, juego: &str) {
+        self.reviews += 1;
+        *self.juegos.entry(juego.to_string()).or_insert(0) += 1;
+    }
+
+    /// Suma las cantidades de otra Agrupacion a la actual
+    fn combinar(&mut self, otra: Agrupacion) {
+        self.reviews += otra.reviews;
+        for (juego, reviews) in otra.juegos {
+            *self.juegos.entry(juego).or_insert(0) += reviews;
+        }
+    }
+}
+
+/// Struct que almacena las estadísticas obtenidas al cruzar las reseñas con los metadatos
+/// generos, desarrolladores y anios son HashMaps donde la clave es el género, el desarrollador o
+///     el año de lanzamiento y el valor su Agrupacion
+/// encontradas es la cantidad de reseñas cuyo juego está en los metadatos
+/// no_encontradas es la cantidad de reseñas cuyo juego no está en los metadatos
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AgregadoMetadatos {
+    pub generos: HashMap<String, Agrupacion>,
+    pub desarrolladores: HashMap<String, Agrupacion>,
+    pub anios: HashMap<u16, Agrupacion>,
+    pub encontradas: usize,
+    pub no_encontradas: usize,
+}
+
+impl AgregadoMetadatos {
+    /// Busca los metadatos del juego de la review y suma la reseña a cada uno de sus géneros, a su
+    /// desarrollador y a su año de lanzamiento. Si el juego no está en los metadatos la reseña se
+    /// cuenta como no encontrada
+    pub fn agregar_review(&mut self, review: &Review, metadatos: &Metadatos) {
+        let Some(juego) = metadatos.juegos.get(&review.app_id) else {
+            self.no_encontradas += 1;
+            return;
+        };
+        self.encontradas += 1;
+        for genero in &juego.generos {
+            self.generos
+                .entry(genero.clone())
+                .or_default()
+                .agregar(&review.app_name);
+        }
+        if !juego.desarrollador.is_empty() {
+            self.desarrolladores
+                .entry(juego.desarrollador.clone())
+                .or_default()
+                .agregar(&review.app_name);
+        }
+        if let Some(anio) = juego.anio {
+            self.anios
+                .entry(anio)
+                .or_default()
+                .agregar(&review.app_name);
+        }
+    }
+
+    /// Combina las estadísticas de otra instancia con las actuales
+    pub fn combinar(&mut self, otro: AgregadoMetadatos) {
+        for (genero, agrupacion) in otro.generos {
+            self.generos.entry(genero).or_default().combinar(agrupacion);
+        }
+        for (desarrollador, agrupacion) in otro.desarrolladores {
+            self.desarrolladores
+                .entry(desarrollador)
+                .or_default()
+                .combinar(agrupacion);
+        }
+        for (anio, agrupacion) in otro.anios {
+            self.anios.entry(anio).or_default().combinar(agrupacion);
+        }
+        self.encontradas += otro.encontradas;
+        self.no_encontradas += otro.no_encontradas;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn review(app_id: u32, app_name: &str) -> Review {
+        Review {
+            app_id,
+            app_name: app_name.to_string(),
+            language: "english".to_string(),
+            review: "Nice".to_string(),
+            votes_helpful: 0,
+            truncada: false,
+        }
+    }
+
+    #[test]
+    fn cargar_archivo_de_metadatos() {
+        let path = std::env::temp_dir().join("metadatos_test.csv");
+        let mut archivo = std::fs::File::create(&path).unwrap();
+        writeln!(archivo, "app_id,genres,developer,release_year").unwrap();
+        writeln!(archivo, "292030,RPG; Open World,CD PROJEKT RED,2015").unwrap();
+        writeln!(archivo, "105600,Sandbox,Re-Logic,").unwrap();
+
+        let metadatos = Metadatos::cargar(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let witcher = &metadatos.juegos[&292030];
+        assert_eq!(witcher.generos, vec!["RPG", "Open World"]);
+        assert_eq!(witcher.desarrollador, "CD PROJEKT RED");
+        assert_eq!(witcher.anio, Some(2015));
+        assert_eq!(metadatos.juegos[&105600].anio, None);
+    }
+
+    #[test]
+    fn agregar_reviews_con_y_sin_metadatos() {
+        let mut metadatos = Metadatos::default();
+        metadatos.juegos.insert(
+            1,
+            MetadatosJuego {
+                generos: vec!["RPG".to_string(), "Action".to_string()],
+                desarrollador: "Estudio".to_string(),
+                anio: Some(2020),
+            },
+        );
+        let mut agregado = AgregadoMetadatos::default();
+
+        agregado.agregar_review(&review(1, "Juego 1"), &metadatos);
+        agregado.agregar_review(&review(1, "Juego 1"), &metadatos);
+        agregado.agregar_review(&review(2, "Juego 2"), &metadatos);
+
+        assert_eq!(agregado.encontradas, 2);
+        assert_eq!(agregado.no_encontradas, 1);
+        assert_eq!(agregado.generos["RPG"].reviews, 2);
+        assert_eq!(agregado.generos["Action"].juegos["Juego 1"], 2);
+        assert_eq!(agregado.desarrolladores["Estudio"].reviews, 2);
+        assert_eq!(agregado.anios[&2020].reviews, 2);
+    }
+}
@@ -0,0 +1,39 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Struct que representa la sección de metadatos del archivo de salida
+/// encontradas es la cantidad de reseñas cuyo juego está en el archivo de metadatos
+/// no_encontradas es la cantidad de reseñas cuyo juego no está en el archivo de metadatos
+/// generos es el ranking de los géneros con más reseñas
+/// desarrolladores es el ranking de los desarrolladores con más reseñas
+/// anios es el ranking de los años de lanzamiento con más reseñas
+#[derive(Serialize, JsonSchema)]
+pub struct SeccionMetadatos {
+    #[serde(rename = "matched_reviews")]
+    pub encontradas: u32,
+    #[serde(rename = "unmatched_reviews")]
+    pub no_encontradas: u32,
+    #[serde(rename = "top_genres")]
+    pub generos: Vec<AgrupacionMasVotada>,
+    #[serde(rename = "top_developers")]
+    pub desarrolladores: Vec<AgrupacionMasVotada>,
+    #[serde(rename = "top_release_years")]
+    pub anios: Vec<AgrupacionMasVotada>,
+}
+
+/// Struct que representa un grupo de juegos dentro de un ranking
+/// nombre es el género, el desarrollador o el año de lanzamiento
+/// reviews es la cantidad de reseñas de todos los juegos del grupo
+/// juegos es la cantidad de juegos distintos del grupo que tienen reseñas
+/// top_juego es el juego del grupo con más reseñas
+#[derive(Serialize, JsonSchema)]
+pub struct AgrupacionMasVotada {
+    #[serde(rename = "name")]
+    pub nombre: String,
+    #[serde(rename = "review_count")]
+    pub reviews: u32,
+    #[serde(rename = "game_count")]
+    pub juegos: u32,
+    #[serde(rename = "top_game")]
+    pub top_juego: String,
+}
@@ -0,0 +1,412 @@
+use crate::alias_idiomas::AliasIdiomas;
+use crate::colacion;
+use crate::estadisticas::Estadisticas;
+use crate::review::{POSICION_APP_NAME, POSICION_LANGUAGE};
+use clap::ValueEnum;
+use csv::StringRecord;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem;
+
+/// Constantes del hash FNV-1a de 64 bits con el que se decide si una reseña entra en la muestra.
+/// También se usa para la huella de los metadatos (ver metadatos.rs)
+pub const FNV_BASE: u64 = 0xcbf2_9ce4_8422_2325;
+pub const FNV_PRIMO: u64 = 0x0000_0100_0000_01b3;
+
+/// Cantidad mínima de reseñas de cada estrato que entran en la muestra si no se indica otra
+pub const MINIMO_ESTRATO_DEFAULT: usize = 10;
+
+/// Dimensión por la que se estratifica la muestra. Cada juego o cada idioma se muestrea con su
+/// propia proporción, de forma que ninguno quede con menos reseñas que el mínimo (ver
+/// Muestreo::completar_estratos), y se extrapola con su propio total de reseñas leídas, así que sus
+/// cantidades extrapoladas son exactas
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+pub enum Estrato {
+    #[value(name = "game")]
+    #[serde(rename = "game")]
+    Juego,
+    #[value(name = "language")]
+    #[serde(rename = "language")]
+    Idioma,
+}
+
+/// Forma en que se informan las cantidades de reseñas de un análisis muestreado
+/// Muestreadas son las cantidades de reseñas de la muestra, sin modificar
+/// Extrapoladas son las cantidades de la muestra escaladas al total de reseñas leídas
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Conteos {
+    #[default]
+    #[value(name = "sampled")]
+    #[serde(rename = "sampled")]
+    Muestreados,
+    #[value(name = "extrapolated")]
+    #[serde(rename = "extrapolated")]
+    Extrapolados,
+}
+
+/// Struct que almacena las opciones del muestreo de reseñas
+/// fraccion es la proporción de reseñas que entran en la muestra, entre 0 (excluido) y 1
+/// semilla es la semilla del hash que decide qué reseñas entran. Con la misma semilla y los mismos
+///     archivos la muestra es siempre la misma, sin importar la cantidad de threads
+/// estrato es la dimensión por la que se estratifica la muestra. Si es None todas las
+///     cantidades se escalan con la proporción global de reseñas muestreadas
+/// minimo_estrato es la cantidad mínima de reseñas de cada estrato que entran en la muestra. Los
+///     estratos con menos reseñas leídas entran completos. Sólo se usa si hay un estrato
+/// conteos es la forma de informar las cantidades de reseñas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Muestreo {
+    pub fraccion: f64,
+    pub semilla: u64,
+    pub estrato: Option<Estrato>,
+    pub minimo_estrato: usize,
+    pub conteos: Conteos,
+}
+
+/// Struct que almacena cuántas reseñas se leyeron y cuántas entraron en la muestra
+/// leidas y muestreadas son los totales de todas las reseñas
+/// por_estrato es un HashMap donde la clave es el juego o el idioma (con su alias aplicado) y el
+///     valor es la dupla de reseñas leídas y muestreadas de ese estrato. Sólo se completa si la
+///     muestra está estratificada
+/// candidatos es un HashMap donde la clave es el estrato y el valor son las reseñas de ese estrato
+///     que no entraron en la muestra, con el valor de su hash. Se guardan sólo las de menor valor,
+///     como mucho minimo_estrato, para completar los estratos con pocas reseñas muestreadas
+/// minimo_estrato es la cantidad mínima de reseñas por estrato con la que se muestreó
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TotalesMuestreo {
+    pub leidas: usize,
+    pub muestreadas: usize,
+    pub por_estrato: HashMap<String, (usize, usize)>,
+    pub candidatos: HashMap<String, Vec<(f64, Vec<String>)>>,
+    pub minimo_estrato: usize,
+}
+
+impl Muestreo {
+    /// Recibe un registro del csv y decide si entra en la muestra, sumándolo a los totales. La
+    /// decisión depende sólo del contenido del registro y de la semilla, así que es la misma en
+    /// cualquier thread y en cualquier chunk
+    /// Si la muestra está estratificada y el registro no entra, se guarda como candidato de su
+    /// estrato por si al terminar el estrato no llega al mínimo (ver completar_estratos)
+    pub fn incluir(
+        &self,
+        record: &StringRecord,
+        alias: &AliasIdiomas,
+        totales: &mut TotalesMuestreo,
+    ) -> bool {
+        let valor = self.valor(record);
+        let incluida = valor < self.fraccion;
+        totales.leidas += 1;
+        totales.muestreadas += incluida as usize;
+        if let Some(clave) = self
+            .estrato
+            .and_then(|estrato| clave_estrato(record, estrato, alias))
+        {
+            totales.minimo_estrato = self.minimo_estrato;
+            if !incluida {
+                totales.agregar_candidato(&clave, valor, record);
+            }
+            let (leidas, muestreadas) = totales.por_estrato.entry(clave).or_default();
+            *leidas += 1;
+            *muestreadas += incluida as usize;
+        }
+        incluida
+    }
+
+    /// Completa la muestra de los estratos que tienen menos reseñas muestreadas que el mínimo con
+    /// sus candidatos de menor valor, sumándolos a los totales. Así cada estrato se muestrea con su
+    /// propia proporción: la fracción pedida, o la necesaria para llegar al mínimo si con la
+    /// fracción no alcanzaba. Como los candidatos se eligen por el valor del hash, el resultado es
+    /// el mismo sin importar cómo se dividieron los registros en chunks
+    /// Devuelve los registros agregados a la muestra, ordenados por estrato, para que se parseen
+    /// (ver parsear_csv::procesar_registros)
+    pub fn completar_estratos(&self, totales: &mut TotalesMuestreo) -> Vec<StringRecord> {
+        let mut candidatos: Vec<_> = mem::take(&mut totales.candidatos).into_iter().collect();
+        candidatos.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut agregados = Vec::new();
+        for (clave, candidatos) in candidatos {
+            let Some((_, muestreadas)) = totales.por_estrato.get_mut(&clave) else {
+                continue;
+            };
+            let faltantes = self.minimo_estrato.saturating_sub(*muestreadas);
+            for (_, campos) in candidatos.into_iter().take(faltantes) {
+                *muestreadas += 1;
+                totales.muestreadas += 1;
+                agregados.push(StringRecord::from(campos));
+            }
+        }
+        agregados
+    }
+
+    /// Devuelve un valor pseudoaleatorio uniforme en [0, 1) a partir de la semilla y del
+    /// contenido del registro
+    fn valor(&self, record: &StringRecord) -> f64 {
+        let mut hash = FNV_BASE;
+        for byte in self
+            .semilla
+            .to_le_bytes()
+            .iter()
+            .chain(record.as_slice().as_bytes())
+        {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIMO);
+        }
+        // Se mezclan los bits (finalizador de splitmix64) para que los registros que difieren en
+        // pocos bytes den valores independientes
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Si se pidieron conteos extrapolados, escala las cantidades de reseñas por juego y por idioma
+    /// de las estadísticas al total de reseñas leídas. Con un estrato, cada juego o idioma se
+    /// escala con su propia proporción, y la otra dimensión se arma sumando las cantidades ya
+    /// escaladas. El resto de las secciones quedan con las cantidades de la muestra
+    pub fn extrapolar(&self, e: &mut Estadisticas) {
+        if self.conteos != Conteos::Extrapolados {
+            return;
+        }
+        let totales = &e.muestreo;
+        let global = proporcion(totales.leidas, totales.muestreadas);
+        let factor = |clave: &str| {
+            totales
+                .por_estrato
+                .get(clave)
+                .map(|(leidas, muestreadas)| proporcion(*leidas, *muestreadas))
+                .unwrap_or(global)
+        };
+
+        let mut por_idioma: HashMap<String, f64> = HashMap::new();
+        let mut por_juego: HashMap<String, usize> = HashMap::new();
+        for (nombre, juego) in &mut e.juegos {
+            let mut total_juego = 0.0;
+            for (idioma, cantidad) in &mut juego.idiomas {
+                let factor = match self.estrato {
+                    Some(Estrato::Juego) => factor(nombre),
+                    Some(Estrato::Idioma) => factor(idioma),
+                    None => global,
+                };
+                let escalada = *cantidad as f64 * factor;
+                total_juego += escalada;
+                *por_idioma.entry(idioma.clone()).or_default() += escalada;
+                *cantidad = escalada.round() as usize;
+            }
+            por_juego.insert(nombre.clone(), total_juego.round() as usize);
+        }
+        for (nombre, juego) in &mut e.juegos {
+            juego.reviews = por_juego[nombre];
+        }
+        for (nombre, idioma) in &mut e.idiomas {
+            idioma.cantidad_reviews =
+                por_idioma.get(nombre).copied().unwrap_or(0.0).round() as usize;
+        }
+    }
+}
+
+impl TotalesMuestreo {
+    /// Suma los totales de otro chunk, quedándose en cada estrato con los candidatos de menor valor
+    pub fn combinar(&mut self, otro: TotalesMuestreo) {
+        self.leidas += otro.leidas;
+        self.muestreadas += otro.muestreadas;
+        self.minimo_estrato = self.minimo_estrato.max(otro.minimo_estrato);
+        for (clave, (leidas, muestreadas)) in otro.por_estrato {
+            let total = self.por_estrato.entry(clave).or_default();
+            total.0 += leidas;
+            total.1 += muestreadas;
+        }
+        for (clave, otros) in otro.candidatos {
+            let candidatos = self.candidatos.entry(clave).or_default();
+            candidatos.extend(otros);
+            candidatos.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            candidatos.truncate(self.minimo_estrato);
+        }
+    }
+
+    /// Guarda el registro como candidato de su estrato si está entre los minimo_estrato de menor
+    /// valor que no entraron en la muestra. Los candidatos quedan ordenados por valor
+    fn agregar_candidato(&mut self, clave: &str, valor: f64, record: &StringRecord) {
+        if self.minimo_estrato == 0 {
+            return;
+        }
+        if !self.candidatos.contains_key(clave) {
+            self.candidatos.insert(clave.to_string(), Vec::new());
+        }
+        let candidatos = self
+            .candidatos
+            .get_mut(clave)
+            .expect("el estrato ya se insertó");
+        if candidatos.len() >= self.minimo_estrato
+            && candidatos
+                .last()
+                .is_some_and(|(ultimo, _)| *ultimo <= valor)
+        {
+            return;
+        }
+        let posicion = candidatos.partition_point(|(otro, _)| *otro <= valor);
+        candidatos.insert(
+            posicion,
+            (valor, record.iter().map(str::to_string).collect()),
+        );
+        candidatos.truncate(self.minimo_estrato);
+    }
+}
+
+/// Devuelve el juego o el idioma (con su alias aplicado) del registro, normalizado igual que al
+/// parsear la reseña
+fn clave_estrato(record: &StringRecord, estrato: Estrato, alias: &AliasIdiomas) -> Option<String> {
+    match estrato {
+        Estrato::Juego => Some(colacion::normalizar(record.get(POSICION_APP_NAME)?).into_owned()),
+        Estrato::Idioma => {
+            let idioma = colacion::normalizar(record.get(POSICION_LANGUAGE)?);
+            Some(
+                alias
+                    .grupo(&idioma)
+                    .cloned()
+                    .unwrap_or_else(|| idioma.into_owned()),
+            )
+        }
+    }
+}
+
+/// Devuelve cuántas reseñas leídas representa cada reseña muestreada
+fn proporcion(leidas: usize, muestreadas: usize) -> f64 {
+    if muestreadas == 0 {
+        0.0
+    } else {
+        leidas as f64 / muestreadas as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::Review;
+
+    fn registro(indice: usize, juego: &str, idioma: &str) -> StringRecord {
+        StringRecord::from(vec![
+            indice.to_string(),
+            "1".to_string(),
+            juego.to_string(),
+            indice.to_string(),
+            idioma.to_string(),
+            "Nice".to_string(),
+        ])
+    }
+
+    fn muestreo(estrato: Option<Estrato>) -> Muestreo {
+        Muestreo {
+            fraccion: 0.25,
+            semilla: 7,
+            estrato,
+            minimo_estrato: 3,
+            conteos: Conteos::Extrapolados,
+        }
+    }
+
+    #[test]
+    fn muestra_determinista() {
+        let registros: Vec<StringRecord> =
+            (0..4000).map(|i| registro(i, "FIFA", "english")).collect();
+        let elegir = |muestreo: Muestreo, registros: &[StringRecord]| -> Vec<usize> {
+            let mut totales = TotalesMuestreo::default();
+            registros
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| muestreo.incluir(r, &AliasIdiomas::default(), &mut totales))
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        let muestra = elegir(muestreo(None), &registros);
+        assert!((900..1100).contains(&muestra.len()));
+        // Decidir cada registro por separado da la misma muestra que decidirlos todos juntos
+        let (primera, segunda) = registros.split_at(1234);
+        let mut por_partes = elegir(muestreo(None), primera);
+        por_partes.extend(elegir(muestreo(None), segunda).iter().map(|i| i + 1234));
+        assert_eq!(muestra, por_partes);
+
+        let otra_semilla = Muestreo {
+            semilla: 8,
+            ..muestreo(None)
+        };
+        assert_ne!(muestra, elegir(otra_semilla, &registros));
+    }
+
+    #[test]
+    fn extrapolar_por_estrato() {
+        let mut e = Estadisticas::default();
+        let mut agregar = |juego: &str, idioma: &str, cantidad: usize| {
+            for _ in 0..cantidad {
+                e.agregar_review(Review {
+                    app_id: 1,
+                    app_name: juego.to_string(),
+                    language: idioma.to_string(),
+                    review: "Nice".to_string(),
+                    votes_helpful: 0,
+                    truncada: false,
+                });
+            }
+        };
+        agregar("FIFA", "english", 2);
+        agregar("FIFA", "spanish", 1);
+        agregar("Terraria", "english", 5);
+        e.muestreo = TotalesMuestreo {
+            leidas: 40,
+            muestreadas: 8,
+            por_estrato: HashMap::from([
+                ("FIFA".to_string(), (30, 3)),
+                ("Terraria".to_string(), (10, 5)),
+            ]),
+            ..TotalesMuestreo::default()
+        };
+
+        muestreo(Some(Estrato::Juego)).extrapolar(&mut e);
+        assert_eq!(e.juegos["FIFA"].reviews, 30);
+        assert_eq!(e.juegos["FIFA"].idiomas["english"], 20);
+        assert_eq!(e.juegos["Terraria"].reviews, 10);
+        assert_eq!(e.idiomas["english"].cantidad_reviews, 30);
+        assert_eq!(e.idiomas["spanish"].cantidad_reviews, 10);
+    }
+
+    #[test]
+    fn los_estratos_chicos_llegan_al_minimo() {
+        let mut registros: Vec<StringRecord> =
+            (0..4000).map(|i| registro(i, "FIFA", "english")).collect();
+        registros.extend((4000..4005).map(|i| registro(i, "Terraria", "english")));
+        registros.push(registro(4005, "Celeste", "english"));
+        let muestreo = Muestreo {
+            fraccion: 0.01,
+            ..muestreo(Some(Estrato::Juego))
+        };
+        let muestrear = |partes: &[&[StringRecord]]| {
+            let mut totales = TotalesMuestreo::default();
+            for parte in partes {
+                let mut parcial = TotalesMuestreo::default();
+                for record in *parte {
+                    muestreo.incluir(record, &AliasIdiomas::default(), &mut parcial);
+                }
+                totales.combinar(parcial);
+            }
+            let agregados = muestreo.completar_estratos(&mut totales);
+            (totales, agregados)
+        };
+
+        let (totales, agregados) = muestrear(&[&registros]);
+        let fifa = totales.por_estrato["FIFA"];
+        assert!(fifa.1 >= 3);
+        assert_eq!(totales.por_estrato["Terraria"], (5, 3));
+        assert_eq!(totales.por_estrato["Celeste"], (1, 1));
+        assert!(totales.candidatos.is_empty());
+        assert!(agregados.iter().all(|record| &record[2] != "FIFA"));
+        assert_eq!(
+            totales.muestreadas,
+            fifa.1 + totales.por_estrato["Terraria"].1 + 1
+        );
+
+        // Los candidatos no dependen de cómo se dividen los registros en chunks
+        let (primera, segunda) = registros.split_at(4002);
+        let (_, por_partes) = muestrear(&[primera, segunda]);
+        assert_eq!(agregados, por_partes);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::muestreo::{Conteos, Estrato};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Struct que representa la sección de muestreo del archivo de salida. Indica que el análisis se
+/// hizo sobre una muestra de las reseñas y cómo se informan sus cantidades
+/// fraccion es la proporción de reseñas que se pidió muestrear
+/// semilla es la semilla con la que se eligió la muestra
+/// estrato es la dimensión por la que se estratificó la muestra, si se estratificó
+/// minimo_estrato es la cantidad mínima de reseñas de cada estrato que entraron en la muestra.
+///     Sólo aparece si se estratificó
+/// conteos indica si las cantidades de reseñas de los juegos y los idiomas son las de la muestra
+///     o están extrapoladas al total
+/// leidas es la cantidad de reseñas leídas
+/// muestreadas es la cantidad de reseñas que entraron en la muestra
+#[derive(Serialize, JsonSchema)]
+pub struct SeccionMuestreo {
+    #[serde(rename = "fraction")]
+    pub fraccion: f64,
+    #[serde(rename = "seed")]
+    pub semilla: u64,
+    #[serde(rename = "stratify", skip_serializing_if = "Option::is_none")]
+    pub estrato: Option<Estrato>,
+    #[serde(rename = "stratum_min", skip_serializing_if = "Option::is_none")]
+    pub minimo_estrato: Option<usize>,
+    #[serde(rename = "counts")]
+    pub conteos: Conteos,
+    #[serde(rename = "reviews_read")]
+    pub leidas: u32,
+    #[serde(rename = "reviews_sampled")]
+    pub muestreadas: u32,
+}
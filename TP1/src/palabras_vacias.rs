@@ -0,0 +1,138 @@
+/// Listas de palabras vacías (stop-words) por idioma. Los nombres de los idiomas coinciden con los
+/// códigos que usa Steam en la columna `language` del csv. Las palabras ya están en minúscula y
+/// normalizadas, que es como quedan los tokens después de pasar por el tokenizador
+const INGLES: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doesn't", "don't", "even", "for", "from", "get", "got", "had", "has", "have", "he", "her",
+    "him", "his", "how", "i", "i'm", "i've", "if", "in", "into", "is", "isn't", "it", "it's",
+    "its", "just", "like", "me", "more", "most", "my", "no", "not", "now", "of", "on", "one",
+    "only", "or", "other", "out", "over", "really", "she", "so", "some", "still", "than", "that",
+    "the", "their", "them", "then", "there", "these", "they", "this", "those", "through", "to",
+    "too", "up", "very", "was", "we", "were", "what", "when", "which", "while", "who", "will",
+    "with", "would", "you", "you're", "your",
+];
+
+const ESPANOL: &[&str] = &[
+    "a", "al", "algo", "como", "con", "de", "del", "el", "ella", "en", "entre", "era", "es", "esa",
+    "ese", "eso", "esta", "este", "esto", "fue", "ha", "hay", "la", "las", "le", "les", "lo",
+    "los", "me", "mi", "mas", "más", "muy", "no", "nos", "o", "para", "pero", "por", "que", "qué",
+    "se", "si", "sin", "sobre", "son", "su", "sus", "también", "te", "tiene", "todo", "tu", "un",
+    "una", "uno", "y", "ya", "yo",
+];
+
+const PORTUGUES: &[&str] = &[
+    "a", "ao", "as", "com", "como", "da", "das", "de", "do", "dos", "e", "é", "ela", "ele", "em",
+    "essa", "esse", "esta", "este", "eu", "isso", "já", "mais", "mas", "me", "meu", "muito", "na",
+    "nao", "não", "no", "nos", "o", "os", "ou", "para", "pra", "por", "que", "se", "sem", "seu",
+    "sua", "tem", "um", "uma", "você",
+];
+
+const FRANCES: &[&str] = &[
+    "a", "au", "aux", "avec", "ce", "c'est", "dans", "de", "des", "du", "elle", "en", "est", "et",
+    "il", "j'ai", "je", "la", "le", "les", "leur", "mais", "me", "mon", "ne", "on", "ou", "par",
+    "pas", "plus", "pour", "qu'il", "que", "qui", "sa", "se", "ses", "son", "sur", "très", "un",
+    "une", "vous", "y",
+];
+
+const ALEMAN: &[&str] = &[
+    "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "das", "dass", "dem",
+    "den", "der", "des", "die", "du", "ein", "eine", "einen", "er", "es", "für", "hat", "ich",
+    "ihr", "im", "in", "ist", "man", "mit", "nicht", "noch", "nur", "oder", "sehr", "sich", "sie",
+    "sind", "so", "und", "von", "was", "wenn", "wie", "wir", "zu",
+];
+
+const RUSO: &[&str] = &[
+    "а",
+    "без",
+    "бы",
+    "в",
+    "во",
+    "вот",
+    "все",
+    "всё",
+    "вы",
+    "да",
+    "для",
+    "до",
+    "его",
+    "если",
+    "есть",
+    "же",
+    "за",
+    "и",
+    "из",
+    "или",
+    "им",
+    "их",
+    "к",
+    "как",
+    "когда",
+    "на",
+    "но",
+    "ну",
+    "о",
+    "от",
+    "по",
+    "так",
+    "там",
+    "то",
+    "только",
+    "ты",
+    "у",
+    "уже",
+    "что",
+    "это",
+    "я",
+];
+
+const TURCO: &[&str] = &[
+    "bir", "bu", "da", "de", "daha", "çok", "gibi", "için", "ile", "ama", "ve", "ne", "o", "en",
+    "her", "mi", "var", "yok", "ben", "sen",
+];
+
+const POLACO: &[&str] = &[
+    "a", "ale", "by", "co", "do", "i", "jak", "jest", "już", "na", "nie", "o", "od", "po", "się",
+    "tak", "to", "w", "z", "za", "że",
+];
+
+const ITALIANO: &[&str] = &[
+    "a", "al", "che", "con", "da", "del", "della", "di", "e", "è", "gli", "il", "in", "la", "le",
+    "lo", "ma", "mi", "non", "per", "più", "si", "un", "una",
+];
+
+/// En chino y japonés el tokenizador separa cada ideograma, por lo que las palabras vacías son
+/// caracteres sueltos (partículas y pronombres)
+const CHINO: &[&str] = &[
+    "的", "了", "是", "我", "你", "他", "她", "它", "们", "这", "那", "就", "都", "也", "还", "在",
+    "有", "不", "个", "吧", "啊", "吗", "呢", "和", "很", "們", "這", "個", "還",
+];
+
+const JAPONES: &[&str] = &[
+    "の", "に", "は", "を", "た", "が", "で", "て", "と", "し", "れ", "さ", "も", "な", "い", "る",
+    "か", "だ", "す", "ま",
+];
+
+/// Devuelve la lista de palabras vacías correspondiente a un idioma. Los idiomas que no tienen
+/// una lista definida devuelven un slice vacío
+fn lista_idioma(idioma: &str) -> &'static [&'static str] {
+    match idioma {
+        "english" => INGLES,
+        "spanish" | "latam" => ESPANOL,
+        "brazilian" | "portuguese" => PORTUGUES,
+        "french" => FRANCES,
+        "german" => ALEMAN,
+        "russian" => RUSO,
+        "turkish" => TURCO,
+        "polish" => POLACO,
+        "italian" => ITALIANO,
+        "schinese" | "tchinese" => CHINO,
+        "japanese" => JAPONES,
+        _ => &[],
+    }
+}
+
+/// Indica si una palabra (ya normalizada y en minúscula) es una palabra vacía en el idioma dado
+pub fn es_palabra_vacia(idioma: &str, palabra: &str) -> bool {
+    lista_idioma(idioma).contains(&palabra)
+}
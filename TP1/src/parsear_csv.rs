@@ -1,67 +1,291 @@
+use crate::archivo::Archivo;
+use crate::configurar_argumentos::Opciones;
+use crate::error::ErrorApp;
 use crate::estadisticas;
 use crate::estadisticas::Estadisticas;
+use crate::progreso::Progreso;
 use crate::review::Review;
-use csv::ReaderBuilder;
+use crate::segmento::Segmento;
+use csv::{Position, Reader, ReaderBuilder, StringRecord};
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
+use std::mem;
+use std::ops::ControlFlow;
 use std::path::Path;
 
 /// Tamaño en registros csv de cada chunk a procesar
 const CHUNK_SIZE: usize = 200_000;
 
-/// Recibe el path del arhcivo y lo abre. Separa el archivo en Chunks y realiza el procesamiento de
-/// cada chunk en paralelo, tanto para el parseo de las Reviews como para el procesamiento de las
-/// mismas. Al final, combina todas las Estadísticas obtenidas de los distintos chunks en una misma
-/// Estadística que es devuelta al terminar la función
-pub fn procesar_csv(path: &Path) -> Estadisticas {
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => {
-            return Estadisticas {
-                juegos: HashMap::new(),
-                idiomas: HashMap::new(),
-            };
+/// Recibe el path de un archivo csv y lo lee separando sus registros en chunks de CHUNK_SIZE
+/// registros. Los registros mal formados se reemplazan por un registro vacío en su misma posición
+/// (ver registro_invalido), y un archivo sin registros se lee como un único chunk vacío. Devuelve
+/// un error si no se pudo abrir el archivo, si el encabezado no tiene las columnas esperadas o si
+/// falla la lectura en medio del archivo
+pub fn leer_chunks(path: &Path) -> Result<Vec<Vec<StringRecord>>, ErrorApp> {
+    leer_chunks_desde(path, None)
+}
+
+/// Igual que leer_chunks, pero si se recibe una posición la lectura empieza en ella en lugar de
+/// al principio del archivo. La posición tiene que ser la del inicio de un registro, como las que
+/// se guardan en los checkpoints (ver checkpoint.rs)
+pub fn leer_chunks_desde(
+    path: &Path,
+    desde: Option<Position>,
+) -> Result<Vec<Vec<StringRecord>>, ErrorApp> {
+    let mut reader = abrir_csv(path, desde)?;
+
+    let mut chunks = Vec::new();
+    let mut actual = Vec::with_capacity(CHUNK_SIZE);
+
+    for resultado in reader.records() {
+        let record = match resultado {
+            Ok(record) => record,
+            Err(e) if e.is_io_error() => return Err(ErrorApp::csv(path, e)),
+            Err(e) => match registro_invalido(&e) {
+                Some(invalido) => invalido,
+                None => continue,
+            },
+        };
+        actual.push(record);
+        if actual.len() >= CHUNK_SIZE {
+            chunks.push(actual);
+            actual = Vec::with_capacity(CHUNK_SIZE);
         }
-    };
+    }
+
+    if !actual.is_empty() || chunks.is_empty() {
+        chunks.push(actual);
+    }
+    Ok(chunks)
+}
+
+/// Devuelve un registro vacío con la posición del registro mal formado que produjo el error, o
+/// None si el error no tiene posición. Al procesarlo no se puede parsear, así que se cuenta entre
+/// los registros inválidos del archivo sin cambiar dónde empiezan los chunks
+fn registro_invalido(error: &csv::Error) -> Option<StringRecord> {
+    let mut record = StringRecord::new();
+    record.set_position(Some(error.position()?.clone()));
+    Some(record)
+}
+
+/// Abre el csv y valida su encabezado. Si se recibe una posición, la lectura sigue desde ella
+fn abrir_csv(path: &Path, desde: Option<Position>) -> Result<Reader<File>, ErrorApp> {
+    let file = File::open(path).map_err(|e| ErrorApp::io(path, e))?;
 
     let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
-    let mut chunks = Vec::new();
+    let encabezado = reader.headers().map_err(|e| ErrorApp::csv(path, e))?;
+    Review::validar_encabezado(encabezado).map_err(|mensaje| ErrorApp::Esquema {
+        path: path.to_path_buf(),
+        linea: 1,
+        mensaje,
+    })?;
+    if let Some(posicion) = desde {
+        reader.seek(posicion).map_err(|e| ErrorApp::csv(path, e))?;
+    }
+    Ok(reader)
+}
+
+/// Recorre el csv sin cargarlo completo en memoria: a medida que lee cada chunk de CHUNK_SIZE
+/// registros llama a `procesar` con el chunk y los bytes que ocupa en el archivo (el primero
+/// incluye el encabezado). Un archivo sin registros se recorre como un único chunk vacío, para
+/// que igual se cuenten sus bytes. Si `procesar` devuelve Break se deja de leer
+/// Devuelve los mismos errores que leer_chunks
+pub fn recorrer_chunks(
+    path: &Path,
+    mut procesar: impl FnMut(Vec<StringRecord>, u64) -> ControlFlow<()>,
+) -> Result<(), ErrorApp> {
+    let mut reader = abrir_csv(path, None)?;
     let mut actual = Vec::with_capacity(CHUNK_SIZE);
+    let mut inicio = 0;
+    let mut hubo_chunks = false;
+    let mut record = StringRecord::new();
 
-    for result in reader.records() {
-        if let Ok(record) = result {
-            actual.push(record);
-            if actual.len() >= CHUNK_SIZE {
-                chunks.push(actual);
-                actual = Vec::with_capacity(CHUNK_SIZE);
+    loop {
+        match reader.read_record(&mut record) {
+            Ok(true) => actual.push(mem::take(&mut record)),
+            Ok(false) => break,
+            Err(e) if e.is_io_error() => return Err(ErrorApp::csv(path, e)),
+            Err(e) => match registro_invalido(&e) {
+                Some(invalido) => actual.push(invalido),
+                None => continue,
+            },
+        }
+        if actual.len() >= CHUNK_SIZE {
+            let fin = reader.position().byte();
+            hubo_chunks = true;
+            if procesar(mem::take(&mut actual), fin - inicio).is_break() {
+                return Ok(());
             }
+            inicio = fin;
         }
     }
 
-    if !actual.is_empty() {
-        chunks.push(actual);
+    if !actual.is_empty() || !hubo_chunks {
+        let _ = procesar(actual, reader.position().byte().saturating_sub(inicio));
     }
+    Ok(())
+}
+
+/// Devuelve los bytes del archivo en los que empieza cada chunk, seguidos del tamaño del archivo.
+/// El primer chunk empieza en `inicio`, que es 0 para contar también el encabezado si se leyó el
+/// archivo completo, de forma que entre todos los chunks se cubre lo que se leyó del archivo
+pub fn limites_chunks(path: &Path, chunks: &[Vec<StringRecord>], inicio_lectura: u64) -> Vec<u64> {
+    let inicio = |chunk: &Vec<StringRecord>| {
+        chunk
+            .first()
+            .and_then(StringRecord::position)
+            .map(|posicion| posicion.byte())
+            .unwrap_or(0)
+    };
+    let ultimo = chunks.last().map(inicio).unwrap_or(0);
+    let tamanio = fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(ultimo)
+        .max(ultimo);
+
+    let mut limites = vec![inicio_lectura];
+    limites.extend(chunks.iter().skip(1).map(inicio));
+    limites.push(tamanio);
+    limites
+}
+
+/// Recibe el path del arhcivo y lo abre. Separa el archivo en Chunks y realiza el procesamiento de
+/// cada chunk en paralelo (ver procesar_chunk). Al final, combina todas las Estadísticas obtenidas
+/// de los distintos chunks en una misma Estadística que es devuelta al terminar la función
+/// Cada chunk procesado se suma al progreso con su cantidad de filas y los bytes que ocupa en el
+/// archivo
+/// Devuelve un error si no se pudo leer el archivo (ver leer_chunks)
+pub fn procesar_csv(
+    path: &Path,
+    opciones: &Opciones,
+    progreso: &Progreso,
+) -> Result<Estadisticas, ErrorApp> {
+    let chunks = leer_chunks(path)?;
+    let limites = limites_chunks(path, &chunks, 0);
 
-    chunks
+    Ok(chunks
         .into_par_iter()
-        .map(|chunk| {
-            let mut estadisticas = Estadisticas {
-                juegos: HashMap::new(),
-                idiomas: HashMap::new(),
-            };
-            for record in chunk {
-                if let Some(review) = Review::parse_record(&record) {
-                    estadisticas.agregar_review(review);
-                }
-            }
+        .zip(limites.par_windows(2))
+        .map(|(chunk, limites)| {
+            let filas = chunk.len() as u64;
+            let estadisticas = procesar_chunk(path, chunk, opciones);
+            progreso.chunk_procesado(filas, limites[1] - limites[0]);
             estadisticas
         })
-        .reduce(
-            || Estadisticas {
-                juegos: HashMap::new(),
-                idiomas: HashMap::new(),
-            },
-            |a, b| estadisticas::combinar_estadisticas(a, b),
-        )
+        .reduce(Estadisticas::default, estadisticas::combinar_estadisticas))
+}
+
+/// Parsea las Reviews de un chunk y devuelve sus estadísticas
+/// Si en las opciones está habilitada la detección de idiomas, se valida el idioma de cada reseña
+/// antes de agregarla (pudiendo reetiquetarla), y si está habilitado el análisis de términos
+/// también se tokeniza cada reseña. Por último, si el idioma tiene un alias configurado, la
+/// reseña se cuenta dentro de su grupo. Si se cargaron metadatos, cada reseña se cruza con los
+/// metadatos de su juego, y si están habilitadas las distribuciones se suman sus votos y su largo
+/// Si están habilitados los segmentos, cada reseña también se agrega a las estadísticas de los
+/// segmentos a los que pertenece, reutilizando la detección y los tokens de la reseña
+/// Si está habilitado el muestreo, las reseñas que no entran en la muestra se descartan antes de
+/// parsearlas (ver muestreo.rs)
+/// Si está habilitado el desglose por archivo, las estadísticas del chunk se guardan además como
+/// parciales del archivo del que se leyó (ver archivo.rs)
+pub fn procesar_chunk(path: &Path, chunk: Vec<StringRecord>, opciones: &Opciones) -> Estadisticas {
+    let mut estadisticas = Estadisticas::default();
+    let mut archivo = Archivo {
+        filas: chunk.len(),
+        ..Archivo::default()
+    };
+    for record in chunk {
+        // Los registros mal formados no se cuentan en el muestreo, igual que antes de leerlos
+        if record.is_empty() {
+            archivo.invalidas += 1;
+            continue;
+        }
+        if let Some(muestreo) = &opciones.muestreo
+            && !muestreo.incluir(&record, &opciones.alias_idiomas, &mut estadisticas.muestreo)
+        {
+            continue;
+        }
+        if !procesar_registro(&mut estadisticas, &record, opciones) {
+            archivo.invalidas += 1;
+        }
+    }
+    if opciones.desglosar_archivos {
+        archivo.juegos = estadisticas
+            .juegos
+            .iter()
+            .map(|(nombre, juego)| (nombre.clone(), juego.reviews))
+            .collect();
+        archivo.idiomas = estadisticas.idiomas.keys().cloned().collect();
+        estadisticas
+            .archivos
+            .insert(path.display().to_string(), archivo);
+    }
+    estadisticas
+}
+
+/// Parsea las reseñas que se agregaron a la muestra al completar los estratos (ver
+/// Muestreo::completar_estratos) y devuelve sus estadísticas. Se procesan igual que las de un
+/// chunk, pero como ya están en la muestra y vienen de distintos archivos, no se vuelven a
+/// muestrear ni se suman al desglose por archivo
+pub fn procesar_registros(registros: Vec<StringRecord>, opciones: &Opciones) -> Estadisticas {
+    let mut estadisticas = Estadisticas::default();
+    for record in registros {
+        procesar_registro(&mut estadisticas, &record, opciones);
+    }
+    estadisticas
+}
+
+/// Parsea la Review del registro y la agrega a las estadísticas, con la detección de idiomas, los
+/// términos, el alias y los segmentos que se hayan habilitado (ver procesar_chunk)
+/// Devuelve false si el registro no se pudo parsear
+fn procesar_registro(
+    estadisticas: &mut Estadisticas,
+    record: &StringRecord,
+    opciones: &Opciones,
+) -> bool {
+    let Some(mut review) = Review::parse_record(record, &opciones.saneamiento) else {
+        return false;
+    };
+    let validacion = opciones.detectar_idioma.then(|| {
+        estadisticas
+            .deteccion
+            .validar_review(&mut review, opciones.reetiquetar_idioma)
+    });
+    let grupo = opciones.alias_idiomas.grupo(&review.language).cloned();
+    let frases = opciones.analizar_terminos.then(|| {
+        let idioma = grupo.as_deref().unwrap_or(&review.language);
+        estadisticas.terminos.agregar_review(&review, idioma)
+    });
+    if let Some(grupo) = grupo {
+        review.language = grupo;
+    }
+    if opciones.segmentar {
+        for segmento in Segmento::de_registro(record) {
+            let segmento = estadisticas.segmentos.entry(segmento).or_default();
+            if let Some(validacion) = &validacion {
+                segmento
+                    .deteccion
+                    .registrar(validacion, opciones.reetiquetar_idioma);
+            }
+            if let Some(frases) = &frases {
+                segmento
+                    .terminos
+                    .agregar_frases(&review.app_name, &review.language, frases);
+            }
+            agregar_review(segmento, review.clone(), opciones);
+        }
+    }
+    agregar_review(estadisticas, review, opciones);
+    true
+}
+
+/// Agrega una review ya validada y con su alias aplicado a las estadísticas, cruzándola con los
+/// metadatos y sumándola a las distribuciones si están habilitados
+fn agregar_review(estadisticas: &mut Estadisticas, review: Review, opciones: &Opciones) {
+    if let Some(metadatos) = &opciones.metadatos {
+        estadisticas.metadatos.agregar_review(&review, metadatos);
+    }
+    if opciones.calcular_distribuciones {
+        estadisticas.distribuciones.agregar_review(&review);
+    }
+    estadisticas.agregar_review(review);
 }
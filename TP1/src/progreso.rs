@@ -0,0 +1,250 @@
+use crate::configurar_argumentos::ModoProgreso;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cada cuánto se informa el progreso por salida de error
+const INTERVALO_REPORTE: Duration = Duration::from_millis(500);
+const BYTES_POR_MB: f64 = 1024.0 * 1024.0;
+
+/// Struct que lleva la cuenta del avance del procesamiento. Los contadores son atómicos para que
+/// los actualicen los threads que procesan los chunks mientras otro thread los informa
+/// modo indica cómo se informa el progreso
+/// inicio es el momento en que empezó el procesamiento
+/// archivos_totales y bytes_totales son la cantidad y el tamaño total de los archivos a procesar
+/// archivos_terminados, bytes_procesados y filas son el avance hasta el momento
+/// filas_workers es la cantidad de filas que procesó cada thread del ThreadPool
+pub struct Progreso {
+    modo: ModoProgreso,
+    inicio: Instant,
+    archivos_totales: usize,
+    bytes_totales: u64,
+    archivos_terminados: AtomicUsize,
+    bytes_procesados: AtomicU64,
+    filas: AtomicU64,
+    filas_workers: Vec<AtomicU64>,
+}
+
+/// Struct con una foto del progreso, que es lo que se imprime en el modo JSON
+#[derive(Serialize, Debug)]
+pub struct EstadoProgreso {
+    #[serde(rename = "elapsed_secs")]
+    pub segundos: f64,
+    #[serde(rename = "files_done")]
+    pub archivos_terminados: usize,
+    #[serde(rename = "files_total")]
+    pub archivos_totales: usize,
+    #[serde(rename = "bytes_done")]
+    pub bytes_procesados: u64,
+    #[serde(rename = "bytes_total")]
+    pub bytes_totales: u64,
+    #[serde(rename = "rows")]
+    pub filas: u64,
+    #[serde(rename = "rows_per_sec")]
+    pub filas_por_segundo: f64,
+    #[serde(rename = "bytes_per_sec")]
+    pub bytes_por_segundo: f64,
+    #[serde(rename = "eta_secs")]
+    pub segundos_restantes: Option<f64>,
+    #[serde(rename = "worker_rows")]
+    pub filas_workers: Vec<u64>,
+    #[serde(rename = "done")]
+    pub terminado: bool,
+}
+
+impl Progreso {
+    /// Crea el progreso de los archivos recibidos, que se van a procesar con n_workers threads.
+    /// El tamaño de los archivos que no se pueden leer se cuenta como 0
+    pub fn new(modo: ModoProgreso, archivos: &[PathBuf], n_workers: usize) -> Progreso {
+        Progreso {
+            modo,
+            inicio: Instant::now(),
+            archivos_totales: archivos.len(),
+            bytes_totales: archivos
+                .iter()
+                .filter_map(|archivo| fs::metadata(archivo).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+            archivos_terminados: AtomicUsize::new(0),
+            bytes_procesados: AtomicU64::new(0),
+            filas: AtomicU64::new(0),
+            filas_workers: (0..n_workers).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Suma un chunk procesado, con su cantidad de filas y su tamaño en bytes dentro del csv. Las
+    /// filas también se suman al thread del ThreadPool que lo procesó
+    pub fn chunk_procesado(&self, filas: u64, bytes: u64) {
+        self.chunk_procesado_por(rayon::current_thread_index(), filas, bytes);
+    }
+
+    /// Igual que chunk_procesado, para los threads que no son de rayon: las filas se suman al
+    /// worker recibido, si hay alguno
+    pub fn chunk_procesado_por(&self, worker: Option<usize>, filas: u64, bytes: u64) {
+        self.filas.fetch_add(filas, Ordering::Relaxed);
+        self.bytes_procesados.fetch_add(bytes, Ordering::Relaxed);
+        if let Some(worker) = worker.and_then(|i| self.filas_workers.get(i)) {
+            worker.fetch_add(filas, Ordering::Relaxed);
+        }
+    }
+
+    /// Suma un archivo terminado, se haya podido leer o no
+    pub fn archivo_terminado(&self) {
+        self.archivos_terminados.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Devuelve el estado actual del progreso. El tiempo restante se estima con la velocidad en
+    /// bytes hasta el momento
+    pub fn estado(&self, terminado: bool) -> EstadoProgreso {
+        let segundos = self.inicio.elapsed().as_secs_f64();
+        let bytes_procesados = self.bytes_procesados.load(Ordering::Relaxed);
+        let filas = self.filas.load(Ordering::Relaxed);
+        let por_segundo = |cantidad: u64| {
+            if segundos > 0.0 {
+                cantidad as f64 / segundos
+            } else {
+                0.0
+            }
+        };
+        let bytes_por_segundo = por_segundo(bytes_procesados);
+        EstadoProgreso {
+            segundos,
+            archivos_terminados: self.archivos_terminados.load(Ordering::Relaxed),
+            archivos_totales: self.archivos_totales,
+            bytes_procesados,
+            bytes_totales: self.bytes_totales,
+            filas,
+            filas_por_segundo: por_segundo(filas),
+            bytes_por_segundo,
+            segundos_restantes: (bytes_por_segundo > 0.0).then(|| {
+                self.bytes_totales.saturating_sub(bytes_procesados) as f64 / bytes_por_segundo
+            }),
+            filas_workers: self
+                .filas_workers
+                .iter()
+                .map(|filas| filas.load(Ordering::Relaxed))
+                .collect(),
+            terminado,
+        }
+    }
+
+    /// Ejecuta la tarea recibida mientras otro thread informa el progreso por salida de error
+    /// cada INTERVALO_REPORTE, y al terminar informa el estado final. Si el modo es Ninguno sólo
+    /// ejecuta la tarea
+    pub fn reportar<T>(&self, tarea: impl FnOnce() -> T) -> T {
+        if self.modo == ModoProgreso::Ninguno {
+            return tarea();
+        }
+        let (fin, esperar_fin) = mpsc::channel::<()>();
+        thread::scope(|s| {
+            s.spawn(move || {
+                // El canal se desconecta cuando termina la tarea y se descarta `fin`
+                while let Err(RecvTimeoutError::Timeout) =
+                    esperar_fin.recv_timeout(INTERVALO_REPORTE)
+                {
+                    self.imprimir(false);
+                }
+                self.imprimir(true);
+            });
+            let resultado = tarea();
+            drop(fin);
+            resultado
+        })
+    }
+
+    /// Imprime el estado actual por salida de error según el modo. En una terminal el modo
+    /// humano reescribe siempre la misma línea
+    fn imprimir(&self, terminado: bool) {
+        let estado = self.estado(terminado);
+        match self.modo {
+            ModoProgreso::Json => {
+                if let Ok(json) = serde_json::to_string(&estado) {
+                    eprintln!("{}", json);
+                }
+            }
+            ModoProgreso::Humano if io::stderr().is_terminal() => {
+                eprint!("\r\x1b[K{}", estado.resumen());
+                if terminado {
+                    eprintln!();
+                }
+            }
+            ModoProgreso::Humano => eprintln!("{}", estado.resumen()),
+            ModoProgreso::Ninguno => {}
+        }
+    }
+}
+
+impl EstadoProgreso {
+    /// Devuelve el estado en una línea para mostrar en la terminal
+    pub fn resumen(&self) -> String {
+        let restante = match (self.terminado, self.segundos_restantes) {
+            (true, _) => format!("terminado en {:.1} s", self.segundos),
+            (false, Some(segundos)) => format!("restan ~{:.0} s", segundos),
+            (false, None) => "restan ?".to_string(),
+        };
+        format!(
+            "Archivos {}/{} | {:.1}/{:.1} MB | {:.0} filas/s | {:.1} MB/s | {} | filas por worker: {:?}",
+            self.archivos_terminados,
+            self.archivos_totales,
+            self.bytes_procesados as f64 / BYTES_POR_MB,
+            self.bytes_totales as f64 / BYTES_POR_MB,
+            self.filas_por_segundo,
+            self.bytes_por_segundo / BYTES_POR_MB,
+            restante,
+            self.filas_workers
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contar_chunks_y_archivos() {
+        let progreso = Progreso::new(ModoProgreso::Ninguno, &[], 2);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        pool.install(|| progreso.chunk_procesado(100, 4_000));
+        progreso.chunk_procesado(50, 1_000);
+        progreso.archivo_terminado();
+
+        let estado = progreso.reportar(|| progreso.estado(true));
+        assert_eq!(estado.archivos_terminados, 1);
+        assert_eq!(estado.archivos_totales, 0);
+        assert_eq!(estado.filas, 150);
+        assert_eq!(estado.bytes_procesados, 5_000);
+        // El chunk procesado fuera del ThreadPool no se le suma a ningún worker
+        assert_eq!(estado.filas_workers.iter().sum::<u64>(), 100);
+        assert_eq!(estado.segundos_restantes, Some(0.0));
+    }
+
+    #[test]
+    fn resumen_en_una_linea() {
+        let estado = EstadoProgreso {
+            segundos: 10.0,
+            archivos_terminados: 1,
+            archivos_totales: 4,
+            bytes_procesados: 10 * 1024 * 1024,
+            bytes_totales: 40 * 1024 * 1024,
+            filas: 20_000,
+            filas_por_segundo: 2_000.0,
+            bytes_por_segundo: 1024.0 * 1024.0,
+            segundos_restantes: Some(30.0),
+            filas_workers: vec![12_000, 8_000],
+            terminado: false,
+        };
+        assert_eq!(
+            estado.resumen(),
+            "Archivos 1/4 | 10.0/40.0 MB | 2000 filas/s | 1.0 MB/s | restan ~30 s | \
+             filas por worker: [12000, 8000]"
+        );
+    }
+}
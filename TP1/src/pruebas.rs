@@ -0,0 +1,35 @@
+use crate::configurar_argumentos::{ModoProgreso, Opciones};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Encabezado de los csv de las pruebas, con las columnas en las posiciones del dataset
+pub const ENCABEZADO: &str =
+    "index,app_id,app_name,review_id,language,review,a,b,c,votes_helpful\n";
+
+/// Crea el directorio vacío y escribe en él un csv por cada contenido, con el encabezado de las
+/// pruebas. Devuelve los paths de los csv en el mismo orden
+pub fn crear_archivos(directorio: &Path, contenidos: &[&str]) -> Vec<PathBuf> {
+    let _ = fs::remove_dir_all(directorio);
+    fs::create_dir_all(directorio).unwrap();
+    contenidos
+        .iter()
+        .enumerate()
+        .map(|(i, contenido)| {
+            let path = directorio.join(format!("reviews{}.csv", i));
+            fs::write(&path, format!("{}{}", ENCABEZADO, contenido)).unwrap();
+            path
+        })
+        .collect()
+}
+
+/// Opciones con las que se procesan los csv de las pruebas: calculan los términos y las
+/// distribuciones para comparar más secciones de la salida, y no informan el progreso
+pub fn opciones() -> Opciones {
+    Opciones {
+        analizar_terminos: true,
+        calcular_distribuciones: true,
+        progreso: ModoProgreso::Ninguno,
+        silencioso: true,
+        ..Opciones::default()
+    }
+}
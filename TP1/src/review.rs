@@ -1,9 +1,12 @@
+use crate::colacion;
+use crate::saneamiento::Saneamiento;
 use csv::StringRecord;
 use serde::Deserialize;
 
 /// Constantes con las posiciones de los campos necesarios para el análisis del csv
-const POSICION_APP_NAME: usize = 2;
-const POSICION_LANGUAGE: usize = 4;
+const POSICION_APP_ID: usize = 1;
+pub const POSICION_APP_NAME: usize = 2;
+pub const POSICION_LANGUAGE: usize = 4;
 const POSICION_REVIEW: usize = 5;
 const POSICION_VOTES_HELPFUL: usize = 9;
 /// Como estoy tomando la cantidad de votos como un u32, y la lectura del archivo agarra el número
@@ -11,45 +14,101 @@ const POSICION_VOTES_HELPFUL: usize = 9;
 /// sea mayor al límite permitido. En este caso, la cantidad de votos no sería válida, y para no
 /// perder la review asigno manualmente la cantidad de votos a 0
 const VALOR_DEFAULT_VOTOS: u32 = 0;
+/// Lo mismo pasa con el id del juego. Steam no usa el id 0, así que una review con un id inválido
+/// no coincide con ningún juego de los metadatos
+const VALOR_DEFAULT_APP_ID: u32 = 0;
+/// Nombre que debe tener en el encabezado cada una de las columnas que se leen
+const COLUMNAS: [(usize, &str); 5] = [
+    (POSICION_APP_ID, "app_id"),
+    (POSICION_APP_NAME, "app_name"),
+    (POSICION_LANGUAGE, "language"),
+    (POSICION_REVIEW, "review"),
+    (POSICION_VOTES_HELPFUL, "votes_helpful"),
+];
 
 /// Struct que almacena la información de una Review de Steam.
 /// Los nombres de los atributos coinciden con los campos del csv
+/// app_id es el id del juego en Steam
 /// app_name es el nombre del juego
 /// language es su idioma
 /// review es el texto de la reseña
 /// votes_helpful son los votos que tuvo dicha reseña
+/// truncada indica si el texto de la reseña se truncó al sanearlo (ver saneamiento.rs)
 #[derive(Debug, Deserialize, Clone)]
 pub struct Review {
+    pub app_id: u32,
     pub app_name: String,
     pub language: String,
     pub review: String,
     pub votes_helpful: u32,
+    #[serde(default)]
+    pub truncada: bool,
 }
 
 impl Review {
     /// Recibe un StringRecord y lo convierte en una instancia de Review, retornando un Option
     /// por si llegara a fallar el parseo de algunos de sus elementos.
-    pub fn parse_record(record: &StringRecord) -> Option<Review> {
-        let app_name = record.get(POSICION_APP_NAME)?.to_string();
-        let language = record.get(POSICION_LANGUAGE)?.to_string();
-        let review = record.get(POSICION_REVIEW)?.to_string();
+    /// El nombre del juego y el idioma se normalizan a NFC, para que los nombres que se ven iguales
+    /// se cuenten juntos, y el texto de la reseña se sanea según las opciones recibidas
+    pub fn parse_record(record: &StringRecord, saneamiento: &Saneamiento) -> Option<Review> {
+        let app_id = record
+            .get(POSICION_APP_ID)?
+            .parse::<u32>()
+            .unwrap_or(VALOR_DEFAULT_APP_ID);
+        let app_name = colacion::normalizar(record.get(POSICION_APP_NAME)?).into_owned();
+        let language = colacion::normalizar(record.get(POSICION_LANGUAGE)?).into_owned();
+        let texto = record.get(POSICION_REVIEW)?;
+        let (review, truncada) = if saneamiento.es_nulo() {
+            (texto.to_string(), false)
+        } else {
+            saneamiento.aplicar(texto)
+        };
         let votes_helpful = record
             .get(POSICION_VOTES_HELPFUL)?
             .parse::<u32>()
             .unwrap_or(VALOR_DEFAULT_VOTOS);
 
         Some(Review {
+            app_id,
             app_name,
             language,
             review,
             votes_helpful,
+            truncada,
         })
     }
+
+    /// Recibe el encabezado de un csv y verifica que las columnas que se leen estén en las
+    /// posiciones esperadas. Devuelve un error describiendo la primera columna que no coincide
+    pub fn validar_encabezado(encabezado: &StringRecord) -> Result<(), String> {
+        for (posicion, nombre) in COLUMNAS {
+            match encabezado.get(posicion) {
+                Some(columna) if columna.trim() == nombre => {}
+                Some(columna) => {
+                    return Err(format!(
+                        "se esperaba la columna {} en la posición {} y se encontró {}",
+                        nombre,
+                        posicion + 1,
+                        columna
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "falta la columna {} (el encabezado tiene {} columnas)",
+                        nombre,
+                        encabezado.len()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::saneamiento::Marcado;
 
     fn generar_vector() -> Vec<String> {
         let mut review_csv = Vec::new();
@@ -61,14 +120,16 @@ mod tests {
     #[test]
     fn test_parsear_review() {
         let mut review = generar_vector();
+        review[POSICION_APP_ID] = "1506830".to_string();
         review[POSICION_APP_NAME] = "FIFA".to_string();
         review[POSICION_LANGUAGE] = "Español".to_string();
         review[POSICION_REVIEW] = "Es el mejor juego que jugue en mi vida".to_string();
         review[POSICION_VOTES_HELPFUL] = "2019".to_string();
         let sr = StringRecord::from(review);
 
-        let r = Review::parse_record(&sr).unwrap();
+        let r = Review::parse_record(&sr, &Saneamiento::default()).unwrap();
 
+        assert_eq!(r.app_id, 1506830);
         assert_eq!(r.app_name, "FIFA");
         assert_eq!(r.language, "Español");
         assert_eq!(r.review, "Es el mejor juego que jugue en mi vida");
@@ -82,7 +143,52 @@ mod tests {
         // a la review debería asignarle 0
         review[POSICION_VOTES_HELPFUL] = "4294967296".to_string();
         let sr = StringRecord::from(review);
-        let r = Review::parse_record(&sr).unwrap();
+        let r = Review::parse_record(&sr, &Saneamiento::default()).unwrap();
         assert_eq!(r.votes_helpful, 0);
     }
+
+    #[test]
+    fn test_parsear_con_app_id_invalido() {
+        let mut review = generar_vector();
+        review[POSICION_APP_ID] = "abc".to_string();
+        let sr = StringRecord::from(review);
+        let r = Review::parse_record(&sr, &Saneamiento::default()).unwrap();
+        assert_eq!(r.app_id, VALOR_DEFAULT_APP_ID);
+    }
+
+    #[test]
+    fn test_parsear_normaliza_nombres() {
+        let mut review = generar_vector();
+        review[POSICION_APP_NAME] = "Pok\u{65}\u{301}mon".to_string();
+        let sr = StringRecord::from(review);
+        let r = Review::parse_record(&sr, &Saneamiento::default()).unwrap();
+        assert_eq!(r.app_name, "Pok\u{e9}mon");
+    }
+
+    #[test]
+    fn test_parsear_saneando_el_texto() {
+        let mut review = generar_vector();
+        review[POSICION_REVIEW] = "[h1]Muy[/h1] bueno\u{7}".to_string();
+        let sr = StringRecord::from(review);
+        let saneamiento = Saneamiento {
+            marcado: Some(Marcado::Quitar),
+            max_grafemas: Some(8),
+        };
+        let r = Review::parse_record(&sr, &saneamiento).unwrap();
+        assert_eq!(r.review, "Muy\nbuen");
+        assert!(r.truncada);
+    }
+
+    #[test]
+    fn test_validar_encabezado() {
+        let mut encabezado = generar_vector();
+        for (posicion, nombre) in COLUMNAS {
+            encabezado[posicion] = nombre.to_string();
+        }
+        assert!(Review::validar_encabezado(&StringRecord::from(encabezado.clone())).is_ok());
+
+        encabezado[POSICION_REVIEW] = "texto".to_string();
+        assert!(Review::validar_encabezado(&StringRecord::from(encabezado)).is_err());
+        assert!(Review::validar_encabezado(&StringRecord::from(vec!["index", "app_id"])).is_err());
+    }
 }
@@ -0,0 +1,282 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Largo máximo que puede tener una etiqueta de BBCode, contando los corchetes. Los corchetes más
+/// largos que esto se dejan como texto
+const LARGO_MAXIMO_ETIQUETA: usize = 256;
+/// Etiquetas de BBCode de Steam que ocupan su propia línea. Se reemplazan por un salto de línea
+/// para que las palabras de los dos lados no queden pegadas
+const ETIQUETAS_BLOQUE: [&str; 11] = [
+    "h1", "h2", "h3", "hr", "list", "olist", "*", "quote", "code", "table", "tr",
+];
+/// Etiquetas de BBCode de Steam que separan las celdas de una tabla. Se reemplazan por un espacio
+const ETIQUETAS_CELDA: [&str; 2] = ["th", "td"];
+/// Etiquetas de BBCode de Steam que sólo cambian el formato del texto. Se quitan sin reemplazo
+const ETIQUETAS_EN_LINEA: [&str; 7] = ["b", "u", "i", "strike", "spoiler", "url", "img"];
+/// Etiqueta dentro de la cual el texto no se interpreta como BBCode
+const ETIQUETA_NOPARSE: &str = "noparse";
+/// Etiqueta que cierra el texto sin interpretar
+const CIERRE_NOPARSE: &str = "[/noparse]";
+
+/// Forma de tratar el BBCode de las reseñas al sanearlas
+/// Quitar elimina las etiquetas y deja sólo su contenido
+/// Renderizar además muestra como texto la información de algunas etiquetas, igual que Steam:
+///     los ítems de las listas empiezan con "• ", los links muestran su dirección entre paréntesis,
+///     las citas muestran a su autor y las líneas horizontales se dibujan con "---"
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Marcado {
+    #[value(name = "strip")]
+    #[serde(rename = "strip")]
+    Quitar,
+    #[value(name = "render")]
+    #[serde(rename = "render")]
+    Renderizar,
+}
+
+/// Struct que almacena las opciones de saneamiento del texto de las reseñas
+/// marcado es la forma de tratar el BBCode. Si es None el texto no se sanea; si no, además se
+///     eliminan los caracteres de control (salvo los saltos de línea y las tabulaciones) y las
+///     líneas en blanco repetidas
+/// max_grafemas es la cantidad máxima de grafemas (lo que se ve como un caracter) que se conservan
+///     de cada reseña. Si es None las reseñas no se truncan
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Saneamiento {
+    pub marcado: Option<Marcado>,
+    pub max_grafemas: Option<usize>,
+}
+
+/// Clase de una etiqueta de BBCode reconocida, según con qué se reemplaza
+#[derive(Clone, Copy, PartialEq)]
+enum Clase {
+    Bloque,
+    Celda,
+    EnLinea,
+    NoParse,
+}
+
+/// Etiqueta de BBCode leída del texto. nombre está en minúscula y valor es lo que sigue al "="
+struct Etiqueta<'a> {
+    nombre: String,
+    valor: Option<&'a str>,
+    cierre: bool,
+    clase: Clase,
+    largo: usize,
+}
+
+impl Saneamiento {
+    /// Indica si las opciones no modifican el texto de las reseñas
+    pub fn es_nulo(&self) -> bool {
+        self.marcado.is_none() && self.max_grafemas.is_none()
+    }
+
+    /// Recibe el texto de una reseña y lo devuelve saneado y truncado según las opciones, junto
+    /// con un bool que indica si se truncó
+    pub fn aplicar(&self, texto: &str) -> (String, bool) {
+        let saneado = match self.marcado {
+            Some(marcado) => limpiar(&quitar_bbcode(texto, marcado)),
+            None => texto.to_string(),
+        };
+        match self.max_grafemas {
+            Some(maximo) => truncar(saneado, maximo),
+            None => (saneado, false),
+        }
+    }
+}
+
+/// Recibe el texto a partir de un "[" y, si empieza con una etiqueta de BBCode de Steam, la
+/// devuelve. Los corchetes que no son etiquetas conocidas (por ejemplo "[10/10]") son texto
+fn leer_etiqueta(texto: &str) -> Option<Etiqueta<'_>> {
+    let fin = texto
+        .char_indices()
+        .take_while(|(i, _)| *i < LARGO_MAXIMO_ETIQUETA)
+        .skip(1)
+        .find(|(_, c)| matches!(c, ']' | '[' | '\n'))
+        .filter(|(_, c)| *c == ']')?
+        .0;
+    let interior = &texto[1..fin];
+    let (cierre, interior) = match interior.strip_prefix('/') {
+        Some(resto) => (true, resto),
+        None => (false, interior),
+    };
+    let (nombre, valor) = match interior.split_once('=') {
+        Some((nombre, valor)) if !cierre => (nombre, Some(valor.trim_matches('"'))),
+        Some(_) => return None,
+        None => (interior, None),
+    };
+    let nombre = nombre.trim().to_lowercase();
+    let clase = if ETIQUETAS_BLOQUE.contains(&nombre.as_str()) {
+        Clase::Bloque
+    } else if ETIQUETAS_CELDA.contains(&nombre.as_str()) {
+        Clase::Celda
+    } else if ETIQUETAS_EN_LINEA.contains(&nombre.as_str()) {
+        Clase::EnLinea
+    } else if nombre == ETIQUETA_NOPARSE {
+        Clase::NoParse
+    } else {
+        return None;
+    };
+    Some(Etiqueta {
+        nombre,
+        valor,
+        cierre,
+        clase,
+        largo: fin + 1,
+    })
+}
+
+/// Recibe el texto de una reseña y le quita las etiquetas de BBCode según el marcado. El texto
+/// dentro de [noparse] se deja como está
+fn quitar_bbcode(texto: &str, marcado: Marcado) -> String {
+    let renderizar = marcado == Marcado::Renderizar;
+    let mut resultado = String::with_capacity(texto.len());
+    // Direcciones de los links abiertos, para mostrarlas al cerrarlos
+    let mut links: Vec<Option<&str>> = Vec::new();
+    let mut resto = texto;
+
+    while let Some(inicio) = resto.find('[') {
+        resultado.push_str(&resto[..inicio]);
+        resto = &resto[inicio..];
+        let Some(etiqueta) = leer_etiqueta(resto) else {
+            resultado.push('[');
+            resto = &resto[1..];
+            continue;
+        };
+        resto = &resto[etiqueta.largo..];
+
+        match (etiqueta.clase, etiqueta.nombre.as_str(), etiqueta.cierre) {
+            (Clase::NoParse, _, false) => {
+                let fin = resto
+                    .as_bytes()
+                    .windows(CIERRE_NOPARSE.len())
+                    .position(|ventana| ventana.eq_ignore_ascii_case(CIERRE_NOPARSE.as_bytes()))
+                    .unwrap_or(resto.len());
+                resultado.push_str(&resto[..fin]);
+                resto = &resto[(fin + CIERRE_NOPARSE.len()).min(resto.len())..];
+            }
+            (Clase::NoParse, _, true) => {}
+            (_, "url", false) => links.push(etiqueta.valor),
+            (_, "url", true) => {
+                if let (true, Some(Some(direccion))) = (renderizar, links.pop()) {
+                    resultado.push_str(&format!(" ({})", direccion));
+                }
+            }
+            (_, "*", _) if renderizar => {
+                saltar_linea(&mut resultado);
+                resultado.push_str("• ");
+            }
+            (_, "hr", false) if renderizar => {
+                saltar_linea(&mut resultado);
+                resultado.push_str("---\n");
+            }
+            (_, "quote", false) if renderizar => {
+                saltar_linea(&mut resultado);
+                if let Some(autor) = etiqueta.valor {
+                    resultado.push_str(&format!("{}: ", autor));
+                }
+            }
+            (Clase::Bloque, _, _) => saltar_linea(&mut resultado),
+            (Clase::Celda, _, _) => resultado.push(' '),
+            (Clase::EnLinea, _, _) => {}
+        }
+        // Los espacios después de una etiqueta de bloque quedarían al principio de la línea
+        if etiqueta.clase == Clase::Bloque {
+            resto = resto.trim_start_matches(' ');
+        }
+    }
+    resultado.push_str(resto);
+    resultado
+}
+
+/// Agrega un salto de línea al texto, salvo que ya termine con uno, para que varias etiquetas de
+/// bloque seguidas no dejen líneas en blanco
+fn saltar_linea(texto: &mut String) {
+    if !texto.ends_with('\n') {
+        texto.push('\n');
+    }
+}
+
+/// Elimina los caracteres de control salvo los saltos de línea y las tabulaciones, los espacios
+/// al final de cada línea y las líneas en blanco repetidas o al principio y al final del texto
+fn limpiar(texto: &str) -> String {
+    let sin_control: String = texto
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .collect();
+    let mut lineas: Vec<&str> = Vec::new();
+    for linea in sin_control.lines().map(str::trim_end) {
+        if !linea.is_empty() || lineas.last().is_some_and(|anterior| !anterior.is_empty()) {
+            lineas.push(linea);
+        }
+    }
+    while lineas.last().is_some_and(|linea| linea.is_empty()) {
+        lineas.pop();
+    }
+    lineas.join("\n")
+}
+
+/// Deja sólo los primeros max_grafemas grafemas del texto. Devuelve además si se truncó
+fn truncar(texto: String, max_grafemas: usize) -> (String, bool) {
+    match texto.grapheme_indices(true).nth(max_grafemas) {
+        Some((corte, _)) => (texto[..corte].to_string(), true),
+        None => (texto, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanear(marcado: Marcado, texto: &str) -> String {
+        Saneamiento {
+            marcado: Some(marcado),
+            max_grafemas: None,
+        }
+        .aplicar(texto)
+        .0
+    }
+
+    #[test]
+    fn quitar_y_renderizar_bbcode() {
+        let texto = "[h1]Review[/h1][b]Great[/b] game [URL=https://x.io]here[/url] 10/10 [i]\
+                     [10/10][/i][list][*]fun[*]short[/list][noparse][b][/noparse]";
+        assert_eq!(
+            sanear(Marcado::Quitar, texto),
+            "Review\nGreat game here 10/10 [10/10]\nfun\nshort\n[b]"
+        );
+        assert_eq!(
+            sanear(Marcado::Renderizar, texto),
+            "Review\nGreat game here (https://x.io) 10/10 [10/10]\n• fun\n• short\n[b]"
+        );
+        assert_eq!(
+            sanear(
+                Marcado::Renderizar,
+                "[quote=Gabe]Hi[/quote][hr][/hr][table][tr][td]a[/td][td]b[/td][/tr][/table]"
+            ),
+            "Gabe: Hi\n---\n a  b"
+        );
+    }
+
+    #[test]
+    fn quitar_caracteres_de_control() {
+        assert_eq!(
+            sanear(Marcado::Quitar, "\n\nLine\u{7}\r\n\n\n\tNext  \u{1b}\n"),
+            "Line\n\n\tNext"
+        );
+    }
+
+    #[test]
+    fn truncar_por_grafemas() {
+        let saneamiento = Saneamiento {
+            marcado: None,
+            max_grafemas: Some(3),
+        };
+        // La bandera y la e con el acento combinable son un solo grafema cada una
+        assert_eq!(
+            saneamiento.aplicar("🇦🇷e\u{301}ab"),
+            ("🇦🇷e\u{301}a".to_string(), true)
+        );
+        assert_eq!(saneamiento.aplicar("abc"), ("abc".to_string(), false));
+        assert!(Saneamiento::default().es_nulo());
+    }
+}
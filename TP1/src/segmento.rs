@@ -0,0 +1,82 @@
+use csv::StringRecord;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Constantes con las posiciones de las columnas del csv que definen cada segmento
+const POSICION_STEAM_PURCHASE: usize = 13;
+const POSICION_RECEIVED_FOR_FREE: usize = 14;
+const POSICION_WRITTEN_DURING_EARLY_ACCESS: usize = 15;
+
+/// Segmentos en los que se pueden separar las reseñas para calcular sus estadísticas por
+/// separado. Cada segmento contiene las reseñas que tienen en True la columna del mismo nombre,
+/// así que una reseña puede estar en varios segmentos o en ninguno
+/// CompraSteam son las reseñas de quienes compraron el juego en Steam
+/// Gratis son las reseñas de quienes recibieron el juego gratis (por ejemplo, con una key)
+/// AccesoAnticipado son las reseñas escritas mientras el juego estaba en acceso anticipado
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum Segmento {
+    #[serde(rename = "steam_purchase")]
+    CompraSteam,
+    #[serde(rename = "received_for_free")]
+    Gratis,
+    #[serde(rename = "written_during_early_access")]
+    AccesoAnticipado,
+}
+
+impl Segmento {
+    /// Todos los segmentos, en el orden en que se muestran en la salida
+    pub const TODOS: [Segmento; 3] = [
+        Segmento::CompraSteam,
+        Segmento::Gratis,
+        Segmento::AccesoAnticipado,
+    ];
+
+    /// Posición en el csv de la columna que define el segmento
+    fn posicion(self) -> usize {
+        match self {
+            Segmento::CompraSteam => POSICION_STEAM_PURCHASE,
+            Segmento::Gratis => POSICION_RECEIVED_FOR_FREE,
+            Segmento::AccesoAnticipado => POSICION_WRITTEN_DURING_EARLY_ACCESS,
+        }
+    }
+
+    /// Devuelve los segmentos a los que pertenece el registro. Si a un registro le falta la
+    /// columna de un segmento, o tiene un valor que no es True, no pertenece a ese segmento
+    pub fn de_registro(record: &StringRecord) -> impl Iterator<Item = Segmento> + '_ {
+        Segmento::TODOS.into_iter().filter(|segmento| {
+            record
+                .get(segmento.posicion())
+                .is_some_and(|valor| valor.trim().eq_ignore_ascii_case("true"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registro(steam_purchase: &str, received_for_free: &str, early_access: &str) -> StringRecord {
+        let mut campos = vec!["-"; 23];
+        campos[POSICION_STEAM_PURCHASE] = steam_purchase;
+        campos[POSICION_RECEIVED_FOR_FREE] = received_for_free;
+        campos[POSICION_WRITTEN_DURING_EARLY_ACCESS] = early_access;
+        StringRecord::from(campos)
+    }
+
+    #[test]
+    fn segmentos_de_un_registro() {
+        let segmentos = |record: &StringRecord| Segmento::de_registro(record).collect::<Vec<_>>();
+        assert_eq!(
+            segmentos(&registro("True", "False", "True")),
+            vec![Segmento::CompraSteam, Segmento::AccesoAnticipado]
+        );
+        assert_eq!(
+            segmentos(&registro("False", "true", "False")),
+            vec![Segmento::Gratis]
+        );
+        assert!(segmentos(&registro("False", "False", "1")).is_empty());
+        assert!(segmentos(&StringRecord::from(vec!["0", "1", "FIFA"])).is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::colacion;
+use crate::configurar_argumentos::Opciones;
+use crate::estadisticas::Estadisticas;
+use crate::formatear_datos_json::Output;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Cantidad de juegos que devuelve /games si no se indica el parámetro n
+const TOP_JUEGOS_DEFAULT: usize = 3;
+/// Cantidad de reseñas por página que devuelve /languages/<idioma>/reviews si no se indica
+/// per_page, y máximo que se puede pedir
+const RESENIAS_POR_PAGINA_DEFAULT: usize = 10;
+const RESENIAS_POR_PAGINA_MAXIMO: usize = 100;
+/// Tiempo máximo que se espera a que un cliente envíe su pedido. Las conexiones se atienden de a
+/// una, así que sin este límite un cliente que no envía nada bloquearía a todos los demás
+const TIMEOUT_LECTURA: Duration = Duration::from_secs(5);
+
+/// Struct que representa una respuesta HTTP
+/// estado es el código de estado HTTP
+/// cuerpo es el JSON que se envía como cuerpo de la respuesta
+#[derive(Debug)]
+pub struct Respuesta {
+    pub estado: u16,
+    pub cuerpo: String,
+}
+
+/// Cuerpo de las respuestas de error
+#[derive(Serialize)]
+struct RespuestaError {
+    error: String,
+}
+
+impl Respuesta {
+    fn ok<T: Serialize>(valor: &T) -> Respuesta {
+        Respuesta {
+            estado: 200,
+            cuerpo: serde_json::to_string_pretty(valor).expect("Error al serializar respuesta"),
+        }
+    }
+
+    fn error(estado: u16, mensaje: String) -> Respuesta {
+        Respuesta {
+            estado,
+            cuerpo: serde_json::to_string_pretty(&RespuestaError { error: mensaje })
+                .expect("Error al serializar respuesta"),
+        }
+    }
+
+    /// Texto de la línea de estado HTTP para cada código que usa el servidor
+    fn texto_estado(&self) -> &'static str {
+        match self.estado {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Struct que responde consultas HTTP sobre estadísticas ya calculadas, para no tener que volver a
+/// procesar los csv en cada consulta
+/// estadisticas son las estadísticas de todos los archivos, calculadas una sola vez al iniciar
+/// opciones son las opciones con las que se calcularon las estadísticas
+/// output es la salida completa, la misma que se escribe en el archivo de salida del análisis
+pub struct Servidor {
+    estadisticas: Estadisticas,
+    opciones: Opciones,
+    output: String,
+}
+
+impl Servidor {
+    /// Recibe las estadísticas ya calculadas y arma el servidor. Las reseñas de cada idioma se
+    /// ordenan una sola vez por votos, para que paginar no tenga que reordenarlas en cada consulta
+    pub fn new(mut estadisticas: Estadisticas, opciones: Opciones) -> Servidor {
+        for idioma in estadisticas.idiomas.values_mut() {
+            idioma
+                .top_reviews
+                .sort_by_key(|(_, votos, _)| std::cmp::Reverse(*votos));
+        }
+        let output = serde_json::to_string_pretty(&Output::new(&estadisticas, &opciones))
+            .expect("Error al serializar salida");
+        Servidor {
+            estadisticas,
+            opciones,
+            output,
+        }
+    }
+
+    /// Escucha conexiones en localhost en el puerto recibido y las atiende de a una. Sólo termina
+    /// si falla el socket del servidor; los errores de una conexión se informan y se sigue
+    /// atendiendo
+    pub fn escuchar(&self, puerto: u16) -> io::Result<()> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, puerto))?;
+        println!("Escuchando en http://{}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(|stream| self.atender(stream)) {
+                eprintln!("Error al atender una conexión: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lee el pedido HTTP de una conexión, ignorando headers y cuerpo, y escribe la respuesta
+    fn atender(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT_LECTURA))?;
+        let mut lector = BufReader::new(&stream);
+        let mut linea = String::new();
+        lector.read_line(&mut linea)?;
+        let mut header = String::new();
+        while lector.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+            header.clear();
+        }
+
+        let mut partes = linea.split_whitespace();
+        let respuesta = match (partes.next(), partes.next()) {
+            (Some(metodo), Some(ruta)) => self.responder(metodo, ruta),
+            _ => Respuesta::error(400, "Pedido HTTP inválido".to_string()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            respuesta.estado,
+            respuesta.texto_estado(),
+            respuesta.cuerpo.len(),
+            respuesta.cuerpo
+        )?;
+        stream.flush()
+    }
+
+    /// Recibe el método y la ruta de un pedido y arma la respuesta. Las rutas disponibles son:
+    ///     /output devuelve la salida completa del análisis
+    ///     /games?n=<n> devuelve los n juegos con más reseñas
+    ///     /games/<juego> devuelve todos los idiomas de un juego
+    ///     /languages/<idioma>/reviews?page=<p>&per_page=<n> devuelve una página de las reseñas
+    ///         más votadas de un idioma, empezando por la página 1
+    pub fn responder(&self, metodo: &str, ruta: &str) -> Respuesta {
+        if metodo != "GET" {
+            return Respuesta::error(405, format!("Método no soportado: {}", metodo));
+        }
+        let (camino, query) = ruta.split_once('?').unwrap_or((ruta, ""));
+        let parametros = parsear_query(query);
+        let segmentos: Vec<String> = camino
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(decodificar_url)
+            .collect();
+        let segmentos: Vec<&str> = segmentos.iter().map(String::as_str).collect();
+        let alias = &self.opciones.alias_idiomas;
+        let colacion = self.opciones.colacion;
+
+        match segmentos.as_slice() {
+            ["output"] => Respuesta {
+                estado: 200,
+                cuerpo: self.output.clone(),
+            },
+            ["games"] => match parametro(&parametros, "n", TOP_JUEGOS_DEFAULT) {
+                Ok(n) => Respuesta::ok(&Output::top_juegos(&self.estadisticas, alias, colacion, n)),
+                Err(e) => Respuesta::error(400, e),
+            },
+            ["games", juego] => match self
+                .estadisticas
+                .juegos
+                .get(colacion::normalizar(juego).as_ref())
+            {
+                Some(datos) => Respuesta::ok(&Output::armar_juego(
+                    juego,
+                    datos,
+                    alias,
+                    colacion,
+                    datos.idiomas.len(),
+                )),
+                None => Respuesta::error(404, format!("No existe el juego {}", juego)),
+            },
+            ["languages", idioma, "reviews"] => {
+                let Some(datos) = self.estadisticas.idiomas.get(*idioma) else {
+                    return Respuesta::error(404, format!("No existe el idioma {}", idioma));
+                };
+                let pagina = parametro(&parametros, "page", 1);
+                let por_pagina = parametro(&parametros, "per_page", RESENIAS_POR_PAGINA_DEFAULT);
+                match (pagina, por_pagina) {
+                    (Ok(pagina), Ok(por_pagina))
+                        if pagina >= 1
+                            && (1..=RESENIAS_POR_PAGINA_MAXIMO).contains(&por_pagina) =>
+                    {
+                        match (pagina - 1).checked_mul(por_pagina) {
+                            Some(desde) => Respuesta::ok(&Output::armar_idioma(
+                                idioma, datos, alias, desde, por_pagina,
+                            )),
+                            None => {
+                                Respuesta::error(400, format!("page fuera de rango: {}", pagina))
+                            }
+                        }
+                    }
+                    (Err(e), _) | (_, Err(e)) => Respuesta::error(400, e),
+                    _ => Respuesta::error(
+                        400,
+                        format!(
+                            "page debe ser mayor a 0 y per_page estar entre 1 y {}",
+                            RESENIAS_POR_PAGINA_MAXIMO
+                        ),
+                    ),
+                }
+            }
+            _ => Respuesta::error(404, format!("Ruta desconocida: {}", camino)),
+        }
+    }
+}
+
+/// Separa los parámetros de la query de una URL en un HashMap
+fn parsear_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|parametro| !parametro.is_empty())
+        .map(|parametro| {
+            let (clave, valor) = parametro.split_once('=').unwrap_or((parametro, ""));
+            (decodificar_url(clave), decodificar_url(valor))
+        })
+        .collect()
+}
+
+/// Devuelve el valor numérico de un parámetro de la query, o el valor default si no está
+fn parametro(
+    parametros: &HashMap<String, String>,
+    nombre: &str,
+    default: usize,
+) -> Result<usize, String> {
+    match parametros.get(nombre) {
+        Some(valor) => valor
+            .parse()
+            .map_err(|_| format!("Valor inválido para {}: {}", nombre, valor)),
+        None => Ok(default),
+    }
+}
+
+/// Decodifica un texto de una URL: los '+' pasan a espacios y las secuencias %XX al byte que
+/// representan. Las secuencias inválidas se dejan como están
+fn decodificar_url(texto: &str) -> String {
+    let bytes = texto.as_bytes();
+    let mut decodificado = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hexa = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hexa| std::str::from_utf8(hexa).ok())
+            .and_then(|hexa| u8::from_str_radix(hexa, 16).ok());
+        match (bytes[i], hexa) {
+            (b'+', _) => decodificado.push(b' '),
+            (b'%', Some(byte)) => {
+                decodificado.push(byte);
+                i += 2;
+            }
+            (byte, _) => decodificado.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decodificado).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::Review;
+
+    fn review(juego: &str, idioma: &str, texto: &str, votos: u32) -> Review {
+        Review {
+            app_id: 1,
+            app_name: juego.to_string(),
+            language: idioma.to_string(),
+            review: texto.to_string(),
+            votes_helpful: votos,
+            truncada: false,
+        }
+    }
+
+    fn generar_servidor() -> Servidor {
+        let mut estadisticas = Estadisticas::default();
+        estadisticas.agregar_review(review("Juego A", "english", "Good", 5));
+        estadisticas.agregar_review(review("Juego A", "spanish", "Bueno", 7));
+        estadisticas.agregar_review(review("Juego A", "french", "Bon", 1));
+        estadisticas.agregar_review(review("Juego A", "german", "Gut", 2));
+        estadisticas.agregar_review(review("Juego B", "english", "Great", 9));
+        estadisticas.agregar_review(review("Juego B", "english", "Fine", 3));
+        Servidor::new(estadisticas, Opciones::default())
+    }
+
+    #[test]
+    fn top_juegos_con_n() {
+        let servidor = generar_servidor();
+        let respuesta = servidor.responder("GET", "/games?n=1");
+        assert_eq!(respuesta.estado, 200);
+        let juegos: serde_json::Value = serde_json::from_str(&respuesta.cuerpo).unwrap();
+        assert_eq!(juegos.as_array().unwrap().len(), 1);
+        assert_eq!(juegos[0]["game"], "Juego A");
+    }
+
+    #[test]
+    fn juego_con_todos_sus_idiomas() {
+        let servidor = generar_servidor();
+        let respuesta = servidor.responder("GET", "/games/Juego%20A");
+        assert_eq!(respuesta.estado, 200);
+        let juego: serde_json::Value = serde_json::from_str(&respuesta.cuerpo).unwrap();
+        assert_eq!(juego["languages"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn reviews_de_un_idioma_paginadas() {
+        let servidor = generar_servidor();
+        let respuesta = servidor.responder("GET", "/languages/english/reviews?page=2&per_page=1");
+        assert_eq!(respuesta.estado, 200);
+        let idioma: serde_json::Value = serde_json::from_str(&respuesta.cuerpo).unwrap();
+        assert_eq!(idioma["review_count"], 3);
+        assert_eq!(idioma["top_reviews"][0]["review"], "Good");
+        assert_eq!(idioma["top_reviews"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn pedidos_invalidos() {
+        let servidor = generar_servidor();
+        assert_eq!(servidor.responder("POST", "/games").estado, 405);
+        assert_eq!(servidor.responder("GET", "/games/Otro").estado, 404);
+        assert_eq!(servidor.responder("GET", "/games?n=abc").estado, 400);
+        assert_eq!(
+            servidor
+                .responder("GET", "/languages/english/reviews?page=0")
+                .estado,
+            400
+        );
+        assert_eq!(
+            servidor
+                .responder(
+                    "GET",
+                    "/languages/english/reviews?page=18446744073709551615&per_page=100"
+                )
+                .estado,
+            400
+        );
+    }
+
+    #[test]
+    fn decodificar_textos_de_url() {
+        assert_eq!(decodificar_url("Juego+A%3A%20B"), "Juego A: B");
+        assert_eq!(decodificar_url("100%"), "100%");
+        assert_eq!(decodificar_url("%C3%B1"), "ñ");
+    }
+}
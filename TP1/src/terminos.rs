@@ -0,0 +1,316 @@
+use crate::juego::Juego;
+use crate::palabras_vacias;
+use crate::review::Review;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+
+/// Largo máximo en caracteres de un token. Los tokens más largos suelen ser arte ASCII, links o
+/// texto sin espacios y no aportan nada al análisis, así que se descartan
+const LARGO_MAXIMO_TOKEN: usize = 40;
+
+/// Cantidad de juegos, los de más reseñas, para los que se conservan los términos al combinar
+/// estadísticas. Sólo se informan los términos de los juegos del top, así que no tiene sentido
+/// acumular los de todos; el margen permite que un juego que todavía no está en el top lo alcance
+const JUEGOS_CON_TERMINOS: usize = 50;
+
+/// Struct que almacena las frecuencias de los términos de un conjunto de reseñas
+/// unigramas es un HashMap donde la clave es una palabra y el valor la cantidad de apariciones
+/// bigramas es un HashMap donde la clave son dos palabras contiguas en el texto original y el valor
+///     la cantidad de apariciones
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Terminos {
+    pub unigramas: HashMap<String, u32>,
+    pub bigramas: HashMap<String, u32>,
+}
+
+impl Terminos {
+    /// Recibe las frases de una reseña (ver tokenizar_frases) y actualiza las frecuencias de
+    /// unigramas y bigramas. Los bigramas se arman sólo dentro de cada frase
+    pub fn agregar_frases(&mut self, frases: &[Vec<String>]) {
+        for frase in frases {
+            for token in frase {
+                *self.unigramas.entry(token.clone()).or_insert(0) += 1;
+            }
+            for par in frase.windows(2) {
+                *self
+                    .bigramas
+                    .entry(unir_bigrama(&par[0], &par[1]))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Suma las frecuencias de otra instancia de Terminos a la actual
+    pub fn combinar(&mut self, otro: Terminos) {
+        for (termino, cantidad) in otro.unigramas {
+            *self.unigramas.entry(termino).or_insert(0) += cantidad;
+        }
+        for (termino, cantidad) in otro.bigramas {
+            *self.bigramas.entry(termino).or_insert(0) += cantidad;
+        }
+    }
+}
+
+/// Struct que almacena el análisis de términos de todas las reseñas leídas
+/// por_idioma es un HashMap donde la clave es el idioma y el valor sus Terminos
+/// por_juego es un HashMap donde la clave es el nombre del juego y el valor sus Terminos
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AnalisisTerminos {
+    pub por_idioma: HashMap<String, Terminos>,
+    pub por_juego: HashMap<String, Terminos>,
+}
+
+impl AnalisisTerminos {
+    /// Tokeniza el texto de la review y suma sus términos tanto a su juego como al idioma
+    /// recibido, que puede ser distinto al de la review si el idioma tiene un alias. Las palabras
+    /// vacías siempre son las del idioma de la review
+    /// Devuelve las frases, para sumarlas también a otros análisis sin volver a tokenizar
+    pub fn agregar_review(&mut self, review: &Review, idioma: &str) -> Vec<Vec<String>> {
+        let frases = tokenizar_frases(&review.review, &review.language);
+        self.agregar_frases(&review.app_name, idioma, &frases);
+        frases
+    }
+
+    /// Suma las frases de una reseña ya tokenizada a su juego y a su idioma
+    pub fn agregar_frases(&mut self, juego: &str, idioma: &str, frases: &[Vec<String>]) {
+        if frases.is_empty() {
+            return;
+        }
+        self.por_idioma
+            .entry(idioma.to_string())
+            .or_default()
+            .agregar_frases(frases);
+        self.por_juego
+            .entry(juego.to_string())
+            .or_default()
+            .agregar_frases(frases);
+    }
+
+    /// Combina el análisis de otra instancia con el actual, sumando las frecuencias de los
+    /// idiomas y juegos que estén en ambas
+    pub fn combinar(&mut self, otro: AnalisisTerminos) {
+        for (idioma, terminos) in otro.por_idioma {
+            self.por_idioma
+                .entry(idioma)
+                .or_default()
+                .combinar(terminos);
+        }
+        for (juego, terminos) in otro.por_juego {
+            self.por_juego.entry(juego).or_default().combinar(terminos);
+        }
+    }
+
+    /// Descarta los términos de los juegos que no están entre los JUEGOS_CON_TERMINOS con más
+    /// reseñas, para que la memoria no crezca con la cantidad de juegos leídos. Los empates se
+    /// resuelven por nombre, igual que en el top de juegos
+    pub fn podar_juegos(&mut self, juegos: &HashMap<String, Juego>) {
+        if self.por_juego.len() <= JUEGOS_CON_TERMINOS {
+            return;
+        }
+        let mut candidatos: Vec<(&String, usize)> = self
+            .por_juego
+            .keys()
+            .map(|nombre| (nombre, juegos.get(nombre).map_or(0, |juego| juego.reviews)))
+            .collect();
+        candidatos.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let conservar: HashSet<String> = candidatos
+            .into_iter()
+            .take(JUEGOS_CON_TERMINOS)
+            .map(|(nombre, _)| nombre.clone())
+            .collect();
+        self.por_juego
+            .retain(|nombre, _| conservar.contains(nombre));
+    }
+}
+
+/// Indica si un caracter pertenece a una escritura que no separa palabras con espacios (ideogramas
+/// CJK, hiragana y katakana). Estos caracteres se toman como tokens individuales
+fn es_caracter_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+/// Une dos tokens consecutivos en un bigrama. Los ideogramas se unen sin espacio para que el
+/// bigrama se lea como una palabra
+fn unir_bigrama(a: &str, b: &str) -> String {
+    let es_cjk = |s: &str| s.chars().all(es_caracter_cjk);
+    if es_cjk(a) && es_cjk(b) {
+        format!("{}{}", a, b)
+    } else {
+        format!("{} {}", a, b)
+    }
+}
+
+/// Termina la frase actual, si tiene algún token, para que el próximo token empiece una nueva
+fn cortar_frase(frases: &mut Vec<Vec<String>>) {
+    if frases.last().is_none_or(|frase| !frase.is_empty()) {
+        frases.push(Vec::new());
+    }
+}
+
+/// Agrega un token válido al final de la frase actual
+fn agregar_a_frase(frases: &mut Vec<Vec<String>>, token: String) {
+    match frases.last_mut() {
+        Some(frase) => frase.push(token),
+        None => frases.push(vec![token]),
+    }
+}
+
+/// Agrega el token actual a la frase actual si es válido: tiene al menos 2 caracteres, no es
+/// sólo numérico, no supera el largo máximo y no es una palabra vacía del idioma. Un token
+/// descartado corta la frase, porque las palabras de cada lado no eran contiguas
+fn cerrar_token(actual: &mut String, idioma: &str, frases: &mut Vec<Vec<String>>) {
+    if actual.is_empty() {
+        return;
+    }
+    let token = actual.trim_end_matches('\'');
+    let largo = token.chars().count();
+    if (2..=LARGO_MAXIMO_TOKEN).contains(&largo)
+        && !token.chars().all(|c| c.is_numeric())
+        && !palabras_vacias::es_palabra_vacia(idioma, token)
+    {
+        agregar_a_frase(frases, token.to_string());
+    } else {
+        cortar_frase(frases);
+    }
+    actual.clear();
+}
+
+/// Recibe el texto de una reseña y su idioma y lo separa en frases: secuencias de tokens que
+/// estaban contiguos en el texto original.
+/// El texto se normaliza con NFKC (por ejemplo los caracteres de ancho completo pasan a su forma
+/// normal) y se pasa a minúscula. Las palabras se separan por cualquier caracter que no sea
+/// alfanumérico, y los ideogramas se toman de a uno. Se descartan las palabras vacías del idioma.
+/// Las frases se cortan en los signos de puntuación y en los tokens descartados
+pub fn tokenizar_frases(texto: &str, idioma: &str) -> Vec<Vec<String>> {
+    let normalizado = texto.nfkc().collect::<String>().to_lowercase();
+    let mut frases = Vec::new();
+    let mut actual = String::new();
+
+    for c in normalizado.chars() {
+        if es_caracter_cjk(c) {
+            cerrar_token(&mut actual, idioma, &mut frases);
+            let ideograma = c.to_string();
+            if palabras_vacias::es_palabra_vacia(idioma, &ideograma) {
+                cortar_frase(&mut frases);
+            } else {
+                agregar_a_frase(&mut frases, ideograma);
+            }
+        } else if c.is_alphanumeric() || (c == '\'' && !actual.is_empty()) {
+            actual.push(c);
+        } else {
+            cerrar_token(&mut actual, idioma, &mut frases);
+            if !c.is_whitespace() {
+                cortar_frase(&mut frases);
+            }
+        }
+    }
+    cerrar_token(&mut actual, idioma, &mut frases);
+    frases.retain(|frase| !frase.is_empty());
+
+    frases
+}
+
+/// Recibe el texto de una reseña y su idioma y lo separa en tokens (ver tokenizar_frases)
+pub fn tokenizar(texto: &str, idioma: &str) -> Vec<String> {
+    tokenizar_frases(texto, idioma)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Recibe un HashMap de frecuencias y devuelve los n términos más frecuentes, desempatando por
+/// orden alfabético
+pub fn top_terminos(frecuencias: &HashMap<String, u32>, n: usize) -> Vec<(String, u32)> {
+    let mut terminos: Vec<(String, u32)> = frecuencias
+        .iter()
+        .map(|(termino, cantidad)| (termino.clone(), *cantidad))
+        .collect();
+    terminos.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terminos.truncate(n);
+    terminos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizar_normaliza_y_saca_palabras_vacias() {
+        let tokens = tokenizar("The GAME is ＧＲＥＡＴ, really fun!!", "english");
+        assert_eq!(tokens, vec!["game", "great", "fun"]);
+    }
+
+    #[test]
+    fn tokenizar_descarta_numeros_y_tokens_cortos() {
+        let tokens = tokenizar("10/10 x juego 2021", "spanish");
+        assert_eq!(tokens, vec!["juego"]);
+    }
+
+    #[test]
+    fn tokenizar_separa_ideogramas() {
+        let tokens = tokenizar("这个游戏很好玩", "schinese");
+        assert_eq!(tokens, vec!["游", "戏", "好", "玩"]);
+    }
+
+    #[test]
+    fn tokenizar_frases_corta_en_puntuacion_y_palabras_vacias() {
+        let frases = tokenizar_frases("Great game, and the story is fun", "english");
+        assert_eq!(
+            frases,
+            vec![vec!["great", "game"], vec!["story"], vec!["fun"]]
+        );
+    }
+
+    #[test]
+    fn agregar_frases_cuenta_unigramas_y_bigramas() {
+        let mut terminos = Terminos::default();
+        terminos.agregar_frases(&tokenizar_frases("great game, great fun", "english"));
+
+        assert_eq!(terminos.unigramas.get("great"), Some(&2));
+        assert_eq!(terminos.bigramas.get("great game"), Some(&1));
+        assert_eq!(terminos.bigramas.get("great fun"), Some(&1));
+        assert_eq!(terminos.bigramas.get("game great"), None);
+        assert_eq!(
+            top_terminos(&terminos.unigramas, 2),
+            vec![("great".to_string(), 2), ("fun".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn bigramas_de_ideogramas_sin_espacio() {
+        let mut terminos = Terminos::default();
+        terminos.agregar_frases(&tokenizar_frases("这个游戏很好玩", "schinese"));
+        assert_eq!(terminos.bigramas.get("游戏"), Some(&1));
+        assert_eq!(terminos.bigramas.get("好玩"), Some(&1));
+        assert_eq!(terminos.bigramas.get("戏好"), None);
+    }
+
+    #[test]
+    fn podar_juegos_conserva_los_de_mas_reviews() {
+        let mut analisis = AnalisisTerminos::default();
+        let mut juegos = HashMap::new();
+        for i in 0..JUEGOS_CON_TERMINOS + 10 {
+            let nombre = format!("Juego {:03}", i);
+            analisis.agregar_frases(&nombre, "english", &[vec!["fun".to_string()]]);
+            juegos.insert(
+                nombre,
+                Juego {
+                    reviews: i,
+                    ..Default::default()
+                },
+            );
+        }
+
+        analisis.podar_juegos(&juegos);
+
+        assert_eq!(analisis.por_juego.len(), JUEGOS_CON_TERMINOS);
+        assert!(analisis.por_juego.contains_key("Juego 059"));
+        assert!(!analisis.por_juego.contains_key("Juego 009"));
+    }
+}
@@ -0,0 +1,50 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Struct que representa la sección de términos del archivo de salida
+/// idiomas es un vector con los términos más frecuentes de cada idioma
+/// juegos es un vector con los términos más frecuentes de cada uno de los juegos del top
+#[derive(Serialize, JsonSchema)]
+pub struct SeccionTerminos {
+    #[serde(rename = "languages")]
+    pub idiomas: Vec<TerminosIdioma>,
+    #[serde(rename = "games")]
+    pub juegos: Vec<TerminosJuego>,
+}
+
+/// Struct que almacena los términos más frecuentes de un idioma
+/// idioma es el nombre del idioma
+/// unigramas son las palabras más frecuentes
+/// bigramas son los pares de palabras consecutivas más frecuentes
+#[derive(Serialize, JsonSchema)]
+pub struct TerminosIdioma {
+    #[serde(rename = "language")]
+    pub idioma: String,
+    #[serde(rename = "top_unigrams")]
+    pub unigramas: Vec<TerminoContado>,
+    #[serde(rename = "top_bigrams")]
+    pub bigramas: Vec<TerminoContado>,
+}
+
+/// Struct que almacena los términos más frecuentes de un juego
+/// nombre es el nombre del juego
+/// unigramas son las palabras más frecuentes
+/// bigramas son los pares de palabras consecutivas más frecuentes
+#[derive(Serialize, JsonSchema)]
+pub struct TerminosJuego {
+    #[serde(rename = "game")]
+    pub nombre: String,
+    #[serde(rename = "top_unigrams")]
+    pub unigramas: Vec<TerminoContado>,
+    #[serde(rename = "top_bigrams")]
+    pub bigramas: Vec<TerminoContado>,
+}
+
+/// Struct que almacena un término junto con su cantidad de apariciones
+#[derive(Serialize, JsonSchema)]
+pub struct TerminoContado {
+    #[serde(rename = "term")]
+    pub termino: String,
+    #[serde(rename = "count")]
+    pub cantidad: u32,
+}
@@ -0,0 +1,3 @@
+//! Biblioteca compartida por las aplicaciones de usuario (comensal, restaurante y repartidor). Declara y expone los submódulos del cliente.
+
+pub mod sesion;
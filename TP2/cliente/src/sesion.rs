@@ -0,0 +1,556 @@
+//! Este módulo contiene la sesión de una aplicación de usuario con el servidor coordinador: descubre al coordinador, mantiene la conexión con él, y ante una desconexión o un cambio de coordinador se reconecta y reenvía los mensajes que no fueron confirmados.
+
+// Imports de crates externas.
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::time::{sleep, sleep_until, timeout, Duration, Instant};
+
+// Imports de funciones/estructuras propias.
+use common::confiable::{Pendientes, Reintentos};
+use common::protocolo::{empaquetar, Mensaje, Recibido, Sobre};
+use common::transporte::{Conexion, EscritorTramas, LectorTramas, Transporte};
+
+// Constantes.
+const TIMEOUT_COORDINADOR: u64 = 3;
+const INTERVALO_VERIFICACION_SEGS: u64 = 5;
+const BUSCANDO_COORDINADOR: &str = "Buscando coordinador...";
+const ERROR_ESCRIBIENDO_CONSULTA_LIDER: &str =
+    "Error escribiendo consulta para averiguar el líder:";
+const ERROR_LEER_LINEA: &str = "Error al leer línea:";
+const TIMEOUT_LEER_LINEA: &str = "Timeout al leer línea.";
+const SIN_COORDINADOR: &str = "El servidor todavía no conoce al coordinador:";
+const NO_CONECTA_SERVIDOR: &str = "No se pudo conectar al servidor:";
+const NO_CONECTA_COORDINADOR: &str = "No se pudo conectar al coordinador:";
+const ENCONTRO_COORDINADOR: &str = "Encontró al coordinador:";
+const CONECTADO_COORDINADOR: &str = "Conectado al coordinador.";
+const CAMBIO_COORDINADOR: &str = "Cambió el coordinador:";
+const SERVER_CERRO_CONEXION: &str = "El servidor cerró la conexión.";
+const ERROR_LEYENDO_SERVIDOR: &str = "Error leyendo del servidor:";
+const ERROR_ENVIAR: &str = "Error al enviar un mensaje, se reenviará hasta que se confirme:";
+const ERROR_REENVIAR: &str = "Error al reenviar un mensaje sin confirmar:";
+const ERROR_SIN_CONFIRMACION: &str =
+    "El servidor no confirmó los mensajes enviados, intentando reconectar...";
+const RECONEXION_EXITOSA: &str = "Reconectado con el servidor.";
+const REENVIO_PENDIENTES: &str = "Reenviados los mensajes sin confirmar al servidor.";
+const ERROR_AL_RECONECTAR: &str = "Error al reconectar con el servidor.";
+
+// 'Enum' que contiene los errores que pueden ocurrir al iniciar una sesión.
+#[derive(Debug, PartialEq)]
+pub enum ErrorSesion {
+    SinCoordinador,
+}
+
+// Implementación del trait 'Display'.
+impl fmt::Display for ErrorSesion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorSesion::SinCoordinador => {
+                write!(f, "No se pudo conectar con ningún servidor coordinador.")
+            }
+        }
+    }
+}
+
+// Implementación del trait 'Error'.
+impl std::error::Error for ErrorSesion {}
+
+// Eventos de la sesión que le interesan a la aplicación.
+#[derive(Debug)]
+pub enum Evento {
+    // Mensaje recibido del servidor.
+    Mensaje(Recibido),
+    // El servidor confirmó el mensaje enviado de forma confiable con este identificador.
+    Confirmado(u64),
+    // La sesión se reconectó, posiblemente con otro coordinador. La aplicación debe volver a identificarse ante
+    // el servidor antes de volver a llamar a 'recibir', que recién entonces reenvía los mensajes sin confirmar.
+    Reconectado,
+}
+
+// Sesión de una aplicación de usuario con el servidor coordinador.
+pub struct Sesion {
+    nombre: &'static str,
+    servidores: Vec<SocketAddr>,
+    transporte: Arc<dyn Transporte>,
+    reintentos: Reintentos,
+    coordinador: SocketAddr,
+    direccion: SocketAddr,
+    lector: LectorTramas,
+    escritor: EscritorTramas,
+    pendientes: Pendientes,
+    reenviar_al_recibir: bool,
+    proxima_verificacion: Instant,
+}
+
+// Implementación de la sesión.
+impl Sesion {
+    // Constructor. Busca al coordinador entre los 'servidores' y se conecta con él. El 'nombre' de la aplicación
+    // se usa como prefijo de los logs.
+    pub async fn iniciar(
+        nombre: &'static str,
+        servidores: Vec<SocketAddr>,
+        transporte: Arc<dyn Transporte>,
+    ) -> Result<Self, ErrorSesion> {
+        let reintentos = Reintentos::default();
+        let (coordinador, conexion) =
+            conectar_con_coordinador(nombre, &servidores, transporte.as_ref(), &reintentos)
+                .await
+                .ok_or(ErrorSesion::SinCoordinador)?;
+        Ok(Sesion {
+            nombre,
+            servidores,
+            transporte,
+            reintentos,
+            coordinador,
+            direccion: conexion.local,
+            lector: conexion.lector,
+            escritor: conexion.escritor,
+            pendientes: Pendientes::new(reintentos),
+            reenviar_al_recibir: false,
+            proxima_verificacion: Instant::now() + Duration::from_secs(INTERVALO_VERIFICACION_SEGS),
+        })
+    }
+
+    // Envía un mensaje al coordinador, sin esperar su confirmación.
+    pub async fn enviar(&mut self, mensaje: Mensaje) -> io::Result<()> {
+        let sobre = empaquetar(self.direccion, mensaje);
+        self.escritor.enviar(&sobre).await
+    }
+
+    // Envía un mensaje de forma confiable: queda pendiente, y se retransmite, hasta que el servidor lo confirme.
+    // Aunque falle el envío, el mensaje queda pendiente y se reenvía al reconectar. Devuelve el identificador del
+    // mensaje, que es el que llega en 'Evento::Confirmado'.
+    pub async fn enviar_confiable(&mut self, mensaje: Mensaje) -> u64 {
+        let sobre = empaquetar(self.direccion, mensaje);
+        self.pendientes.registrar(sobre.clone());
+        if let Err(e) = self.escritor.enviar(&sobre).await {
+            eprintln!("{} - {} {}", self.nombre, ERROR_ENVIAR, e);
+        }
+
+        sobre.id_mensaje
+    }
+
+    // Dirección de la conexión actual con el coordinador, que es con la que el servidor identifica a la aplicación.
+    // Cambia al reconectarse.
+    pub fn direccion(&self) -> SocketAddr {
+        self.direccion
+    }
+
+    // Espera el próximo evento de la sesión. Mientras tanto retransmite los mensajes sin confirmar y verifica
+    // que el coordinador siga siendo el mismo, reconectándose de ser necesario. Devuelve 'None' si la sesión
+    // se perdió y no se pudo reconectar.
+    pub async fn recibir(&mut self) -> Option<Evento> {
+        if self.reenviar_al_recibir {
+            self.reenviar_pendientes().await;
+        }
+
+        loop {
+            tokio::select! {
+                resultado = self.lector.recibir() => {
+                    match resultado {
+                        Ok(Some(Ok(Sobre { mensaje: Mensaje::Confirmacion(id_mensaje), .. }))) => {
+                            if self.pendientes.confirmar(id_mensaje) {
+                                return Some(Evento::Confirmado(id_mensaje));
+                            }
+                        }
+
+                        Ok(Some(Ok(Sobre { mensaje: Mensaje::QuienEsCoordinador(quien_es), .. }))) => {
+                            let nuevo = quien_es.direccion_coordinador;
+                            if nuevo != self.coordinador {
+                                println!("{} - {} {}", self.nombre, CAMBIO_COORDINADOR, nuevo);
+                                return self.reconectar(Some(nuevo)).await;
+                            }
+                        }
+
+                        Ok(Some(recibido)) => return Some(Evento::Mensaje(recibido)),
+
+                        Ok(None) => {
+                            println!("{} - {}", self.nombre, SERVER_CERRO_CONEXION);
+                            return self.reconectar(None).await;
+                        }
+
+                        Err(e) => {
+                            eprintln!("{} - {} {}", self.nombre, ERROR_LEYENDO_SERVIDOR, e);
+                            return self.reconectar(None).await;
+                        }
+                    }
+                }
+
+                _ = self.pendientes.esperar_vencimiento() => {
+                    if !self.retransmitir().await {
+                        println!("{} - {}", self.nombre, ERROR_SIN_CONFIRMACION);
+                        return self.reconectar(None).await;
+                    }
+                }
+
+                _ = sleep_until(self.proxima_verificacion) => {
+                    self.verificar_coordinador().await;
+                }
+            }
+        }
+    }
+
+    // Espera a que el servidor confirme todos los mensajes enviados de forma confiable. El resto de los eventos
+    // recibidos mientras tanto se descartan. Devuelve 'false' si la sesión se perdió antes.
+    pub async fn esperar_confirmaciones(&mut self) -> bool {
+        while !self.pendientes.esta_vacio() {
+            if self.recibir().await.is_none() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Retransmite los mensajes cuya confirmación no llegó a tiempo. Devuelve 'false' si alguno agotó sus
+    // intentos, en cuyo caso se asume que la conexión con el coordinador se perdió. Los mensajes agotados
+    // siguen pendientes, para reenviarlos al reconectar.
+    async fn retransmitir(&mut self) -> bool {
+        let revision = self.pendientes.revisar();
+        for sobre in revision.reenviar {
+            if let Err(e) = self.escritor.enviar(&sobre).await {
+                eprintln!("{} - {} {}", self.nombre, ERROR_REENVIAR, e);
+            }
+        }
+
+        let sin_agotados = revision.agotados.is_empty();
+        for sobre in revision.agotados {
+            self.pendientes.registrar(sobre);
+        }
+
+        sin_agotados
+    }
+
+    // Le pregunta al coordinador actual quién es el coordinador, para detectar si cambió. La respuesta se
+    // recibe en 'recibir'.
+    async fn verificar_coordinador(&mut self) {
+        self.proxima_verificacion =
+            Instant::now() + Duration::from_secs(INTERVALO_VERIFICACION_SEGS);
+        let consulta = empaquetar(self.direccion, Mensaje::ConsultarCoordinador);
+        if let Err(e) = self.escritor.enviar(&consulta).await {
+            eprintln!(
+                "{} - {} {}",
+                self.nombre, ERROR_ESCRIBIENDO_CONSULTA_LIDER, e
+            );
+        }
+    }
+
+    // Reenvía, desde la dirección de la conexión actual, los mensajes que no fueron confirmados.
+    async fn reenviar_pendientes(&mut self) {
+        self.reenviar_al_recibir = false;
+        for sobre in self.pendientes.reiniciar(self.direccion) {
+            if let Err(e) = self.escritor.enviar(&sobre).await {
+                eprintln!("{} - {} {}", self.nombre, ERROR_REENVIAR, e);
+            }
+        }
+
+        println!("{} - {}", self.nombre, REENVIO_PENDIENTES);
+    }
+
+    // Reconecta con el coordinador. Si se conoce al nuevo coordinador se intenta conectar directamente con él; si
+    // no, se lo vuelve a buscar entre los servidores. Los mensajes sin confirmar no se reenvían acá sino en la
+    // próxima llamada a 'recibir', para que antes la aplicación se identifique ante el nuevo servidor.
+    async fn reconectar(&mut self, nuevo_coordinador: Option<SocketAddr>) -> Option<Evento> {
+        let mut conectado = None;
+        if let Some(coordinador) = nuevo_coordinador {
+            match self.transporte.conectar(coordinador).await {
+                Ok(conexion) => conectado = Some((coordinador, conexion)),
+                Err(_) => println!(
+                    "{} - {} {}",
+                    self.nombre, NO_CONECTA_COORDINADOR, coordinador
+                ),
+            }
+        }
+
+        if conectado.is_none() {
+            conectado = conectar_con_coordinador(
+                self.nombre,
+                &self.servidores,
+                self.transporte.as_ref(),
+                &self.reintentos,
+            )
+            .await;
+        }
+
+        let Some((coordinador, conexion)) = conectado else {
+            eprintln!("{} - {}", self.nombre, ERROR_AL_RECONECTAR);
+            return None;
+        };
+
+        self.coordinador = coordinador;
+        self.direccion = conexion.local;
+        self.lector = conexion.lector;
+        self.escritor = conexion.escritor;
+        self.proxima_verificacion =
+            Instant::now() + Duration::from_secs(INTERVALO_VERIFICACION_SEGS);
+        self.reenviar_al_recibir = true;
+        println!("{} - {}", self.nombre, RECONEXION_EXITOSA);
+        Some(Evento::Reconectado)
+    }
+}
+
+// Función que busca al coordinador entre los servidores y se conecta con él. Si ningún servidor lo conoce, o no
+// se puede conectar con él, vuelve a intentarlo con una espera que crece exponencialmente.
+async fn conectar_con_coordinador(
+    nombre: &str,
+    servidores: &[SocketAddr],
+    transporte: &dyn Transporte,
+    reintentos: &Reintentos,
+) -> Option<(SocketAddr, Conexion)> {
+    for intento in 0..reintentos.intentos_maximos {
+        if intento > 0 {
+            sleep(reintentos.espera(intento - 1)).await;
+        }
+
+        println!("{} - {}", nombre, BUSCANDO_COORDINADOR);
+        for &servidor in servidores {
+            let Some((coordinador, consulta)) =
+                consultar_coordinador(nombre, servidor, transporte).await
+            else {
+                continue;
+            };
+
+            println!("{} - {} {}", nombre, ENCONTRO_COORDINADOR, coordinador);
+            // Si el servidor consultado es el coordinador se sigue usando la misma conexión.
+            if coordinador == servidor {
+                println!("{} - {}", nombre, CONECTADO_COORDINADOR);
+                return Some((coordinador, consulta));
+            }
+
+            match transporte.conectar(coordinador).await {
+                Ok(conexion) => {
+                    println!("{} - {}", nombre, CONECTADO_COORDINADOR);
+                    return Some((coordinador, conexion));
+                }
+
+                Err(_) => println!("{} - {} {}", nombre, NO_CONECTA_COORDINADOR, coordinador),
+            }
+        }
+    }
+
+    None
+}
+
+// Función que le pregunta a un servidor quién es el coordinador. Devuelve su dirección junto con la conexión
+// abierta con el servidor consultado, o 'None' si el servidor no responde o todavía no lo conoce.
+async fn consultar_coordinador(
+    nombre: &str,
+    servidor: SocketAddr,
+    transporte: &dyn Transporte,
+) -> Option<(SocketAddr, Conexion)> {
+    let Ok(mut conexion) = transporte.conectar(servidor).await else {
+        println!("{} - {} {}", nombre, NO_CONECTA_SERVIDOR, servidor);
+        return None;
+    };
+
+    let msj = empaquetar(conexion.local, Mensaje::ConsultarCoordinador);
+    if let Err(e) = conexion.escritor.enviar(&msj).await {
+        eprintln!("{} - {} {}", nombre, ERROR_ESCRIBIENDO_CONSULTA_LIDER, e);
+        return None;
+    }
+
+    match timeout(
+        Duration::from_secs(TIMEOUT_COORDINADOR),
+        conexion.lector.recibir(),
+    )
+    .await
+    {
+        Ok(Ok(recibido)) => match recibido.and_then(Result::ok).map(|sobre| sobre.mensaje) {
+            Some(Mensaje::QuienEsCoordinador(quien_es_coordinador_msj)) => {
+                Some((quien_es_coordinador_msj.direccion_coordinador, conexion))
+            }
+
+            _ => {
+                println!("{} - {} {}", nombre, SIN_COORDINADOR, servidor);
+                None
+            }
+        },
+
+        Ok(Err(e)) => {
+            println!("{} - {} {:?}", nombre, ERROR_LEER_LINEA, e);
+            None
+        }
+
+        Err(_) => {
+            println!("{} - {}", nombre, TIMEOUT_LEER_LINEA);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::mensajes::{MensajeIdentidad, QuienEsCoordinador, SolicitarPedido};
+    use common::protocolo::CODECS;
+    use common::transporte::{Escucha, TransporteMemoria};
+
+    fn direccion(puerto: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], puerto))
+    }
+
+    fn pedido() -> Mensaje {
+        Mensaje::SolicitarPedido(SolicitarPedido {
+            comida: "Pizza".to_string(),
+            destino: (1.0, 2.0),
+        })
+    }
+
+    fn identidad() -> Mensaje {
+        Mensaje::MensajeIdentidad(MensajeIdentidad {
+            ubicacion: (1.0, 2.0),
+            soy_repartidor: false,
+        })
+    }
+
+    // Acepta la próxima conexión de la escucha.
+    async fn aceptar(escucha: &mut Box<dyn Escucha>) -> Conexion {
+        escucha
+            .aceptar()
+            .await
+            .unwrap()
+            .responder_saludo()
+            .await
+            .unwrap()
+    }
+
+    // Lee el próximo sobre de la conexión.
+    async fn leer(conexion: &mut Conexion) -> Sobre {
+        conexion.lector.recibir().await.unwrap().unwrap().unwrap()
+    }
+
+    // Acepta una conexión y responde la consulta de quién es el coordinador con 'coordinador'.
+    async fn atender_consulta(escucha: &mut Box<dyn Escucha>, coordinador: SocketAddr) -> Conexion {
+        let mut conexion = aceptar(escucha).await;
+        let consulta = leer(&mut conexion).await;
+        assert!(matches!(consulta.mensaje, Mensaje::ConsultarCoordinador));
+        let respuesta = Mensaje::QuienEsCoordinador(QuienEsCoordinador {
+            direccion_coordinador: coordinador,
+        });
+        conexion
+            .escritor
+            .enviar(&empaquetar(conexion.local, respuesta))
+            .await
+            .unwrap();
+        conexion
+    }
+
+    #[tokio::test]
+    async fn la_sesion_se_conecta_con_el_coordinador_que_le_indican() {
+        let transporte = Arc::new(TransporteMemoria::new(CODECS.to_vec()));
+        let (servidor, coordinador) = (direccion(9100), direccion(9101));
+        let mut escucha_servidor = transporte.escuchar(servidor).await.unwrap();
+        let mut escucha_coordinador = transporte.escuchar(coordinador).await.unwrap();
+        let servidores = tokio::spawn(async move {
+            atender_consulta(&mut escucha_servidor, coordinador).await;
+            aceptar(&mut escucha_coordinador).await
+        });
+
+        let sesion = Sesion::iniciar("TEST", vec![servidor], transporte)
+            .await
+            .unwrap();
+        let conexion = servidores.await.unwrap();
+        assert_eq!(sesion.coordinador, coordinador);
+        assert_eq!(sesion.direccion, conexion.remota);
+    }
+
+    #[tokio::test]
+    async fn si_cae_el_coordinador_se_reconecta_con_otro_y_reenvia_lo_pendiente() {
+        let transporte = Arc::new(TransporteMemoria::new(CODECS.to_vec()));
+        let (caido, nuevo) = (direccion(9110), direccion(9111));
+        let mut escucha_caido = transporte.escuchar(caido).await.unwrap();
+        let mut escucha_nuevo = transporte.escuchar(nuevo).await.unwrap();
+
+        // El primer coordinador recibe el pedido pero se cae antes de confirmarlo.
+        let primero = tokio::spawn(async move {
+            let mut conexion = atender_consulta(&mut escucha_caido, caido).await;
+            leer(&mut conexion).await
+        });
+        let mut sesion = Sesion::iniciar("TEST", vec![caido, nuevo], transporte)
+            .await
+            .unwrap();
+        let id_pedido = sesion.enviar_confiable(pedido()).await;
+        let original = primero.await.unwrap();
+        assert_eq!(original.id_mensaje, id_pedido);
+        let direccion_anterior = sesion.direccion;
+
+        // El nuevo coordinador recibe primero la identidad y luego el pedido reenviado, y lo confirma.
+        let segundo = tokio::spawn(async move {
+            let mut conexion = atender_consulta(&mut escucha_nuevo, nuevo).await;
+            let identificacion = leer(&mut conexion).await;
+            let reenviado = leer(&mut conexion).await;
+            let confirmacion = Mensaje::Confirmacion(reenviado.id_mensaje);
+            conexion
+                .escritor
+                .enviar(&empaquetar(nuevo, confirmacion))
+                .await
+                .unwrap();
+            (identificacion, reenviado, conexion)
+        });
+
+        assert!(matches!(sesion.recibir().await, Some(Evento::Reconectado)));
+        assert_eq!(sesion.coordinador, nuevo);
+        assert_ne!(sesion.direccion, direccion_anterior);
+        sesion.enviar(identidad()).await.unwrap();
+        assert!(matches!(
+            sesion.recibir().await,
+            Some(Evento::Confirmado(id)) if id == original.id_mensaje
+        ));
+
+        let (identificacion, reenviado, _conexion) = segundo.await.unwrap();
+        assert!(matches!(
+            identificacion.mensaje,
+            Mensaje::MensajeIdentidad(_)
+        ));
+        assert!(matches!(reenviado.mensaje, Mensaje::SolicitarPedido(_)));
+        assert_eq!(reenviado.id_mensaje, original.id_mensaje);
+        assert_eq!(reenviado.id_origen, original.id_origen);
+        assert_eq!(reenviado.id_enviador, sesion.direccion);
+        assert!(sesion.pendientes.esta_vacio());
+    }
+
+    #[tokio::test]
+    async fn si_cambia_el_coordinador_se_conecta_directamente_con_el_nuevo() {
+        let transporte = Arc::new(TransporteMemoria::new(CODECS.to_vec()));
+        let (anterior, nuevo) = (direccion(9120), direccion(9121));
+        let mut escucha_anterior = transporte.escuchar(anterior).await.unwrap();
+        let mut escucha_nuevo = transporte.escuchar(nuevo).await.unwrap();
+
+        // El coordinador anterior recibe el pedido y avisa que ahora el coordinador es otro.
+        let primero = tokio::spawn(async move {
+            let mut conexion = atender_consulta(&mut escucha_anterior, anterior).await;
+            let original = leer(&mut conexion).await;
+            let cambio = Mensaje::QuienEsCoordinador(QuienEsCoordinador {
+                direccion_coordinador: nuevo,
+            });
+            conexion
+                .escritor
+                .enviar(&empaquetar(anterior, cambio))
+                .await
+                .unwrap();
+            (original, conexion)
+        });
+        let mut sesion = Sesion::iniciar("TEST", vec![anterior], transporte)
+            .await
+            .unwrap();
+        sesion.enviar_confiable(pedido()).await;
+        let (original, _conexion_anterior) = primero.await.unwrap();
+
+        // El nuevo coordinador no recibe ninguna consulta: la sesión le reenvía directamente el pedido.
+        let segundo = tokio::spawn(async move {
+            let mut conexion = aceptar(&mut escucha_nuevo).await;
+            let reenviado = leer(&mut conexion).await;
+            (reenviado, conexion)
+        });
+
+        assert!(matches!(sesion.recibir().await, Some(Evento::Reconectado)));
+        assert_eq!(sesion.coordinador, nuevo);
+        let recibir = tokio::time::timeout(Duration::from_millis(100), sesion.recibir()).await;
+        assert!(recibir.is_err());
+
+        let (reenviado, _conexion) = segundo.await.unwrap();
+        assert_eq!(reenviado.id_mensaje, original.id_mensaje);
+        assert_eq!(reenviado.id_enviador, sesion.direccion);
+    }
+}
@@ -3,49 +3,25 @@
 // Imports de crates externas.
 use rand::seq::IndexedRandom;
 use std::net::SocketAddr;
-use std::time::Duration;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
-use tokio::time::timeout;
 
 // Imports de funciones/estructuras propias.
+use cliente::sesion::{Evento, Sesion};
 use common::mensajes::{
-    FinalizarViaje, IniciarViajeDelivery, QuienEsCoordinador, RechazarViaje, SolicitarPedido,
+    FinalizarViaje, IdentificarComensal, IniciarViajeDelivery, SolicitarPedido,
+    RECHAZADO_POR_RESTAURANTE,
 };
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{Mensaje, Recibido};
 use common::utils::obtener_tupla_random;
 
 // Constantes.
-const TIMEOUT_ACK_SEGS: u64 = 2;
-const TIMEOUT_COORDINADOR: u64 = 3;
-const ERROR_CONECTARSE_SERVIDOR: &str = "COMENSAL - No se pudo conectar con ningún servidor.";
-const MENSAJE_SERVER_CERRADO: &str = "COMENSAL - El servidor ha cerrado la conexión.";
-const ERROR_LEER_DEL_SERVIDOR: &str = "COMENSAL - Error al leer del servidor:";
-const ERROR_SOLICITAR_VIAJE: &str = "COMENSAL - Error al enviar 'SolicitarViaje':";
+const VERSION_RECHAZADA: &str =
+    "COMENSAL - El servidor rechazó la versión del protocolo del mensaje, versión enviada:";
 const PEDIDO_RECHAZADO_POR_RESTAURANTE: &str =
     "COMENSAL - La comida que elegiste no puede ser preparada por el restaurante.";
-const MENSAJE_DESCONOCIDO: &str = "COMENSAL - Mensaje desconocido recibido del servidor:";
 const ESPERAR_COMIDA: &str = "COMENSAL - Esperando la comida.";
 const VIAJE_FINALIZADO: &str = "COMENSAL - Llego la comida.";
-const ERROR_ENVIAR_FINAL_VIAJE: &str =
-    "COMENSAL - Error al enviar 'FinalizarViaje' al servidor. Intentando reconectar...";
-const ERROR_AL_RECONECTAR: &str = "COMENSAL - Error al reconectar con el servidor.";
-const ACK_MENSAJE: &str = "ACK";
-const ACK_DE_COORDINADOR: &str = "Ack";
-const RECIBIDIO_ACK: &str = "COMENSAL - Recibido ACK del servidor.";
-const ERROR_TIMEOUT_ACK_SEGS: &str =
-    "COMENSAL - Timeout al esperar ACK del servidor, intentando reconectar...";
-const SE_RECONECTA_ENVIA_FINAL: &str =
-    "COMENSAL - Reconectado y enviado 'FinalizarViaje' al servidor.";
-const BUSCANDO_COORDINADOR: &str = "COMENSAL - Buscando coordinador...";
-const ERROR_ESCRIBIENDO_CONSULTA_LIDER: &str =
-    "COMENSAL - Error escribiendo consulta para averiguar el líder:";
-const ERROR_LEER_LINEA: &str = "COMENSAL - Error al leer línea:";
-const TIMEOUT_LEER_LINEA: &str = "COMENSAL - Timeout al leer línea.";
-const COMENSAL_NO_CONECTA_COORDINADOR: &str = "COMENSAL - No se pudo conectar al coordinador:";
-const COMENSAL_ENCONTRO_COORDINADOR: &str = "COMENSAL - Encontró al coordinador:";
-const COMENSAL_CONECTADO_COORDINADOR: &str = "COMENSAL - Conectado al coordinador.";
+const RECIBIDIO_ACK: &str = "COMENSAL - Recibida la confirmación del servidor.";
+const ERROR_SIN_CONFIRMACION: &str = "COMENSAL - Se perdió la sesión con el servidor.";
 const PEDIDO_ELEGIDO_MSJ: &str = "COMENSAL - Pedido elegido:";
 const COMIDAS: [&str; 50] = [
     "Pizza",
@@ -109,54 +85,43 @@ const TRANSACCION_INVALIDA: &str =
 
 // Este actor implementa al 'Comensal' que realiza los pedidos por la aplicación.
 pub struct Comensal {
-    servidores: Vec<SocketAddr>,
-    lector: Lines<BufReader<OwnedReadHalf>>,
-    escritor: OwnedWriteHalf,
+    sesion: Sesion,
     mi_ubicación: (f32, f32),
+    id_pedido: Option<u64>,
+    pedido_confirmado: bool,
+    direcciones: Vec<SocketAddr>,
 }
 
 // Implementación de los métodos de construcción, inicialización y handlers del actor `Comensal`.
 impl Comensal {
     // Constructor.
-    pub async fn new(servidores: Vec<SocketAddr>) -> Self {
-        // Separamos el stream en dos mitades: Lectura y escritura.
-        let tcp_stream: Option<TcpStream> = conectar_con_coordinador(servidores.clone()).await;
-        if let Some(stream) = tcp_stream {
-            let (rx, wx) = stream.into_split();
-            Self {
-                servidores,
-                lector: BufReader::new(rx).lines(),
-                escritor: wx,
-                mi_ubicación: obtener_tupla_random(),
-            }
-        } else {
-            panic!("{}", ERROR_CONECTARSE_SERVIDOR);
+    pub fn new(sesion: Sesion) -> Self {
+        Self {
+            sesion,
+            mi_ubicación: obtener_tupla_random(),
+            id_pedido: None,
+            pedido_confirmado: false,
+            direcciones: Vec::new(),
         }
     }
 
     // Inicio del actor 'Comensal'.
     pub async fn run(&mut self) {
         self.solicitar_un_delivery().await;
-        loop {
-            tokio::select! {
-                resultado = self.lector.next_line() => {
-                    match resultado {
-                        Ok(Some(mensaje)) => {
-                         self.handle_mensaje_servidor(mensaje).await;
-                     }
-                        Ok(None) => {
-                            println!("{}", MENSAJE_SERVER_CERRADO);
-                            break;
-                        }
-
-                        Err(e) => {
-                            eprintln!("{} {}", ERROR_LEER_DEL_SERVIDOR, e);
-                            break;
-                        }
+        while let Some(evento) = self.sesion.recibir().await {
+            match evento {
+                Evento::Mensaje(mensaje) => self.handle_mensaje_servidor(mensaje).await,
+                Evento::Confirmado(id_mensaje) => {
+                    if self.id_pedido == Some(id_mensaje) {
+                        self.pedido_confirmado = true;
                     }
+                    println!("{}", RECIBIDIO_ACK);
                 }
+                Evento::Reconectado => self.identificarse().await,
             }
         }
+
+        eprintln!("{}", ERROR_SIN_CONFIRMACION);
     }
 
     // Método para solicitar un viaje.
@@ -170,34 +135,55 @@ impl Comensal {
             destino: self.mi_ubicación,
         };
 
-        if let Ok(serializado) = serde_json::to_string(&solicitar_viaje) {
-            if let Err(e) = self
-                .escritor
-                .write_all(format!("{}\n", serializado).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_SOLICITAR_VIAJE, e);
-            }
+        self.direcciones.push(self.sesion.direccion());
+        let id_pedido = self
+            .sesion
+            .enviar_confiable(Mensaje::SolicitarPedido(solicitar_viaje))
+            .await;
+        self.id_pedido = Some(id_pedido);
+    }
+
+    // Método para identificarse ante el servidor luego de una reconexión. Si el pedido todavía no estaba confirmado
+    // la sesión lo reenvía sola desde la nueva conexión. Si ya lo estaba, el servidor lo tiene registrado con alguna
+    // de las direcciones anteriores del comensal, por lo que se le envían para que no lo vuelva a pedir.
+    async fn identificarse(&mut self) {
+        let direcciones_anteriores = self.direcciones.clone();
+        self.direcciones.push(self.sesion.direccion());
+        if !self.pedido_confirmado {
+            return;
         }
+
+        self.sesion
+            .enviar_confiable(Mensaje::IdentificarComensal(IdentificarComensal {
+                direcciones_anteriores,
+            }))
+            .await;
     }
 
     // Manejador de los mensajes recibidos del servidor.
-    async fn handle_mensaje_servidor(&mut self, mensaje: String) {
-        if let Ok(iniciar_viaje) = serde_json::from_str::<IniciarViajeDelivery>(&mensaje) {
-            println!("{}", SE_COCINO_EL_PEDIDO);
-            println!("{}", REPARTIDOR_EN_CAMINO);
-            self.esperar_comida(iniciar_viaje).await;
-        } else if let Ok(_) = serde_json::from_str::<RechazarViaje>(&mensaje) {
-            if mensaje == r#"{"respuesta":"Viaje rechazado por restaurante"}"# {
-                println!("{}", PEDIDO_RECHAZADO_POR_RESTAURANTE);
-            } else {
-                println!("{}", TRANSACCION_INVALIDA);
+    async fn handle_mensaje_servidor(&mut self, mensaje: Recibido) {
+        match mensaje.map(|sobre| sobre.mensaje) {
+            Ok(Mensaje::IniciarViajeDelivery(iniciar_viaje)) => {
+                println!("{}", SE_COCINO_EL_PEDIDO);
+                println!("{}", REPARTIDOR_EN_CAMINO);
+                self.esperar_comida(iniciar_viaje).await;
+            }
+
+            Ok(Mensaje::RechazarViaje(rechazo)) => {
+                if rechazo.respuesta == RECHAZADO_POR_RESTAURANTE {
+                    println!("{}", PEDIDO_RECHAZADO_POR_RESTAURANTE);
+                } else {
+                    println!("{}", TRANSACCION_INVALIDA);
+                }
+
+                std::process::exit(0);
+            }
+
+            Ok(Mensaje::VersionRechazada(version)) => {
+                eprintln!("{} {}", VERSION_RECHAZADA, version);
             }
 
-            std::process::exit(0);
-        } else if mensaje == "Viaje rechazado por restaurante".to_string() {
-            println!("COMENSAL - El restaurante rechazo el pedido.");
-            std::process::exit(0);
+            _ => {}
         }
     }
 
@@ -219,134 +205,16 @@ impl Comensal {
             pos_destino: (destination.0, destination.1),
         };
 
-        if let Ok(viaje_finalizado_serializado) = serde_json::to_string(&viaje_finalizado) {
-            if (self
-                .escritor
-                .write_all(format!("{}\n", viaje_finalizado_serializado).as_bytes())
-                .await)
-                .is_err()
-            {
-                println!("{}", ERROR_ENVIAR_FINAL_VIAJE);
-                if !self.intentar_reconectar(viaje_finalizado_serializado).await {
-                    eprintln!("{}", ERROR_AL_RECONECTAR);
-                }
-
-                return;
-            }
-
-            let ack_future = self.lector.next_line();
-            match tokio::time::timeout(
-                tokio::time::Duration::from_secs(TIMEOUT_ACK_SEGS),
-                ack_future,
-            )
-            .await
-            {
-                Ok(Ok(Some(ack_mensaje))) => {
-                    if ack_mensaje.trim() == ACK_MENSAJE {
-                        println!("{}", RECIBIDIO_ACK);
-                    } else {
-                        println!("{} {}", MENSAJE_DESCONOCIDO, ack_mensaje);
-                    }
-                }
+        self.sesion
+            .enviar_confiable(Mensaje::FinalizarViaje(viaje_finalizado))
+            .await;
 
-                Ok(Ok(None)) | Ok(Err(_)) | Err(_) => {
-                    println!("{}", ERROR_TIMEOUT_ACK_SEGS);
-                    if !self.intentar_reconectar(viaje_finalizado_serializado).await {
-                        eprintln!("{}", ERROR_AL_RECONECTAR);
-                    }
-                }
-            }
-        }
-        std::process::exit(0);
-    }
-
-    // Método que intenta reconectar con el servidor coordinador y enviar el mensaje de finalización del viaje.
-    async fn intentar_reconectar(&mut self, mensaje: String) -> bool {
-        let coordinador = conectar_con_coordinador(self.servidores.clone()).await;
-        if let Some(stream) = coordinador {
-            let (lector, escritor) = stream.into_split();
-            self.lector = BufReader::new(lector).lines();
-            self.escritor = escritor;
-            if let Err(e) = self
-                .escritor
-                .write_all(format!("{}\n", mensaje).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_ENVIAR_FINAL_VIAJE, e);
-            } else {
-                println!("{}", SE_RECONECTA_ENVIA_FINAL);
-                return true;
-            }
-        }
-
-        false
-    }
-}
-
-// Función que establece la conexión con el servidor coordinador, con proceso de identificación previo.
-async fn conectar_con_coordinador(servidores: Vec<SocketAddr>) -> Option<TcpStream> {
-    let mut direccion_coordinador: Option<SocketAddr> = None;
-    println!("{}", BUSCANDO_COORDINADOR);
-    for servidor in servidores {
-        match TcpStream::connect(servidor).await {
-            Ok(stream) => {
-                let (lector, mut escritor) = split(stream);
-                let msj = MensajeTCP("WhoIsCoordinator\n".to_string());
-                if let Err(e) = escritor.write_all(msj.0.as_bytes()).await {
-                    eprintln!("{} {}", ERROR_ESCRIBIENDO_CONSULTA_LIDER, e);
-                }
-
-                let mut lector = BufReader::new(lector);
-                let mut linea = String::new();
-                match timeout(
-                    Duration::from_secs(TIMEOUT_COORDINADOR),
-                    lector.read_line(&mut linea),
-                )
-                .await
-                {
-                    Ok(Ok(_)) => {
-                        if let Ok(quien_es_coordinador_msj) =
-                            serde_json::from_str::<QuienEsCoordinador>(&linea)
-                        {
-                            direccion_coordinador =
-                                Some(quien_es_coordinador_msj.direccion_coordinador);
-                            break;
-                        } else if linea.trim() == ACK_DE_COORDINADOR {
-                            break;
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        println!("{} {:?}", ERROR_LEER_LINEA, e);
-                    }
-                    Err(_) => {
-                        println!("{}", TIMEOUT_LEER_LINEA);
-                    }
-                }
-                break;
-            }
-            Err(_) => {
-                println!("{} {}", COMENSAL_NO_CONECTA_COORDINADOR, servidor);
-            }
-        }
-
-        if direccion_coordinador.is_some() {
-            break;
+        if self.sesion.esperar_confirmaciones().await {
+            println!("{}", RECIBIDIO_ACK);
+        } else {
+            eprintln!("{}", ERROR_SIN_CONFIRMACION);
         }
-    }
 
-    if let Some(ip_coordinador) = direccion_coordinador {
-        println!("{} {}", COMENSAL_ENCONTRO_COORDINADOR, ip_coordinador);
-        let stream = match TcpStream::connect(ip_coordinador).await {
-            Ok(stream) => {
-                println!("{}", COMENSAL_CONECTADO_COORDINADOR);
-                Some(stream)
-            }
-
-            Err(_) => None,
-        };
-
-        return stream;
+        std::process::exit(0);
     }
-
-    None
 }
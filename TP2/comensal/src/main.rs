@@ -2,6 +2,8 @@
 mod comensal;
 
 // Imports de crates externas.
+use cliente::sesion::Sesion;
+use common::transporte::transporte_desde_entorno;
 use std::net::SocketAddr;
 
 // Módulos locales utilizados.
@@ -11,6 +13,7 @@ use comensal::Comensal;
 const DIRECCION_IP_BASE: &str = "127.0.0.1:";
 const PUERTO_INICIAL: u16 = 8080;
 const PUERTO_FINAL: u16 = 8084;
+const NOMBRE: &str = "COMENSAL";
 
 // Método 'main' que inicia el actor de cada comensal.
 #[tokio::main]
@@ -21,7 +24,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         servidores.push(servidor);
     }
 
-    let mut comensal = Comensal::new(servidores).await;
+    let sesion = match Sesion::iniciar(NOMBRE, servidores, transporte_desde_entorno()).await {
+        Ok(sesion) => sesion,
+        Err(e) => {
+            eprintln!("{} - {}", NOMBRE, e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut comensal = Comensal::new(sesion);
     comensal.run().await;
     Ok(())
 }
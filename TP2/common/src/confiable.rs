@@ -0,0 +1,438 @@
+//! Este módulo contiene las piezas de la entrega confiable (al menos una vez) de los mensajes críticos: la política de reintentos, los mensajes enviados que esperan su confirmación y la ventana con la que quien los recibe descarta los duplicados.
+
+// Imports de crates externas.
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use tokio::time::{sleep_until, timeout, Duration, Instant};
+
+// Imports de funciones/estructuras propias.
+use crate::protocolo::{Mensaje, Sobre};
+use crate::transporte::LectorTramas;
+
+// Constantes.
+const ESPERA_INICIAL_MILIS: u64 = 500;
+const ESPERA_MAXIMA_MILIS: u64 = 8000;
+const INTENTOS_MAXIMOS: u32 = 5;
+const CAPACIDAD_VENTANA: usize = 4096;
+
+// Política de retransmisión de los mensajes confiables: la espera hasta cada reintento se duplica a partir de
+// 'espera_inicial', sin superar 'espera_maxima', y el mensaje se da por perdido luego de 'intentos_maximos'.
+#[derive(Debug, Clone, Copy)]
+pub struct Reintentos {
+    pub espera_inicial: Duration,
+    pub espera_maxima: Duration,
+    pub intentos_maximos: u32,
+}
+
+// Implementación del trait 'Default'.
+impl Default for Reintentos {
+    fn default() -> Self {
+        Reintentos {
+            espera_inicial: Duration::from_millis(ESPERA_INICIAL_MILIS),
+            espera_maxima: Duration::from_millis(ESPERA_MAXIMA_MILIS),
+            intentos_maximos: INTENTOS_MAXIMOS,
+        }
+    }
+}
+
+// Implementación del cálculo de las esperas.
+impl Reintentos {
+    // Espera antes de reintentar luego del intento número 'intento' (empezando en 0).
+    pub fn espera(&self, intento: u32) -> Duration {
+        self.espera_inicial
+            .saturating_mul(2u32.saturating_pow(intento))
+            .min(self.espera_maxima)
+    }
+}
+
+// Mensaje enviado que todavía no fue confirmado.
+#[derive(Debug)]
+struct Pendiente {
+    sobre: Sobre,
+    intentos: u32,
+    vencimiento: Instant,
+}
+
+// Resultado de revisar los mensajes pendientes.
+#[derive(Debug, Default)]
+pub struct Revision {
+    // Mensajes cuya espera venció y que deben retransmitirse.
+    pub reenviar: Vec<Sobre>,
+    // Mensajes que agotaron sus intentos. Ya no se retransmiten: quien los envió decide qué hacer con ellos.
+    pub agotados: Vec<Sobre>,
+}
+
+// Mensajes enviados de forma confiable que esperan su confirmación, por identificador.
+#[derive(Debug, Default)]
+pub struct Pendientes {
+    mensajes: HashMap<u64, Pendiente>,
+    reintentos: Reintentos,
+}
+
+// Implementación del seguimiento de los mensajes pendientes.
+impl Pendientes {
+    // Constructor.
+    pub fn new(reintentos: Reintentos) -> Self {
+        Pendientes {
+            mensajes: HashMap::new(),
+            reintentos,
+        }
+    }
+
+    // Registra un mensaje que se acaba de enviar por primera vez.
+    pub fn registrar(&mut self, sobre: Sobre) {
+        let vencimiento = Instant::now() + self.reintentos.espera(0);
+        self.mensajes.insert(
+            sobre.id_mensaje,
+            Pendiente {
+                sobre,
+                intentos: 0,
+                vencimiento,
+            },
+        );
+    }
+
+    // Marca como confirmado al mensaje 'id_mensaje'. Devuelve 'false' si no estaba pendiente.
+    pub fn confirmar(&mut self, id_mensaje: u64) -> bool {
+        self.mensajes.remove(&id_mensaje).is_some()
+    }
+
+    // Indica si no queda ningún mensaje sin confirmar.
+    pub fn esta_vacio(&self) -> bool {
+        self.mensajes.is_empty()
+    }
+
+    // Devuelve los mensajes a retransmitir y quita los que agotaron sus intentos.
+    pub fn revisar(&mut self) -> Revision {
+        let ahora = Instant::now();
+        let mut revision = Revision::default();
+        let mut agotados = Vec::new();
+        for (id_mensaje, pendiente) in self.mensajes.iter_mut() {
+            if pendiente.vencimiento > ahora {
+                continue;
+            }
+
+            pendiente.intentos += 1;
+            if pendiente.intentos >= self.reintentos.intentos_maximos {
+                agotados.push(*id_mensaje);
+            } else {
+                pendiente.vencimiento = ahora + self.reintentos.espera(pendiente.intentos);
+                revision.reenviar.push(pendiente.sobre.clone());
+            }
+        }
+
+        for id_mensaje in agotados {
+            if let Some(pendiente) = self.mensajes.remove(&id_mensaje) {
+                revision.agotados.push(pendiente.sobre);
+            }
+        }
+
+        revision
+    }
+
+    // Devuelve todos los mensajes pendientes, reiniciando sus intentos y marcándolos como enviados desde
+    // 'id_enviador'. Se usa para reenviarlos luego de reconectarse, ya que la nueva conexión tiene otra dirección.
+    // El origen de los mensajes no cambia, por lo que quien los recibe igual reconoce los duplicados.
+    pub fn reiniciar(&mut self, id_enviador: SocketAddr) -> Vec<Sobre> {
+        let vencimiento = Instant::now() + self.reintentos.espera(0);
+        self.mensajes
+            .values_mut()
+            .map(|pendiente| {
+                pendiente.sobre.id_enviador = id_enviador;
+                pendiente.intentos = 0;
+                pendiente.vencimiento = vencimiento;
+                pendiente.sobre.clone()
+            })
+            .collect()
+    }
+
+    // Quita y devuelve todos los mensajes pendientes. Se usa cuando se pierde la conexión por la que se enviaron.
+    pub fn vaciar(&mut self) -> Vec<Sobre> {
+        self.mensajes
+            .drain()
+            .map(|(_, pendiente)| pendiente.sobre)
+            .collect()
+    }
+
+    // Espera hasta que venza el próximo mensaje pendiente. Si no hay ninguno, no termina nunca, por lo que se
+    // puede usar como una rama más de un 'select!'.
+    pub async fn esperar_vencimiento(&self) {
+        match self
+            .mensajes
+            .values()
+            .map(|pendiente| pendiente.vencimiento)
+            .min()
+        {
+            Some(vencimiento) => sleep_until(vencimiento).await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+// Estado de un mensaje confiable recibido según la ventana de duplicados.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Recepcion {
+    // Es la primera vez que se recibe: hay que procesarlo.
+    Nuevo,
+    // Ya se recibió y se está procesando: no se procesa de nuevo, ni se confirma todavía.
+    EnProceso,
+    // Ya se recibió y se procesó: no se procesa de nuevo, pero se vuelve a confirmar.
+    Procesado,
+}
+
+// Ventana de los últimos mensajes confiables recibidos, identificados por el proceso que los creó y su
+// identificador. No se usa la dirección de quien los envía, que cambia si se reconecta.
+// Permite procesar una única vez los mensajes que llegan repetidos por una retransmisión.
+#[derive(Debug)]
+pub struct VentanaDuplicados {
+    vistos: HashMap<(u64, u64), bool>,
+    orden: VecDeque<(u64, u64)>,
+    capacidad: usize,
+}
+
+// Implementación del trait 'Default'.
+impl Default for VentanaDuplicados {
+    fn default() -> Self {
+        VentanaDuplicados::new(CAPACIDAD_VENTANA)
+    }
+}
+
+// Implementación de la detección de duplicados.
+impl VentanaDuplicados {
+    // Constructor. La ventana recuerda los últimos 'capacidad' mensajes.
+    pub fn new(capacidad: usize) -> Self {
+        VentanaDuplicados {
+            vistos: HashMap::with_capacity(capacidad),
+            orden: VecDeque::with_capacity(capacidad),
+            capacidad,
+        }
+    }
+
+    // Registra un sobre recibido y devuelve su estado. Si es nuevo, queda en proceso hasta que se lo marque como
+    // procesado o se lo olvide.
+    pub fn registrar(&mut self, sobre: &Sobre) -> Recepcion {
+        let clave = (sobre.id_origen, sobre.id_mensaje);
+        if let Some(&procesado) = self.vistos.get(&clave) {
+            return if procesado {
+                Recepcion::Procesado
+            } else {
+                Recepcion::EnProceso
+            };
+        }
+
+        self.vistos.insert(clave, false);
+        self.orden.push_back(clave);
+        if self.orden.len() > self.capacidad {
+            if let Some(mas_viejo) = self.orden.pop_front() {
+                self.vistos.remove(&mas_viejo);
+            }
+        }
+
+        Recepcion::Nuevo
+    }
+
+    // Marca como procesado a un mensaje registrado, para que sus retransmisiones se vuelvan a confirmar.
+    pub fn terminar(&mut self, id_origen: u64, id_mensaje: u64) {
+        if let Some(procesado) = self.vistos.get_mut(&(id_origen, id_mensaje)) {
+            *procesado = true;
+        }
+    }
+
+    // Olvida un mensaje registrado, para que su próxima retransmisión se procese. Se usa cuando no se pudo procesar.
+    pub fn olvidar(&mut self, id_origen: u64, id_mensaje: u64) {
+        let clave = (id_origen, id_mensaje);
+        if self.vistos.remove(&clave).is_some() {
+            self.orden.retain(|registrado| *registrado != clave);
+        }
+    }
+}
+
+// Espera, hasta 'plazo', la confirmación del mensaje 'id_mensaje'. Se usa en las conexiones que se abren para un
+// único pedido, por lo que el resto de los sobres recibidos mientras tanto se descartan.
+pub async fn esperar_confirmacion(
+    lector: &mut LectorTramas,
+    id_mensaje: u64,
+    plazo: Duration,
+) -> bool {
+    let limite = Instant::now() + plazo;
+    loop {
+        match timeout(
+            limite.saturating_duration_since(Instant::now()),
+            lector.recibir(),
+        )
+        .await
+        {
+            Ok(Ok(Some(Ok(Sobre {
+                mensaje: Mensaje::Confirmacion(confirmado),
+                ..
+            }))))
+                if confirmado == id_mensaje =>
+            {
+                return true
+            }
+            Ok(Ok(Some(_))) => continue,
+            Ok(Ok(None)) | Ok(Err(_)) | Err(_) => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocolo::empaquetar;
+
+    fn direccion(puerto: u16) -> SocketAddr {
+        format!("127.0.0.1:{}", puerto).parse().unwrap()
+    }
+
+    fn sin_esperas(intentos_maximos: u32) -> Reintentos {
+        Reintentos {
+            espera_inicial: Duration::ZERO,
+            espera_maxima: Duration::ZERO,
+            intentos_maximos,
+        }
+    }
+
+    #[test]
+    fn la_espera_se_duplica_hasta_el_maximo() {
+        let reintentos = Reintentos::default();
+        assert_eq!(reintentos.espera(0), Duration::from_millis(500));
+        assert_eq!(reintentos.espera(1), Duration::from_millis(1000));
+        assert_eq!(reintentos.espera(3), Duration::from_millis(4000));
+        assert_eq!(reintentos.espera(4), Duration::from_millis(8000));
+        assert_eq!(reintentos.espera(5), Duration::from_millis(8000));
+        assert_eq!(reintentos.espera(u32::MAX), Duration::from_millis(8000));
+    }
+
+    #[test]
+    fn la_confirmacion_quita_al_mensaje_pendiente() {
+        let mut pendientes = Pendientes::new(sin_esperas(3));
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        let id_mensaje = sobre.id_mensaje;
+        pendientes.registrar(sobre);
+
+        assert!(!pendientes.esta_vacio());
+        assert!(pendientes.confirmar(id_mensaje));
+        assert!(pendientes.esta_vacio());
+        assert!(!pendientes.confirmar(id_mensaje));
+        assert!(pendientes.revisar().reenviar.is_empty());
+    }
+
+    #[test]
+    fn los_mensajes_vencidos_se_reenvian_hasta_agotar_los_intentos() {
+        let mut pendientes = Pendientes::new(sin_esperas(2));
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        let id_mensaje = sobre.id_mensaje;
+        pendientes.registrar(sobre);
+
+        let revision = pendientes.revisar();
+        assert_eq!(revision.reenviar.len(), 1);
+        assert_eq!(revision.reenviar[0].id_mensaje, id_mensaje);
+        assert!(revision.agotados.is_empty());
+
+        let revision = pendientes.revisar();
+        assert!(revision.reenviar.is_empty());
+        assert_eq!(revision.agotados.len(), 1);
+        assert_eq!(revision.agotados[0].id_mensaje, id_mensaje);
+        assert!(pendientes.esta_vacio());
+    }
+
+    #[test]
+    fn los_mensajes_que_no_vencieron_no_se_reenvian() {
+        let mut pendientes = Pendientes::default();
+        pendientes.registrar(empaquetar(direccion(8000), Mensaje::Confirmacion(1)));
+
+        let revision = pendientes.revisar();
+        assert!(revision.reenviar.is_empty());
+        assert!(revision.agotados.is_empty());
+        assert!(!pendientes.esta_vacio());
+    }
+
+    #[test]
+    fn reiniciar_devuelve_los_pendientes_y_vaciar_los_quita() {
+        let mut pendientes = Pendientes::new(sin_esperas(2));
+        pendientes.registrar(empaquetar(direccion(8000), Mensaje::Confirmacion(1)));
+        pendientes.registrar(empaquetar(direccion(8000), Mensaje::Confirmacion(2)));
+
+        // Luego de reiniciar, los mensajes vuelven a tener todos sus intentos y salen desde la nueva dirección.
+        pendientes.revisar();
+        let reiniciados = pendientes.reiniciar(direccion(8001));
+        assert_eq!(reiniciados.len(), 2);
+        assert!(reiniciados
+            .iter()
+            .all(|sobre| sobre.id_enviador == direccion(8001)));
+        let revision = pendientes.revisar();
+        assert_eq!(revision.reenviar.len(), 2);
+        assert!(revision
+            .reenviar
+            .iter()
+            .all(|sobre| sobre.id_enviador == direccion(8001)));
+
+        assert_eq!(pendientes.vaciar().len(), 2);
+        assert!(pendientes.esta_vacio());
+    }
+
+    #[test]
+    fn la_ventana_descarta_los_duplicados_de_cada_origen() {
+        let mut ventana = VentanaDuplicados::default();
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        let mut mismo_id_otro_origen = sobre.clone();
+        mismo_id_otro_origen.id_origen = sobre.id_origen.wrapping_add(1);
+
+        assert_eq!(ventana.registrar(&sobre), Recepcion::Nuevo);
+        assert_eq!(ventana.registrar(&sobre), Recepcion::EnProceso);
+        assert_eq!(ventana.registrar(&mismo_id_otro_origen), Recepcion::Nuevo);
+    }
+
+    #[test]
+    fn un_reenvio_desde_otra_direccion_sigue_siendo_un_duplicado() {
+        let mut ventana = VentanaDuplicados::default();
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        let mut reenviado = sobre.clone();
+        reenviado.id_enviador = direccion(8001);
+
+        ventana.registrar(&sobre);
+        ventana.terminar(sobre.id_origen, sobre.id_mensaje);
+        assert_eq!(ventana.registrar(&reenviado), Recepcion::Procesado);
+    }
+
+    #[test]
+    fn un_duplicado_se_informa_procesado_recien_cuando_termina() {
+        let mut ventana = VentanaDuplicados::default();
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        ventana.registrar(&sobre);
+        assert_eq!(ventana.registrar(&sobre), Recepcion::EnProceso);
+
+        ventana.terminar(sobre.id_origen, sobre.id_mensaje);
+        assert_eq!(ventana.registrar(&sobre), Recepcion::Procesado);
+    }
+
+    #[test]
+    fn la_ventana_olvida_a_los_mensajes_mas_viejos() {
+        let mut ventana = VentanaDuplicados::new(2);
+        let sobres: Vec<Sobre> = (0..3)
+            .map(|i| empaquetar(direccion(8000), Mensaje::Confirmacion(i)))
+            .collect();
+        for sobre in &sobres {
+            assert_eq!(ventana.registrar(sobre), Recepcion::Nuevo);
+        }
+
+        // El primero salió de la ventana, los otros dos siguen en ella.
+        assert_eq!(ventana.registrar(&sobres[2]), Recepcion::EnProceso);
+        assert_eq!(ventana.registrar(&sobres[1]), Recepcion::EnProceso);
+        assert_eq!(ventana.registrar(&sobres[0]), Recepcion::Nuevo);
+    }
+
+    #[test]
+    fn un_mensaje_olvidado_se_vuelve_a_procesar() {
+        let mut ventana = VentanaDuplicados::new(2);
+        let sobre = empaquetar(direccion(8000), Mensaje::Confirmacion(1));
+        let otro = empaquetar(direccion(8000), Mensaje::Confirmacion(2));
+        ventana.registrar(&sobre);
+        ventana.registrar(&otro);
+
+        ventana.olvidar(sobre.id_origen, sobre.id_mensaje);
+        assert_eq!(ventana.registrar(&sobre), Recepcion::Nuevo);
+        assert_eq!(ventana.registrar(&otro), Recepcion::EnProceso);
+    }
+}
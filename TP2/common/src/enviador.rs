@@ -0,0 +1,215 @@
+//! Actor responsable de enviar sobres por una conexión saliente. Recibe mensajes de tipo 'EnviarSobre' y los escribe en la conexión con el codec negociado, sin importar el transporte. Los sobres enviados con 'EnviarConfiable' se retransmiten hasta recibir su confirmación. Los envíos que fallan se le avisan a quien inició el actor con 'EnvioFallido'.
+
+// Imports de crates externas.
+use actix::prelude::*;
+use actix_async_handler::async_handler;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+// Imports de funciones/estructuras propias.
+use crate::confiable::{Pendientes, Reintentos};
+use crate::protocolo::{Mensaje, Recibido, Sobre};
+use crate::transporte::{Conexion, EscritorTramas};
+
+// Constantes.
+const ERROR_ENVIO: &str = "Error al enviar sobre a";
+const AVISO_SIN_CONFIRMACION: &str = "No se recibió la confirmación del mensaje enviado a";
+const INTERVALO_REVISION_MILIS: u64 = 100;
+
+// Estructura que representa al actor 'Enviador' que enviará los sobres a 'destino'. Si la escritura falla, la mitad
+// de escritura se descarta y no se envían más sobres.
+pub struct Enviador {
+    pub escribir: Option<EscritorTramas>,
+    destino: SocketAddr,
+    pendientes: Pendientes,
+    avisos: Option<Recipient<EnvioFallido>>,
+}
+
+// Estructura que representa un sobre a enviar.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct EnviarSobre(pub Sobre);
+
+// Estructura que representa un sobre a enviar de forma confiable: se retransmite hasta que se confirme.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct EnviarConfiable(pub Sobre);
+
+// Estructura que representa la confirmación recibida de un sobre enviado de forma confiable.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Confirmado(pub u64);
+
+// Aviso de un envío que no se pudo completar, para quien inició el 'Enviador'.
+#[derive(Message, Debug)]
+#[rtype(result = "()")]
+pub enum EnvioFallido {
+    // No se pudo escribir en la conexión con 'destino'. El 'Enviador' ya no envía más sobres por ella.
+    ConexionPerdida { destino: SocketAddr, error: String },
+    // El sobre confiable enviado a 'destino' no se confirmó: agotó sus reintentos o se perdió la conexión.
+    SinConfirmacion { destino: SocketAddr, sobre: Sobre },
+}
+
+// Implementación de la construcción del actor 'Enviador'.
+impl Enviador {
+    // Constructor. Las confirmaciones de los envíos confiables las recibe quien lee la conexión, que debe
+    // avisarlas con 'Confirmado'. Sin 'con_avisos', los envíos fallidos solo se informan por la salida de error.
+    pub fn new(destino: SocketAddr, escritor: EscritorTramas) -> Self {
+        Enviador {
+            escribir: Some(escritor),
+            destino,
+            pendientes: Pendientes::new(Reintentos::default()),
+            avisos: None,
+        }
+    }
+
+    // Indica a quién avisarle los envíos fallidos.
+    pub fn con_avisos(mut self, avisos: Recipient<EnvioFallido>) -> Self {
+        self.avisos = Some(avisos);
+        self
+    }
+
+    // Inicia un 'Enviador' que además lee la conexión para recibir las confirmaciones de sus envíos confiables, y
+    // le avisa a 'avisos' los envíos fallidos. Se usa en las conexiones por las que solo se envían mensajes.
+    pub fn iniciar_con_confirmaciones(
+        conexion: Conexion,
+        avisos: Recipient<EnvioFallido>,
+    ) -> Addr<Self> {
+        Enviador::create(|contexto| {
+            Enviador::add_stream(conexion.lector.en_flujo(), contexto);
+            Enviador::new(conexion.remota, conexion.escritor).con_avisos(avisos)
+        })
+    }
+
+    // Avisa un envío fallido a quien inició el 'Enviador', o lo informa por la salida de error si nadie lo espera.
+    fn avisar(&self, aviso: EnvioFallido) {
+        match &self.avisos {
+            Some(avisos) => avisos.do_send(aviso),
+            None => match aviso {
+                EnvioFallido::ConexionPerdida { destino, error } => {
+                    eprintln!("{} {}: {}", ERROR_ENVIO, destino, error)
+                }
+                EnvioFallido::SinConfirmacion { destino, sobre } => {
+                    eprintln!("{} {}: {:?}", AVISO_SIN_CONFIRMACION, destino, sobre)
+                }
+            },
+        }
+    }
+
+    // Guarda la mitad de escritura luego de escribir un sobre. Si la escritura falló se avisa la conexión perdida, y
+    // los envíos confiables pendientes se avisan como no confirmados, ya que no se pueden retransmitir.
+    fn terminar_escritura(&mut self, resultado: io::Result<EscritorTramas>) {
+        match resultado {
+            Ok(escribir) => self.escribir = Some(escribir),
+            Err(e) => {
+                self.avisar(EnvioFallido::ConexionPerdida {
+                    destino: self.destino,
+                    error: e.to_string(),
+                });
+                for sobre in self.pendientes.vaciar() {
+                    self.avisar(EnvioFallido::SinConfirmacion {
+                        destino: self.destino,
+                        sobre,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Implementa el trait 'Actor'.
+impl Actor for Enviador {
+    type Context = Context<Self>;
+
+    // Revisa periódicamente los envíos confiables, retransmitiendo los que no se confirmaron a tiempo.
+    fn started(&mut self, contexto: &mut Self::Context) {
+        contexto.run_interval(
+            Duration::from_millis(INTERVALO_REVISION_MILIS),
+            |enviador, contexto| {
+                let revision = enviador.pendientes.revisar();
+                for sobre in revision.reenviar {
+                    contexto.notify(EnviarSobre(sobre));
+                }
+
+                for sobre in revision.agotados {
+                    enviador.avisar(EnvioFallido::SinConfirmacion {
+                        destino: enviador.destino,
+                        sobre,
+                    });
+                }
+            },
+        );
+    }
+}
+
+// Escribe un sobre en la conexión. Devuelve la mitad de escritura, o el error si no se pudo escribir.
+async fn escribir_sobre(mut escribir: EscritorTramas, sobre: Sobre) -> io::Result<EscritorTramas> {
+    escribir.enviar(&sobre).await?;
+    Ok(escribir)
+}
+
+// Implementación de los handlers del actor 'Enviador'.
+#[allow(clippy::unused_unit)]
+#[async_handler]
+impl Handler<EnviarSobre> for Enviador {
+    type Result = ();
+    // Método que maneja el envío de un sobre.
+    async fn handle(&mut self, msj: EnviarSobre, _contexto: &mut Self::Context) -> Self::Result {
+        if let Some(escribir) = self.escribir.take() {
+            let resultado_escritura = escribir_sobre(escribir, msj.0).await;
+            self.terminar_escritura(resultado_escritura);
+        }
+    }
+}
+
+// Implementación de los handlers del actor 'Enviador'.
+#[allow(clippy::unused_unit)]
+#[async_handler]
+impl Handler<EnviarConfiable> for Enviador {
+    type Result = ();
+    // Método que maneja el envío confiable de un sobre: lo registra como pendiente y lo envía. Si la conexión ya se
+    // perdió, se avisa que no se va a confirmar.
+    async fn handle(
+        &mut self,
+        msj: EnviarConfiable,
+        _contexto: &mut Self::Context,
+    ) -> Self::Result {
+        if let Some(escribir) = self.escribir.take() {
+            self.pendientes.registrar(msj.0.clone());
+            let resultado_escritura = escribir_sobre(escribir, msj.0).await;
+            self.terminar_escritura(resultado_escritura);
+        } else {
+            self.avisar(EnvioFallido::SinConfirmacion {
+                destino: self.destino,
+                sobre: msj.0,
+            });
+        }
+    }
+}
+
+// Implementación de los handlers del actor 'Enviador'.
+impl Handler<Confirmado> for Enviador {
+    type Result = ();
+    // Método que maneja la confirmación de un envío confiable.
+    fn handle(&mut self, msj: Confirmado, _contexto: &mut Self::Context) -> Self::Result {
+        self.pendientes.confirmar(msj.0);
+    }
+}
+
+// Lectura de las confirmaciones, cuando el 'Enviador' lee la conexión. El resto de los sobres se ignoran.
+impl StreamHandler<io::Result<Recibido>> for Enviador {
+    fn handle(&mut self, recibido: io::Result<Recibido>, _contexto: &mut Self::Context) {
+        if let Ok(Ok(Sobre {
+            mensaje: Mensaje::Confirmacion(id_mensaje),
+            ..
+        })) = recibido
+        {
+            self.pendientes.confirmar(id_mensaje);
+        }
+    }
+
+    // Si el otro extremo cierra la conexión el actor sigue vivo, igual que cuando no lee la conexión: el próximo
+    // envío falla y se avisa la conexión perdida.
+    fn finished(&mut self, _contexto: &mut Self::Context) {}
+}
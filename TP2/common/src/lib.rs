@@ -1,6 +1,10 @@
 //! Módulo principal de la biblioteca. Declara y expone los submódulos del sistema.
 
+pub mod confiable;
+pub mod enviador;
 pub mod mensajes;
 pub mod mensajes_gateway;
-pub mod tcp_enviador;
+pub mod mensajes_servidor;
+pub mod protocolo;
+pub mod transporte;
 pub mod utils;
@@ -9,7 +9,7 @@ use std::net::SocketAddr;
 use crate::mensajes_gateway::MensajeGateway;
 
 // Mensaje para solicitar la autorización de un pago al gateway.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "(bool)")]
 pub struct EnviarMensajePago {
     pub id_comensal: String,
@@ -18,7 +18,7 @@ pub struct EnviarMensajePago {
 }
 
 // Mensaje con el resultado de la autorización del pago.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct Autorizacion {
     pub direccion_comensal: SocketAddr,
@@ -26,14 +26,14 @@ pub struct Autorizacion {
 }
 
 // Mensaje que responde quien es el coordinador actual del sistema.
-#[derive(Debug, Serialize, Deserialize, Message)]
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[rtype(result = "()")]
 pub struct QuienEsCoordinador {
     pub direccion_coordinador: SocketAddr,
 }
 
 // Mensaje que envia un comensal para solicitar un viaje.
-#[derive(Message, serde::Serialize, serde::Deserialize, Debug)]
+#[derive(Message, serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct SolicitarRepartidor {
     pub comida: String,
@@ -68,6 +68,9 @@ pub struct IniciarViajeDelivery {
     pub destino_i: (f32, f32),
 }
 
+// Respuesta de 'RechazarViaje' cuando es el restaurante el que no acepta el pedido.
+pub const RECHAZADO_POR_RESTAURANTE: &str = "Viaje rechazado por restaurante";
+
 // Mensaje que le indica al comensal que el viaje no ha sido aceptado por el conductor.
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[rtype(result = "()")]
@@ -100,15 +103,24 @@ pub struct BuscandoTrabajoRepartidor {
 }
 
 // Mensaje para identificar quien es, o no, un repartidor.
-#[derive(Message, serde::Serialize, serde::Deserialize, Debug)]
+#[derive(Message, serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct MensajeIdentidad {
     pub ubicacion: (f32, f32),
     pub soy_repartidor: bool,
 }
 
+// Mensaje con el que un comensal se vuelve a identificar luego de reconectarse. Lleva las direcciones de sus
+// conexiones anteriores, con alguna de las cuales el servidor registró su pedido, para que las novedades del pedido
+// se le envíen a la nueva conexión.
+#[derive(Message, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct IdentificarComensal {
+    pub direcciones_anteriores: Vec<SocketAddr>,
+}
+
 // Mensaje que envia un comensal para solicitar un delivery
-#[derive(Message, serde::Serialize, serde::Deserialize, Debug)]
+#[derive(Message, serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct SolicitarPedido {
     pub comida: String,
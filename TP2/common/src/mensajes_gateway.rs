@@ -5,23 +5,15 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 // Define los mensajes que puede recibir el actor 'Gateway'.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub enum MensajeGateway {
     Validar,
     Pagar,
 }
 
-// Mensaje para requerir una validación de pago o un cobro efectivo.
-#[derive(Message, Serialize, Deserialize, Debug)]
-#[rtype(result = "()")]
-pub enum RequerirPago {
-    ValidarAutorizacionPago(ValidarAutorizacionPago),
-    EfectivizarPago(EfectivizarPago),
-}
-
 // Mensaje para requerir una validación de autorización de pago.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct ValidarAutorizacionPago {
     pub id_comensal: String,
@@ -29,7 +21,7 @@ pub struct ValidarAutorizacionPago {
 }
 
 // Respuesta de autorización de pago, indicando si fue autorizado o no.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct RespuestaAutorizacion {
     pub id_comensal: String,
@@ -37,7 +29,7 @@ pub struct RespuestaAutorizacion {
 }
 
 // Mensaje para requerir un cobro efectivo.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub struct EfectivizarPago {
     pub id_comensal: String,
@@ -45,7 +37,7 @@ pub struct EfectivizarPago {
 }
 
 // Respuesta al cobro efectivo, indicando si el pago fue realizado o si hubo un error.
-#[derive(Message, Serialize, Deserialize, Debug)]
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
 pub enum RespuestaPago {
     PagoHecho,
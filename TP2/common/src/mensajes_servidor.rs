@@ -0,0 +1,105 @@
+//! Este módulo contiene la definición de los mensajes que intercambian las instancias del 'Servidor' entre sí.
+
+// Imports de crates externas.
+use actix::Message;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+// Mensaje que encapsula el estado del repartidor.
+#[derive(Debug, Clone, Serialize, Deserialize, Message, PartialEq)]
+#[rtype(result = "()")]
+pub enum EstadoRepartidor {
+    Active,
+    Waiting,
+    OnTrip,
+}
+
+// Enum que representa las acciones que se pueden realizar en actualizaciones.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum Accion {
+    Insertar,
+    Eliminar,
+    Actualizar,
+}
+
+// Mensaje que puede ser enviado y recibido para indicar que una elección está ocurriendo.
+#[derive(Debug, Serialize, Deserialize, Clone, Message)]
+#[rtype(result = "()")]
+pub struct MensajeEleccion {
+    pub candidatos: Vec<SocketAddr>,
+}
+
+// Este mensaje es recibido por el coordinador y responde con un ACK.
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[rtype(result = "()")]
+pub struct MensajePing {
+    pub id_enviador: SocketAddr,
+}
+
+// Mensaje que le notifica a todos los servidores quien es el nuevo coordinador.
+#[derive(Debug, Serialize, Deserialize, Clone, Message)]
+#[rtype(result = "()")]
+pub struct MensajeCoordinador {
+    pub coordinador: SocketAddr,
+}
+
+// Mensaje que modela el viaje del pedido desde el 'Restaurante' hasta el 'Cliente'.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct HacerPedido {
+    pub id_comensal_mt: SocketAddr,
+    pub id_repartidor_mt: SocketAddr,
+}
+
+// Este mensaje le dice a los Servidores que manejen un pedido de un comensal. 'intentos' cuenta las veces que el
+// pedido se volvió a gestionar porque el 'Coordinador' no confirmó el viaje.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct HandlePedido {
+    pub id_comensal_ht: SocketAddr,
+    pub intentos: u32,
+}
+
+// Mensaje que se usa para actualizar a los comensales de los Servidores no coordinadores.
+#[derive(Message, Serialize, Deserialize, Clone, Debug)]
+#[rtype(result = "()")]
+pub struct ActualizarComensales {
+    pub accion: Accion,
+    pub comensal: SocketAddr,
+    pub origen: (f32, f32),
+    pub destino: (f32, f32),
+}
+
+// Mensaje para actuailzar a los repartidores en Servidores no coordinadores.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct ActualizarRepartidores {
+    pub accion: Accion,
+    pub repartidor: SocketAddr,
+    pub posicion: (f32, f32),
+    pub id_comensal_actual: Option<SocketAddr>,
+    pub status: EstadoRepartidor,
+}
+
+// Mensaje para actualizar el estado de los 'Repartidores'.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct UpdateRepartidores {
+    pub accion: Accion,
+    pub repartidor: SocketAddr,
+    pub posicion: (f32, f32),
+    pub id_comensal_actual: Option<SocketAddr>,
+    pub status: EstadoRepartidor,
+    pub repartidor_activo: bool,
+}
+
+// Mensaje para actualizar el estado de los 'Restaurantes'.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct ActualizarRestaurantes {
+    pub accion: Accion,
+    pub restaurante: SocketAddr,
+    pub posicion: (f32, f32),
+    pub id_comensal_actual: Option<SocketAddr>,
+    pub status: EstadoRepartidor,
+}
@@ -0,0 +1,336 @@
+//! Este módulo contiene el 'Sobre' con el que viaja cada mensaje por la red: todo mensaje entre los actores del sistema se envía dentro de un 'Sobre' que indica la versión del protocolo, un identificador del mensaje, el proceso que lo creó y la dirección de quién lo envía. También contiene los 'Codec' con los que se codifica cada sobre.
+
+// Imports de crates externas.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Imports de funciones/estructuras propias.
+use crate::mensajes::{
+    Autorizacion, BuscandoTrabajoRepartidor, EnviarMensajePago, FinalizarViaje,
+    IdentificarComensal, IniciarViajeDelivery, MensajeIdentidad, OfertarViaje, PedidoAlRestaurante,
+    Posicion, QuienEsCoordinador, RechazarViaje, RecibirPedido, RespuestaOfertaViaje,
+    SolicitarPedido, SolicitarRepartidor,
+};
+use crate::mensajes_gateway::{
+    EfectivizarPago, RespuestaAutorizacion, RespuestaPago, ValidarAutorizacionPago,
+};
+use crate::mensajes_servidor::{
+    ActualizarComensales, ActualizarRepartidores, ActualizarRestaurantes, HacerPedido,
+    HandlePedido, MensajeCoordinador, MensajeEleccion, MensajePing, UpdateRepartidores,
+};
+
+// Versión del protocolo que habla esta versión del sistema. Se incrementa cuando cambia el formato de algún mensaje.
+pub const VERSION_PROTOCOLO: u64 = 2;
+
+// Constantes.
+const ERROR_SERIALIZAR: &str = "Error al serializar el sobre:";
+
+// Contador de los identificadores de los mensajes enviados por este proceso.
+static PROXIMO_ID_MENSAJE: AtomicU64 = AtomicU64::new(0);
+
+// Primer identificador de los mensajes de este proceso. Se toma de la hora de inicio (en microsegundos) para que un
+// proceso que se reinicia en la misma dirección no repita identificadores que otro ya marcó como recibidos.
+static PRIMER_ID_MENSAJE: OnceLock<u64> = OnceLock::new();
+
+// Identificador de este proceso, elegido al azar al enviar el primer mensaje.
+static ID_ORIGEN: OnceLock<u64> = OnceLock::new();
+
+// Todos los mensajes que pueden viajar por la red. El tipo de cada mensaje viaja en el campo 'tipo', por lo que
+// mensajes con los mismos campos (por ejemplo 'ActualizarRepartidores' y 'UpdateRepartidores') no se confunden.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "tipo", content = "contenido")]
+pub enum Mensaje {
+    // Confirmación de recepción de un mensaje.
+    Ack,
+    // Confirmación de recepción de un mensaje confiable. Contiene el identificador del sobre confirmado.
+    Confirmacion(u64),
+    // Rechazo de un sobre cuya versión del protocolo no es soportada. Contiene la versión recibida.
+    VersionRechazada(u64),
+
+    // Mensajes entre los clientes y el servidor ('common::mensajes').
+    ConsultarCoordinador,
+    QuienEsCoordinador(QuienEsCoordinador),
+    EnviarMensajePago(EnviarMensajePago),
+    Autorizacion(Autorizacion),
+    SolicitarRepartidor(SolicitarRepartidor),
+    OfertarViaje(OfertarViaje),
+    RespuestaOfertaViaje(RespuestaOfertaViaje),
+    IniciarViajeDelivery(IniciarViajeDelivery),
+    RechazarViaje(RechazarViaje),
+    FinalizarViaje(FinalizarViaje),
+    Posicion(Posicion),
+    BuscandoTrabajoRepartidor(BuscandoTrabajoRepartidor),
+    MensajeIdentidad(MensajeIdentidad),
+    IdentificarComensal(IdentificarComensal),
+    SolicitarPedido(SolicitarPedido),
+    RecibirPedido(RecibirPedido),
+    PedidoAlRestaurante(PedidoAlRestaurante),
+
+    // Mensajes entre el servidor y el gateway ('common::mensajes_gateway').
+    ValidarAutorizacionPago(ValidarAutorizacionPago),
+    RespuestaAutorizacion(RespuestaAutorizacion),
+    EfectivizarPago(EfectivizarPago),
+    RespuestaPago(RespuestaPago),
+
+    // Mensajes entre las instancias del servidor ('common::mensajes_servidor').
+    MensajeEleccion(MensajeEleccion),
+    MensajePing(MensajePing),
+    MensajeCoordinador(MensajeCoordinador),
+    HacerPedido(HacerPedido),
+    HandlePedido(HandlePedido),
+    ActualizarComensales(ActualizarComensales),
+    ActualizarRepartidores(ActualizarRepartidores),
+    UpdateRepartidores(UpdateRepartidores),
+    ActualizarRestaurantes(ActualizarRestaurantes),
+}
+
+// Implementación de la clasificación de los mensajes.
+impl Mensaje {
+    // Indica si el mensaje se envía de forma confiable: quien lo recibe responde 'Confirmacion' con el
+    // identificador del sobre y descarta los duplicados, y quien lo envía lo retransmite hasta recibirla.
+    pub fn requiere_confirmacion(&self) -> bool {
+        matches!(
+            self,
+            Mensaje::SolicitarPedido(_)
+                | Mensaje::IdentificarComensal(_)
+                | Mensaje::FinalizarViaje(_)
+                | Mensaje::HacerPedido(_)
+                | Mensaje::HandlePedido(_)
+                | Mensaje::ActualizarComensales(_)
+                | Mensaje::ActualizarRepartidores(_)
+                | Mensaje::ActualizarRestaurantes(_)
+        )
+    }
+}
+
+// Estructura que envuelve a cada mensaje que viaja por la red.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+// 'id_enviador' es la dirección a la que se responde, que cambia si quien envía se reconecta. 'id_origen' identifica
+// al proceso que creó el mensaje y no cambia, por lo que junto con 'id_mensaje' identifica a un mensaje aunque se
+// retransmita por otra conexión.
+pub struct Sobre {
+    pub version: u64,
+    pub id_mensaje: u64,
+    pub id_origen: u64,
+    pub id_enviador: SocketAddr,
+    pub mensaje: Mensaje,
+}
+
+// Primeros campos de un sobre. Sólo se leen si el sobre completo no se pudo leer, para distinguir un sobre de otra
+// versión de uno malformado.
+#[derive(Deserialize)]
+struct Cabecera {
+    version: u64,
+}
+
+// Resultado de leer un sobre recibido.
+pub type Recibido = Result<Sobre, ErrorProtocolo>;
+
+// Codificaciones con las que puede viajar un sobre. Se negocia una por conexión, al conectarse.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Codec {
+    // JSON: legible, útil para depurar.
+    Json,
+    // MessagePack: binario y más compacto, para los mensajes frecuentes como las posiciones.
+    Binario,
+}
+
+// Codecs soportados, en orden de preferencia.
+pub const CODECS: [Codec; 2] = [Codec::Binario, Codec::Json];
+
+// 'Enum' que contiene los errores que pueden ocurrir al leer un sobre.
+#[derive(Debug, PartialEq)]
+pub enum ErrorProtocolo {
+    Malformado(String),
+    VersionDesconocida(u64),
+}
+
+// Implementación del trait 'Display'.
+impl fmt::Display for ErrorProtocolo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorProtocolo::Malformado(error) => write!(f, "Sobre malformado: {}", error),
+            ErrorProtocolo::VersionDesconocida(version) => write!(
+                f,
+                "Versión del protocolo desconocida: {} (se soporta la {})",
+                version, VERSION_PROTOCOLO
+            ),
+        }
+    }
+}
+
+// Implementación del trait 'From'.
+impl From<serde_json::Error> for ErrorProtocolo {
+    fn from(err: serde_json::Error) -> ErrorProtocolo {
+        ErrorProtocolo::Malformado(err.to_string())
+    }
+}
+
+// Implementación del trait 'From'.
+impl From<rmp_serde::decode::Error> for ErrorProtocolo {
+    fn from(err: rmp_serde::decode::Error) -> ErrorProtocolo {
+        ErrorProtocolo::Malformado(err.to_string())
+    }
+}
+
+// Implementación de la construcción de los sobres.
+impl Sobre {
+    // Constructor. Asigna al mensaje el próximo identificador de este proceso.
+    pub fn nuevo(id_enviador: SocketAddr, mensaje: Mensaje) -> Self {
+        Sobre {
+            version: VERSION_PROTOCOLO,
+            id_mensaje: proximo_id_mensaje(),
+            id_origen: *ID_ORIGEN.get_or_init(rand::random),
+            id_enviador,
+            mensaje,
+        }
+    }
+}
+
+// Devuelve el próximo identificador de los mensajes de este proceso.
+fn proximo_id_mensaje() -> u64 {
+    let primero = PRIMER_ID_MENSAJE.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|desde_epoch| desde_epoch.as_micros() as u64)
+            .unwrap_or_default()
+    });
+    primero.wrapping_add(PROXIMO_ID_MENSAJE.fetch_add(1, Ordering::Relaxed))
+}
+
+// Arma el sobre de un mensaje enviado por 'id_enviador'.
+pub fn empaquetar(id_enviador: SocketAddr, mensaje: Mensaje) -> Sobre {
+    Sobre::nuevo(id_enviador, mensaje)
+}
+
+// Implementación de la codificación de los sobres.
+impl Codec {
+    // Codifica un sobre.
+    pub fn codificar(self, sobre: &Sobre) -> Vec<u8> {
+        let codificado = match self {
+            Codec::Json => serde_json::to_vec(sobre).map_err(|e| e.to_string()),
+            // Los campos viajan con su nombre, ya que los mensajes se identifican por su 'tipo'.
+            Codec::Binario => rmp_serde::to_vec_named(sobre).map_err(|e| e.to_string()),
+        };
+        codificado.unwrap_or_else(|e| panic!("{} {}", ERROR_SERIALIZAR, e))
+    }
+
+    // Lee un sobre codificado y chequea su versión. Si el sobre no se puede leer se lee sólo su cabecera, para
+    // rechazar explícitamente los sobres de otra versión aunque su contenido tampoco se pueda leer.
+    pub fn decodificar(self, bytes: &[u8]) -> Recibido {
+        let sobre: Result<Sobre, ErrorProtocolo> = match self {
+            Codec::Json => serde_json::from_slice(bytes).map_err(ErrorProtocolo::from),
+            Codec::Binario => rmp_serde::from_slice(bytes).map_err(ErrorProtocolo::from),
+        };
+        match sobre {
+            Ok(sobre) if sobre.version == VERSION_PROTOCOLO => Ok(sobre),
+            Ok(sobre) => Err(ErrorProtocolo::VersionDesconocida(sobre.version)),
+            Err(error) => Err(match self.decodificar_cabecera(bytes) {
+                Some(cabecera) if cabecera.version != VERSION_PROTOCOLO => {
+                    ErrorProtocolo::VersionDesconocida(cabecera.version)
+                }
+                _ => error,
+            }),
+        }
+    }
+
+    // Lee sólo la cabecera de un sobre codificado.
+    fn decodificar_cabecera(self, bytes: &[u8]) -> Option<Cabecera> {
+        match self {
+            Codec::Json => serde_json::from_slice(bytes).ok(),
+            Codec::Binario => rmp_serde::from_slice(bytes).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direccion() -> SocketAddr {
+        "127.0.0.1:8000".parse().unwrap()
+    }
+
+    #[test]
+    fn el_sobre_se_lee_igual_que_se_escribio() {
+        for codec in CODECS {
+            let sobre = empaquetar(direccion(), Mensaje::Confirmacion(42));
+            let leido = codec.decodificar(&codec.codificar(&sobre)).unwrap();
+            assert_eq!(leido.version, VERSION_PROTOCOLO);
+            assert_eq!(leido.id_mensaje, sobre.id_mensaje);
+            assert_eq!(leido.id_origen, sobre.id_origen);
+            assert_eq!(leido.id_enviador, direccion());
+            assert!(matches!(leido.mensaje, Mensaje::Confirmacion(42)));
+        }
+    }
+
+    #[test]
+    fn cada_sobre_tiene_un_identificador_distinto() {
+        let primero = empaquetar(direccion(), Mensaje::Ack);
+        let segundo = empaquetar("127.0.0.1:8001".parse().unwrap(), Mensaje::Ack);
+        assert_ne!(primero.id_mensaje, segundo.id_mensaje);
+        // El origen es el del proceso, sin importar la dirección desde la que se envía.
+        assert_eq!(primero.id_origen, segundo.id_origen);
+    }
+
+    #[test]
+    fn se_rechaza_una_version_desconocida() {
+        for codec in CODECS {
+            let mut sobre = empaquetar(direccion(), Mensaje::Ack);
+            sobre.version = VERSION_PROTOCOLO + 1;
+            assert_eq!(
+                codec.decodificar(&codec.codificar(&sobre)).err(),
+                Some(ErrorProtocolo::VersionDesconocida(VERSION_PROTOCOLO + 1))
+            );
+        }
+    }
+
+    #[test]
+    fn se_rechaza_una_version_desconocida_con_un_mensaje_que_no_se_puede_leer() {
+        let bytes = br#"{"version":7,"id_mensaje":1,"id_enviador":"127.0.0.1:8000","mensaje":{"tipo":"Nuevo"}}"#;
+        assert_eq!(
+            Codec::Json.decodificar(bytes).err(),
+            Some(ErrorProtocolo::VersionDesconocida(7))
+        );
+    }
+
+    #[test]
+    fn un_mensaje_desconocido_de_la_misma_version_es_malformado() {
+        let bytes = br#"{"version":2,"id_mensaje":1,"id_origen":3,"id_enviador":"127.0.0.1:8000","mensaje":{"tipo":"Nuevo"}}"#;
+        assert!(matches!(
+            Codec::Json.decodificar(bytes),
+            Err(ErrorProtocolo::Malformado(_))
+        ));
+        assert!(matches!(
+            Codec::Binario.decodificar(b"no es un sobre"),
+            Err(ErrorProtocolo::Malformado(_))
+        ));
+    }
+
+    #[test]
+    fn los_mensajes_con_el_mismo_contenido_se_distinguen_por_su_tipo() {
+        for codec in CODECS {
+            let leer = |mensaje| {
+                let sobre = empaquetar(direccion(), mensaje);
+                codec.decodificar(&codec.codificar(&sobre)).unwrap().mensaje
+            };
+            assert!(matches!(
+                leer(Mensaje::Confirmacion(3)),
+                Mensaje::Confirmacion(3)
+            ));
+            assert!(matches!(
+                leer(Mensaje::VersionRechazada(3)),
+                Mensaje::VersionRechazada(3)
+            ));
+            assert!(matches!(leer(Mensaje::Ack), Mensaje::Ack));
+            assert!(matches!(
+                leer(Mensaje::ConsultarCoordinador),
+                Mensaje::ConsultarCoordinador
+            ));
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! Este módulo contiene la abstracción del transporte por el que viajan las tramas entre los actores del sistema: conectarse, escuchar, enviar y recibir tramas. Se implementa sobre TCP, sobre sockets Unix y sobre una red en memoria que permite levantar todo el sistema dentro de un mismo proceso.
+
+// Imports de crates externas.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{split, AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::codec::{FramedRead, LengthDelimitedCodec};
+
+// Imports de funciones/estructuras propias.
+use crate::protocolo::{Codec, Recibido, Sobre, CODECS};
+
+// Constantes.
+const VARIABLE_TRANSPORTE: &str = "TRANSPORTE";
+const VARIABLE_DIRECTORIO_SOCKETS: &str = "DIRECTORIO_SOCKETS";
+const VARIABLE_CODECS: &str = "CODECS";
+const TRANSPORTE_TCP: &str = "tcp";
+const TRANSPORTE_UNIX: &str = "unix";
+const CODEC_JSON: &str = "json";
+const CODEC_BINARIO: &str = "binario";
+const SEPARADOR_CODECS: char = ',';
+const CODIGO_ERROR: i32 = 1;
+const ERROR_TRANSPORTE_DESCONOCIDO: &str = "Transporte desconocido (se soporta 'tcp' o 'unix'):";
+const ERROR_CODEC_DESCONOCIDO: &str = "Codec desconocido (se soporta 'json' o 'binario'):";
+const ERROR_SALUDO: &str = "Saludo de conexión inválido:";
+const ERROR_TIMEOUT_SALUDO: &str = "Timeout esperando el saludo de conexión.";
+const ERROR_SIN_CODEC_EN_COMUN: &str = "No hay un codec en común con el otro extremo.";
+const ERROR_TRAMA_GRANDE: &str = "La trama supera el tamaño máximo:";
+const ERROR_DIRECCION_EN_USO: &str = "La dirección ya está en uso:";
+const ERROR_SIN_ESCUCHA: &str = "No hay nadie escuchando en:";
+const ERROR_ESCUCHA_CERRADA: &str = "La red en memoria se cerró.";
+const TIMEOUT_SALUDO_SEGS: u64 = 3;
+const TAMANIO_MAXIMO_TRAMA: usize = 1024 * 1024;
+const TAMANIO_BUFFER_MEMORIA: usize = 64 * 1024;
+const PUERTO_EFIMERO_INICIAL: u16 = 49152;
+
+// Contador de los puertos de las direcciones efímeras asignadas por este proceso.
+static PROXIMO_PUERTO_EFIMERO: AtomicU16 = AtomicU16::new(PUERTO_EFIMERO_INICIAL);
+
+// Futuro devuelto por los métodos de los transportes.
+pub type Futuro<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+// Mitades de lectura y escritura de una conexión, sin importar el transporte.
+type MitadLectura = Box<dyn AsyncRead + Send + Unpin>;
+type MitadEscritura = Box<dyn AsyncWrite + Send + Unpin>;
+
+// Transporte por el que se conectan los actores. Las direcciones son siempre 'SocketAddr', ya que son
+// también la identidad de cada actor; cada transporte decide cómo llegar a ellas.
+pub trait Transporte: Send + Sync {
+    // Abre una conexión con quien escucha en 'destino'.
+    fn conectar(&self, destino: SocketAddr) -> Futuro<'_, io::Result<Conexion>>;
+
+    // Empieza a escuchar conexiones entrantes en 'direccion'.
+    fn escuchar(&self, direccion: SocketAddr) -> Futuro<'_, io::Result<Box<dyn Escucha>>>;
+}
+
+// Extremo que acepta las conexiones entrantes de un transporte.
+pub trait Escucha: Send {
+    // Espera la próxima conexión entrante. El saludo no se espera acá, para que una conexión que tarda en saludar
+    // no demore a las siguientes: lo responde quien acepta, con 'ConexionEntrante::responder_saludo'.
+    fn aceptar(&mut self) -> Futuro<'_, io::Result<ConexionEntrante>>;
+}
+
+// Conexión establecida entre dos actores, separada en sus mitades de lectura y escritura.
+pub struct Conexion {
+    pub local: SocketAddr,
+    pub remota: SocketAddr,
+    pub lector: LectorTramas,
+    pub escritor: EscritorTramas,
+}
+
+// Conexión aceptada que todavía no respondió el saludo, por lo que aún no tiene un codec.
+pub struct ConexionEntrante {
+    conexion: Conexion,
+    codecs: Vec<Codec>,
+    // Indica si la dirección remota es la que informa el saludo, en los transportes en los que quien se conecta
+    // no tiene una propia.
+    remota_del_saludo: bool,
+}
+
+// Mitad de lectura de una conexión: devuelve los sobres recibidos de a uno.
+pub struct LectorTramas {
+    tramas: FramedRead<MitadLectura, LengthDelimitedCodec>,
+    codec: Codec,
+}
+
+// Mitad de escritura de una conexión: envía los sobres de a uno.
+pub struct EscritorTramas {
+    escritor: MitadEscritura,
+    codec: Codec,
+}
+
+// Primera trama de cada conexión, enviada por quien se conecta: su dirección y los codecs que soporta.
+#[derive(Serialize, Deserialize)]
+struct Saludo {
+    direccion: SocketAddr,
+    codecs: Vec<Codec>,
+}
+
+// Respuesta al saludo: el codec elegido para la conexión, si hay alguno en común.
+#[derive(Serialize, Deserialize)]
+struct RespuestaSaludo {
+    codec: Option<Codec>,
+}
+
+// Implementación de la lectura de tramas.
+impl LectorTramas {
+    fn nuevo(lector: MitadLectura) -> Self {
+        let tramas = LengthDelimitedCodec::builder()
+            .max_frame_length(TAMANIO_MAXIMO_TRAMA)
+            .new_read(lector);
+        LectorTramas {
+            tramas,
+            codec: Codec::Json,
+        }
+    }
+
+    // Espera la próxima trama. Devuelve 'None' si el otro extremo cerró la conexión.
+    async fn recibir_trama(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.tramas
+            .next()
+            .await
+            .transpose()
+            .map(|trama| trama.map(|trama| trama.to_vec()))
+    }
+
+    // Espera el próximo sobre. Devuelve 'None' si el otro extremo cerró la conexión, y el error del
+    // protocolo si la trama llegó completa pero no es un sobre válido.
+    pub async fn recibir(&mut self) -> io::Result<Option<Recibido>> {
+        let codec = self.codec;
+        Ok(self
+            .tramas
+            .next()
+            .await
+            .transpose()?
+            .map(|trama| codec.decodificar(&trama)))
+    }
+
+    // Convierte al lector en un 'Stream' de sobres, para agregarlo a un actor con 'add_stream'.
+    pub fn en_flujo(self) -> impl Stream<Item = io::Result<Recibido>> {
+        let codec = self.codec;
+        self.tramas
+            .map(move |trama| trama.map(|trama| codec.decodificar(&trama)))
+    }
+}
+
+// Implementación de la escritura de tramas.
+impl EscritorTramas {
+    fn nuevo(escritor: MitadEscritura) -> Self {
+        EscritorTramas {
+            escritor,
+            codec: Codec::Json,
+        }
+    }
+
+    // Envía una trama, precedida por su largo (4 bytes, 'big endian').
+    async fn enviar_trama(&mut self, trama: &[u8]) -> io::Result<()> {
+        let largo = u32::try_from(trama.len())
+            .ok()
+            .filter(|_| trama.len() <= TAMANIO_MAXIMO_TRAMA)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} {}", ERROR_TRAMA_GRANDE, trama.len()),
+                )
+            })?;
+        let mut buffer = Vec::with_capacity(trama.len() + std::mem::size_of::<u32>());
+        buffer.extend_from_slice(&largo.to_be_bytes());
+        buffer.extend_from_slice(trama);
+        self.escritor.write_all(&buffer).await
+    }
+
+    // Envía un sobre, codificado con el codec negociado para la conexión.
+    pub async fn enviar(&mut self, sobre: &Sobre) -> io::Result<()> {
+        let trama = self.codec.codificar(sobre);
+        self.enviar_trama(&trama).await
+    }
+}
+
+// Implementación de la construcción de conexiones y del saludo inicial.
+impl Conexion {
+    fn nueva<F>(local: SocketAddr, remota: SocketAddr, flujo: F) -> Self
+    where
+        F: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (lector, escritor) = split(flujo);
+        Conexion {
+            local,
+            remota,
+            lector: LectorTramas::nuevo(Box::new(lector)),
+            escritor: EscritorTramas::nuevo(Box::new(escritor)),
+        }
+    }
+
+    // Codec negociado para la conexión.
+    pub fn codec(&self) -> Codec {
+        self.escritor.codec
+    }
+
+    // Fija el codec de ambas mitades de la conexión.
+    fn usar_codec(&mut self, codec: Codec) {
+        self.lector.codec = codec;
+        self.escritor.codec = codec;
+    }
+
+    // Espera la próxima trama del saludo, con un tiempo límite. El saludo siempre viaja en JSON.
+    async fn recibir_saludo<T: for<'de> Deserialize<'de>>(&mut self) -> io::Result<T> {
+        let trama = timeout(
+            Duration::from_secs(TIMEOUT_SALUDO_SEGS),
+            self.lector.recibir_trama(),
+        )
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, ERROR_TIMEOUT_SALUDO))??
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, ERROR_SALUDO))?;
+        serde_json::from_slice(&trama).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} {}", ERROR_SALUDO, e),
+            )
+        })
+    }
+
+    // Envía una trama del saludo.
+    async fn enviar_saludo<T: Serialize>(&mut self, saludo: &T) -> io::Result<()> {
+        let trama = serde_json::to_vec(saludo).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} {}", ERROR_SALUDO, e),
+            )
+        })?;
+        self.escritor.enviar_trama(&trama).await
+    }
+
+    // Saludo del lado de quien se conecta: ofrece sus codecs y usa el que elija el otro extremo.
+    async fn saludar(mut self, codecs: &[Codec]) -> io::Result<Self> {
+        let saludo = Saludo {
+            direccion: self.local,
+            codecs: codecs.to_vec(),
+        };
+        self.enviar_saludo(&saludo).await?;
+        let respuesta: RespuestaSaludo = self.recibir_saludo().await?;
+        let codec = respuesta
+            .codec
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, ERROR_SIN_CODEC_EN_COMUN))?;
+        self.usar_codec(codec);
+        Ok(self)
+    }
+
+    // Saludo del lado de quien acepta: elige el primero de sus codecs que también ofrezca el otro extremo.
+    // Devuelve la conexión y la dirección informada por quien se conecta.
+    async fn responder_saludo(mut self, codecs: &[Codec]) -> io::Result<(Self, SocketAddr)> {
+        let saludo: Saludo = self.recibir_saludo().await?;
+        let codec = codecs
+            .iter()
+            .find(|codec| saludo.codecs.contains(codec))
+            .copied();
+        self.enviar_saludo(&RespuestaSaludo { codec }).await?;
+        let codec = codec
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, ERROR_SIN_CODEC_EN_COMUN))?;
+        self.usar_codec(codec);
+        Ok((self, saludo.direccion))
+    }
+}
+
+// Implementación del saludo de las conexiones aceptadas.
+impl ConexionEntrante {
+    // Responde el saludo de quien se conecta y devuelve la conexión lista para enviar y recibir sobres.
+    pub async fn responder_saludo(self) -> io::Result<Conexion> {
+        let (mut conexion, remota) = self.conexion.responder_saludo(&self.codecs).await?;
+        if self.remota_del_saludo {
+            conexion.remota = remota;
+        }
+        Ok(conexion)
+    }
+}
+
+// Devuelve el transporte elegido con la variable de entorno 'TRANSPORTE' (por defecto, TCP), con los codecs
+// elegidos con la variable 'CODECS' (por defecto, todos).
+pub fn transporte_desde_entorno() -> Arc<dyn Transporte> {
+    let codecs = codecs_desde_entorno();
+    match std::env::var(VARIABLE_TRANSPORTE) {
+        Err(_) => Arc::new(TransporteTcp::new(codecs)),
+        Ok(nombre) if nombre == TRANSPORTE_TCP => Arc::new(TransporteTcp::new(codecs)),
+        Ok(nombre) if nombre == TRANSPORTE_UNIX => Arc::new(TransporteUnix::new(
+            std::env::var(VARIABLE_DIRECTORIO_SOCKETS)
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir()),
+            codecs,
+        )),
+        Ok(nombre) => {
+            eprintln!("{} {}", ERROR_TRANSPORTE_DESCONOCIDO, nombre);
+            std::process::exit(CODIGO_ERROR);
+        }
+    }
+}
+
+// Devuelve los codecs de la variable de entorno 'CODECS', separados por coma y en orden de preferencia.
+fn codecs_desde_entorno() -> Vec<Codec> {
+    let Ok(nombres) = std::env::var(VARIABLE_CODECS) else {
+        return CODECS.to_vec();
+    };
+
+    nombres
+        .split(SEPARADOR_CODECS)
+        .map(|nombre| match nombre.trim() {
+            CODEC_JSON => Codec::Json,
+            CODEC_BINARIO => Codec::Binario,
+            otro => {
+                eprintln!("{} {}", ERROR_CODEC_DESCONOCIDO, otro);
+                std::process::exit(CODIGO_ERROR);
+            }
+        })
+        .collect()
+}
+
+// Genera una dirección para el extremo que se conecta en los transportes que no tienen una propia. La IP se
+// arma con el identificador del proceso y el puerto con un contador, por lo que no se repite entre procesos.
+fn direccion_efimera() -> SocketAddr {
+    let [_, a, b, c] = std::process::id().to_be_bytes();
+    let puerto = PROXIMO_PUERTO_EFIMERO.fetch_add(1, Ordering::Relaxed);
+    SocketAddr::new(Ipv4Addr::new(127, a, b, c).into(), puerto)
+}
+
+// Transporte sobre TCP. Es el que se usa por defecto.
+pub struct TransporteTcp {
+    codecs: Vec<Codec>,
+}
+
+// Implementación de la construcción del transporte sobre TCP.
+impl TransporteTcp {
+    // Constructor.
+    pub fn new(codecs: Vec<Codec>) -> Self {
+        TransporteTcp { codecs }
+    }
+}
+
+// Implementación del trait 'Transporte' sobre TCP.
+impl Transporte for TransporteTcp {
+    fn conectar(&self, destino: SocketAddr) -> Futuro<'_, io::Result<Conexion>> {
+        Box::pin(async move {
+            let stream = TcpStream::connect(destino).await?;
+            let local = stream.local_addr()?;
+            Conexion::nueva(local, destino, stream)
+                .saludar(&self.codecs)
+                .await
+        })
+    }
+
+    fn escuchar(&self, direccion: SocketAddr) -> Futuro<'_, io::Result<Box<dyn Escucha>>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(direccion).await?;
+            Ok(Box::new(EscuchaTcp {
+                listener,
+                codecs: self.codecs.clone(),
+            }) as Box<dyn Escucha>)
+        })
+    }
+}
+
+// Escucha de conexiones TCP.
+struct EscuchaTcp {
+    listener: TcpListener,
+    codecs: Vec<Codec>,
+}
+
+// Implementación del trait 'Escucha' sobre TCP.
+impl Escucha for EscuchaTcp {
+    fn aceptar(&mut self) -> Futuro<'_, io::Result<ConexionEntrante>> {
+        Box::pin(async move {
+            let (stream, remota) = self.listener.accept().await?;
+            let local = stream.local_addr()?;
+            Ok(ConexionEntrante {
+                conexion: Conexion::nueva(local, remota, stream),
+                codecs: self.codecs.clone(),
+                remota_del_saludo: false,
+            })
+        })
+    }
+}
+
+// Transporte sobre sockets Unix. Cada dirección se traduce a un archivo dentro de 'directorio', y la dirección
+// de quien se conecta es la que informa en el saludo, ya que los sockets Unix no tienen una.
+pub struct TransporteUnix {
+    directorio: PathBuf,
+    codecs: Vec<Codec>,
+}
+
+// Implementación de la construcción del transporte sobre sockets Unix.
+impl TransporteUnix {
+    // Constructor.
+    pub fn new(directorio: PathBuf, codecs: Vec<Codec>) -> Self {
+        TransporteUnix { directorio, codecs }
+    }
+
+    // Ruta del archivo del socket que corresponde a 'direccion'.
+    fn ruta(&self, direccion: SocketAddr) -> PathBuf {
+        self.directorio
+            .join(format!("tp2-{}-{}.sock", direccion.ip(), direccion.port()))
+    }
+}
+
+// Implementación del trait 'Transporte' sobre sockets Unix.
+impl Transporte for TransporteUnix {
+    fn conectar(&self, destino: SocketAddr) -> Futuro<'_, io::Result<Conexion>> {
+        Box::pin(async move {
+            let stream = UnixStream::connect(self.ruta(destino)).await?;
+            Conexion::nueva(direccion_efimera(), destino, stream)
+                .saludar(&self.codecs)
+                .await
+        })
+    }
+
+    fn escuchar(&self, direccion: SocketAddr) -> Futuro<'_, io::Result<Box<dyn Escucha>>> {
+        Box::pin(async move {
+            let ruta = self.ruta(direccion);
+            // Se borra el archivo que haya dejado una ejecución anterior en la misma dirección, pero sólo si nadie
+            // acepta conexiones en él: si no, la dirección está en uso.
+            if ruta.exists() {
+                if UnixStream::connect(&ruta).await.is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        format!("{} {}", ERROR_DIRECCION_EN_USO, direccion),
+                    ));
+                }
+                std::fs::remove_file(&ruta)?;
+            }
+            let listener = UnixListener::bind(&ruta)?;
+            Ok(Box::new(EscuchaUnix {
+                listener,
+                direccion,
+                codecs: self.codecs.clone(),
+            }) as Box<dyn Escucha>)
+        })
+    }
+}
+
+// Escucha de conexiones por sockets Unix.
+struct EscuchaUnix {
+    listener: UnixListener,
+    direccion: SocketAddr,
+    codecs: Vec<Codec>,
+}
+
+// Implementación del trait 'Escucha' sobre sockets Unix.
+impl Escucha for EscuchaUnix {
+    fn aceptar(&mut self) -> Futuro<'_, io::Result<ConexionEntrante>> {
+        Box::pin(async move {
+            let (stream, _) = self.listener.accept().await?;
+            Ok(ConexionEntrante {
+                conexion: Conexion::nueva(self.direccion, self.direccion, stream),
+                codecs: self.codecs.clone(),
+                remota_del_saludo: true,
+            })
+        })
+    }
+}
+
+// Escuchas registradas en una red en memoria, por dirección.
+type Escuchas = Arc<Mutex<HashMap<SocketAddr, mpsc::UnboundedSender<Conexion>>>>;
+
+// Transporte sobre una red en memoria. Todas las copias ('clone') de un mismo transporte comparten la red, por
+// lo que permite levantar servidores, clientes y gateway dentro de un único proceso.
+#[derive(Clone)]
+pub struct TransporteMemoria {
+    escuchas: Escuchas,
+    codecs: Vec<Codec>,
+}
+
+// Implementación de la construcción del transporte en memoria.
+impl TransporteMemoria {
+    // Constructor. Crea una red nueva, sin nadie escuchando.
+    pub fn new(codecs: Vec<Codec>) -> Self {
+        TransporteMemoria {
+            escuchas: Arc::new(Mutex::new(HashMap::new())),
+            codecs,
+        }
+    }
+
+    // Devuelve un transporte sobre la misma red, pero con otros codecs.
+    pub fn con_codecs(&self, codecs: Vec<Codec>) -> Self {
+        TransporteMemoria {
+            escuchas: self.escuchas.clone(),
+            codecs,
+        }
+    }
+}
+
+// Implementación del trait 'Transporte' sobre la red en memoria.
+impl Transporte for TransporteMemoria {
+    fn conectar(&self, destino: SocketAddr) -> Futuro<'_, io::Result<Conexion>> {
+        Box::pin(async move {
+            let local = direccion_efimera();
+            let (propio, ajeno) = tokio::io::duplex(TAMANIO_BUFFER_MEMORIA);
+            {
+                let escuchas = self
+                    .escuchas
+                    .lock()
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                escuchas
+                    .get(&destino)
+                    .and_then(|escucha| escucha.send(Conexion::nueva(destino, local, ajeno)).ok())
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            format!("{} {}", ERROR_SIN_ESCUCHA, destino),
+                        )
+                    })?;
+            }
+
+            Conexion::nueva(local, destino, propio)
+                .saludar(&self.codecs)
+                .await
+        })
+    }
+
+    fn escuchar(&self, direccion: SocketAddr) -> Futuro<'_, io::Result<Box<dyn Escucha>>> {
+        Box::pin(async move {
+            let mut escuchas = self
+                .escuchas
+                .lock()
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            if escuchas
+                .get(&direccion)
+                .is_some_and(|escucha| !escucha.is_closed())
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} {}", ERROR_DIRECCION_EN_USO, direccion),
+                ));
+            }
+
+            let (enviador, receptor) = mpsc::unbounded_channel();
+            escuchas.insert(direccion, enviador);
+            Ok(Box::new(EscuchaMemoria {
+                receptor,
+                codecs: self.codecs.clone(),
+            }) as Box<dyn Escucha>)
+        })
+    }
+}
+
+// Escucha de conexiones de la red en memoria.
+struct EscuchaMemoria {
+    receptor: mpsc::UnboundedReceiver<Conexion>,
+    codecs: Vec<Codec>,
+}
+
+// Implementación del trait 'Escucha' sobre la red en memoria.
+impl Escucha for EscuchaMemoria {
+    fn aceptar(&mut self) -> Futuro<'_, io::Result<ConexionEntrante>> {
+        Box::pin(async move {
+            let conexion =
+                self.receptor.recv().await.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::BrokenPipe, ERROR_ESCUCHA_CERRADA)
+                })?;
+            Ok(ConexionEntrante {
+                conexion,
+                codecs: self.codecs.clone(),
+                remota_del_saludo: false,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocolo::{empaquetar, Mensaje};
+
+    fn direccion(puerto: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], puerto))
+    }
+
+    // Conecta con quien escucha en 'destino' y acepta la conexión del otro lado.
+    async fn conectar(
+        transporte: &dyn Transporte,
+        escucha: &mut Box<dyn Escucha>,
+        destino: SocketAddr,
+    ) -> (Conexion, Conexion) {
+        let (saliente, entrante) = tokio::join!(transporte.conectar(destino), async {
+            escucha.aceptar().await?.responder_saludo().await
+        });
+        (saliente.unwrap(), entrante.unwrap())
+    }
+
+    #[actix_rt::test]
+    async fn los_sobres_van_y_vuelven_por_la_red_en_memoria() {
+        let transporte = TransporteMemoria::new(CODECS.to_vec());
+        let mut escucha = transporte.escuchar(direccion(9000)).await.unwrap();
+        let (mut saliente, mut entrante) =
+            conectar(&transporte, &mut escucha, direccion(9000)).await;
+        assert_eq!(saliente.remota, direccion(9000));
+        assert_eq!(entrante.remota, saliente.local);
+
+        let sobre = empaquetar(saliente.local, Mensaje::ConsultarCoordinador);
+        saliente.escritor.enviar(&sobre).await.unwrap();
+        let recibido = entrante.lector.recibir().await.unwrap().unwrap().unwrap();
+        assert_eq!(recibido.id_mensaje, sobre.id_mensaje);
+        assert!(matches!(recibido.mensaje, Mensaje::ConsultarCoordinador));
+
+        entrante
+            .escritor
+            .enviar(&empaquetar(
+                direccion(9000),
+                Mensaje::Confirmacion(sobre.id_mensaje),
+            ))
+            .await
+            .unwrap();
+        let respuesta = saliente.lector.recibir().await.unwrap().unwrap().unwrap();
+        assert!(matches!(respuesta.mensaje, Mensaje::Confirmacion(id) if id == sobre.id_mensaje));
+
+        drop(entrante);
+        assert!(saliente.lector.recibir().await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn la_red_en_memoria_rechaza_direcciones_sin_escucha_o_en_uso() {
+        let transporte = TransporteMemoria::new(CODECS.to_vec());
+        let error = transporte.conectar(direccion(9001)).await.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+
+        let escucha = transporte.escuchar(direccion(9001)).await.unwrap();
+        let error = transporte.escuchar(direccion(9001)).await.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+
+        // Cuando se cierra la escucha la dirección se puede volver a usar.
+        drop(escucha);
+        assert!(transporte.escuchar(direccion(9001)).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn el_socket_unix_solo_se_borra_si_quedo_de_otra_ejecucion() {
+        let directorio =
+            std::env::temp_dir().join(format!("tp2-transporte-{}", std::process::id()));
+        std::fs::create_dir_all(&directorio).unwrap();
+        let transporte = TransporteUnix::new(directorio.clone(), CODECS.to_vec());
+
+        // Un archivo que nadie escucha se reemplaza.
+        std::fs::write(transporte.ruta(direccion(9002)), b"").unwrap();
+        let mut escucha = transporte.escuchar(direccion(9002)).await.unwrap();
+
+        // El socket de una escucha activa no se borra.
+        let error = transporte.escuchar(direccion(9002)).await.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+        // La conexión con la que se probó si el socket estaba en uso se cierra sin saludar.
+        let sin_saludo = escucha.aceptar().await.unwrap();
+        assert!(sin_saludo.responder_saludo().await.is_err());
+        let (saliente, _) = conectar(&transporte, &mut escucha, direccion(9002)).await;
+        assert_eq!(saliente.remota, direccion(9002));
+
+        std::fs::remove_dir_all(&directorio).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn se_usa_el_primer_codec_de_quien_acepta_que_ofrece_quien_se_conecta() {
+        let red = TransporteMemoria::new(vec![Codec::Json, Codec::Binario]);
+        let mut escucha = red.escuchar(direccion(9003)).await.unwrap();
+        for (codecs, elegido) in [
+            (vec![Codec::Binario, Codec::Json], Codec::Json),
+            (vec![Codec::Binario], Codec::Binario),
+        ] {
+            let transporte = red.con_codecs(codecs);
+            let (mut saliente, mut entrante) =
+                conectar(&transporte, &mut escucha, direccion(9003)).await;
+            assert_eq!(saliente.codec(), elegido);
+            assert_eq!(entrante.codec(), elegido);
+
+            // El sobre viaja con el codec elegido.
+            let sobre = empaquetar(saliente.local, Mensaje::Ack);
+            saliente.escritor.enviar(&sobre).await.unwrap();
+            let recibido = entrante.lector.recibir().await.unwrap().unwrap().unwrap();
+            assert_eq!(recibido.id_mensaje, sobre.id_mensaje);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn sin_un_codec_en_comun_se_rechaza_la_conexion() {
+        let red = TransporteMemoria::new(vec![Codec::Binario]);
+        let mut escucha = red.escuchar(direccion(9004)).await.unwrap();
+        let transporte = red.con_codecs(vec![Codec::Json]);
+        let (saliente, entrante) = tokio::join!(transporte.conectar(direccion(9004)), async {
+            escucha.aceptar().await?.responder_saludo().await
+        });
+        assert_eq!(saliente.err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(entrante.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[actix_rt::test]
+    async fn una_conexion_que_no_saluda_no_demora_a_las_siguientes() {
+        let transporte = TransporteMemoria::new(CODECS.to_vec());
+        let mut escucha = transporte.escuchar(direccion(9005)).await.unwrap();
+        let (_callado, ajeno) = tokio::io::duplex(TAMANIO_BUFFER_MEMORIA);
+        transporte.escuchas.lock().unwrap()[&direccion(9005)]
+            .send(Conexion::nueva(direccion(9005), direccion(9006), ajeno))
+            .unwrap();
+
+        let _sin_saludo = escucha.aceptar().await.unwrap();
+        let (saliente, entrante) = conectar(&transporte, &mut escucha, direccion(9005)).await;
+        assert_eq!(entrante.remota, saliente.local);
+    }
+}
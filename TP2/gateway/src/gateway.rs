@@ -5,13 +5,12 @@ use actix::prelude::*;
 use rand::Rng;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::io::{split, AsyncBufReadExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
-use tokio_stream::wrappers::LinesStream;
 
 // Imports de funciones/estructuras propias.
-use common::mensajes_gateway::{RequerirPago, RespuestaAutorizacion, RespuestaPago};
-use common::tcp_enviador::{MensajeTCP, TcpEnviador};
+use common::enviador::{Enviador, EnviarSobre};
+use common::mensajes_gateway::{RespuestaAutorizacion, RespuestaPago};
+use common::protocolo::{empaquetar, ErrorProtocolo, Mensaje, Recibido};
+use common::transporte::{Conexion, Transporte};
 
 // Constantes.
 const PORCENTAJE_ERROR: u8 = 3; // Treinta porciento de probabilidad de error en la autorización de pago.
@@ -23,17 +22,17 @@ const MSJ_AUTORIZADO: &str = "AUTORIZADO";
 const MSJ_RECHAZADO: &str = "RECHAZADO";
 const ERROR_MENSAJE_AUTORIZACION: &str = "Error al enviar mensaje 'RespuestaAutorizacion' a";
 const AVISO_MENSAJE_ENVIADO: &str = "Mensaje 'RespuestaAutorizacion' enviado a";
-const ERROR_SERIALIZAR_MENSAJE_CHEQUEO: &str = "Error para serializar 'RespuestaAutorizacion'";
 const ERROR_AVISO_PAGO_REALIZADO: &str = "Error al enviar 'PagoHecho' a";
 const AVISO_PAGO_ENVIADO: &str = "Enviado 'PagoHecho' a";
-const ERROR_SERIALIZAR_MENSAJE_PAGO: &str = "Error al serializar 'PagoHecho'";
-const ERROR_DESERIALIZAR_MENSAJE: &str = "Error al deserializar el mensaje recibido.";
+const ERROR_DESERIALIZAR_MENSAJE: &str = "Error al deserializar el mensaje recibido:";
+const MENSAJE_INESPERADO: &str = "Mensaje inesperado:";
 const ERROR_LEYENDO_LINEA: &str = "Error leyendo la línea:";
 
 // Este actor implementa al 'Gateway' que efectua o rechaza los pagos.
 pub struct GatewayActor {
-    tcp_enviador: Arc<Addr<TcpEnviador>>,
+    enviador: Arc<Addr<Enviador>>,
     pub direccion: SocketAddr,
+    pub direccion_gateway: SocketAddr,
 }
 
 // Implementa el trait 'Actor'.
@@ -44,37 +43,48 @@ impl Actor for GatewayActor {
 // Implementación de los métodos de construcción e inicialización del actor `GatewayActor`.
 impl GatewayActor {
     // Constructor.
-    pub fn new(stream: TcpStream, direccion: SocketAddr) -> Addr<Self> {
-        // Separamos el stream en dos mitades: Lectura y escritura.
+    pub fn new(conexion: Conexion, direccion_gateway: SocketAddr) -> Addr<Self> {
+        // La conexión ya viene separada en dos mitades: Lectura y escritura.
         GatewayActor::create(|contexto| {
-            let (mitad_lectura, mitad_escritura) = split(stream);
-            GatewayActor::add_stream(
-                LinesStream::new(BufReader::new(mitad_lectura).lines()),
-                contexto,
-            );
-            let escribir = Some(mitad_escritura);
-            let enviador_actor = TcpEnviador { escribir }.start();
-            let tcp_enviador = Arc::new(enviador_actor);
+            let direccion = conexion.remota;
+            GatewayActor::add_stream(conexion.lector.en_flujo(), contexto);
+            let enviador_actor = Enviador::new(direccion, conexion.escritor).start();
+            let enviador = Arc::new(enviador_actor);
             GatewayActor {
-                tcp_enviador,
+                enviador,
                 direccion,
+                direccion_gateway,
             }
         })
     }
 
     // Inicio del actor `GatewayActor`.
-    pub async fn start(direccion: SocketAddr) -> Result<(), std::io::Error> {
-        let listener = TcpListener::bind(direccion)
+    pub async fn start(
+        direccion: SocketAddr,
+        transporte: Arc<dyn Transporte>,
+    ) -> Result<(), std::io::Error> {
+        let mut listener = transporte
+            .escuchar(direccion)
             .await
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
         loop {
-            match listener.accept().await {
-                Ok((stream, direccion_cliente)) => {
-                    println!(
-                        "[{}] {} {:?}",
-                        direccion, MSJ_CONEXION_RECIBIDA, direccion_cliente
-                    );
-                    GatewayActor::new(stream, direccion_cliente);
+            match listener.aceptar().await {
+                // El saludo se responde en otra tarea, para seguir aceptando conexiones mientras tanto.
+                Ok(entrante) => {
+                    actix::spawn(async move {
+                        match entrante.responder_saludo().await {
+                            Ok(conexion) => {
+                                println!(
+                                    "[{}] {} {:?}",
+                                    direccion, MSJ_CONEXION_RECIBIDA, conexion.remota
+                                );
+                                GatewayActor::new(conexion, direccion);
+                            }
+                            Err(e) => {
+                                println!("[{}] {} {:?}", direccion, ERROR_ACEPTAR_CONEXION, e);
+                            }
+                        }
+                    });
                 }
 
                 Err(e) => {
@@ -86,102 +96,122 @@ impl GatewayActor {
 }
 
 // Implementación de los handlers del actor 'GatewayActor'.
-impl StreamHandler<Result<String, tokio::io::Error>> for GatewayActor {
+impl StreamHandler<Result<Recibido, tokio::io::Error>> for GatewayActor {
     // Método que maneja las interacciones del gateway con el servidor coordinador.
-    fn handle(&mut self, linea: Result<String, tokio::io::Error>, contexto: &mut Context<Self>) {
-        let tcp_enviador = self.tcp_enviador.clone();
+    fn handle(&mut self, linea: Result<Recibido, tokio::io::Error>, contexto: &mut Context<Self>) {
+        let enviador = self.enviador.clone();
         let direccion = self.direccion;
-        if let Ok(data) = linea {
-            if let Ok(mensaje) = serde_json::from_str::<RequerirPago>(&data) {
-                match mensaje {
-                    // Handler para el chequeo inicial de autorización de pago (Modela la suficiencia de saldo).
-                    RequerirPago::ValidarAutorizacionPago(mensaje_autorizacion) => {
-                        let mut rng = rand::thread_rng();
-
-                        // Se randomiza la autorización de pago.
-                        let autorizado = rng.gen_range(1..=10) >= PORCENTAJE_ERROR;
-                        let respuesta = RespuestaAutorizacion {
-                            id_comensal: mensaje_autorizacion.id_comensal.clone(),
-                            autorizado,
-                        };
-
-                        println!(
-                            "{} [{}] {} [{}]",
-                            LOG_TRANSACCION_1,
-                            if autorizado {
-                                MSJ_AUTORIZADO
-                            } else {
-                                MSJ_RECHAZADO
-                            },
-                            LOG_TRANSACCION_2,
-                            mensaje_autorizacion.id_comensal
-                        );
-
-                        if let Ok(mensaje_serializado) = serde_json::to_string(&respuesta) {
-                            tcp_enviador
-                                .send(MensajeTCP(mensaje_serializado))
-                                .into_actor(self)
-                                .then(move |result, _, _| {
-                                    if let Err(err) = result {
-                                        eprintln!(
-                                            "[{:?}] {} {}: {:?}",
-                                            direccion,
-                                            ERROR_MENSAJE_AUTORIZACION,
-                                            mensaje_autorizacion.id_comensal,
-                                            err
-                                        );
-                                    } else {
-                                        println!(
-                                            "[{:?}] {} {}",
-                                            direccion,
-                                            AVISO_MENSAJE_ENVIADO,
-                                            mensaje_autorizacion.id_comensal
-                                        );
-                                    }
-                                    fut::ready(())
-                                })
-                                .wait(contexto);
+        let direccion_gateway = self.direccion_gateway;
+        let data = match linea {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("[{:?}] {} {}", direccion, ERROR_LEYENDO_LINEA, err);
+                return;
+            }
+        };
+
+        let sobre = match data {
+            Ok(sobre) => sobre,
+            Err(ErrorProtocolo::VersionDesconocida(version)) => {
+                // Se le avisa al servidor que su versión del protocolo no es soportada.
+                eprintln!(
+                    "[{:?}] {}",
+                    direccion,
+                    ErrorProtocolo::VersionDesconocida(version)
+                );
+                enviador.do_send(EnviarSobre(empaquetar(
+                    direccion_gateway,
+                    Mensaje::VersionRechazada(version),
+                )));
+                return;
+            }
+            Err(err) => {
+                eprintln!("[{:?}] {} {}", direccion, ERROR_DESERIALIZAR_MENSAJE, err);
+                return;
+            }
+        };
+
+        match sobre.mensaje {
+            // Handler para el chequeo inicial de autorización de pago (Modela la suficiencia de saldo).
+            Mensaje::ValidarAutorizacionPago(mensaje_autorizacion) => {
+                let mut rng = rand::thread_rng();
+
+                // Se randomiza la autorización de pago.
+                let autorizado = rng.gen_range(1..=10) >= PORCENTAJE_ERROR;
+                let respuesta = RespuestaAutorizacion {
+                    id_comensal: mensaje_autorizacion.id_comensal.clone(),
+                    autorizado,
+                };
+
+                println!(
+                    "{} [{}] {} [{}]",
+                    LOG_TRANSACCION_1,
+                    if autorizado {
+                        MSJ_AUTORIZADO
+                    } else {
+                        MSJ_RECHAZADO
+                    },
+                    LOG_TRANSACCION_2,
+                    mensaje_autorizacion.id_comensal
+                );
+
+                let mensaje_serializado =
+                    empaquetar(direccion_gateway, Mensaje::RespuestaAutorizacion(respuesta));
+                enviador
+                    .send(EnviarSobre(mensaje_serializado))
+                    .into_actor(self)
+                    .then(move |result, _, _| {
+                        if let Err(err) = result {
+                            eprintln!(
+                                "[{:?}] {} {}: {:?}",
+                                direccion,
+                                ERROR_MENSAJE_AUTORIZACION,
+                                mensaje_autorizacion.id_comensal,
+                                err
+                            );
                         } else {
-                            eprintln!("[{:?}] {}", direccion, ERROR_SERIALIZAR_MENSAJE_CHEQUEO);
+                            println!(
+                                "[{:?}] {} {}",
+                                direccion, AVISO_MENSAJE_ENVIADO, mensaje_autorizacion.id_comensal
+                            );
                         }
-                    }
-
-                    // Handler para el pago efectivo (Modela la ejecución del pago).
-                    RequerirPago::EfectivizarPago(mensaje_pago) => {
-                        let respuesta = RespuestaPago::PagoHecho;
-                        if let Ok(mensaje_serializado) = serde_json::to_string(&respuesta) {
-                            tcp_enviador
-                                .send(MensajeTCP(mensaje_serializado))
-                                .into_actor(self)
-                                .then(move |result, _, _| {
-                                    if let Err(err) = result {
-                                        eprintln!(
-                                            "[{:?}] {} {}: {:?}",
-                                            direccion,
-                                            ERROR_AVISO_PAGO_REALIZADO,
-                                            mensaje_pago.id_comensal,
-                                            err
-                                        );
-                                    } else {
-                                        println!(
-                                            "[{:?}] {} {}",
-                                            direccion, AVISO_PAGO_ENVIADO, mensaje_pago.id_comensal
-                                        );
-                                    }
-
-                                    fut::ready(())
-                                })
-                                .wait(contexto);
+                        fut::ready(())
+                    })
+                    .wait(contexto);
+            }
+
+            // Handler para el pago efectivo (Modela la ejecución del pago).
+            Mensaje::EfectivizarPago(mensaje_pago) => {
+                let respuesta = RespuestaPago::PagoHecho;
+                let mensaje_serializado =
+                    empaquetar(direccion_gateway, Mensaje::RespuestaPago(respuesta));
+                enviador
+                    .send(EnviarSobre(mensaje_serializado))
+                    .into_actor(self)
+                    .then(move |result, _, _| {
+                        if let Err(err) = result {
+                            eprintln!(
+                                "[{:?}] {} {}: {:?}",
+                                direccion,
+                                ERROR_AVISO_PAGO_REALIZADO,
+                                mensaje_pago.id_comensal,
+                                err
+                            );
                         } else {
-                            eprintln!("[{:?}] {}", direccion, ERROR_SERIALIZAR_MENSAJE_PAGO);
+                            println!(
+                                "[{:?}] {} {}",
+                                direccion, AVISO_PAGO_ENVIADO, mensaje_pago.id_comensal
+                            );
                         }
-                    }
-                }
-            } else {
-                eprintln!("[{:?}] {}", direccion, ERROR_DESERIALIZAR_MENSAJE);
+
+                        fut::ready(())
+                    })
+                    .wait(contexto);
+            }
+
+            otro => {
+                eprintln!("[{:?}] {} {:?}", direccion, MENSAJE_INESPERADO, otro);
             }
-        } else if let Err(err) = linea {
-            eprintln!("[{:?}] {} {}", direccion, ERROR_LEYENDO_LINEA, err);
         }
     }
 }
@@ -2,6 +2,7 @@
 
 // Módulos locales utilizados.
 mod gateway;
+use common::transporte::transporte_desde_entorno;
 use gateway::GatewayActor;
 
 // Constantes.
@@ -20,6 +21,6 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
-    GatewayActor::start(direccion).await?;
+    GatewayActor::start(direccion, transporte_desde_entorno()).await?;
     Ok(())
 }
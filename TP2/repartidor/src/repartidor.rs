@@ -2,52 +2,30 @@
 
 // Imports de crates externas.
 use rand::Rng;
-use std::net::SocketAddr;
 use std::time::Duration;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
-use tokio::time::{sleep, timeout};
+use tokio::time::sleep;
 
 // Imports de funciones/estructuras propias.
+use cliente::sesion::{Evento, Sesion};
 use common::mensajes::{
-    FinalizarViaje, IniciarViajeDelivery, OfertarViaje, Posicion, QuienEsCoordinador,
-    RespuestaOfertaViaje,
+    FinalizarViaje, IniciarViajeDelivery, OfertarViaje, Posicion, RespuestaOfertaViaje,
 };
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{Mensaje, Recibido};
 use common::utils::obtener_tupla_random;
 
 // Constantes.
 const PROBABILIDAD_ACEPTACION: f64 = 0.8;
 const MIN_DELAY: f32 = 1.0;
 const MAX_DELAY: f32 = 2.0;
-const TIMEOUT_ACK_SEGS: u64 = 2;
-const TIMEOUT_COORDINADOR: u64 = 3;
-const ERROR_CONEXION_SERVIDORES: &str = "No se pudo conectar a ningún servidor de coordinación.";
-const SERVER_CERRO_CONEXION: &str = "REPARTIDOR - El servidor cerró la conexión.";
-const ERROR_LEYENDO_SERVIDOR: &str = "REPARTIDOR - Error leyendo del servidor: ";
+const VERSION_RECHAZADA: &str =
+    "REPARTIDOR - El servidor rechazó la versión del protocolo del mensaje, versión enviada:";
+const SESION_PERDIDA: &str = "REPARTIDOR - Se perdió la sesión con el servidor.";
 const MENSAJE_DESCONOCIDO: &str = "REPARTIDOR - Mensaje desconocido:";
 const ERROR_ENVIAR_RESPUESTA: &str = "REPARTIDOR - Error al enviar respuesta:";
 const ERROR_ENVIAR_POSICION: &str = "REPARTIDOR - Error al enviar posición:";
 const VIAJE_TERMINADO: &str = "REPARTIDOR - Viaje terminado.";
-const ERROR_AVISAR_FIN_VIAJE: &str = "REPARTIDOR - Error al enviar 'FinalizarViaje':";
-const ACK_MENSAJE: &str = "ACK";
-const ACK_DE_COORDINADOR: &str = "Ack";
 const SE_RECIBE_EL_ACK: &str =
-    "REPARTIDOR - Se recibe el ACK del servidor, viaje finalizado con éxito.";
-const MENSAJE_INESPERADO_SERVIDOR: &str = "REPARTIDOR - Mensaje inesperado del servidor:";
-const ERROR_TIMEOUT_ACK_SEGS: &str =
-    "REPARTIDOR - Timeout al esperar ACK del servidor, intentando reconectar...";
-const ERROR_AL_RECONECTAR: &str = "REPARTIDOR - Error al reconectar con el servidor.";
-const RECONEXION_EXITOSA: &str = "REPARTIDOR - Reconexión exitosa con el servidor.";
-const BUSCANDO_COORDINADOR: &str = "REPARTIDOR - Buscando coordinador...";
-const ERROR_ESCRIBIENDO_CONSULTA_LIDER: &str =
-    "REPARTIDOR - Error escribiendo consulta para averiguar el líder:";
-const ERROR_LEER_LINEA: &str = "REPARTIDOR - Error al leer línea:";
-const TIMEOUT_LEER_LINEA: &str = "REPARTIDOR - Timeout al leer línea.";
-const REPARTIDOR_NO_CONECTA_COORDINADOR: &str = "REPARTIDOR - No se pudo conectar al coordinador:";
-const REPARTIDOR_ENCONTRO_COORDINADOR: &str = "REPARTIDOR - Encontró al coordinador:";
-const REPARTIDOR_CONECTADO_COORDINADOR: &str = "REPARTIDOR - Conectado al coordinador.";
+    "REPARTIDOR - Se recibe la confirmación del servidor, viaje finalizado con éxito.";
 const RECIBI_PEDIDO_DEL_SERVIDOR: &str = "REPARTIDOR - Recibí un pedido del servidor.";
 const INICIANDO_DIRECCION_RESTAURANTE: &str =
     "REPARTIDOR - Iniciando viaje desde un restaurante con direccion: ";
@@ -55,30 +33,19 @@ const INICIANDO_DIRECCION_COMENSAL: &str = "hacia un comensal con direccion: ";
 
 // Este actor implementa al 'Repartidor' que lleva los pedidos del 'Restaurante' al 'Comensal'.
 pub struct Repartidor {
-    servidores: Vec<SocketAddr>,
-    lector: Lines<BufReader<OwnedReadHalf>>,
-    escritor: OwnedWriteHalf,
+    sesion: Sesion,
     ubicacion: Posicion,
 }
 
 // Implementación de los métodos de construcción, inicialización y handlers del actor `Repartidor`.
 impl Repartidor {
     // Constructor.
-    pub async fn new(servidores: Vec<SocketAddr>) -> Self {
-        // Separamos el stream en dos mitades: Lectura y escritura.
-        let tcp_stream: Option<TcpStream> = conectar_con_coordinador(servidores.clone()).await;
-        if let Some(stream) = tcp_stream {
-            let (rx, wx) = stream.into_split();
-            Self {
-                servidores,
-                lector: BufReader::new(rx).lines(),
-                escritor: wx,
-                ubicacion: Posicion {
-                    posicion: obtener_tupla_random(),
-                },
-            }
-        } else {
-            panic!("{}", ERROR_CONEXION_SERVIDORES);
+    pub fn new(sesion: Sesion) -> Self {
+        Self {
+            sesion,
+            ubicacion: Posicion {
+                posicion: obtener_tupla_random(),
+            },
         }
     }
 
@@ -86,37 +53,39 @@ impl Repartidor {
     pub async fn run(&mut self) {
         // Informa su posición inicial al servidor.
         self.enviar_posicion().await;
-        loop {
-            tokio::select! {
-                resultado = self.lector.next_line() => {
-                    match resultado {
-                        Ok(Some(mensaje)) => {
-                            self.handle_mensaje_servidor(mensaje).await;
-                        }
-
-                        Ok(None) => {
-                            println!("{}", SERVER_CERRO_CONEXION);
-                            break;
-                        }
-
-                        Err(e) => {
-                            eprintln!("{} {}", ERROR_LEYENDO_SERVIDOR, e);
-                            break;
-                        }
-                    }
-                }
+        while let Some(evento) = self.sesion.recibir().await {
+            match evento {
+                Evento::Mensaje(mensaje) => self.handle_mensaje_servidor(mensaje).await,
+                Evento::Confirmado(_) => println!("{}", SE_RECIBE_EL_ACK),
+                // El nuevo coordinador todavía no conoce al repartidor.
+                Evento::Reconectado => self.enviar_posicion().await,
             }
         }
+
+        eprintln!("{}", SESION_PERDIDA);
     }
 
     // Handler de los mensajes recibidos del servidor coordinador.
-    async fn handle_mensaje_servidor(&mut self, mensaje: String) {
-        if let Ok(pedido) = serde_json::from_str::<OfertarViaje>(&mensaje) {
-            self.handle_puede_aceptar_viaje(pedido).await;
-        } else if let Ok(iniciar_viaje) = serde_json::from_str::<IniciarViajeDelivery>(&mensaje) {
-            self.handle_iniciar_viaje(iniciar_viaje).await;
-        } else {
-            eprintln!("{} {}", MENSAJE_DESCONOCIDO, mensaje);
+    async fn handle_mensaje_servidor(&mut self, mensaje: Recibido) {
+        match mensaje.map(|sobre| sobre.mensaje) {
+            Ok(Mensaje::OfertarViaje(pedido)) => {
+                self.handle_puede_aceptar_viaje(pedido).await;
+            }
+
+            Ok(Mensaje::IniciarViajeDelivery(iniciar_viaje)) => {
+                self.handle_iniciar_viaje(iniciar_viaje).await;
+            }
+
+            Ok(Mensaje::VersionRechazada(version)) => {
+                eprintln!("{} {}", VERSION_RECHAZADA, version);
+            }
+
+            // Aviso del servidor de que el viaje terminó, o de que todavía no conoce al coordinador.
+            Ok(Mensaje::Ack) => {}
+
+            otro => {
+                eprintln!("{} {:?}", MENSAJE_DESCONOCIDO, otro);
+            }
         }
     }
 
@@ -131,28 +100,20 @@ impl Repartidor {
             esta_aceptado,
         };
 
-        if let Ok(serializado) = serde_json::to_string(&respuesta) {
-            if let Err(err) = self
-                .escritor
-                .write_all(format!("{}\n", serializado).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_ENVIAR_RESPUESTA, err);
-            }
+        if let Err(err) = self
+            .sesion
+            .enviar(Mensaje::RespuestaOfertaViaje(respuesta))
+            .await
+        {
+            eprintln!("{} {}", ERROR_ENVIAR_RESPUESTA, err);
         }
     }
 
     // Envía la posición actual del repartidor al servidor coordinador.
     async fn enviar_posicion(&mut self) {
         let posicion = self.ubicacion.clone();
-        if let Ok(serializado) = serde_json::to_string(&posicion) {
-            if let Err(err) = self
-                .escritor
-                .write_all(format!("{}\n", serializado).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_ENVIAR_POSICION, err);
-            }
+        if let Err(err) = self.sesion.enviar(Mensaje::Posicion(posicion)).await {
+            eprintln!("{} {}", ERROR_ENVIAR_POSICION, err);
         }
     }
 
@@ -181,122 +142,9 @@ impl Repartidor {
             pos_destino: (destino.0, destino.1),
         };
 
-        if let Ok(viaje_terminado_serializado) = serde_json::to_string(&viaje_finalizado) {
-            if let Err(err) = self
-                .escritor
-                .write_all(format!("{}\n", viaje_terminado_serializado).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_AVISAR_FIN_VIAJE, err);
-            }
-
-            let ack_future = self.lector.next_line();
-            match tokio::time::timeout(Duration::from_secs(TIMEOUT_ACK_SEGS), ack_future).await {
-                Ok(Ok(Some(ack_mensaje))) => {
-                    if ack_mensaje.trim() == ACK_MENSAJE {
-                        println!("{}", SE_RECIBE_EL_ACK);
-                    } else {
-                        println!("{} {}", MENSAJE_INESPERADO_SERVIDOR, ack_mensaje);
-                    }
-                }
-
-                Ok(Ok(None)) | Ok(Err(_)) | Err(_) => {
-                    println!("{}", ERROR_TIMEOUT_ACK_SEGS);
-                    if !self.intentar_reconectar(viaje_terminado_serializado).await {
-                        eprintln!("{}", ERROR_AL_RECONECTAR);
-                    }
-                }
-            }
-        }
-    }
-
-    // Método que intenta reconectar con el servidor coordinador y enviar el mensaje de finalización del viaje.
-    async fn intentar_reconectar(&mut self, mensaje: String) -> bool {
-        let coordinador = conectar_con_coordinador(self.servidores.clone()).await;
-        if let Some(stream) = coordinador {
-            let (lector, escritor) = stream.into_split();
-            self.lector = BufReader::new(lector).lines();
-            self.escritor = escritor;
-            if let Err(e) = self
-                .escritor
-                .write_all(format!("{}\n", mensaje).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_AVISAR_FIN_VIAJE, e);
-            } else {
-                println!("{}", RECONEXION_EXITOSA);
-                return true;
-            }
-        }
-
-        false
-    }
-}
-
-// Función que establece la conexión con el servidor coordinador, con proceso de identificación previo.
-async fn conectar_con_coordinador(servidores: Vec<SocketAddr>) -> Option<TcpStream> {
-    let mut direccion_coordinador: Option<SocketAddr> = None;
-    println!("{}", BUSCANDO_COORDINADOR);
-    for servidor in servidores {
-        match TcpStream::connect(servidor).await {
-            Ok(stream) => {
-                let (lector, mut escritor) = split(stream);
-                let msj = MensajeTCP("WhoIsCoordinator\n".to_string());
-                if let Err(e) = escritor.write_all(msj.0.as_bytes()).await {
-                    eprintln!("{} {}", ERROR_ESCRIBIENDO_CONSULTA_LIDER, e);
-                }
-
-                let mut lector = BufReader::new(lector);
-                let mut linea = String::new();
-                match timeout(
-                    Duration::from_secs(TIMEOUT_COORDINADOR),
-                    lector.read_line(&mut linea),
-                )
-                .await
-                {
-                    Ok(Ok(_)) => {
-                        if let Ok(quien_es_coordinador_msj) =
-                            serde_json::from_str::<QuienEsCoordinador>(&linea)
-                        {
-                            direccion_coordinador =
-                                Some(quien_es_coordinador_msj.direccion_coordinador);
-                            break;
-                        } else if linea.trim() == ACK_DE_COORDINADOR {
-                            break;
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        println!("{} {:?}", ERROR_LEER_LINEA, e);
-                    }
-                    Err(_) => {
-                        println!("{}", TIMEOUT_LEER_LINEA);
-                    }
-                }
-                break;
-            }
-            Err(_) => {
-                println!("{} {}", REPARTIDOR_NO_CONECTA_COORDINADOR, servidor);
-            }
-        }
-
-        if direccion_coordinador.is_some() {
-            break;
-        }
-    }
-
-    if let Some(ip_coordinador) = direccion_coordinador {
-        println!("{} {}", REPARTIDOR_ENCONTRO_COORDINADOR, ip_coordinador);
-        let stream = match TcpStream::connect(ip_coordinador).await {
-            Ok(stream) => {
-                println!("{}", REPARTIDOR_CONECTADO_COORDINADOR);
-                Some(stream)
-            }
-
-            Err(_) => None,
-        };
-
-        return stream;
+        // El viaje terminado queda pendiente hasta que el servidor lo confirme.
+        self.sesion
+            .enviar_confiable(Mensaje::FinalizarViaje(viaje_finalizado))
+            .await;
     }
-
-    None
 }
@@ -5,48 +5,33 @@ use rand::Rng;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::time::{Duration, Instant};
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
-use tokio::time::{sleep, timeout};
+use tokio::time::sleep;
 
 // Imports de funciones/estructuras propias.
+use cliente::sesion::{Evento, Sesion};
 use common::mensajes::{
-    MensajeIdentidad, Posicion, QuienEsCoordinador, RecibirPedido, RespuestaOfertaViaje,
-    SolicitarRepartidor,
+    MensajeIdentidad, Posicion, RecibirPedido, RespuestaOfertaViaje, SolicitarRepartidor,
 };
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{Mensaje, Recibido};
 use common::utils::obtener_tupla_random;
 
 // Constantes.
 const PROBABILIDAD_ACEPTACION: f64 = 0.9;
-const TIMEOUT_COORDINADOR: u64 = 3;
-const ERROR_CONEXION_SERVIDORES: &str = "No se pudo conectar a ningún servidor de coordinación.";
-const SERVER_CERRO_CONEXION: &str = "RESTAURANTE - El servidor cerró la conexión.";
-const ERROR_LEYENDO_SERVIDOR: &str = "RESTAURANTE - Error leyendo del servidor: ";
+const VERSION_RECHAZADA: &str =
+    "RESTAURANTE - El servidor rechazó la versión del protocolo del mensaje, versión enviada:";
+const SESION_PERDIDA: &str = "RESTAURANTE - Se perdió la sesión con el servidor.";
 const MSJ_ESTADO_ACEPTACION_VIAJE: &str = "RESTAURANTE - ¿Se acepto el pedido?:";
 const CASO_POSITIVO: &str = "Sí";
 const CASO_NEGATIVO: &str = "No";
 const ERROR_ENVIAR_RESPUESTA: &str = "RESTAURANTE - Error al enviar respuesta:";
 const POSICION_ENVIADA: &str = "RESTAURANTE - Posición enviada:";
 const ERROR_ENVIAR_POSICION: &str = "RESTAURANTE - Error al enviar posición:";
-const ACK_DE_COORDINADOR: &str = "Ack";
-const BUSCANDO_COORDINADOR: &str = "RESTAURANTE - Buscando coordinador...";
-const ERROR_ESCRIBIENDO_CONSULTA_LIDER: &str =
-    "RESTAURANTE - Error escribiendo consulta para averiguar el líder:";
-const ERROR_LEER_LINEA: &str = "RESTAURANTE - Error al leer línea:";
-const TIMEOUT_LEER_LINEA: &str = "RESTAURANTE - Timeout al leer línea.";
-const RESTAURANTE_NO_CONECTA_COORDINADOR: &str =
-    "RESTAURANTE - No se pudo conectar al coordinador:";
-const RESTAURANTE_ENCONTRO_COORDINADOR: &str = "RESTAURANTE - Encontró al coordinador:";
-const RESTAURANTE_CONECTADO_COORDINADOR: &str = "RESTAURANTE - Conectado al coordinador.";
 const ERROR_SOLICITAR_VIAJE: &str = "RESTAURANTE - Error al solicitar viaje:";
 const PEDIDO_LISTO: &str = "RESTAURANTE - El pedido ya está listo para ser retirado por el repartidor. La direccion del comensal es: ";
 
 // Este actor implementa al 'Restaurante' que prepara los pedidos que hace el 'Comensal'.
 pub struct Restaurante {
-    lector: Lines<BufReader<OwnedReadHalf>>,
-    escritor: OwnedWriteHalf,
+    sesion: Sesion,
     ubicacion_fija: Posicion, // Posición fija del restaurante.,
     clientes_atendidos: Vec<SocketAddr>,
     ultima_cocina: HashMap<String, Instant>,
@@ -55,23 +40,14 @@ pub struct Restaurante {
 // Implementación de los métodos de construcción, inicialización y handlers del actor 'Restaurante'.
 impl Restaurante {
     // Constructor.
-    pub async fn new(servidores: Vec<SocketAddr>) -> Self {
-        // Separamos el stream en dos mitades: Lectura y escritura.
-        let tcp_stream: Option<TcpStream> = conectar_con_coordinador(servidores.clone()).await;
-        let posicion = Posicion {
-            posicion: obtener_tupla_random(),
-        };
-        if let Some(stream) = tcp_stream {
-            let (rx, wx) = stream.into_split();
-            Self {
-                lector: BufReader::new(rx).lines(),
-                escritor: wx,
-                ubicacion_fija: posicion,
-                clientes_atendidos: Vec::new(),
-                ultima_cocina: HashMap::new(),
-            }
-        } else {
-            panic!("{}", ERROR_CONEXION_SERVIDORES);
+    pub fn new(sesion: Sesion) -> Self {
+        Self {
+            sesion,
+            ubicacion_fija: Posicion {
+                posicion: obtener_tupla_random(),
+            },
+            clientes_atendidos: Vec::new(),
+            ultima_cocina: HashMap::new(),
         }
     }
 
@@ -79,31 +55,31 @@ impl Restaurante {
     pub async fn run(&mut self) {
         // Informa su posición inicial al servidor.
         self.enviar_posicion().await;
-        loop {
-            tokio::select! {
-                resultado = self.lector.next_line() => {
-                    match resultado {
-                        Ok(Some(mensaje)) => {
-                            self.handle_mensaje_servidor(mensaje).await;
-                        }
-                        Ok(None) => {
-                            println!("{}", SERVER_CERRO_CONEXION);
-                            break;
-                        }
-                        Err(e) => {
-                            eprintln!("{} {}", ERROR_LEYENDO_SERVIDOR, e);
-                            break;
-                        }
-                    }
-                }
+        while let Some(evento) = self.sesion.recibir().await {
+            match evento {
+                Evento::Mensaje(mensaje) => self.handle_mensaje_servidor(mensaje).await,
+                // El restaurante no envía mensajes confiables.
+                Evento::Confirmado(_) => {}
+                // El nuevo coordinador todavía no conoce al restaurante.
+                Evento::Reconectado => self.enviar_posicion().await,
             }
         }
+
+        eprintln!("{}", SESION_PERDIDA);
     }
 
     // Handler de los mensajes recibidos del servidor coordinador.
-    async fn handle_mensaje_servidor(&mut self, mensaje: String) {
-        if let Ok(pedido) = serde_json::from_str::<RecibirPedido>(&mensaje) {
-            self.handle_recibir_pedido(pedido).await;
+    async fn handle_mensaje_servidor(&mut self, mensaje: Recibido) {
+        match mensaje.map(|sobre| sobre.mensaje) {
+            Ok(Mensaje::RecibirPedido(pedido)) => {
+                self.handle_recibir_pedido(pedido).await;
+            }
+
+            Ok(Mensaje::VersionRechazada(version)) => {
+                eprintln!("{} {}", VERSION_RECHAZADA, version);
+            }
+
+            _ => {}
         }
     }
 
@@ -154,14 +130,12 @@ impl Restaurante {
                 esta_aceptado,
             };
 
-            if let Ok(serializado) = serde_json::to_string(&respuesta) {
-                if let Err(err) = self
-                    .escritor
-                    .write_all(format!("{}\n", serializado).as_bytes())
-                    .await
-                {
-                    eprintln!("{} {}", ERROR_ENVIAR_RESPUESTA, err);
-                }
+            if let Err(err) = self
+                .sesion
+                .enviar(Mensaje::RespuestaOfertaViaje(respuesta))
+                .await
+            {
+                eprintln!("{} {}", ERROR_ENVIAR_RESPUESTA, err);
             }
 
             // Si el pedido es aceptado, se solicita un repartidor.
@@ -173,14 +147,12 @@ impl Restaurante {
                 direccion_comensal: msj.direccion_comensal_o,
             };
 
-            if let Ok(serializado) = serde_json::to_string(&solicitar_viaje) {
-                if let Err(e) = self
-                    .escritor
-                    .write_all(format!("{}\n", serializado).as_bytes())
-                    .await
-                {
-                    eprintln!("{} {}", ERROR_SOLICITAR_VIAJE, e);
-                }
+            if let Err(e) = self
+                .sesion
+                .enviar(Mensaje::SolicitarRepartidor(solicitar_viaje))
+                .await
+            {
+                eprintln!("{} {}", ERROR_SOLICITAR_VIAJE, e);
             }
         }
     }
@@ -198,82 +170,12 @@ impl Restaurante {
             POSICION_ENVIADA, position.posicion.0, position.posicion.1
         );
 
-        if let Ok(serializado) = serde_json::to_string(&mensaje_identidad) {
-            if let Err(err) = self
-                .escritor
-                .write_all(format!("{}\n", serializado).as_bytes())
-                .await
-            {
-                eprintln!("{} {}", ERROR_ENVIAR_POSICION, err);
-            }
+        if let Err(err) = self
+            .sesion
+            .enviar(Mensaje::MensajeIdentidad(mensaje_identidad))
+            .await
+        {
+            eprintln!("{} {}", ERROR_ENVIAR_POSICION, err);
         }
     }
 }
-
-// Función que establece la conexión con el servidor coordinador, con proceso de identificación previo.
-async fn conectar_con_coordinador(servidores: Vec<SocketAddr>) -> Option<TcpStream> {
-    let mut direccion_coordinador: Option<SocketAddr> = None;
-    println!("{}", BUSCANDO_COORDINADOR);
-    for servidor in servidores {
-        match TcpStream::connect(servidor).await {
-            Ok(stream) => {
-                let (lector, mut escritor) = split(stream);
-                let msj = MensajeTCP("WhoIsCoordinator\n".to_string());
-                if let Err(e) = escritor.write_all(msj.0.as_bytes()).await {
-                    eprintln!("{} {}", ERROR_ESCRIBIENDO_CONSULTA_LIDER, e);
-                }
-
-                let mut lector = BufReader::new(lector);
-                let mut linea = String::new();
-                match timeout(
-                    Duration::from_secs(TIMEOUT_COORDINADOR),
-                    lector.read_line(&mut linea),
-                )
-                .await
-                {
-                    Ok(Ok(_)) => {
-                        if let Ok(quien_es_coordinador_msj) =
-                            serde_json::from_str::<QuienEsCoordinador>(&linea)
-                        {
-                            direccion_coordinador =
-                                Some(quien_es_coordinador_msj.direccion_coordinador);
-                            break;
-                        } else if linea.trim() == ACK_DE_COORDINADOR {
-                            break;
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        println!("{} {:?}", ERROR_LEER_LINEA, e);
-                    }
-                    Err(_) => {
-                        println!("{}", TIMEOUT_LEER_LINEA);
-                    }
-                }
-                break;
-            }
-            Err(_) => {
-                println!("{} {}", RESTAURANTE_NO_CONECTA_COORDINADOR, servidor);
-            }
-        }
-
-        if direccion_coordinador.is_some() {
-            break;
-        }
-    }
-
-    if let Some(ip_coordinador) = direccion_coordinador {
-        println!("{} {}", RESTAURANTE_ENCONTRO_COORDINADOR, ip_coordinador);
-        let stream = match TcpStream::connect(ip_coordinador).await {
-            Ok(stream) => {
-                println!("{}", RESTAURANTE_CONECTADO_COORDINADOR);
-                Some(stream)
-            }
-
-            Err(_) => None,
-        };
-
-        return stream;
-    }
-
-    None
-}
@@ -9,13 +9,13 @@ use std::time::Instant;
 // Imports de funciones/estructuras propias.
 use crate::server::client_server::EstadoRepartidor;
 use crate::utils::entidades::{EntidadComensal, EntidadRepartidor, EntidadRestaurante};
-use common::tcp_enviador::TcpEnviador;
+use common::enviador::Enviador;
 
 // Implementación de la estructura `RepartidorAusente` que representa un repartidor muerto.
 pub struct RepartidorAusente {
     pub id_repartidor: SocketAddr,
     pub id_comensal: Option<SocketAddr>,
-    pub enviador_comensal: Option<Arc<Addr<TcpEnviador>>>,
+    pub enviador_comensal: Option<Arc<Addr<Enviador>>>,
 }
 
 // Mensaje para obtener un 'Comensal' del almacenamiento.
@@ -54,7 +54,7 @@ pub struct InsertarRepartidor {
     pub id: SocketAddr,
     pub posicion_restaurante: (f32, f32),
     pub id_comensal_actual: Option<SocketAddr>,
-    pub enviador_repartidor: Option<Arc<Addr<TcpEnviador>>>,
+    pub enviador_repartidor: Option<Arc<Addr<Enviador>>>,
     pub estado: EstadoRepartidor,
     pub time_stamp: Instant,
 }
@@ -66,7 +66,7 @@ pub struct InsertarComensal {
     pub id: SocketAddr,
     pub posicion_restaurante_del_pedido: (f32, f32),
     pub ubicacion_comensal: (f32, f32),
-    pub enviador_comensal: Option<Arc<Addr<TcpEnviador>>>,
+    pub enviador_comensal: Option<Arc<Addr<Enviador>>>,
 }
 
 // Mensaje para insertar un 'Repartidor' en el almacenamiento.
@@ -75,7 +75,7 @@ pub struct InsertarComensal {
 pub struct InsertarRestaurante {
     pub id: SocketAddr,
     pub posicion_repartidor: (f32, f32),
-    pub enviador_repartidor: Option<Arc<Addr<TcpEnviador>>>,
+    pub enviador_repartidor: Option<Arc<Addr<Enviador>>>,
     pub estado: EstadoRepartidor,
     pub time_stamp: Instant,
 }
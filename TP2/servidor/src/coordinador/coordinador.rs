@@ -7,8 +7,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
-use tokio::io::split;
-use tokio::net::TcpStream;
 
 // Imports de funciones/estructuras propias.
 use crate::coordinador::mensajes_coordinador::*;
@@ -16,14 +14,17 @@ use crate::coordinador::mensajes_coordinador::{ObtenerContadorPeer, ObtenerPeerD
 use crate::utils::constantes::MAX_REINTENTOS;
 use crate::utils::constantes::TIEMPO_MAXIMO_SIN_PING;
 use crate::utils::errores_servidor::ServidorError;
-use common::tcp_enviador::{MensajeTCP, TcpEnviador};
+use common::enviador::{Enviador, EnviarConfiable, EnvioFallido};
+use common::protocolo::{empaquetar, Mensaje, Sobre};
+use common::transporte::Transporte;
 
 // Constantes.
 const SLEEP_ENTRE_INTENTOS: u64 = 500;
 const ERROR_CONECTAR_SOCKETS: &str = "Error al conectar los Sockets:";
-const ERROR_SERIALIZAR: &str = "Error al serializar el mensaje del pedido:";
 const ERROR_ACTUALIZAR_PEER: &str = "Error para actualizar el 'Peer':";
 const ERROR_AL_CONECTAR: &str = "Error al conectar al";
+const ERROR_CONEXION_PEER: &str = "Se perdió la conexión con el 'Peer'";
+const ERROR_SIN_CONFIRMACION: &str = "El 'Peer' no confirmó el mensaje";
 
 // Estructura que encapsula al 'Actor' que representa al 'Servidor' coordinador del sistema distribuido.
 pub struct Coordinador {
@@ -31,6 +32,7 @@ pub struct Coordinador {
     pub sockets: Vec<SocketAddr>,
     pub handlers_sockets: Peers,
     pub contador_sockets: u8,
+    pub transporte: Arc<dyn Transporte>,
 }
 
 // Implementa el trait 'Actor' para el actor 'Coordinador'.
@@ -40,12 +42,17 @@ impl Actor for Coordinador {
 
 // Constructor del 'Actor'.
 impl Coordinador {
-    pub fn new(direccion: SocketAddr, sockets: Vec<SocketAddr>) -> Addr<Self> {
+    pub fn new(
+        direccion: SocketAddr,
+        sockets: Vec<SocketAddr>,
+        transporte: Arc<dyn Transporte>,
+    ) -> Addr<Self> {
         Coordinador::create(|_ctx| Coordinador {
             direccion,
             sockets,
             handlers_sockets: Arc::new(HashMap::new()),
             contador_sockets: 0,
+            transporte,
         })
     }
 }
@@ -56,10 +63,11 @@ impl Handler<ConvertirseEnCoordinador> for Coordinador {
     fn handle(&mut self, _msg: ConvertirseEnCoordinador, _ctx: &mut Self::Context) -> Self::Result {
         let direccion = self.direccion;
         let sockets = self.sockets.clone();
+        let transporte = self.transporte.clone();
         let actor_addr = _ctx.address();
         Box::pin(
             async move {
-                if let Err(e) = conectar_a_peers(direccion, sockets, actor_addr).await {
+                if let Err(e) = conectar_a_peers(direccion, sockets, transporte, actor_addr).await {
                     eprintln!("{} {:?}", ERROR_CONECTAR_SOCKETS, e);
                 }
             }
@@ -73,6 +81,7 @@ impl Handler<ConvertirseEnCoordinador> for Coordinador {
 impl Handler<HandlePedido> for Coordinador {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, msg: HandlePedido, _ctx: &mut Self::Context) -> Self::Result {
+        let direccion = self.direccion;
         let sockets = self.sockets.clone();
         let actor_addr = _ctx.address();
         Box::pin(
@@ -99,16 +108,11 @@ impl Handler<HandlePedido> for Coordinador {
                 }
 
                 if let Some(h) = handle {
-                    match serde_json::to_string(&msg) {
-                        Ok(handle_funcional_msj) => {
-                            h.0.try_send(MensajeTCP(handle_funcional_msj))
-                                .expect("Error al manejar el pedido.");
-                        }
-
-                        Err(err) => {
-                            eprintln!("{} {}", ERROR_SERIALIZAR, err);
-                        }
-                    }
+                    h.0.try_send(EnviarConfiable(empaquetar(
+                        direccion,
+                        Mensaje::HandlePedido(msg),
+                    )))
+                    .expect("Error al manejar el pedido.");
                 }
             }
             .into_actor(self)
@@ -121,7 +125,7 @@ impl Handler<HandlePedido> for Coordinador {
 impl Handler<ActualizarComensales> for Coordinador {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, msg: ActualizarComensales, _ctx: &mut Self::Context) -> Self::Result {
-        let msg = serde_json::to_string(&msg).expect("Error al convertir a JSON.");
+        let msg = empaquetar(self.direccion, Mensaje::ActualizarComensales(msg));
         let actor_addr = _ctx.address();
         Box::pin(
             async move {
@@ -137,7 +141,7 @@ impl Handler<ActualizarComensales> for Coordinador {
 impl Handler<ActualizarRepartidores> for Coordinador {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, msg: ActualizarRepartidores, _ctx: &mut Self::Context) -> Self::Result {
-        let msg = serde_json::to_string(&msg).expect("Error al convertir a JSON.");
+        let msg = empaquetar(self.direccion, Mensaje::ActualizarRepartidores(msg));
         let actor_addr = _ctx.address();
         Box::pin(
             async move {
@@ -165,6 +169,7 @@ impl Handler<ConectarNuevoServidor> for Coordinador {
     fn handle(&mut self, msg: ConectarNuevoServidor, _ctx: &mut Self::Context) -> Self::Result {
         let direccion = self.direccion;
         let peer = msg.nuevo_servidor;
+        let transporte = self.transporte.clone();
         let actor_addr = _ctx.address();
         Box::pin(
             async move {
@@ -180,7 +185,7 @@ impl Handler<ConectarNuevoServidor> for Coordinador {
                     return;
                 }
 
-                match conectar_a_peer(direccion, peer, actor_addr).await {
+                match conectar_a_peer(direccion, peer, transporte.as_ref(), actor_addr).await {
                     Ok(_) => (),
                     Err(e) => println!("[{}] {} {:?}: {:?}", direccion, ERROR_AL_CONECTAR, peer, e),
                 }
@@ -205,6 +210,34 @@ impl Handler<AgregarServidorADiccionario> for Coordinador {
     }
 }
 
+// Handler de los envíos a los 'Peers' que fallaron. Si se perdió la conexión con un 'Peer' se lo quita del
+// diccionario y se intenta reconectar. Los pedidos que un 'Peer' no confirmó se vuelven a repartir entre los 'Peers'.
+impl Handler<EnvioFallido> for Coordinador {
+    type Result = ();
+    fn handle(&mut self, msg: EnvioFallido, ctx: &mut Self::Context) -> Self::Result {
+        match msg {
+            EnvioFallido::ConexionPerdida { destino, error } => {
+                println!(
+                    "[{}] {} {:?}: {}",
+                    self.direccion, ERROR_CONEXION_PEER, destino, error
+                );
+                Arc::make_mut(&mut self.handlers_sockets).remove(&destino);
+                ctx.notify(ConectarNuevoServidor {
+                    nuevo_servidor: destino,
+                });
+            }
+
+            EnvioFallido::SinConfirmacion { destino, sobre } => match sobre.mensaje {
+                Mensaje::HandlePedido(pedido) => ctx.notify(pedido),
+                otro => println!(
+                    "[{}] {} {:?}: {:?}",
+                    self.direccion, ERROR_SIN_CONFIRMACION, destino, otro
+                ),
+            },
+        }
+    }
+}
+
 // Handler para obtener el 'Peer Dict'.
 impl Handler<ObtenerPeerDict> for Coordinador {
     type Result = Peers;
@@ -213,11 +246,12 @@ impl Handler<ObtenerPeerDict> for Coordinador {
     }
 }
 
-// Método para hacer broadcast en todas las instancias de servidores con la información actual.
-pub async fn actualizacion_broadcast(coord_actor: Addr<Coordinador>, msg: String) {
+// Método para hacer broadcast en todas las instancias de servidores con la información actual. Cada actualización se
+// envía de forma confiable, por lo que se retransmite a las instancias que no la confirmen.
+pub async fn actualizacion_broadcast(coord_actor: Addr<Coordinador>, msg: Sobre) {
     let handles = coord_actor.send(ObtenerPeerDict).await.unwrap_or_default();
     for handle in handles.values() {
-        let res = handle.0.try_send(MensajeTCP(msg.clone()));
+        let res = handle.0.try_send(EnviarConfiable(msg.clone()));
         if res.is_err() {
             continue;
         }
@@ -228,11 +262,12 @@ pub async fn actualizacion_broadcast(coord_actor: Addr<Coordinador>, msg: String
 async fn conectar_a_peers(
     direccion: SocketAddr,
     sockets: Vec<SocketAddr>,
+    transporte: Arc<dyn Transporte>,
     coord_actor: Addr<Coordinador>,
 ) -> Result<(), ServidorError> {
     println!("[{}] Conectandose a los sockets {:?}", direccion, sockets);
     for &peer in sockets.iter() {
-        conectar_a_peer(direccion, peer, coord_actor.clone()).await?;
+        conectar_a_peer(direccion, peer, transporte.as_ref(), coord_actor.clone()).await?;
     }
 
     Ok(())
@@ -242,6 +277,7 @@ async fn conectar_a_peers(
 async fn conectar_a_peer(
     direccion: SocketAddr,
     peer: SocketAddr,
+    transporte: &dyn Transporte,
     coord_actor: Addr<Coordinador>,
 ) -> Result<(), ServidorError> {
     if direccion == peer {
@@ -249,11 +285,11 @@ async fn conectar_a_peer(
     }
 
     let mut intentos = 0;
-    let mut stream = None;
+    let mut conexion = None;
     while intentos < MAX_REINTENTOS {
-        match TcpStream::connect(peer).await {
-            Ok(s) => {
-                stream = Some(s);
+        match transporte.conectar(peer).await {
+            Ok(c) => {
+                conexion = Some(c);
                 break;
             }
 
@@ -264,11 +300,11 @@ async fn conectar_a_peer(
         }
     }
 
-    if let Some(stream) = stream {
+    if let Some(conexion) = conexion {
         println!("[{}] Conectado a {:?}", direccion, peer);
-        let (_, w_half) = split(stream);
-        let escribir = Some(w_half);
-        let sender_actor = TcpEnviador { escribir }.start();
+        // El 'Enviador' lee las confirmaciones de las actualizaciones que le envía al 'Peer'.
+        let sender_actor =
+            Enviador::iniciar_con_confirmaciones(conexion, coord_actor.clone().recipient());
         coord_actor
             .try_send(AgregarServidorADiccionario {
                 serv_address: peer,
@@ -286,7 +322,7 @@ async fn conectar_a_peer(
 impl Handler<ActualizarRestaurantes> for Coordinador {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, msg: ActualizarRestaurantes, _ctx: &mut Self::Context) -> Self::Result {
-        let msg = serde_json::to_string(&msg).expect("Error al convertir a JSON.");
+        let msg = empaquetar(self.direccion, Mensaje::ActualizarRestaurantes(msg));
         let actor_addr = _ctx.address();
         Box::pin(
             async move {
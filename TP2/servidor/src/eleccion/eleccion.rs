@@ -4,9 +4,6 @@
 use actix::prelude::*;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::io::{split, BufReader};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio::time::{timeout, Duration};
 
 // Imports de funciones/estructuras propias.
@@ -17,8 +14,8 @@ use crate::eleccion::mensajes_eleccion::{
     ObtenerDireccionCoordinador, ObtenerDireccionLider, PingCoordinador, SoyCoordinador,
 };
 use crate::utils::logs::log_eleccion;
-use common::mensajes::QuienEsCoordinador;
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{empaquetar, Mensaje};
+use common::transporte::Transporte;
 
 // Constantes.
 const MIN_PEER_PORT: u16 = 8080;
@@ -40,17 +37,17 @@ const ERROR_BROADCAST: &str = "Error al enviar mensaje de broadcast:";
 const PINGING_COORDINADOR: &str = "Haciendo ping al coordinador.";
 const ERROR_ESCRIBIENDO_MENSAJE: &str = "Error escribiendo mensaje de elección.";
 const VOLVIENDOSE_COORDINADOR: &str = "Volviéndose coordinador.";
-const ACK: &str = "Ack";
 
 // Actor responsable de la elección del coordinador en un sistema distribuido.
 // Este actor implementa el algoritmo de elección de anillo (Ring Election).
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct EleccionCoordinador {
     pub id: SocketAddr,
     pub id_coordinador: Option<SocketAddr>,
     pub coordinador: Arc<Addr<Coordinador>>,
     pub peers: Arc<Vec<SocketAddr>>,
     pub en_eleccion: bool,
+    pub transporte: Arc<dyn Transporte>,
 }
 
 // Implementa el trait `Actor` para el actor `EleccionCoordinador`.
@@ -67,6 +64,7 @@ impl EleccionCoordinador {
         id: SocketAddr,
         coordinador: Arc<Addr<Coordinador>>,
         peers: Arc<Vec<SocketAddr>>,
+        transporte: Arc<dyn Transporte>,
     ) -> Addr<Self> {
         EleccionCoordinador::create(|_ctx| EleccionCoordinador {
             id,
@@ -74,6 +72,7 @@ impl EleccionCoordinador {
             coordinador,
             peers,
             en_eleccion: false,
+            transporte,
         })
     }
 }
@@ -253,10 +252,7 @@ impl EleccionCoordinador {
         let msg = MensajeEleccion {
             candidatos: candidates.clone(),
         };
-        let msg = MensajeTCP(format!(
-            "{}\n",
-            serde_json::to_string(&msg).expect("Error convirtiendo a JSON.")
-        ));
+        let msg = empaquetar(self.id, Mensaje::MensajeEleccion(msg));
 
         let mut proximo_puerto_socket = self.id.port();
         let mut tiene_ack = false;
@@ -273,23 +269,20 @@ impl EleccionCoordinador {
                 TRATANDO_CONECTAR_PROX_SOCK, siguiente_socket
             ));
             for _ in MIN_INTENTOS..MAX_INTENTOS {
-                if let Ok(stream) = TcpStream::connect(siguiente_socket).await {
-                    let (lector, mut escritor) = split(stream);
+                if let Ok(mut conexion) = self.transporte.conectar(siguiente_socket).await {
                     log_eleccion(format!("{} {:?}", CONECTADO_AL_PROX, siguiente_socket));
-                    if escritor.write_all(msg.0.as_bytes()).await.is_err() {
+                    if conexion.escritor.enviar(&msg).await.is_err() {
                         eprintln!("Error escribiendo mensaje de elección.");
                         continue;
                     }
 
-                    let mut lector = BufReader::new(lector);
-                    let mut line = String::new();
                     match timeout(
                         Duration::from_secs(TIMEOUT_SEGUNDOS),
-                        lector.read_line(&mut line),
+                        conexion.lector.recibir(),
                     )
                     .await
                     {
-                        Ok(Ok(_)) => {
+                        Ok(Ok(line)) => {
                             log_eleccion(format!("{} {:?}", RECIBIDO, line));
                             tiene_ack = true;
                         }
@@ -366,27 +359,16 @@ impl EleccionCoordinador {
 
     // Envía un mensaje de coordinador a todos los peers.
     pub async fn broadcast_coordinador(&self, new_coord: MensajeCoordinador) {
-        let msg = format!(
-            "{}\n",
-            serde_json::to_string(&new_coord).expect("Error al convertir a JSON.")
-        );
-
-        let msg = MensajeTCP(msg);
+        let msg = empaquetar(self.id, Mensaje::MensajeCoordinador(new_coord.clone()));
         for &peer in self.peers.iter() {
             if peer == self.id {
                 continue;
             }
 
             for _ in MIN_INTENTOS..MAX_INTENTOS {
-                match TcpStream::connect(peer).await {
-                    Ok(s) => {
-                        let stream = Some(s);
-                        let (_, mut escritor) = match stream {
-                            Some(stream) => split(stream),
-                            None => panic!("Stream es NULL"),
-                        };
-
-                        if let Err(e) = escritor.write_all(msg.0.as_bytes()).await {
+                match self.transporte.conectar(peer).await {
+                    Ok(mut conexion) => {
+                        if let Err(e) = conexion.escritor.enviar(&msg).await {
                             eprintln!("{}: {}", ERROR_BROADCAST, e);
                         }
 
@@ -426,33 +408,24 @@ impl EleccionCoordinador {
 
         let mut tiene_ack = false;
         for _ in MIN_INTENTOS..MAX_INTENTOS {
-            match TcpStream::connect(coord).await {
-                Ok(s) => {
-                    let stream = Some(s);
-                    let (lector, mut escritor) = match stream {
-                        Some(stream) => split(stream),
-                        None => panic!("Stream es NULL"),
-                    };
-
+            match self.transporte.conectar(coord).await {
+                Ok(mut conexion) => {
                     // ENVIANDO PING Y ESPERANDO AL ACK.
                     let ping_msg = MensajePing {
                         id_enviador: self.id,
                     };
-                    let msg =
-                        MensajeTCP(format!("{}\n", serde_json::to_string(&ping_msg).unwrap()));
-                    if let Err(e) = escritor.write_all(msg.0.as_bytes()).await {
+                    let msg = empaquetar(self.id, Mensaje::MensajePing(ping_msg));
+                    if let Err(e) = conexion.escritor.enviar(&msg).await {
                         eprintln!("{} {}", ERROR_ESCRIBIENDO_MENSAJE, e);
                     }
 
-                    let mut lector = BufReader::new(lector);
-                    let mut line = String::new();
                     match timeout(
                         Duration::from_secs(TIMEOUT_SEGUNDOS),
-                        lector.read_line(&mut line),
+                        conexion.lector.recibir(),
                     )
                     .await
                     {
-                        Ok(Ok(_)) => {
+                        Ok(Ok(line)) => {
                             log_eleccion(format!("[{:?}] Recibido: {:?}", self.id, line));
                             tiene_ack = true;
                         }
@@ -517,38 +490,35 @@ impl EleccionCoordinador {
         let mut got_res = false;
         for &peer in peers.iter().filter(|&&peer| peer != id) {
             for _ in MIN_INTENTOS..MAX_INTENTOS {
-                if let Ok(stream) = TcpStream::connect(peer).await {
-                    let (lector, mut escritor) = split(stream);
-                    let msg = MensajeTCP("WhoIsCoordinator\n".to_string());
-                    if escritor.write_all(msg.0.as_bytes()).await.is_err() {
+                if let Ok(mut conexion) = self.transporte.conectar(peer).await {
+                    let msg = empaquetar(id, Mensaje::ConsultarCoordinador);
+                    if conexion.escritor.enviar(&msg).await.is_err() {
                         eprintln!("Error leyendo mensaje de elección.");
                         continue;
                     }
 
-                    let mut lector = BufReader::new(lector);
-                    let mut line = String::new();
                     match timeout(
                         Duration::from_secs(TIMEOUT_SEGUNDOS),
-                        lector.read_line(&mut line),
+                        conexion.lector.recibir(),
                     )
                     .await
                     {
-                        Ok(Ok(_)) => {
-                            if let Ok(who_is_coord_msg) =
-                                serde_json::from_str::<QuienEsCoordinador>(&line)
-                            {
-                                self.recibir_mensaje_coordinador(
-                                    MensajeCoordinador {
-                                        coordinador: who_is_coord_msg.direccion_coordinador,
-                                    },
-                                    addr.clone(),
-                                )
-                                .await;
-
-                                got_res = true;
-                                break;
-                            } else if line.trim() == ACK {
-                                break;
+                        Ok(Ok(recibido)) => {
+                            match recibido.and_then(Result::ok).map(|sobre| sobre.mensaje) {
+                                Some(Mensaje::QuienEsCoordinador(who_is_coord_msg)) => {
+                                    self.recibir_mensaje_coordinador(
+                                        MensajeCoordinador {
+                                            coordinador: who_is_coord_msg.direccion_coordinador,
+                                        },
+                                        addr.clone(),
+                                    )
+                                    .await;
+
+                                    got_res = true;
+                                    break;
+                                }
+                                Some(Mensaje::Ack) => break,
+                                _ => {}
                             }
                         }
 
@@ -2,9 +2,11 @@
 
 // Imports de crates externas.
 use actix::Message;
-use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 
+// Los mensajes de la elección que viajan por la red se definen en 'common', junto al resto de los mensajes del protocolo.
+pub use common::mensajes_servidor::{MensajeCoordinador, MensajeEleccion, MensajePing};
+
 // Mensaje interno para modelar un ping al coordinador.
 #[derive(Debug, Clone, Message)]
 #[rtype(result = "()")]
@@ -25,27 +27,6 @@ pub struct ObtenerDireccionLider;
 #[rtype(result = "()")]
 pub struct IniciarEleccion;
 
-// Mensaje que puede ser enviado y recibido para indicar que una elección está ocurriendo.
-#[derive(Debug, Serialize, Deserialize, Clone, Message)]
-#[rtype(result = "()")]
-pub struct MensajeEleccion {
-    pub candidatos: Vec<SocketAddr>,
-}
-
-// Este mensaje es recibido por el coordinador y responde con un ACK.
-#[derive(Debug, Serialize, Deserialize, Message)]
-#[rtype(result = "()")]
-pub struct MensajePing {
-    pub id_enviador: SocketAddr,
-}
-
-// Mensaje que le notifica a todos los servidores quien es el nuevo coordinador.
-#[derive(Debug, Serialize, Deserialize, Clone, Message)]
-#[rtype(result = "()")]
-pub struct MensajeCoordinador {
-    pub coordinador: SocketAddr,
-}
-
 // Mensaje interno para asignar un nuevo coordinador.
 #[derive(Message)]
 #[rtype(result = "()")]
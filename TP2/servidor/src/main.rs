@@ -1,6 +1,7 @@
 //! Este módulo contiene la lógica de ejecución inicial del 'Servidor'.
 
 // Imports de crates externas.
+use common::transporte::transporte_desde_entorno;
 use common::utils::socket_addr_desde_string;
 use server::server::Server;
 use std::net::SocketAddr;
@@ -31,6 +32,6 @@ async fn main() -> Result<(), ServidorError> {
         .map(|puerto| format!("{}{}", DIRECCION_IP_BASE, puerto))
         .map(socket_addr_desde_string)
         .collect();
-    Server::start(direccion, sockets).await?;
+    Server::start(direccion, sockets, transporte_desde_entorno()).await?;
     Ok(())
 }
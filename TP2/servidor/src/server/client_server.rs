@@ -17,21 +17,16 @@ use crate::eleccion::mensajes_eleccion::SoyCoordinador;
 use crate::server::server::Server;
 use crate::utils::acciones_gateway::{generar_mensaje_chequeo_pago, obtener_respuesta_pago};
 use crate::utils::logs::log_funcionamiento;
+use common::enviador::EnviarSobre;
 use common::mensajes::{
-    Autorizacion, MensajeIdentidad, PedidoAlRestaurante, Posicion, RechazarViaje, SolicitarPedido,
-    SolicitarRepartidor,
+    Autorizacion, IdentificarComensal, MensajeIdentidad, PedidoAlRestaurante, Posicion,
+    RechazarViaje, SolicitarPedido, SolicitarRepartidor, RECHAZADO_POR_RESTAURANTE,
 };
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{empaquetar, Mensaje};
 use common::utils::obtener_tupla_random;
 
-// Mensaje que encapsula el estado del repartidor.
-#[derive(Debug, Clone, Serialize, Deserialize, Message, PartialEq)]
-#[rtype(result = "()")]
-pub enum EstadoRepartidor {
-    Active,
-    Waiting,
-    OnTrip,
-}
+// El estado del repartidor viaja en las actualizaciones entre servidores, por lo que se define en 'common'.
+pub use common::mensajes_servidor::EstadoRepartidor;
 
 // Mensaje que indica que un viaje finalizo.
 #[derive(Message, Serialize, Deserialize, Debug, Clone)]
@@ -41,12 +36,19 @@ pub struct ViajeFinalizado {
     pub id_repartidor_tf: SocketAddr,
 }
 
+// Función que devuelve el comensal dueño del pedido por el que se solicita un repartidor. La solicitud la envía el
+// 'Restaurante', por lo que la dirección de la conexión es la suya: el pedido se identifica por la dirección del
+// comensal que lo hizo, que es con la que se guardó en el almacenamiento y con la que se le avisa del viaje.
+fn comensal_del_pedido(solicitud: &SolicitarRepartidor) -> SocketAddr {
+    solicitud.direccion_comensal
+}
+
 // Handler para manejar la solicitud de un repartidor.
 impl Handler<SolicitarRepartidor> for Server {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, msg: SolicitarRepartidor, _ctx: &mut Self::Context) -> Self::Result {
         let actor_almacenamiento = self.almacenamiento_addr.clone();
-        let client_addr = self.client_addr;
+        let direccion = self.addr;
         let cord_eleccion_clone = self.eleccion_coordinador.clone();
         let cord_clone = self.coordinador.clone();
         Box::pin(
@@ -58,19 +60,12 @@ impl Handler<SolicitarRepartidor> for Server {
                         })
                         .await
                     {
-                        let mensaje_tcp = match serde_json::to_string(&RechazarViaje {
-                            respuesta: "Viaje rechazado por restaurante".to_string().to_owned(),
-                        }) {
-                            Ok(json_string) => MensajeTCP(json_string),
-                            Err(err) => {
-                                eprintln!(
-                                    "{} {}",
-                                    crate::utils::acciones_gateway::ERROR_AL_SERIALIZAR,
-                                    err
-                                );
-                                MensajeTCP(crate::utils::acciones_gateway::ERROR_STR.to_string())
-                            }
-                        };
+                        let mensaje_tcp = EnviarSobre(empaquetar(
+                            direccion,
+                            Mensaje::RechazarViaje(RechazarViaje {
+                                respuesta: RECHAZADO_POR_RESTAURANTE.to_string(),
+                            }),
+                        ));
 
                         if let Some(sender) = pedido_rechazado.enviador_comensal.as_ref() {
                             sender
@@ -91,7 +86,8 @@ impl Handler<SolicitarRepartidor> for Server {
                     {
                         if let Err(err) = cord_clone
                             .send(HandlePedido {
-                                id_comensal_ht: client_addr,
+                                id_comensal_ht: comensal_del_pedido(&msg),
+                                intentos: 0,
                             })
                             .await
                         {
@@ -112,7 +108,7 @@ impl Handler<Posicion> for Server {
         let cord_election_clone = self.eleccion_coordinador.clone();
         let cord_clone = self.coordinador.clone();
 
-        let tcp_enviador = self.tcp_enviador.clone();
+        let enviador = self.enviador.clone();
         let client_addr = self.client_addr;
         let actor_almacenamiento = self.almacenamiento_addr.clone();
 
@@ -128,7 +124,7 @@ impl Handler<Posicion> for Server {
                             id: client_addr,
                             id_comensal_actual: None,
                             posicion_restaurante: msg.posicion,
-                            enviador_repartidor: Some(tcp_enviador),
+                            enviador_repartidor: Some(enviador),
                             estado: EstadoRepartidor::Active,
                             time_stamp: std::time::Instant::now(),
                         })
@@ -160,9 +156,10 @@ impl Handler<SolicitarPedido> for Server {
     fn handle(&mut self, msg: SolicitarPedido, ctx: &mut Self::Context) -> Self::Result {
         log_funcionamiento("| ----- Solicitud de Pedido DESDE COMENSAL ----- | ".to_string());
 
-        let tcp_enviador = self.tcp_enviador.clone();
+        let enviador = self.enviador.clone();
         let client_addr = self.client_addr;
         let almacenamiento_actor = self.almacenamiento_addr.clone();
+        let transporte = self.transporte.clone();
 
         let cord_eleccion_clone = self.eleccion_coordinador.clone();
         let cord_clone = self.coordinador.clone();
@@ -180,7 +177,7 @@ impl Handler<SolicitarPedido> for Server {
                             id: client_addr,
                             posicion_restaurante_del_pedido: obtener_tupla_random(),
                             ubicacion_comensal: msg.destino,
-                            enviador_comensal: Some(tcp_enviador),
+                            enviador_comensal: Some(enviador),
                         })
                         .await
                         .unwrap();
@@ -212,8 +209,11 @@ impl Handler<SolicitarPedido> for Server {
                             .expect("Error al enviar falta de instancias.");
                     } else {
                         let passenger_id = format!("{:?}", client_addr.clone());
-                        let auth =
-                            obtener_respuesta_pago(generar_mensaje_chequeo_pago(passenger_id)).await;
+                        let auth = obtener_respuesta_pago(
+                            generar_mensaje_chequeo_pago(passenger_id),
+                            transporte.as_ref(),
+                        )
+                        .await;
                         log_funcionamiento(format!("El pago fue autorizado: {:?}", auth));
                         direccion
                             .try_send(Autorizacion {
@@ -249,7 +249,7 @@ impl Handler<MensajeIdentidad> for Server {
         let cord_election_clone = self.eleccion_coordinador.clone();
         let cord_clone = self.coordinador.clone();
 
-        let tcp_enviador = self.tcp_enviador.clone();
+        let enviador = self.enviador.clone();
         let client_addr = self.client_addr;
         let actor_almacenamiento = self.almacenamiento_addr.clone();
 
@@ -263,7 +263,7 @@ impl Handler<MensajeIdentidad> for Server {
                     actor_almacenamiento
                         .send(InsertarRestaurante {
                             id: client_addr,
-                            enviador_repartidor: Some(tcp_enviador),
+                            enviador_repartidor: Some(enviador),
                             posicion_repartidor: msg.ubicacion,
                             estado: EstadoRepartidor::Active,
                             time_stamp: std::time::Instant::now(),
@@ -289,3 +289,71 @@ impl Handler<MensajeIdentidad> for Server {
         )
     }
 }
+
+// Handler para manejar el mensaje con el que un comensal se vuelve a identificar luego de reconectarse. El pedido
+// sigue registrado con la dirección anterior del comensal, y sólo se actualiza la conexión por la que se le
+// envían sus novedades.
+impl Handler<IdentificarComensal> for Server {
+    type Result = ResponseActFuture<Self, ()>;
+    fn handle(&mut self, msg: IdentificarComensal, _ctx: &mut Self::Context) -> Self::Result {
+        log_funcionamiento("| ----- Comensal Reconectado ----- |".to_string());
+
+        let enviador = self.enviador.clone();
+        let actor_almacenamiento = self.almacenamiento_addr.clone();
+
+        Box::pin(
+            async move {
+                for direccion_anterior in msg.direcciones_anteriores {
+                    if let Ok(Some(comensal)) = actor_almacenamiento
+                        .send(ObtenerComensal {
+                            id: direccion_anterior,
+                        })
+                        .await
+                    {
+                        actor_almacenamiento
+                            .send(InsertarComensal {
+                                id: direccion_anterior,
+                                posicion_restaurante_del_pedido: comensal.posicion_comensal,
+                                ubicacion_comensal: comensal.destino_comensal,
+                                enviador_comensal: Some(enviador),
+                            })
+                            .await
+                            .unwrap();
+
+                        log_funcionamiento(format!(
+                            "Comensal {:?} identificado en su nueva conexión",
+                            direccion_anterior
+                        ));
+                        return;
+                    }
+                }
+
+                log_funcionamiento(
+                    "No se encontró el pedido del comensal reconectado.".to_string(),
+                );
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn el_pedido_es_del_comensal_y_no_del_restaurante_que_lo_solicita() {
+        let restaurante = SocketAddr::from(([127, 0, 0, 1], 9000));
+        let comensal = SocketAddr::from(([127, 0, 0, 1], 9100));
+        let solicitud = SolicitarRepartidor {
+            comida: "Pizza".to_string(),
+            origen: (1.0, 2.0),
+            destino: (3.0, 4.0),
+            pedido_aceptado: true,
+            direccion_comensal: comensal,
+        };
+
+        assert_eq!(comensal_del_pedido(&solicitud), comensal);
+        assert_ne!(comensal_del_pedido(&solicitud), restaurante);
+    }
+}
@@ -4,29 +4,30 @@
 use actix::prelude::*;
 use actix::Message;
 use std::net::SocketAddr;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
-use tokio::time::{timeout, Duration};
+use std::sync::Arc;
 
 // Imports de funciones/estructuras propias.
+use crate::almacenamiento::almacenamiento::Almacenamiento;
 use crate::almacenamiento::mensajes_almacenamiento::ObtenerRepartidorCercano;
 use crate::coordinador::mensajes_coordinador::HandlePedido;
 use crate::eleccion::mensajes_eleccion::ObtenerDireccionLider;
 use crate::server::server::Server;
 use crate::server::server_coordinador::HacerPedido;
-use crate::utils::constantes::MAX_REINTENTOS;
 use crate::utils::logs::log_funcionamiento;
-use common::tcp_enviador::MensajeTCP;
+use common::confiable::{esperar_confirmacion, Reintentos};
+use common::protocolo::{empaquetar, Mensaje};
+use common::transporte::Transporte;
 
 // Constantes.
-const TIMEOUT_SEGUNDOS: u64 = 3;
-const SLEEP_DELAY: u64 = 1;
+const MAXIMO_GESTIONES_LOCALES: u32 = 3;
+const SIN_COORDINADOR: &str = "El 'Coordinador' no confirmó el viaje, intente nuevamente.";
 
 // Mensaje que modela la busqueda de un 'Repartidor' cercano al 'Restaurante' para llevarle el 'Pedido'.
 #[derive(Message, Debug)]
 #[rtype(result = "()")]
 pub struct EncontrarRepartidorCercano {
     pub direccion_comensal: SocketAddr,
+    pub intentos: u32,
 }
 
 // Handler para manejar los 'Pedidos' que recibe el 'Server'.
@@ -39,6 +40,7 @@ impl Handler<HandlePedido> for Server {
             async move {
                 let encontrar_msj = EncontrarRepartidorCercano {
                     direccion_comensal: msg.id_comensal_ht,
+                    intentos: msg.intentos,
                 };
 
                 actor_addr
@@ -60,7 +62,10 @@ impl Handler<EncontrarRepartidorCercano> for Server {
     ) -> Self::Result {
         let coord_elecc_clone = self.eleccion_coordinador.clone();
         let comensal_actual = _msg.direccion_comensal;
+        let intentos = _msg.intentos;
         let almacenamiento_actor = self.almacenamiento_addr.clone();
+        let direccion = self.addr;
+        let transporte = self.transporte.clone();
         let self_addr = ctx.address();
         Box::pin(
             async move {
@@ -92,7 +97,16 @@ impl Handler<EncontrarRepartidorCercano> for Server {
                     .expect("Error al obtener dirección del 'Coordinador'.");
 
                 if let Some(coord_addr) = coord_addr {
-                    enviar_viaje_a_coordinador(hacer_viaje, coord_addr, self_addr).await;
+                    enviar_viaje_a_coordinador(
+                        direccion,
+                        transporte.as_ref(),
+                        hacer_viaje,
+                        coord_addr,
+                        self_addr,
+                        almacenamiento_actor,
+                        intentos,
+                    )
+                    .await;
                 } else {
                     log_funcionamiento("Dirección del 'Coordinador' no encontrada.".to_string());
                 }
@@ -102,85 +116,72 @@ impl Handler<EncontrarRepartidorCercano> for Server {
     }
 }
 
-// Método para enviarle el viaje al 'Coordinador' para que lo gestione.
+// Método para enviarle el viaje al 'Coordinador' para que lo gestione. Se reintenta, con esperas crecientes, hasta
+// que el 'Coordinador' lo confirme. Si no lo confirma, el pedido se vuelve a gestionar desde esta instancia, que
+// busca de nuevo al repartidor y al 'Coordinador', que pudo haber cambiado. Luego de 'MAXIMO_GESTIONES_LOCALES'
+// gestiones fallidas, el pedido se le rechaza al comensal.
 async fn enviar_viaje_a_coordinador(
+    direccion: SocketAddr,
+    transporte: &dyn Transporte,
     hacer_viaje: HacerPedido,
     coordinator_addr: SocketAddr,
     admin_addr: Addr<Server>,
+    almacenamiento_actor: Arc<Addr<Almacenamiento>>,
+    intentos: u32,
 ) {
     log_funcionamiento(format!(
         "| ----- Enviar viaje al 'Coordinador' [{:?}] ----- |",
         coordinator_addr
     ));
 
-    let mut got_res = false;
-    let mut got_ack = false;
-    for _ in 0..MAX_REINTENTOS {
-        let con = TcpStream::connect(coordinator_addr).await;
-        match con {
-            Ok(s) => {
-                let stream = Some(s);
-                let (lector, mut escritor) = match stream {
-                    Some(stream) => split(stream),
-                    None => panic!("Stream is NULL"),
-                };
-
-                if let Ok(serializado) = serde_json::to_string(&hacer_viaje) {
-                    let serializado = format!("{}\n", serializado);
-                    let msg = MensajeTCP(serializado);
-                    match escritor.write_all(msg.0.as_bytes()).await {
-                        Ok(_) => (),
-                        Err(e) => println!("Error al escribir: {}", e),
-                    }
-                } else {
-                    eprintln!("Error al serializar.");
-                }
-
-                let mut lector = BufReader::new(lector);
-                let mut line = String::new();
-                match timeout(
-                    Duration::from_secs(TIMEOUT_SEGUNDOS),
-                    lector.read_line(&mut line),
-                )
-                .await
-                {
-                    Ok(Ok(_)) => {
-                        if line.trim() == "Ack" {
-                            got_ack = true;
-                        }
-                        got_res = true;
-                    }
-
-                    Ok(Err(_)) => {
-                        println!("Error al leer la línea.");
+    // El mismo sobre se reenvía en cada intento, para que el 'Coordinador' descarte los duplicados.
+    let sobre = empaquetar(direccion, Mensaje::HacerPedido(hacer_viaje.clone()));
+    let reintentos = Reintentos::default();
+    for intento in 0..reintentos.intentos_maximos {
+        if let Ok(mut conexion) = transporte.conectar(coordinator_addr).await {
+            match conexion.escritor.enviar(&sobre).await {
+                Ok(_) => {
+                    if esperar_confirmacion(
+                        &mut conexion.lector,
+                        sobre.id_mensaje,
+                        reintentos.espera(intento),
+                    )
+                    .await
+                    {
+                        log_funcionamiento("Se envió el viaje al 'Coordinador'".to_string());
+                        return;
                     }
 
-                    Err(_) => {
-                        log_funcionamiento("Timeout.".to_string());
-                        got_res = false;
-                    }
+                    log_funcionamiento("Timeout.".to_string());
                 }
-                break;
-            }
-            Err(_) => {
-                tokio::time::sleep(Duration::from_secs(SLEEP_DELAY)).await;
-                continue;
+
+                Err(e) => println!("Error al escribir: {}", e),
             }
         }
-    }
 
-    if !got_res {
-        log_funcionamiento("Error al enviar el viaje al 'Coordinador'.".to_string());
-        return;
+        tokio::time::sleep(reintentos.espera(intento)).await;
     }
 
-    if got_ack {
-        log_funcionamiento("Se envió el viaje al 'Coordinador'".to_string());
-    } else {
-        admin_addr
-            .try_send(HandlePedido {
-                id_comensal_ht: hacer_viaje.id_comensal_mt,
-            })
-            .expect("Error en el proceso de gestionar el pedido para enviarlo al 'Coordinador'.");
+    log_funcionamiento("Error al enviar el viaje al 'Coordinador'.".to_string());
+    if intentos + 1 >= MAXIMO_GESTIONES_LOCALES {
+        log_funcionamiento(format!(
+            "Se agotaron las gestiones del pedido del comensal {:?}.",
+            hacer_viaje.id_comensal_mt
+        ));
+        Server::rechazar_pasajero(
+            direccion,
+            &hacer_viaje,
+            almacenamiento_actor,
+            SIN_COORDINADOR.to_string(),
+        )
+        .await;
+    } else if let Err(e) = admin_addr.try_send(HandlePedido {
+        id_comensal_ht: hacer_viaje.id_comensal_mt,
+        intentos: intentos + 1,
+    }) {
+        println!(
+            "Error en el proceso de gestionar el pedido para enviarlo al 'Coordinador': {}",
+            e
+        );
     }
 }
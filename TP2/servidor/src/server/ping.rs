@@ -12,8 +12,9 @@ use crate::eleccion::mensajes_eleccion::{MensajePing, ObtenerDireccionLider, Pin
 use crate::server::server::Server;
 use crate::utils::constantes::INTERVALO_PING;
 use crate::utils::logs::log_eleccion;
+use common::enviador::EnviarSobre;
 use common::mensajes::QuienEsCoordinador;
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{empaquetar, Mensaje};
 
 // Mensaje que pregunta quien es el coordinador actual del sistema.
 #[derive(Message)]
@@ -24,12 +25,13 @@ pub struct WhoIsCoordinator;
 impl Handler<MensajePing> for Server {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, message: MensajePing, _: &mut Self::Context) -> Self::Result {
-        let tcp_enviador_clone = self.tcp_enviador.clone();
+        let enviador_clone = self.enviador.clone();
         let coord_clone = self.coordinador.clone();
+        let direccion = self.addr;
         Box::pin(
             async move {
-                tcp_enviador_clone
-                    .try_send(MensajeTCP("Ack".to_string()))
+                enviador_clone
+                    .try_send(EnviarSobre(empaquetar(direccion, Mensaje::Ack)))
                     .expect("Error al enviar la respuesta.");
 
                 // Conectarse al socket si no está conectado.
@@ -49,7 +51,8 @@ impl Handler<WhoIsCoordinator> for Server {
     type Result = ResponseActFuture<Self, ()>;
     fn handle(&mut self, _: WhoIsCoordinator, _: &mut Self::Context) -> Self::Result {
         let coord_elecc_clone = self.eleccion_coordinador.clone();
-        let tcp_enviador_clone = self.tcp_enviador.clone();
+        let enviador_clone = self.enviador.clone();
+        let direccion = self.addr;
         Box::pin(
             async move {
                 let coord_addr = coord_elecc_clone
@@ -57,21 +60,17 @@ impl Handler<WhoIsCoordinator> for Server {
                     .await
                     .expect("Error al obtener la direccion del coordinador");
 
-                if coord_addr.is_none() {
-                    tcp_enviador_clone
-                        .try_send(MensajeTCP("Ack".to_string()))
-                        .expect("Error al enviar la respuesta.");
-                    return;
-                }
-
-                let who_is_coord_msg = QuienEsCoordinador {
-                    direccion_coordinador: coord_addr.unwrap(),
+                let respuesta = match coord_addr {
+                    Some(direccion_coordinador) => {
+                        Mensaje::QuienEsCoordinador(QuienEsCoordinador {
+                            direccion_coordinador,
+                        })
+                    }
+                    None => Mensaje::Ack,
                 };
 
-                tcp_enviador_clone
-                    .try_send(MensajeTCP(
-                        serde_json::to_string(&who_is_coord_msg).unwrap(),
-                    ))
+                enviador_clone
+                    .try_send(EnviarSobre(empaquetar(direccion, respuesta)))
                     .expect("Error al enviar la respuesta.");
             }
             .into_actor(self),
@@ -2,6 +2,7 @@
 
 // Imports de crates externas.
 use actix::Addr;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -13,11 +14,13 @@ use crate::coordinador::mensajes_coordinador::{
     Accion, ActualizarComensales, ActualizarRepartidores,
 };
 use crate::server::client_server::EstadoRepartidor;
+use common::enviador::EnviarSobre;
 use common::mensajes::RechazarViaje;
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{empaquetar, Mensaje};
 
 // Funcion que mata a los repartidores que estan ausentes/muertos en el sistema.
 async fn matar_repartidores_ausentes(
+    direccion: SocketAddr,
     actor_almacenamiento: Arc<Addr<Almacenamiento>>,
     direccion_coordinador: Arc<Addr<Coordinador>>,
 ) {
@@ -54,17 +57,14 @@ async fn matar_repartidores_ausentes(
                 })
                 .expect("Error al enviar mensaje.");
 
-            let tcp_message = MensajeTCP(
-                serde_json::to_string(&RechazarViaje {
+            let trama = EnviarSobre(empaquetar(
+                direccion,
+                Mensaje::RechazarViaje(RechazarViaje {
                     respuesta: "El repartidor esta desconectado, intente nuevamente".to_string(),
-                })
-                .unwrap(),
-            );
+                }),
+            ));
             if let Some(sender) = repartidor_ausente.enviador_comensal.as_ref() {
-                sender
-                    .send(tcp_message)
-                    .await
-                    .expect("Error al enviar mensaje.");
+                sender.send(trama).await.expect("Error al enviar mensaje.");
             }
 
             println!(
@@ -77,6 +77,7 @@ async fn matar_repartidores_ausentes(
 
 // Método que spawnea la tarea de 'Reaper' de forma indefinida.
 pub fn spawn_reaper_task(
+    direccion: SocketAddr,
     actor_almacenamiento: Arc<Addr<Almacenamiento>>,
     direccion_coordinador: Arc<Addr<Coordinador>>,
 ) {
@@ -84,6 +85,7 @@ pub fn spawn_reaper_task(
         loop {
             tokio::time::sleep(Duration::from_secs(5)).await;
             matar_repartidores_ausentes(
+                direccion,
                 actor_almacenamiento.clone(),
                 direccion_coordinador.clone(),
             )
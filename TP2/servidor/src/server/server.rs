@@ -2,42 +2,38 @@
 
 // Imports de crates externas.
 use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::io::{split, AsyncBufReadExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
-use tokio_stream::wrappers::LinesStream;
+use std::sync::{Arc, Mutex};
 
 // Imports de funciones/estructuras propias.
-use super::server_coordinador::HacerPedido;
 use crate::almacenamiento::almacenamiento::Almacenamiento;
 use crate::coordinador::coordinador::Coordinador;
-use crate::coordinador::mensajes_coordinador::{
-    ActualizarComensales, ActualizarRepartidores, HandlePedido, UpdateRepartidores,
-};
 use crate::eleccion::eleccion::EleccionCoordinador;
-use crate::eleccion::mensajes_eleccion::{MensajeCoordinador, MensajeEleccion, MensajePing};
 use crate::server::ping::{spawn_ping_task, WhoIsCoordinator};
 use crate::server::reaper::spawn_reaper_task;
 use crate::server::server_almacenamiento::{
     HacerActualizacionComensal, HacerActualizacionRepartidor, HacerActualizacionRepartidores,
+    HacerActualizacionRestaurantes,
 };
 use crate::utils::errores_servidor::ServidorError;
 use crate::utils::logs::{log_eleccion, log_funcionamiento};
 use actix::prelude::*;
-use common::mensajes::{
-    FinalizarViaje, Posicion, RespuestaOfertaViaje, SolicitarPedido, SolicitarRepartidor,
-};
-use common::tcp_enviador::{MensajeTCP, TcpEnviador};
+use common::confiable::{Recepcion, VentanaDuplicados};
+use common::enviador::{Confirmado, Enviador, EnviarSobre};
+use common::protocolo::{empaquetar, ErrorProtocolo, Mensaje, Recibido, Sobre};
+use common::transporte::{Conexion, Escucha, Transporte};
 pub type CoordEleccion = Arc<Addr<EleccionCoordinador>>;
+pub type Duplicados = Arc<Mutex<VentanaDuplicados>>;
 
 // Estructura que encapsula al 'Actor' que representa a una instancia del 'Servidor' del programa.
 pub struct Server {
     pub addr: SocketAddr,
     pub client_addr: SocketAddr,
-    pub tcp_enviador: Arc<Addr<TcpEnviador>>,
+    pub enviador: Arc<Addr<Enviador>>,
     pub eleccion_coordinador: CoordEleccion,
     pub coordinador: Arc<Addr<Coordinador>>,
     pub almacenamiento_addr: Arc<Addr<Almacenamiento>>,
+    pub transporte: Arc<dyn Transporte>,
+    pub duplicados: Duplicados,
 }
 
 // Implementación del trait actor.
@@ -49,54 +45,65 @@ impl Actor for Server {
 impl Server {
     // Constructor del servidor.
     pub fn new(
-        stream: TcpStream,
-        client_addr: SocketAddr,
+        conexion: Conexion,
         addr: SocketAddr,
         eleccion_coordinador: CoordEleccion,
         coordinador: Arc<Addr<Coordinador>>,
         almacenamiento_addr: Arc<Addr<Almacenamiento>>,
+        transporte: Arc<dyn Transporte>,
+        duplicados: Duplicados,
     ) -> Addr<Self> {
         Server::create(|ctx| {
-            let (r_half, w_half) = split(stream);
-            Server::add_stream(LinesStream::new(BufReader::new(r_half).lines()), ctx);
-            let escribir = Some(w_half);
-            let sender_actor = TcpEnviador { escribir }.start();
-            let tcp_enviador = Arc::new(sender_actor);
+            let client_addr = conexion.remota;
+            Server::add_stream(conexion.lector.en_flujo(), ctx);
+            let sender_actor = Enviador::new(client_addr, conexion.escritor).start();
+            let enviador = Arc::new(sender_actor);
 
             Server {
                 addr,
                 client_addr,
-                tcp_enviador,
+                enviador,
                 eleccion_coordinador,
                 coordinador,
                 almacenamiento_addr,
+                transporte,
+                duplicados,
             }
         })
     }
 
     // Método iniciador del actor.
-    pub async fn start(addr: SocketAddr, peers: Vec<SocketAddr>) -> Result<(), ServidorError> {
+    pub async fn start(
+        addr: SocketAddr,
+        peers: Vec<SocketAddr>,
+        transporte: Arc<dyn Transporte>,
+    ) -> Result<(), ServidorError> {
         if peers.is_empty() {
             return Err(ServidorError::InvalidSockets("No hay peers".to_string()));
         }
 
-        let listener = TcpListener::bind(addr).await.map_err(|e| {
+        let listener = transporte.escuchar(addr).await.map_err(|e| {
             ServidorError::BindError(format!("Failed to bind to {}: {:?}", addr, e))
         })?;
 
-        let coordinador = Arc::new(Coordinador::new(addr, peers.clone()));
+        let coordinador = Arc::new(Coordinador::new(addr, peers.clone(), transporte.clone()));
 
         let eleccion_coordinador = Arc::new(EleccionCoordinador::new(
             addr,
             Arc::clone(&coordinador),
             Arc::new(peers),
+            transporte.clone(),
         ));
 
         let almacenamiento_actor = Arc::new(Almacenamiento::start()?);
 
         spawn_ping_task(eleccion_coordinador.clone());
 
-        spawn_reaper_task(almacenamiento_actor.clone(), coordinador.clone());
+        spawn_reaper_task(addr, almacenamiento_actor.clone(), coordinador.clone());
+
+        // La ventana de duplicados es compartida por todas las conexiones, ya que un mensaje retransmitido
+        // puede llegar por una conexión distinta a la del original.
+        let duplicados = Arc::new(Mutex::new(VentanaDuplicados::default()));
 
         accept_connections(
             listener,
@@ -104,19 +111,123 @@ impl Server {
             eleccion_coordinador,
             coordinador,
             almacenamiento_actor,
+            transporte,
+            duplicados,
         )
         .await
     }
+
+    // Le responde un mensaje al cliente de esta conexión.
+    fn responder(&self, mensaje: Mensaje) {
+        self.enviador
+            .try_send(EnviarSobre(empaquetar(self.addr, mensaje)))
+            .expect("No se pudo enviar la respuesta");
+    }
+
+    // Registra un mensaje confiable recibido en la ventana de duplicados y devuelve su estado.
+    fn registrar_recibido(&self, sobre: &Sobre) -> Recepcion {
+        self.duplicados
+            .lock()
+            .map(|mut duplicados| duplicados.registrar(sobre))
+            .unwrap_or(Recepcion::Nuevo)
+    }
+
+    // Procesa un mensaje confiable y lo confirma recién cuando su handler terminó, marcándolo como procesado. Si no
+    // se pudo procesar, no se confirma y se quita de la ventana de duplicados, para que se procese la
+    // retransmisión del enviador.
+    fn procesar_confiable<M>(
+        &self,
+        mensaje: M,
+        id_origen: u64,
+        id_mensaje: u64,
+        ctx: &mut Context<Self>,
+    ) where
+        M: Message<Result = ()> + Send + 'static,
+        Server: Handler<M>,
+    {
+        ctx.spawn(
+            ctx.address()
+                .send(mensaje)
+                .into_actor(self)
+                .map(move |resultado, actor, _| match resultado {
+                    Ok(()) => {
+                        if let Ok(mut duplicados) = actor.duplicados.lock() {
+                            duplicados.terminar(id_origen, id_mensaje);
+                        }
+                        actor.responder(Mensaje::Confirmacion(id_mensaje));
+                    }
+                    Err(e) => {
+                        log_funcionamiento(format!(
+                            "[{:?}] No se pudo procesar el mensaje {} del origen {}: {:?}",
+                            actor.addr, id_mensaje, id_origen, e
+                        ));
+                        if let Ok(mut duplicados) = actor.duplicados.lock() {
+                            duplicados.olvidar(id_origen, id_mensaje);
+                        }
+                    }
+                }),
+        );
+    }
 }
 
 // Handlers de mensajes del servidor.
-impl StreamHandler<Result<String, std::io::Error>> for Server {
-    fn handle(&mut self, read: Result<String, std::io::Error>, ctx: &mut Self::Context) {
-        if let Ok(line) = read {
-            let mensaje = line.trim();
+impl StreamHandler<Result<Recibido, std::io::Error>> for Server {
+    fn handle(&mut self, read: Result<Recibido, std::io::Error>, ctx: &mut Self::Context) {
+        let recibido = match read {
+            Ok(recibido) => recibido,
+            Err(e) => {
+                println!("[{:?}] Error al leer trama {:?}", self.addr, e);
+                return;
+            }
+        };
+
+        let sobre = match recibido {
+            Ok(sobre) => sobre,
+            Err(ErrorProtocolo::VersionDesconocida(version)) => {
+                // Se le avisa al enviador que su versión del protocolo no es soportada.
+                println!(
+                    "[{:?}] {}",
+                    self.addr,
+                    ErrorProtocolo::VersionDesconocida(version)
+                );
+                self.responder(Mensaje::VersionRechazada(version));
+                return;
+            }
+            Err(e) => {
+                println!("[{:?}] {}", self.addr, e);
+                return;
+            }
+        };
+
+        // Un duplicado no se procesa de nuevo. Si el original ya se procesó se vuelve a confirmar, ya que la
+        // confirmación anterior pudo haberse perdido; si todavía se está procesando se descarta sin confirmar, y
+        // la confirmación se envía cuando termine.
+        if sobre.mensaje.requiere_confirmacion() {
+            let recepcion = self.registrar_recibido(&sobre);
+            if recepcion != Recepcion::Nuevo {
+                log_funcionamiento(format!(
+                    "[{:?}] Mensaje duplicado de {:?} descartado: {}",
+                    self.addr, sobre.id_enviador, sobre.id_mensaje
+                ));
+                if recepcion == Recepcion::Procesado {
+                    self.responder(Mensaje::Confirmacion(sobre.id_mensaje));
+                }
+                return;
+            }
+        }
+
+        let id_enviador = sobre.id_enviador;
+        let id_origen = sobre.id_origen;
+        let id_mensaje = sobre.id_mensaje;
+
+        match sobre.mensaje {
+            // Handler de la confirmación de un mensaje enviado de forma confiable por esta conexión.
+            Mensaje::Confirmacion(id_mensaje) => {
+                self.enviador.do_send(Confirmado(id_mensaje));
+            }
 
             // Handler del mensaje 'Ping'.
-            if let Ok(ping_msg) = serde_json::from_str::<MensajePing>(mensaje) {
+            Mensaje::MensajePing(ping_msg) => {
                 log_eleccion(format!("[{:?}] Recibido Ping, enviando Ack", self.addr));
                 ctx.address()
                     .try_send(ping_msg)
@@ -124,42 +235,30 @@ impl StreamHandler<Result<String, std::io::Error>> for Server {
             }
 
             // Handler del mensaje 'QuienEsElCoordinador'.
-            if mensaje == "WhoIsCoordinator" {
+            Mensaje::ConsultarCoordinador => {
                 ctx.address()
                     .try_send(WhoIsCoordinator {})
                     .expect("No se pudo enviar WhoIsCoordinator");
             }
 
             // Handler del mensaje 'Eleccion'.
-            if let Ok(mensaje_eleccion) = serde_json::from_str::<MensajeEleccion>(mensaje) {
-                let tcp_enviador_clone = self.tcp_enviador.clone();
-
-                tcp_enviador_clone
-                    .try_send(MensajeTCP("Ack".to_string()))
-                    .expect("No se pudo enviar la respuesta");
-
+            Mensaje::MensajeEleccion(mensaje_eleccion) => {
+                self.responder(Mensaje::Ack);
                 self.eleccion_coordinador
-                    .try_send(MensajeEleccion {
-                        candidatos: mensaje_eleccion.candidatos,
-                    })
+                    .try_send(mensaje_eleccion)
                     .expect("No se pudo enviar el mensaje de elección");
             }
 
             // Handler del mensaje que indica si esta instancia es o no un coordinador.
-            if let Ok(mensaje_coordinador) = serde_json::from_str::<MensajeCoordinador>(mensaje) {
-                let tcp_enviador_clone = self.tcp_enviador.clone();
-                tcp_enviador_clone
-                    .try_send(MensajeTCP("Ack".to_string()))
-                    .expect("No se pudo enviar la respuesta");
+            Mensaje::MensajeCoordinador(mensaje_coordinador) => {
+                self.responder(Mensaje::Ack);
                 self.eleccion_coordinador
-                    .try_send(MensajeCoordinador {
-                        coordinador: mensaje_coordinador.coordinador,
-                    })
+                    .try_send(mensaje_coordinador)
                     .expect("No se pudo enviar el mensaje de coordinador");
             }
 
             // Handler del mensaje para solicitar un repartidor.
-            if let Ok(solicitar_repartidor) = serde_json::from_str::<SolicitarRepartidor>(mensaje) {
+            Mensaje::SolicitarRepartidor(solicitar_repartidor) => {
                 log_funcionamiento(format!(
                     "[Pedido DESDE EL RESTAURANTE: {:?}]",
                     solicitar_repartidor.comida
@@ -170,27 +269,23 @@ impl StreamHandler<Result<String, std::io::Error>> for Server {
             }
 
             // Handler del mensaje para solicitar un comensal.
-            if let Ok(solicitar_una_comida) = serde_json::from_str::<SolicitarPedido>(mensaje) {
+            Mensaje::SolicitarPedido(solicitar_una_comida) => {
                 log_funcionamiento(format!(
                     "[Pedido de comida DESDE EL COMENSAL: {:?}]",
                     solicitar_una_comida.comida
                 ));
-                ctx.address()
-                    .try_send(solicitar_una_comida)
-                    .expect("Error en SolicitarViaje");
+                self.procesar_confiable(solicitar_una_comida, id_origen, id_mensaje, ctx);
             }
 
             // Handler del mensaje que indica que hay un repartidor listo.
-            if let Ok(repartidor_listo) = serde_json::from_str::<Posicion>(mensaje) {
+            Mensaje::Posicion(repartidor_listo) => {
                 ctx.address()
                     .try_send(repartidor_listo)
                     .expect("Error en PosicionRepartidor");
             }
 
             // Handler del mensaje para indicar que hay un nuevo restaurante.
-            if let Ok(mensaje_identidad) =
-                serde_json::from_str::<common::mensajes::MensajeIdentidad>(mensaje)
-            {
+            Mensaje::MensajeIdentidad(mensaje_identidad) => {
                 log_funcionamiento(format!(
                     "[Identidad recibida, es repartidor: {:?}]",
                     mensaje_identidad.soy_repartidor
@@ -200,60 +295,70 @@ impl StreamHandler<Result<String, std::io::Error>> for Server {
                     .expect("Error en PosicionRepartidor");
             }
 
+            // Handler del mensaje con el que un comensal se vuelve a identificar luego de reconectarse.
+            Mensaje::IdentificarComensal(identificar_comensal) => {
+                self.procesar_confiable(identificar_comensal, id_origen, id_mensaje, ctx);
+            }
+
             // Handler del mensaje para indicar que finalizo un viaje.
-            if let Ok(pedido_finalizado) = serde_json::from_str::<FinalizarViaje>(mensaje) {
-                ctx.address()
-                    .try_send(pedido_finalizado)
-                    .expect("Error en FinalizarViaje");
+            Mensaje::FinalizarViaje(pedido_finalizado) => {
+                self.procesar_confiable(pedido_finalizado, id_origen, id_mensaje, ctx);
             }
 
             // Handler del mensaje para manejar los pedidos.
-            if let Ok(manejar_pedido) = serde_json::from_str::<HandlePedido>(mensaje) {
-                ctx.address()
-                    .try_send(manejar_pedido)
-                    .expect("Error en HandlePedido");
+            Mensaje::HandlePedido(manejar_pedido) => {
+                self.procesar_confiable(manejar_pedido, id_origen, id_mensaje, ctx);
             }
 
             // Handler del mensaje que indica que puede aceptar un pedido.
-            if let Ok(respuesta_aceptar_pedido) =
-                serde_json::from_str::<RespuestaOfertaViaje>(mensaje)
-            {
+            Mensaje::RespuestaOfertaViaje(respuesta_aceptar_pedido) => {
                 ctx.address()
                     .try_send(respuesta_aceptar_pedido)
                     .expect("Error en RespuestaOfertaViaje");
             }
 
             // Handler del mensaje para hacer un pedido.
-            if let Ok(hacer_pedido) = serde_json::from_str::<HacerPedido>(mensaje) {
-                self.tcp_enviador
-                    .try_send(MensajeTCP("Ack".to_string()))
-                    .expect("No se pudo enviar la respuesta");
-                ctx.address()
-                    .try_send(hacer_pedido)
-                    .expect("Error en MakeTrip");
+            Mensaje::HacerPedido(hacer_pedido) => {
+                self.procesar_confiable(hacer_pedido, id_origen, id_mensaje, ctx);
             }
 
             // Handler del mensaje para indicar actualizaciones internas del coordinador.
-            if let Ok(actualizar_comensal) = serde_json::from_str::<ActualizarComensales>(mensaje) {
-                ctx.address()
-                    .try_send(HacerActualizacionComensal {
+            Mensaje::ActualizarComensales(actualizar_comensal) => {
+                self.procesar_confiable(
+                    HacerActualizacionComensal {
                         upt_msg: actualizar_comensal,
-                    })
-                    .expect("No se pudo enviar HacerActualizacionComensal");
+                    },
+                    id_origen,
+                    id_mensaje,
+                    ctx,
+                );
             }
 
             // Handler del mensaje para indicar actualizaciones en los repartidores.
-            if let Ok(actualizar_repartidor) =
-                serde_json::from_str::<ActualizarRepartidores>(mensaje)
-            {
-                ctx.address()
-                    .try_send(HacerActualizacionRepartidores {
+            Mensaje::ActualizarRepartidores(actualizar_repartidor) => {
+                self.procesar_confiable(
+                    HacerActualizacionRepartidores {
                         upt_msg: actualizar_repartidor,
-                    })
-                    .expect("No se pudo enviar MakeUpdateRepartidor");
+                    },
+                    id_origen,
+                    id_mensaje,
+                    ctx,
+                );
+            }
+
+            // Handler del mensaje para indicar actualizaciones en los restaurantes.
+            Mensaje::ActualizarRestaurantes(actualizar_restaurante) => {
+                self.procesar_confiable(
+                    HacerActualizacionRestaurantes {
+                        upt_msg: actualizar_restaurante,
+                    },
+                    id_origen,
+                    id_mensaje,
+                    ctx,
+                );
             }
 
-            if let Ok(buscando_trabajo) = serde_json::from_str::<UpdateRepartidores>(mensaje) {
+            Mensaje::UpdateRepartidores(buscando_trabajo) => {
                 println!("[{:?}] Recibido ActualizarRepartidores", self.addr);
                 ctx.address()
                     .try_send(HacerActualizacionRepartidor {
@@ -261,32 +366,65 @@ impl StreamHandler<Result<String, std::io::Error>> for Server {
                     })
                     .expect("Error en ActualizarRepartidores");
             }
-        } else {
-            println!("[{:?}] Error al leer línea {:?}", self.addr, read);
+
+            otro => {
+                log_funcionamiento(format!(
+                    "[{:?}] Mensaje inesperado de {:?}: {:?}",
+                    self.addr, id_enviador, otro
+                ));
+                // Un mensaje confiable que el servidor no procesa no se confirma, para que el enviador no lo dé
+                // por aplicado.
+                if otro.requiere_confirmacion() {
+                    if let Ok(mut duplicados) = self.duplicados.lock() {
+                        duplicados.olvidar(id_origen, id_mensaje);
+                    }
+                }
+            }
         }
     }
 }
 
 // Método para aceptar conexiones.
 async fn accept_connections(
-    listener: TcpListener,
+    mut listener: Box<dyn Escucha>,
     addr: SocketAddr,
     eleccion_coordinador: Arc<Addr<EleccionCoordinador>>,
     coordinador: Arc<Addr<Coordinador>>,
     almacenamiento_actor: Arc<Addr<Almacenamiento>>,
+    transporte: Arc<dyn Transporte>,
+    duplicados: Duplicados,
 ) -> Result<(), ServidorError> {
     loop {
-        match listener.accept().await {
-            Ok((stream, client_addr)) => {
-                log_eleccion(format!("[{}] Conexión recibida de {:?}", addr, client_addr));
-                Server::new(
-                    stream,
-                    client_addr,
-                    addr,
-                    eleccion_coordinador.clone(),
-                    Arc::clone(&coordinador),
-                    almacenamiento_actor.clone(),
-                );
+        match listener.aceptar().await {
+            Ok(entrante) => {
+                // El saludo se responde en otra tarea, para seguir aceptando conexiones mientras tanto.
+                let eleccion_coordinador = eleccion_coordinador.clone();
+                let coordinador = Arc::clone(&coordinador);
+                let almacenamiento_actor = almacenamiento_actor.clone();
+                let transporte = transporte.clone();
+                let duplicados = duplicados.clone();
+                actix::spawn(async move {
+                    match entrante.responder_saludo().await {
+                        Ok(conexion) => {
+                            log_eleccion(format!(
+                                "[{}] Conexión recibida de {:?}",
+                                addr, conexion.remota
+                            ));
+                            Server::new(
+                                conexion,
+                                addr,
+                                eleccion_coordinador,
+                                coordinador,
+                                almacenamiento_actor,
+                                transporte,
+                                duplicados,
+                            );
+                        }
+                        Err(e) => {
+                            println!("[{}] Error al aceptar conexión: {:?}", addr, e);
+                        }
+                    }
+                });
             }
             Err(e) => {
                 println!("[{}] Error al aceptar conexión: {:?}", addr, e);
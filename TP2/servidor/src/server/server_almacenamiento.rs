@@ -11,7 +11,8 @@ use crate::almacenamiento::mensajes_almacenamiento::{
     InsertarRestaurante,
 };
 use crate::coordinador::mensajes_coordinador::{
-    Accion, ActualizarComensales, ActualizarRepartidores, UpdateRepartidores,
+    Accion, ActualizarComensales, ActualizarRepartidores, ActualizarRestaurantes,
+    UpdateRepartidores,
 };
 use crate::server::client_server::EstadoRepartidor;
 use crate::server::server::Server;
@@ -31,6 +32,13 @@ pub struct HacerActualizacionComensal {
     pub upt_msg: ActualizarComensales,
 }
 
+/// Mensaje para actualizar los restaurantes en el Almacenamiento.
+#[derive(Message, Serialize, Deserialize, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct HacerActualizacionRestaurantes {
+    pub upt_msg: ActualizarRestaurantes,
+}
+
 // Mensaje para hacer actualizaciones en un repartidor.
 #[derive(Message, Serialize, Deserialize, Debug, Clone)]
 #[rtype(result = "()")]
@@ -51,7 +59,7 @@ impl Handler<HacerActualizacionRepartidores> for Server {
         let accion = driver_update.upt_msg.accion;
         let comensal = driver_update.upt_msg.id_comensal_actual;
         let estado_comensal = driver_update.upt_msg.status;
-        let tcp_enviador_clone = self.tcp_enviador.clone();
+        let enviador_clone = self.enviador.clone();
         let almacenamiento_actor = self.almacenamiento_addr.clone();
 
         Box::pin(
@@ -62,7 +70,7 @@ impl Handler<HacerActualizacionRepartidores> for Server {
                             id: d_addr,
                             posicion_restaurante: posicion,
                             id_comensal_actual: None,
-                            enviador_repartidor: Some(tcp_enviador_clone),
+                            enviador_repartidor: Some(enviador_clone),
                             estado: EstadoRepartidor::Active,
                             time_stamp: std::time::Instant::now(),
                         })
@@ -170,3 +178,41 @@ impl Handler<HacerActualizacionRepartidor> for Server {
         )
     }
 }
+
+// Handler para manejar las actualizaciones en los restaurantes que difunde el coordinador.
+impl Handler<HacerActualizacionRestaurantes> for Server {
+    type Result = ResponseActFuture<Self, ()>;
+    fn handle(
+        &mut self,
+        restaurante_update: HacerActualizacionRestaurantes,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let r_addr = restaurante_update.upt_msg.restaurante;
+        let accion = restaurante_update.upt_msg.accion;
+        let actor_almacenamiento = self.almacenamiento_addr.clone();
+        Box::pin(
+            async move {
+                if accion == Accion::Insertar {
+                    actor_almacenamiento
+                        .send(InsertarRestaurante {
+                            id: r_addr,
+                            posicion_repartidor: restaurante_update.upt_msg.posicion,
+                            enviador_repartidor: None,
+                            estado: restaurante_update.upt_msg.status,
+                            time_stamp: Instant::now(),
+                        })
+                        .await
+                        .expect("Error al enviar AddRestaurante al almacenamiento");
+
+                    log_funcionamiento(format!("Restaurante agregado {:?}", r_addr));
+                } else {
+                    log_funcionamiento(format!(
+                        "Acción {:?} no soportada para el restaurante {:?}",
+                        accion, r_addr
+                    ));
+                }
+            }
+            .into_actor(self),
+        )
+    }
+}
@@ -2,6 +2,7 @@
 
 // Imports de crates externas.
 use actix::prelude::*;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -19,10 +20,11 @@ use crate::utils::acciones_gateway::{generar_mensaje_pago_hecho, obtener_respues
 use crate::utils::logs::log_funcionamiento;
 
 use common::mensajes::{PedidoAlRestaurante, RecibirPedido};
+use common::protocolo::{empaquetar, Mensaje};
 use common::utils::obtener_tupla_random;
 use common::{
+    enviador::EnviarSobre,
     mensajes::{FinalizarViaje, IniciarViajeDelivery, RespuestaOfertaViaje},
-    tcp_enviador::MensajeTCP,
 };
 
 // Handler para manejar el mensaje de finalizacion de viaje.
@@ -35,11 +37,13 @@ impl Handler<FinalizarViaje> for Server {
         ));
 
         let almacenamiento_actor = self.almacenamiento_addr.clone();
+        let direccion = self.addr;
         let id_repartidor = msg.direccion_conductor_f;
         let id_comensal = msg.direccion_comensal_f;
         let posicion_destino = msg.pos_destino;
 
         let coord_clone = self.coordinador.clone();
+        let transporte = self.transporte.clone();
 
         Box::pin(
             async move {
@@ -53,10 +57,14 @@ impl Handler<FinalizarViaje> for Server {
                 }
 
                 let string_id_comensal = format!("{:?}", id_comensal.clone());
-                obtener_respuesta_pago(generar_mensaje_pago_hecho(string_id_comensal)).await;
+                obtener_respuesta_pago(
+                    generar_mensaje_pago_hecho(string_id_comensal),
+                    transporte.as_ref(),
+                )
+                .await;
 
-                avisar_al_comensal(almacenamiento_actor.clone(), msg.clone()).await;
-                avisar_al_repartidor(almacenamiento_actor.clone(), msg).await;
+                avisar_al_comensal(direccion, almacenamiento_actor.clone(), msg.clone()).await;
+                avisar_al_repartidor(direccion, almacenamiento_actor.clone(), msg).await;
 
                 almacenamiento_actor
                     .send(FinalizarViaje {
@@ -92,7 +100,11 @@ impl Handler<FinalizarViaje> for Server {
 }
 
 // Método para avisarle a un comensal que se recibio el fin del viaje.
-async fn avisar_al_comensal(storage: Arc<Addr<Almacenamiento>>, msg: FinalizarViaje) {
+async fn avisar_al_comensal(
+    direccion: SocketAddr,
+    storage: Arc<Addr<Almacenamiento>>,
+    msg: FinalizarViaje,
+) {
     let comensal = storage
         .send(ObtenerComensal {
             id: msg.direccion_comensal_f,
@@ -102,14 +114,18 @@ async fn avisar_al_comensal(storage: Arc<Addr<Almacenamiento>>, msg: FinalizarVi
     if let Some(entidad_comensal) = comensal {
         if let Some(sender_comensal) = entidad_comensal.enviador_comensal.as_ref() {
             sender_comensal
-                .try_send(MensajeTCP("ACK".to_string()))
+                .try_send(EnviarSobre(empaquetar(direccion, Mensaje::Ack)))
                 .expect("Error al enviar FinalizarViaje");
         }
     }
 }
 
 // Método para avisarle a un repartidor que se recibio el fin del viaje.
-async fn avisar_al_repartidor(storage: Arc<Addr<Almacenamiento>>, msg: FinalizarViaje) {
+async fn avisar_al_repartidor(
+    direccion: SocketAddr,
+    storage: Arc<Addr<Almacenamiento>>,
+    msg: FinalizarViaje,
+) {
     let repartidor = storage
         .send(ObtenerRepartidor {
             id: msg.direccion_conductor_f,
@@ -119,7 +135,7 @@ async fn avisar_al_repartidor(storage: Arc<Addr<Almacenamiento>>, msg: Finalizar
     if let Some(entidad_repartidor) = repartidor {
         if let Some(sender_comensal) = entidad_repartidor.restaurante_sender.as_ref() {
             sender_comensal
-                .try_send(MensajeTCP("ACK".to_string()))
+                .try_send(EnviarSobre(empaquetar(direccion, Mensaje::Ack)))
                 .expect("Error al enviar FinalizarViaje");
         }
     }
@@ -134,8 +150,9 @@ impl Handler<RespuestaOfertaViaje> for Server {
         let coord_clone: Arc<Addr<crate::coordinador::coordinador::Coordinador>> =
             self.coordinador.clone();
         let almacenamiento_actor = self.almacenamiento_addr.clone();
-        let enviador_repartidor = self.tcp_enviador.clone();
+        let enviador_repartidor = self.enviador.clone();
         let id_repartidor = self.client_addr;
+        let direccion = self.addr;
 
         Box::pin(
             async move {
@@ -161,22 +178,18 @@ impl Handler<RespuestaOfertaViaje> for Server {
                         };
                         let enviador_comensal = entidad_comensal.enviador_comensal.as_ref();
                         if let Some(enviador_comensal) = enviador_comensal {
-                            match serde_json::to_string(&mensaje_comienzo) {
-                                Ok(json_string) => {
-                                    enviador_comensal
-                                        .try_send(MensajeTCP(json_string.clone()))
-                                        .expect("Error al enviar IniciarViaje");
-                                    enviador_repartidor
-                                        .try_send(MensajeTCP(json_string))
-                                        .expect("Error al enviar IniciarViaje");
-                                }
-                                Err(err) => {
-                                    eprintln!(
-                                        "Error al serializar el mensaje IniciarViaje: {}",
-                                        err
-                                    );
-                                }
-                            }
+                            enviador_comensal
+                                .try_send(EnviarSobre(empaquetar(
+                                    direccion,
+                                    Mensaje::IniciarViajeDelivery(mensaje_comienzo.clone()),
+                                )))
+                                .expect("Error al enviar IniciarViaje");
+                            enviador_repartidor
+                                .try_send(EnviarSobre(empaquetar(
+                                    direccion,
+                                    Mensaje::IniciarViajeDelivery(mensaje_comienzo),
+                                )))
+                                .expect("Error al enviar IniciarViaje");
 
                             almacenamiento_actor
                                 .send(ActualizarRepartidor {
@@ -225,6 +238,7 @@ impl Handler<RespuestaOfertaViaje> for Server {
                     coord_clone
                         .try_send(HandlePedido {
                             id_comensal_ht: msg.direccion_comensal_r,
+                            intentos: 0,
                         })
                         .expect("Error al enviar el manejo del pedido.");
                 }
@@ -244,7 +258,8 @@ impl Handler<PedidoAlRestaurante> for Server {
             self.client_addr
         ));
         let almacenamiento_actor = self.almacenamiento_addr.clone();
-        let enviador_repartidor = self.tcp_enviador.clone();
+        let enviador_repartidor = self.enviador.clone();
+        let direccion = self.addr;
         let comida = msg.comida.clone();
 
         Box::pin(
@@ -272,22 +287,18 @@ impl Handler<PedidoAlRestaurante> for Server {
                             .unwrap();
                         let enviador_comensal = restaurante.repartidor_sender.as_ref();
                         if let Some(enviador_comensal) = enviador_comensal {
-                            match serde_json::to_string(&mensaje_inicio) {
-                                Ok(json_string) => {
-                                    enviador_comensal
-                                        .try_send(MensajeTCP(json_string.clone()))
-                                        .expect("Error al enviar IniciarViaje");
-                                    enviador_repartidor
-                                        .try_send(MensajeTCP(json_string))
-                                        .expect("Error al enviar IniciarViaje");
-                                }
-                                Err(err) => {
-                                    eprintln!(
-                                        "Error al serializar el mensaje IniciarViaje: {}",
-                                        err
-                                    );
-                                }
-                            }
+                            enviador_comensal
+                                .try_send(EnviarSobre(empaquetar(
+                                    direccion,
+                                    Mensaje::RecibirPedido(mensaje_inicio.clone()),
+                                )))
+                                .expect("Error al enviar IniciarViaje");
+                            enviador_repartidor
+                                .try_send(EnviarSobre(empaquetar(
+                                    direccion,
+                                    Mensaje::RecibirPedido(mensaje_inicio),
+                                )))
+                                .expect("Error al enviar IniciarViaje");
                         }
                     }
                 }
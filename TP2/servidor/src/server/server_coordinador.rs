@@ -2,7 +2,6 @@
 
 // Imports de crates externas.
 use actix::prelude::*;
-use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
@@ -16,16 +15,12 @@ use crate::{
     server::{client_server::EstadoRepartidor, server::Server},
     utils::logs::log_funcionamiento,
 };
+use common::enviador::EnviarSobre;
 use common::mensajes::{OfertarViaje, RechazarViaje};
-use common::tcp_enviador::MensajeTCP;
+use common::protocolo::{empaquetar, Mensaje};
 
-// Mensaje que modela el viaje del pedido desde el 'Restaurante' hasta el 'Cliente'.
-#[derive(Message, Serialize, Deserialize, Debug, Clone)]
-#[rtype(result = "()")]
-pub struct HacerPedido {
-    pub id_comensal_mt: SocketAddr,
-    pub id_repartidor_mt: SocketAddr,
-}
+// El mensaje que modela el viaje del pedido se define en 'common', junto al resto de los mensajes del protocolo.
+pub use common::mensajes_servidor::HacerPedido;
 
 // Handler para manejar el mensaje que modela el 'Pedido'.
 impl Handler<HacerPedido> for Server {
@@ -35,6 +30,7 @@ impl Handler<HacerPedido> for Server {
         let comensal = msg.id_comensal_mt;
         let coord_clone = self.coordinador.clone();
         let almacenamiento_actor = self.almacenamiento_addr.clone();
+        let direccion = self.addr;
         Box::pin(
             async move {
                 if let Ok(Some(repartidor)) = almacenamiento_actor
@@ -48,6 +44,7 @@ impl Handler<HacerPedido> for Server {
                         coord_clone
                             .send(HandlePedido {
                                 id_comensal_ht: comensal,
+                                intentos: 0,
                             })
                             .await
                             .expect("Error al enviar 'HandlePedido'.");
@@ -59,6 +56,7 @@ impl Handler<HacerPedido> for Server {
                         coord_clone
                             .send(HandlePedido {
                                 id_comensal_ht: comensal,
+                                intentos: 0,
                             })
                             .await
                             .expect("Error al enviar 'HandlePedido'.");
@@ -70,16 +68,12 @@ impl Handler<HacerPedido> for Server {
                             direccion_comensal_o: comensal,
                         };
 
-                        match serde_json::to_string(&can_accept_trip) {
-                            Ok(json_string) => {
-                                sender.try_send(MensajeTCP(json_string)).expect(
-                                    "Error al enviar la solicitud al repartidor mas cercano.",
-                                );
-                            }
-                            Err(err) => {
-                                eprintln!("Error al serializar el mensaje de 'StartTrip': {}", err);
-                            }
-                        }
+                        sender
+                            .try_send(EnviarSobre(empaquetar(
+                                direccion,
+                                Mensaje::OfertarViaje(can_accept_trip),
+                            )))
+                            .expect("Error al enviar la solicitud al repartidor mas cercano.");
 
                         almacenamiento_actor
                             .send(ActualizarRepartidor {
@@ -92,6 +86,7 @@ impl Handler<HacerPedido> for Server {
                             .expect("Error al actualizar el estado del repartidor.");
                     } else {
                         Self::rechazar_pasajero(
+                            direccion,
                             &msg,
                             almacenamiento_actor,
                             "Enviador de repartidor no disponible.".to_string(),
@@ -101,6 +96,7 @@ impl Handler<HacerPedido> for Server {
                 } else {
                     // No se encuentran 'Repartidores', por lo que se rechaza el pasajero.
                     Self::rechazar_pasajero(
+                        direccion,
                         &msg,
                         almacenamiento_actor,
                         "Ningun repartidor esta disponible ahora.".to_string(),
@@ -115,7 +111,8 @@ impl Handler<HacerPedido> for Server {
 
 // Handler para rechazar el pedido del cliente.
 impl Server {
-    async fn rechazar_pasajero(
+    pub(crate) async fn rechazar_pasajero(
+        direccion: SocketAddr,
         msg: &HacerPedido,
         actor_almacenamiento: Arc<Addr<Almacenamiento>>,
         mensaje_rechazo: String,
@@ -126,16 +123,16 @@ impl Server {
             })
             .await
         {
-            let tcp_message = MensajeTCP(
-                serde_json::to_string(&RechazarViaje {
+            let trama = EnviarSobre(empaquetar(
+                direccion,
+                Mensaje::RechazarViaje(RechazarViaje {
                     respuesta: mensaje_rechazo,
-                })
-                .unwrap(),
-            );
+                }),
+            ));
 
             if let Some(sender) = comensal_rechazado.enviador_comensal.as_ref() {
                 sender
-                    .send(tcp_message)
+                    .send(trama)
                     .await
                     .expect("Error al enviar 'RechazarViaje'.");
             }
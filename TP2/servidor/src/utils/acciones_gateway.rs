@@ -3,32 +3,27 @@
 // Imports de crates externas.
 use actix::{ActorFutureExt, Handler, ResponseActFuture, WrapFuture};
 use rand::random;
+use std::net::SocketAddr;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
 use tokio::time::timeout;
 
 // Imports de funciones/estructuras propias.
 use super::logs::log_funcionamiento;
 use crate::almacenamiento::mensajes_almacenamiento::ObtenerComensal;
 use crate::server::server::Server;
+use common::enviador::EnviarSobre;
 use common::mensajes::{Autorizacion, EnviarMensajePago, RechazarViaje};
-use common::mensajes_gateway::{
-    EfectivizarPago, MensajeGateway, RequerirPago, RespuestaAutorizacion, RespuestaPago,
-    ValidarAutorizacionPago,
-};
-use common::tcp_enviador::MensajeTCP;
+use common::mensajes_gateway::{EfectivizarPago, MensajeGateway, ValidarAutorizacionPago};
+use common::protocolo::{empaquetar, Mensaje};
+use common::transporte::Transporte;
 
 // Constantes.
 const TIMEOUT_SEGUNDOS: u64 = 5;
-const MIN_BYTES_LEIDOS: usize = 0;
 const MAX_VALOR_PAGO: f32 = 100.0;
 const COMENSAL_STR: &str = "Comensal";
 const FUE_AUTORIZADO_MSJ: &str = "fue autorizado.";
 const NO_FUE_AUTORIZADO_MSJ: &str = "no fue autorizado.";
 const VIAJE_RECHAZADO_SALDO_INSUFICIENTE: &str = "Viaje rechazado por saldo insuficiente.";
-pub(crate) const ERROR_STR: &str = "Error";
-pub(crate) const ERROR_AL_SERIALIZAR: &str = "Error al serializar el mensaje 'RechazarViaje':";
 const ERROR_ENVIAR_MENSAJE_PAGO: &str = "Error al enviar mensaje de pago.";
 const PAGO_EXITOSO: &str = "Pago exitoso para ";
 const ERROR_RECIBIR_RESPUESTA_GATEWAY: &str = "Error al recibir respuesta válida del Gateway.";
@@ -59,15 +54,12 @@ impl Handler<Autorizacion> for Server {
                         .send(ObtenerComensal { id: id_comensal })
                         .await
                     {
-                        let mensaje_tcp = match serde_json::to_string(&RechazarViaje {
-                            respuesta: VIAJE_RECHAZADO_SALDO_INSUFICIENTE.to_owned(),
-                        }) {
-                            Ok(json_string) => MensajeTCP(json_string),
-                            Err(err) => {
-                                eprintln!("{} {}", ERROR_AL_SERIALIZAR, err);
-                                MensajeTCP(ERROR_STR.to_string())
-                            }
-                        };
+                        let mensaje_tcp = EnviarSobre(empaquetar(
+                            direccion,
+                            Mensaje::RechazarViaje(RechazarViaje {
+                                respuesta: VIAJE_RECHAZADO_SALDO_INSUFICIENTE.to_owned(),
+                            }),
+                        ));
 
                         if let Some(sender) = comensal.enviador_comensal.as_ref() {
                             sender
@@ -85,58 +77,57 @@ impl Handler<Autorizacion> for Server {
 }
 
 // Método que maneja la obtención de la respuesta del pago desde el 'Gateway'.
-pub async fn obtener_respuesta_pago(msj: EnviarMensajePago) -> bool {
-    let direccion_gateway = format!(
+pub async fn obtener_respuesta_pago(msj: EnviarMensajePago, transporte: &dyn Transporte) -> bool {
+    let Ok(direccion_gateway) = format!(
         "{}:{}",
         crate::utils::constantes::IP_GATEWAY,
         crate::utils::constantes::PUERTO_GATEWAY
-    );
-
-    let tcp_stream = TcpStream::connect(&direccion_gateway).await;
-    match tcp_stream {
-        Ok(s) => {
-            let (lector, mut escritor) = s.into_split();
+    )
+    .parse::<SocketAddr>() else {
+        log_funcionamiento(ERROR_CONECTAR_GATEWAY.to_string());
+        return false;
+    };
+
+    let conexion = transporte.conectar(direccion_gateway).await;
+    match conexion {
+        Ok(mut conexion) => {
             let mensaje = match msj.tipo_mensaje {
                 MensajeGateway::Validar => {
-                    RequerirPago::ValidarAutorizacionPago(ValidarAutorizacionPago {
+                    Mensaje::ValidarAutorizacionPago(ValidarAutorizacionPago {
                         id_comensal: msj.id_comensal.clone(),
                         valor: msj.valor,
                     })
                 }
 
-                MensajeGateway::Pagar => RequerirPago::EfectivizarPago(EfectivizarPago {
+                MensajeGateway::Pagar => Mensaje::EfectivizarPago(EfectivizarPago {
                     id_comensal: msj.id_comensal.clone(),
                     valor: msj.valor,
                 }),
             };
 
-            if let Ok(serializado) = serde_json::to_string(&mensaje) {
-                let serializado = format!("{}\n", serializado);
-                if escritor.write_all(serializado.as_bytes()).await.is_err() {
-                    log_funcionamiento(ERROR_ENVIAR_MENSAJE_PAGO.to_string());
-                    return false;
-                }
+            let serializado = empaquetar(conexion.local, mensaje);
+            if conexion.escritor.enviar(&serializado).await.is_err() {
+                log_funcionamiento(ERROR_ENVIAR_MENSAJE_PAGO.to_string());
+                return false;
             }
 
-            let mut lector = BufReader::new(lector);
-            let mut linea = String::new();
-            if let Ok(Ok(bytes_leidos)) = timeout(
+            if let Ok(Ok(Some(linea))) = timeout(
                 Duration::from_secs(TIMEOUT_SEGUNDOS),
-                lector.read_line(&mut linea),
+                conexion.lector.recibir(),
             )
             .await
             {
-                if bytes_leidos > MIN_BYTES_LEIDOS {
-                    if let Ok(respuesta_autorizacion) =
-                        serde_json::from_str::<RespuestaAutorizacion>(&linea)
-                    {
+                match linea.map(|sobre| sobre.mensaje) {
+                    Ok(Mensaje::RespuestaAutorizacion(respuesta_autorizacion)) => {
                         return respuesta_autorizacion.autorizado;
                     }
 
-                    if let Ok(_respuesta_pago) = serde_json::from_str::<RespuestaPago>(&linea) {
+                    Ok(Mensaje::RespuestaPago(_respuesta_pago)) => {
                         log_funcionamiento(format!("{} {}", PAGO_EXITOSO, msj.id_comensal));
                         return true;
                     }
+
+                    _ => {}
                 }
             }
 
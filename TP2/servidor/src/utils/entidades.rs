@@ -8,14 +8,14 @@ use std::time::Instant;
 
 // Imports de funciones/estructuras propias.
 use crate::server::client_server::EstadoRepartidor;
-use common::tcp_enviador::TcpEnviador;
+use common::enviador::Enviador;
 
 // Entidad que modela al 'Comensal'.
 #[derive(Debug, Clone)]
 pub struct EntidadComensal {
     pub posicion_comensal: (f32, f32),
     pub destino_comensal: (f32, f32),
-    pub enviador_comensal: Option<Arc<Addr<TcpEnviador>>>,
+    pub enviador_comensal: Option<Arc<Addr<Enviador>>>,
 }
 
 // Entidad que modela al 'Restaurante'.
@@ -23,7 +23,7 @@ pub struct EntidadComensal {
 pub struct EntidadRepartidor {
     pub posicion_repartidor: (f32, f32),
     pub id_actual_comensal: Option<SocketAddr>,
-    pub restaurante_sender: Option<Arc<Addr<TcpEnviador>>>,
+    pub restaurante_sender: Option<Arc<Addr<Enviador>>>,
     pub estado: EstadoRepartidor,
     pub time_stamp: Instant,
 }
@@ -32,7 +32,7 @@ pub struct EntidadRepartidor {
 #[derive(Debug, Clone)]
 pub struct EntidadRestaurante {
     pub posicion_restaurante: (f32, f32),
-    pub repartidor_sender: Option<Arc<Addr<TcpEnviador>>>,
+    pub repartidor_sender: Option<Arc<Addr<Enviador>>>,
     pub estado: EstadoRepartidor,
     pub time_stamp: Instant,
 }